@@ -11,12 +11,10 @@
 //! ** All parties (including new ones) collect the refresh messages and the join messages.
 
 use crate::error::{FsDkrError, FsDkrResult};
-use crate::refresh_message::RefreshMessage;
-use curv::arithmetic::{BasicOps, Modulo, One, Samplable, Zero};
+use crate::local_key::share_from_plaintext;
+use crate::refresh_message::{check_modulus_size, RefreshContext, RefreshMessage};
+use curv::arithmetic::{BasicOps, BitManipulation, Modulo, One, Samplable, Zero};
 use curv::cryptographic_primitives::hashing::Digest;
-use curv::cryptographic_primitives::secret_sharing::feldman_vss::{
-    ShamirSecretSharing, VerifiableSS,
-};
 use curv::elliptic::curves::{Curve, Point, Scalar};
 use curv::BigInt;
 use multi_party_ecdsa::protocols::multi_party_ecdsa::gg_2020::party_i::Keys;
@@ -26,7 +24,8 @@ use paillier::{Decrypt, EncryptionKey, KeyGeneration, Paillier};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::fmt::Debug;
-use zk_paillier::zkproofs::{CompositeDLogProof, DLogStatement, NiCorrectKeyProof};
+use zeroize::Zeroize;
+use zk_paillier::zkproofs::{CompositeDLogProof, DLogStatement, NiCorrectKeyProof, SALT_STRING};
 
 use crate::ring_pedersen_proof::{RingPedersenProof, RingPedersenStatement};
 
@@ -65,7 +64,8 @@ fn generate_h1_h2_n_tilde() -> (BigInt, BigInt, BigInt, BigInt, BigInt) {
 }
 
 /// Generates the DlogStatement and CompositeProofs using the parameters generated by [generate_h1_h2_n_tilde]
-fn generate_dlog_statement_proofs() -> (DLogStatement, CompositeDLogProof, CompositeDLogProof) {
+pub(crate) fn generate_dlog_statement_proofs(
+) -> (DLogStatement, CompositeDLogProof, CompositeDLogProof) {
     let (n_tilde, h1, h2, xhi, xhi_inv) = generate_h1_h2_n_tilde();
 
     let dlog_statement_base_h1 = DLogStatement {
@@ -128,10 +128,50 @@ impl<E: Curve, H: Digest + Clone, const M: usize> JoinMessage<E, H, M> {
             .ok_or(FsDkrError::NewPartyUnassignedIndexError)
     }
 
+    /// Checks the proofs of the message and the size of its Paillier modulus. Returns the party
+    /// index of the message, which has to be assigned.
+    pub(crate) fn verify(&self) -> FsDkrResult<u16> {
+        let party_index = self.get_party_index()?;
+
+        RingPedersenProof::verify(&self.ring_pedersen_proof, &self.ring_pedersen_statement)
+            .map_err(|_| FsDkrError::RingPedersenProofValidation { party_index })?;
+
+        if self
+            .dk_correctness_proof
+            .verify(&self.ek, SALT_STRING)
+            .is_err()
+        {
+            return Err(FsDkrError::PaillierVerificationError { party_index });
+        }
+
+        // creating an inverse dlog statement
+        let dlog_statement_base_h2 = DLogStatement {
+            N: self.dlog_statement.N.clone(),
+            g: self.dlog_statement.ni.clone(),
+            ni: self.dlog_statement.g.clone(),
+        };
+        if self
+            .composite_dlog_proof_base_h1
+            .verify(&self.dlog_statement)
+            .is_err()
+            || self
+                .composite_dlog_proof_base_h2
+                .verify(&dlog_statement_base_h2)
+                .is_err()
+        {
+            return Err(FsDkrError::DLogProofValidation { party_index });
+        }
+
+        check_modulus_size(party_index, &self.ek, crate::PAILLIER_KEY_SIZE)?;
+
+        Ok(party_index)
+    }
+
     /// Collect phase of the protocol. Compared to the [RefreshMessage::collect], this has to be
     /// tailored for a sent JoinMessage on which we assigned party_index. In this collect, a [LocalKey]
     /// is filled with the information provided by the [RefreshMessage]s from the other parties and
-    /// the other join messages (multiple parties can be added/replaced at once).
+    /// the other join messages (multiple parties can be added/replaced at once). `t` is the
+    /// threshold of the current key; the new key takes the threshold the senders reshared with.
     pub fn collect(
         &self,
         refresh_messages: &[RefreshMessage<E, H, M>],
@@ -142,16 +182,6 @@ impl<E: Curve, H: Digest + Clone, const M: usize> JoinMessage<E, H, M> {
     ) -> FsDkrResult<LocalKey<E>> {
         RefreshMessage::validate_collect(refresh_messages, t, n)?;
 
-        for refresh_message in refresh_messages.iter() {
-            RingPedersenProof::verify(
-                &refresh_message.ring_pedersen_proof,
-                &refresh_message.ring_pedersen_statement,
-            )
-            .map_err(|e| FsDkrError::RingPedersenProofValidation {
-                party_index: refresh_message.party_index,
-            })?;
-        }
-
         for join_message in join_messages.iter() {
             RingPedersenProof::verify(
                 &join_message.ring_pedersen_proof,
@@ -175,40 +205,41 @@ impl<E: Curve, H: Digest + Clone, const M: usize> JoinMessage<E, H, M> {
             join_message.get_party_index()?;
         }
 
-        let parameters = ShamirSecretSharing {
-            threshold: t,
-            share_count: n,
-        };
+        // a new party can check the proofs of its own ciphertexts only
+        let context =
+            RefreshContext::for_new_party(party_index, &paillier_key.ek, &self.dlog_statement, n);
+        let verified = RefreshMessage::verify_all(refresh_messages, &context)?;
 
         // generate a new share, the details can be found here https://hackmd.io/@omershlo/Hy1jBo6JY.
-        let (cipher_text_sum, li_vec) = RefreshMessage::get_ciphertext_sum(
-            refresh_messages,
-            party_index,
-            &parameters,
-            &paillier_key.ek,
-        );
-        let new_share = Paillier::decrypt(&paillier_key.dk, cipher_text_sum)
+        let (senders, li_vec) = RefreshMessage::senders_and_weights(&verified, t)?;
+        let cipher_text_sum =
+            RefreshMessage::get_ciphertext_sum(&senders, &li_vec, party_index, &paillier_key.ek);
+        let mut new_share = Paillier::decrypt(&paillier_key.dk, cipher_text_sum)
             .0
             .into_owned();
 
-        let new_share_fe: Scalar<E> = Scalar::<E>::from(&new_share);
+        let new_share_fe: Scalar<E> = share_from_plaintext(&new_share);
+        new_share.zeroize();
         let paillier_dk = paillier_key.dk.clone();
-        let key_linear_x_i = new_share_fe.clone();
-        let key_linear_y = Point::<E>::generator() * new_share_fe.clone();
+        let messages: Vec<_> = refresh_messages.iter().collect();
+        let new_t = RefreshMessage::agreed_new_t(&messages)?;
+        let (pk_vec, vss_scheme) =
+            RefreshMessage::aggregate_public_data(&senders, &li_vec, new_t, n);
+        let tweak_point = RefreshMessage::agreed_tweak_point(&messages)?;
+        let public_key = RefreshMessage::<E, H, M>::target_public_key(
+            &refresh_messages[0].public_key,
+            tweak_point.as_ref(),
+        );
+        if vss_scheme.commitments[0] != public_key {
+            return Err(FsDkrError::PublicKeyNotPreserved);
+        }
+        if Point::<E>::generator() * &new_share_fe != pk_vec[(party_index - 1) as usize] {
+            return Err(FsDkrError::PublicShareValidationError);
+        }
         let keys_linear = SharedKeys {
-            x_i: key_linear_x_i,
-            y: key_linear_y,
+            x_i: new_share_fe,
+            y: public_key.clone(),
         };
-        let mut pk_vec: Vec<_> = (0..n as usize)
-            .map(|i| refresh_messages[0].points_committed_vec[i].clone() * li_vec[0].clone())
-            .collect();
-
-        for i in 0..n as usize {
-            for j in 1..(t + 1) as usize {
-                pk_vec[i] = pk_vec[i].clone()
-                    + refresh_messages[j].points_committed_vec[i].clone() * li_vec[j].clone();
-            }
-        }
 
         // check what parties are assigned in the current rotation and associate their paillier
         // ek to each available party index.
@@ -272,20 +303,16 @@ impl<E: Curve, H: Digest + Clone, const M: usize> JoinMessage<E, H, M> {
             }
         }
 
-        // generate the vss_scheme for the LocalKey
-        let (vss_scheme, _) = VerifiableSS::<E>::share(t, n, &new_share_fe);
-        // TODO: secret cleanup might be needed.
-
         let local_key = LocalKey {
             paillier_dk,
             pk_vec,
             keys_linear,
             paillier_key_vec,
-            y_sum_s: refresh_messages[0].public_key.clone(),
+            y_sum_s: public_key,
             h1_h2_n_tilde_vec: h1_h2_ntilde_vec,
             vss_scheme,
             i: party_index,
-            t: t,
+            t: new_t,
             n: n,
         };
 
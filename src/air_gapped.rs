@@ -0,0 +1,123 @@
+//! Collect split between an online host and an air-gapped machine.
+//!
+//! A party keeping its Paillier decryption keys offline runs
+//! [RefreshMessage::collect_online] on the online host: it verifies the refresh and join
+//! messages exactly like [RefreshMessage::collect] and produces an [OfflineBundle] with the
+//! public data of the refreshed key and the ciphertext of the new share. The online host
+//! records [OfflineBundle::digest] out of band, e.g. by reading it out to the operator, and the
+//! bundle is carried to the offline machine, where [finish_offline] checks the digest, decrypts
+//! the share and only accepts it if it matches the public share the online host verified.
+//!
+//! The bundle holds no secrets.
+//!
+//! [RefreshMessage::collect_online]: crate::refresh_message::RefreshMessage::collect_online
+//! [RefreshMessage::collect]: crate::refresh_message::RefreshMessage::collect
+
+use crate::error::{FsDkrError, FsDkrResult};
+use crate::local_key::share_from_plaintext;
+use curv::arithmetic::Converter;
+use curv::cryptographic_primitives::hashing::{Digest, DigestExt};
+use curv::cryptographic_primitives::secret_sharing::feldman_vss::VerifiableSS;
+use curv::elliptic::curves::{Curve, Point, Scalar};
+use curv::BigInt;
+use multi_party_ecdsa::protocols::multi_party_ecdsa::gg_2020::party_i::SharedKeys;
+use multi_party_ecdsa::protocols::multi_party_ecdsa::gg_2020::state_machine::keygen::LocalKey;
+use paillier::{Decrypt, DecryptionKey, EncryptionKey, Paillier, RawCiphertext};
+use serde::{Deserialize, Serialize};
+use zeroize::Zeroize;
+use zk_paillier::zkproofs::DLogStatement;
+
+/// What the offline machine needs to finish a collect, see the [module docs](self).
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(bound = "E: Curve")]
+pub struct OfflineBundle<E: Curve> {
+    pub(crate) party_index: u16,
+    pub(crate) t: u16,
+    /// The sum of the sub-shares sent to this party, encrypted under its current Paillier key.
+    pub(crate) ciphertext_sum: BigInt,
+    pub(crate) paillier_key_vec: Vec<EncryptionKey>,
+    pub(crate) h1_h2_n_tilde_vec: Vec<DLogStatement>,
+    pub(crate) public_key: Point<E>,
+    pub(crate) pk_vec: Vec<Point<E>>,
+    pub(crate) vss_scheme: VerifiableSS<E>,
+}
+
+impl<E: Curve> OfflineBundle<E> {
+    pub fn party_index(&self) -> u16 {
+        self.party_index
+    }
+
+    /// The public share the decrypted share has to match.
+    pub fn expected_public_share(&self) -> &Point<E> {
+        &self.pk_vec[(self.party_index - 1) as usize]
+    }
+
+    /// Hash of the whole bundle, recorded on the online host and checked by [finish_offline].
+    pub fn digest<H: Digest + Clone>(&self) -> BigInt {
+        let point = |p: &Point<E>| BigInt::from_bytes(&p.to_bytes(true));
+        let mut hash = H::new()
+            .chain_bigint(&BigInt::from(self.party_index as i32))
+            .chain_bigint(&BigInt::from(self.t as i32))
+            .chain_bigint(&self.ciphertext_sum);
+        for ek in self.paillier_key_vec.iter() {
+            hash = hash.chain_bigint(&ek.n);
+        }
+        for statement in self.h1_h2_n_tilde_vec.iter() {
+            hash = hash
+                .chain_bigint(&statement.N)
+                .chain_bigint(&statement.g)
+                .chain_bigint(&statement.ni);
+        }
+        hash = hash.chain_bigint(&point(&self.public_key));
+        for pk in self.pk_vec.iter() {
+            hash = hash.chain_bigint(&point(pk));
+        }
+        hash = hash
+            .chain_bigint(&BigInt::from(self.vss_scheme.parameters.threshold as i32))
+            .chain_bigint(&BigInt::from(self.vss_scheme.parameters.share_count as i32));
+        for commitment in self.vss_scheme.commitments.iter() {
+            hash = hash.chain_bigint(&point(commitment));
+        }
+        hash.result_bigint()
+    }
+}
+
+/// Offline half of the collect: checks `bundle` against the digest recorded on the online host,
+/// decrypts the new share with the current decryption key `dk` and assembles the refreshed key,
+/// which from then on decrypts with `new_dk`. Fails if the decrypted share does not match the
+/// expected public share.
+pub fn finish_offline<E: Curve, H: Digest + Clone>(
+    bundle: OfflineBundle<E>,
+    expected_digest: &BigInt,
+    dk: &DecryptionKey,
+    new_dk: DecryptionKey,
+) -> FsDkrResult<LocalKey<E>> {
+    if &bundle.digest::<H>() != expected_digest {
+        return Err(FsDkrError::OfflineBundleTampered);
+    }
+
+    let mut new_share = Paillier::decrypt(dk, RawCiphertext::from(bundle.ciphertext_sum.clone()))
+        .0
+        .into_owned();
+    let x_i: Scalar<E> = share_from_plaintext(&new_share);
+    new_share.zeroize();
+    if &(Point::<E>::generator() * &x_i) != bundle.expected_public_share() {
+        return Err(FsDkrError::OfflineShareMismatch);
+    }
+
+    Ok(LocalKey {
+        paillier_dk: new_dk,
+        keys_linear: SharedKeys {
+            y: bundle.public_key.clone(),
+            x_i,
+        },
+        y_sum_s: bundle.public_key,
+        i: bundle.party_index,
+        t: bundle.t,
+        n: bundle.pk_vec.len() as u16,
+        pk_vec: bundle.pk_vec,
+        paillier_key_vec: bundle.paillier_key_vec,
+        h1_h2_n_tilde_vec: bundle.h1_h2_n_tilde_vec,
+        vss_scheme: bundle.vss_scheme,
+    })
+}
@@ -0,0 +1,136 @@
+//! Anchoring refresh transcripts in an external append-only log.
+//!
+//! Audits may require every epoch's transcript digest to be recorded somewhere the committee
+//! cannot rewrite, such as a transparency log or a blockchain. An [Anchor] publishes the digest
+//! and returns an [AnchorReceipt] proving the entry exists. [PendingRefresh::commit_anchored]
+//! publishes the transcript digest of the refresh before storing the refreshed key and keeps
+//! the receipt in the [RefreshOutcome]. Anyone holding the outcome can later check the receipt
+//! with [RefreshOutcome::verify_anchor] against their own view of the log.
+//!
+//! The anchored value is the SHA-256 of the big-endian transcript digest, so it has the same
+//! width whatever hash the refresh messages use.
+//!
+//! [PendingRefresh::commit_anchored]: crate::refreshable_key::PendingRefresh::commit_anchored
+
+use crate::error::{FsDkrError, FsDkrResult};
+use crate::refreshable_key::RefreshOutcome;
+use curv::arithmetic::Converter;
+use curv::BigInt;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::sync::Mutex;
+
+/// Opaque proof, returned by [Anchor::publish], that a digest was recorded.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct AnchorReceipt(Vec<u8>);
+
+impl AnchorReceipt {
+    pub fn new(bytes: Vec<u8>) -> Self {
+        AnchorReceipt(bytes)
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+/// An external append-only log transcript digests are anchored in.
+pub trait Anchor {
+    /// Records `digest`. Failing here makes a [AnchorPolicy::Required] commit abort.
+    fn publish(&self, digest: [u8; 32]) -> FsDkrResult<AnchorReceipt>;
+
+    /// Whether `receipt` proves that `digest` was recorded.
+    fn verify(&self, digest: [u8; 32], receipt: &AnchorReceipt) -> bool;
+}
+
+/// What [PendingRefresh::commit_anchored](crate::refreshable_key::PendingRefresh::commit_anchored)
+/// does when the digest cannot be published.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AnchorPolicy {
+    /// The refreshed key is not stored and the error is returned.
+    Required,
+    /// The refreshed key is stored without a receipt.
+    BestEffort,
+}
+
+/// The value anchored for the transcript digest `transcript_digest`.
+pub fn anchor_digest(transcript_digest: &BigInt) -> [u8; 32] {
+    let mut digest = [0u8; 32];
+    digest.copy_from_slice(&Sha256::digest(&transcript_digest.to_bytes()));
+    digest
+}
+
+impl RefreshOutcome {
+    /// Checks that the outcome carries a receipt for its transcript digest that `anchor`
+    /// accepts.
+    pub fn verify_anchor(&self, anchor: &dyn Anchor) -> FsDkrResult<()> {
+        match &self.anchor_receipt {
+            Some(receipt) if anchor.verify(anchor_digest(&self.transcript_digest), receipt) => {
+                Ok(())
+            }
+            _ => Err(FsDkrError::AnchorReceiptInvalid),
+        }
+    }
+}
+
+/// Reference [Anchor] keeping the log in memory, for tests. The receipt is the big-endian
+/// position of the entry.
+#[derive(Debug, Default)]
+pub struct InMemoryAnchor {
+    log: Mutex<Vec<[u8; 32]>>,
+}
+
+impl InMemoryAnchor {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.log.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl Anchor for InMemoryAnchor {
+    fn publish(&self, digest: [u8; 32]) -> FsDkrResult<AnchorReceipt> {
+        let mut log = self.log.lock().unwrap();
+        log.push(digest);
+        Ok(AnchorReceipt::new(
+            ((log.len() - 1) as u64).to_be_bytes().to_vec(),
+        ))
+    }
+
+    fn verify(&self, digest: [u8; 32], receipt: &AnchorReceipt) -> bool {
+        let mut position = [0u8; 8];
+        if receipt.as_bytes().len() != position.len() {
+            return false;
+        }
+        position.copy_from_slice(receipt.as_bytes());
+        let log = self.log.lock().unwrap();
+        log.get(u64::from_be_bytes(position) as usize) == Some(&digest)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_in_memory_anchor() {
+        let anchor = InMemoryAnchor::new();
+        let first = anchor_digest(&BigInt::from(1));
+        let second = anchor_digest(&BigInt::from(2));
+        let first_receipt = anchor.publish(first).unwrap();
+        let second_receipt = anchor.publish(second).unwrap();
+
+        assert_eq!(anchor.len(), 2);
+        assert!(anchor.verify(first, &first_receipt));
+        assert!(anchor.verify(second, &second_receipt));
+        assert!(!anchor.verify(first, &second_receipt));
+        assert!(!anchor.verify(first, &AnchorReceipt::new(vec![0])));
+        assert!(!InMemoryAnchor::new().verify(first, &first_receipt));
+    }
+}
@@ -0,0 +1,143 @@
+//! Refresh of several keys of the same committee in a single round.
+//!
+//! A committee holding many keys refreshes all of them with one broadcast per party: the
+//! [BatchRefreshMessage] of a party carries one [RefreshMessage] per key, each bound to a
+//! session id derived from the session of the batch and the position of the key, so a message
+//! can neither be replayed in another batch nor moved to another key of the same batch.
+//! [BatchRefreshMessage::collect] verifies and applies the refresh key by key, and a key that
+//! fails does not prevent the others from being refreshed.
+
+use crate::error::{FsDkrError, FsDkrResult};
+use crate::refresh_message::{RefreshMessage, RefreshParams};
+use curv::cryptographic_primitives::hashing::{Digest, DigestExt};
+use curv::elliptic::curves::Curve;
+use curv::BigInt;
+use multi_party_ecdsa::protocols::multi_party_ecdsa::gg_2020::state_machine::keygen::LocalKey;
+use paillier::DecryptionKey;
+use serde::{Deserialize, Serialize};
+
+/// The refresh messages of one party for every key of a batch, see the [module docs](self).
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct BatchRefreshMessage<E: Curve, H: Digest + Clone, const M: usize> {
+    pub(crate) party_index: u16,
+    pub(crate) session_id: BigInt,
+    pub(crate) messages: Vec<RefreshMessage<E, H, M>>,
+}
+
+impl<E: Curve, H: Digest + Clone, const M: usize> BatchRefreshMessage<E, H, M> {
+    /// Distributes the refresh of every key of `old_keys` within the batch session
+    /// `session_id`. The keys have to be held by the same party of the same committee. Returns
+    /// the message to broadcast and the new Paillier decryption key of every key, in the order
+    /// of `old_keys`.
+    pub fn distribute(
+        old_keys: &[LocalKey<E>],
+        session_id: &BigInt,
+    ) -> FsDkrResult<(Self, Vec<DecryptionKey>)> {
+        let first = old_keys
+            .first()
+            .ok_or_else(|| FsDkrError::InvalidLocalKey {
+                reason: "empty batch".to_string(),
+            })?;
+        if old_keys
+            .iter()
+            .any(|key| key.i != first.i || key.n != first.n)
+        {
+            return Err(FsDkrError::InvalidLocalKey {
+                reason: "keys of the batch held by different parties".to_string(),
+            });
+        }
+
+        let mut messages = Vec::with_capacity(old_keys.len());
+        let mut new_dks = Vec::with_capacity(old_keys.len());
+        for (k, old_key) in old_keys.iter().enumerate() {
+            let prepared =
+                RefreshMessage::prepare(old_key.i, old_key, old_key.n, &RefreshParams::default())?;
+            let (message, new_dk) =
+                RefreshMessage::finalize(prepared, &Self::key_session_id(session_id, k))?;
+            messages.push(message);
+            new_dks.push(new_dk);
+        }
+        let batch = BatchRefreshMessage {
+            party_index: first.i,
+            session_id: session_id.clone(),
+            messages,
+        };
+        Ok((batch, new_dks))
+    }
+
+    /// Verifies `batch_messages` against every key of `old_keys` and returns the outcome of the
+    /// refresh of each key, in the order of `old_keys`. The keys are handled one at a time, so
+    /// only the messages of the key being refreshed are copied. Fails as a whole only when a
+    /// batch message does not belong to the session or does not cover every key.
+    pub fn collect(
+        batch_messages: &[Self],
+        old_keys: &[LocalKey<E>],
+        session_id: &BigInt,
+        new_dks: Vec<DecryptionKey>,
+    ) -> FsDkrResult<Vec<FsDkrResult<LocalKey<E>>>> {
+        if new_dks.len() != old_keys.len() {
+            return Err(FsDkrError::InvalidLocalKey {
+                reason: "one new Paillier key is needed per key of the batch".to_string(),
+            });
+        }
+        for batch in batch_messages.iter() {
+            let reason = if &batch.session_id != session_id {
+                "belongs to another batch"
+            } else if batch.messages.len() != old_keys.len() {
+                "wrong number of keys"
+            } else if batch
+                .messages
+                .iter()
+                .any(|msg| msg.party_index != batch.party_index)
+            {
+                "sent by another party than it claims"
+            } else {
+                continue;
+            };
+            return Err(FsDkrError::InvalidRefreshMessage {
+                party_index: batch.party_index,
+                reason: reason.to_string(),
+            });
+        }
+
+        let results = old_keys
+            .iter()
+            .zip(new_dks)
+            .enumerate()
+            .map(|(k, (old_key, new_dk))| {
+                let messages: Vec<_> = batch_messages
+                    .iter()
+                    .map(|batch| batch.messages[k].clone())
+                    .collect();
+                RefreshMessage::collect_in_session(
+                    &messages,
+                    old_key,
+                    &Self::key_session_id(session_id, k),
+                    new_dk,
+                    &[],
+                )
+            })
+            .collect();
+        Ok(results)
+    }
+
+    pub fn party_index(&self) -> u16 {
+        self.party_index
+    }
+
+    pub fn session_id(&self) -> &BigInt {
+        &self.session_id
+    }
+
+    pub fn messages(&self) -> &[RefreshMessage<E, H, M>] {
+        &self.messages
+    }
+
+    /// The session the message of the `k`-th key of the batch `session_id` is bound to.
+    fn key_session_id(session_id: &BigInt, k: usize) -> BigInt {
+        H::new()
+            .chain_bigint(session_id)
+            .chain_bigint(&BigInt::from(k as u64))
+            .result_bigint()
+    }
+}
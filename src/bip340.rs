@@ -0,0 +1,137 @@
+//! BIP340 (x-only) handling of refreshed keys.
+//!
+//! Taproot identifies a key by the x coordinate of the joint public key only, and BIP340 signers
+//! negate their secret whenever the y coordinate is odd. A refresh preserves the full point, so the
+//! stored shares keep whatever parity keygen happened to produce. [normalize_for_bip340] negates
+//! the share and all the public data of a key with an odd y coordinate, so that the stored shares
+//! always correspond to the even-y point. Every party of the committee reaches the same decision
+//! from public data alone; [verify_normalizations] compares the digests the parties exchange to
+//! confirm it.
+
+use crate::epoch::key_fingerprint;
+use crate::error::{FsDkrError, FsDkrResult};
+use curv::arithmetic::{BitManipulation, Converter, Zero};
+use curv::cryptographic_primitives::hashing::{Digest, DigestExt};
+use curv::elliptic::curves::{Curve, Point, Scalar};
+use curv::BigInt;
+use multi_party_ecdsa::protocols::multi_party_ecdsa::gg_2020::state_machine::keygen::LocalKey;
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+
+/// Parity of the y coordinate of a public key.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum KeyParity {
+    Even,
+    Odd,
+}
+
+impl KeyParity {
+    pub fn of<E: Curve>(point: &Point<E>) -> Self {
+        match point.y_coord() {
+            Some(y) if y.test_bit(0) => KeyParity::Odd,
+            _ => KeyParity::Even,
+        }
+    }
+}
+
+/// Record of the normalization a party applied to its key.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Bip340Normalization {
+    /// Whether the share and the public data were negated.
+    pub negated: bool,
+    pub x_only_public_key: BigInt,
+    /// Binds the decision to the normalized key, equal for all the parties of a committee.
+    pub digest: BigInt,
+}
+
+/// Replaces the secret share and all the public data of `local_key` by their negation, which is
+/// a consistent sharing of the negated secret.
+pub(crate) fn negate_local_key<E: Curve>(local_key: &mut LocalKey<E>) {
+    let minus_one = Scalar::<E>::zero() - Scalar::<E>::from(1u16);
+    local_key.keys_linear.x_i = &local_key.keys_linear.x_i * &minus_one;
+    local_key.keys_linear.y = &local_key.keys_linear.y * &minus_one;
+    local_key.y_sum_s = &local_key.y_sum_s * &minus_one;
+    for pk in local_key.pk_vec.iter_mut() {
+        *pk = &*pk * &minus_one;
+    }
+    for commitment in local_key.vss_scheme.commitments.iter_mut() {
+        *commitment = &*commitment * &minus_one;
+    }
+}
+
+/// Makes the joint public key of `local_key` have an even y coordinate, negating the key if
+/// needed.
+pub fn normalize_for_bip340<E: Curve, H: Digest + Clone>(
+    local_key: &mut LocalKey<E>,
+) -> Bip340Normalization {
+    let negated = KeyParity::of(&local_key.y_sum_s) == KeyParity::Odd;
+    if negated {
+        negate_local_key(local_key);
+    }
+
+    let x_only_public_key = local_key.y_sum_s.x_coord().unwrap_or_else(BigInt::zero);
+    let digest = H::new()
+        .chain_bigint(&BigInt::from(negated as i32))
+        .chain_bigint(&x_only_public_key)
+        .chain_bigint(&key_fingerprint::<E, H>(local_key))
+        .result_bigint();
+
+    Bip340Normalization {
+        negated,
+        x_only_public_key,
+        digest,
+    }
+}
+
+/// Checks that all the parties applied the same normalization.
+pub fn verify_normalizations(normalizations: &[Bip340Normalization]) -> FsDkrResult<()> {
+    match normalizations.first() {
+        Some(first) if normalizations.iter().any(|n| n.digest != first.digest) => {
+            Err(FsDkrError::Bip340NormalizationMismatch)
+        }
+        _ => Ok(()),
+    }
+}
+
+/// 32-byte big-endian encoding of the x coordinate of a secp256k1 point.
+pub fn x_only_bytes<E: Curve>(point: &Point<E>) -> Vec<u8> {
+    let x = point.x_coord().unwrap_or_else(BigInt::zero).to_bytes();
+    let mut bytes = vec![0u8; 32usize.saturating_sub(x.len())];
+    bytes.extend_from_slice(&x);
+    bytes
+}
+
+/// The BIP340 challenge `e = H_tag(R.x || P.x || m)` for the nonce point `r` and public key
+/// `public_key`.
+pub fn bip340_challenge<E: Curve>(
+    r: &Point<E>,
+    public_key: &Point<E>,
+    message: &[u8],
+) -> Scalar<E> {
+    let tag = Sha256::digest(b"BIP0340/challenge");
+    let e = Sha256::new()
+        .chain(&tag)
+        .chain(&tag)
+        .chain(x_only_bytes(r))
+        .chain(x_only_bytes(public_key))
+        .chain(message)
+        .finalize();
+    Scalar::<E>::from(&BigInt::from_bytes(&e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use curv::elliptic::curves::Secp256k1;
+
+    #[test]
+    fn test_negation_flips_parity() {
+        let x = Scalar::<Secp256k1>::random();
+        let p = Point::<Secp256k1>::generator() * &x;
+        let minus_p = &p * &(Scalar::<Secp256k1>::zero() - Scalar::<Secp256k1>::from(1u16));
+
+        assert_ne!(KeyParity::of(&p), KeyParity::of(&minus_p));
+        assert_eq!(x_only_bytes(&p), x_only_bytes(&minus_p));
+        assert_eq!(x_only_bytes(&p).len(), 32);
+    }
+}
@@ -0,0 +1,84 @@
+//! Rotations certified by the key itself.
+//!
+//! Before the refreshed key is committed, a quorum of the committee signs the digest of the
+//! [EpochGuard] of the rotation with the *old* shares. The resulting [SelfCertifiedRotation]
+//! can be checked by anyone against the joint public key, which the rotation leaves unchanged.
+//! The signature has to be produced while the old key is still available, see
+//! [PendingRefresh](crate::refreshable_key::PendingRefresh).
+
+use crate::epoch::EpochGuard;
+use crate::error::{FsDkrError, FsDkrResult};
+use curv::cryptographic_primitives::hashing::{Digest, DigestExt};
+use curv::elliptic::curves::{Point, Secp256k1};
+use curv::BigInt;
+use multi_party_ecdsa::protocols::multi_party_ecdsa::gg_2020::party_i::{verify, SignatureRecid};
+use serde::{Deserialize, Serialize};
+
+/// The message signed by the old key to certify a rotation.
+pub fn rotation_digest<H: Digest + Clone>(guard: &EpochGuard) -> BigInt {
+    H::new()
+        .chain_bigint(&BigInt::from(guard.epoch()))
+        .chain_bigint(guard.old_fingerprint())
+        .chain_bigint(guard.new_fingerprint())
+        .result_bigint()
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SelfCertifiedRotation {
+    guard: EpochGuard,
+    digest: BigInt,
+    signature: SignatureRecid,
+}
+
+impl SelfCertifiedRotation {
+    /// Bundles `signature` over the digest of `guard`, checking it against `public_key`.
+    pub fn new<H: Digest + Clone>(
+        guard: EpochGuard,
+        signature: SignatureRecid,
+        public_key: &Point<Secp256k1>,
+    ) -> FsDkrResult<Self> {
+        let certificate = SelfCertifiedRotation {
+            digest: rotation_digest::<H>(&guard),
+            guard,
+            signature,
+        };
+        certificate.verify::<H>(public_key)?;
+        Ok(certificate)
+    }
+
+    pub fn guard(&self) -> &EpochGuard {
+        &self.guard
+    }
+
+    pub fn digest(&self) -> &BigInt {
+        &self.digest
+    }
+
+    pub fn signature(&self) -> &SignatureRecid {
+        &self.signature
+    }
+
+    /// Checks that the certificate was signed by the key with joint public key `public_key`.
+    pub fn verify<H: Digest + Clone>(&self, public_key: &Point<Secp256k1>) -> FsDkrResult<()> {
+        if self.digest != rotation_digest::<H>(&self.guard) {
+            return Err(FsDkrError::RotationCertificateInvalid);
+        }
+        verify(&self.signature, public_key, &self.digest)
+            .map_err(|_| FsDkrError::RotationCertificateInvalid)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sha2::Sha256;
+
+    #[test]
+    fn test_rotation_digest_covers_the_whole_epoch() {
+        let guard = |epoch| EpochGuard::new(epoch, BigInt::from(1), BigInt::from(2));
+        assert_ne!(
+            rotation_digest::<Sha256>(&guard(1)),
+            rotation_digest::<Sha256>(&guard(1 + (1 << 32)))
+        );
+    }
+}
@@ -0,0 +1,247 @@
+//! Compact canonical encoding of refresh messages.
+//!
+//! [RefreshMessage::to_bytes] writes a message in the byte layout below and
+//! [RefreshMessage::from_bytes] reads it back, checking every length against the number of
+//! receivers the caller expects and rejecting trailing data. Every message has exactly one
+//! encoding: integers are minimal, points compressed, and the decoder refuses anything else.
+//!
+//! All the fixed-size integers are big-endian. The encoding is built from these items:
+//!
+//! * `u8`, `u16`: one or two bytes.
+//! * `int`: a `u16` header followed by the magnitude of the integer in big-endian bytes, without
+//!   leading zero bytes. The low 15 bits of the header are the number of bytes of the magnitude,
+//!   the high bit is set for a negative integer. Zero is the header `0x0000` alone.
+//! * `point`: the compressed SEC1 encoding of the point (33 bytes on secp256k1 and P-256). The
+//!   point at infinity is the same number of zero bytes.
+//! * `scalar`: an `int` below the order of the curve.
+//! * `list(x)`: a `u16` count followed by that many `x`.
+//! * `option(x)`: a `u8` that is 0 for nothing, or 1 followed by `x`.
+//!
+//! A message with `n` receivers, a sharing threshold `t` and `M` ring-Pedersen rounds is:
+//!
+//! ```text
+//! u8        format version, COMPACT_FORMAT_VERSION
+//! u16       message version, REFRESH_MESSAGE_VERSION
+//! u16       old party index
+//! u16       party index
+//! u16       n
+//! u16       t
+//! point     VSS commitments, t + 1 times
+//! int       ciphertexts, n times
+//! PDL proof n times: int z, point u1, int u2, int u3, int s1, int s2, int s3
+//! range     n times: int z, int e, int s, int s1, int s2
+//! list(int) Paillier key correctness proof
+//! int       ring-Pedersen parameters of the sender: N, h1 (g), h2 (ni)
+//! int       new Paillier modulus of the sender
+//! list(u16) removed party indices
+//! point     joint public key
+//! int       ring-Pedersen statement: S, T, N
+//! int       ring-Pedersen proof: A, M times, then Z, M times
+//! u8        sharing basis: 0 linear, or 1 followed by list(u16) of the quorum
+//! option    list of (u16 old index, u16 new index) of the index remap
+//! option    point, the tweak
+//! option    session binding: int session id, point binding key, point commitment,
+//!           scalar response
+//! list(u8)  metadata
+//! ```
+//!
+//! The ring-Pedersen statement is decoded with the Paillier key of modulus `N`; the `φ(N)` the
+//! sender keeps in it is not sent.
+//!
+//! [RefreshMessage::to_bytes]: crate::refresh_message::RefreshMessage::to_bytes
+//! [RefreshMessage::from_bytes]: crate::refresh_message::RefreshMessage::from_bytes
+
+use crate::error::{FsDkrError, FsDkrResult};
+use curv::arithmetic::{Converter, Zero};
+use curv::elliptic::curves::{Curve, Point, Scalar};
+use curv::BigInt;
+use std::convert::TryFrom;
+
+/// Version of the byte layout, the first byte of an encoded message.
+pub const COMPACT_FORMAT_VERSION: u8 = 1;
+
+const NEGATIVE: u16 = 0x8000;
+
+fn invalid(reason: &str) -> FsDkrError {
+    FsDkrError::InvalidEncoding {
+        reason: reason.to_string(),
+    }
+}
+
+fn point_len<E: Curve>() -> usize {
+    Point::<E>::generator().to_bytes(true).len()
+}
+
+#[derive(Default)]
+pub(crate) struct Writer {
+    bytes: Vec<u8>,
+}
+
+impl Writer {
+    pub(crate) fn into_bytes(self) -> Vec<u8> {
+        self.bytes
+    }
+
+    pub(crate) fn u8(&mut self, value: u8) {
+        self.bytes.push(value);
+    }
+
+    pub(crate) fn u16(&mut self, value: u16) {
+        self.bytes.extend_from_slice(&value.to_be_bytes());
+    }
+
+    /// Panics on an integer of 2^15 bytes or more, far beyond anything a message holds.
+    pub(crate) fn int(&mut self, value: &BigInt) {
+        let magnitude = if value.is_zero() {
+            vec![]
+        } else {
+            value.to_bytes()
+        };
+        let len = u16::try_from(magnitude.len())
+            .ok()
+            .filter(|len| len & NEGATIVE == 0)
+            .expect("integer too large for the compact encoding");
+        let sign = if value < &BigInt::zero() { NEGATIVE } else { 0 };
+        self.u16(len | sign);
+        self.bytes.extend_from_slice(&magnitude);
+    }
+
+    pub(crate) fn point<E: Curve>(&mut self, point: &Point<E>) {
+        if point.is_zero() {
+            self.bytes.resize(self.bytes.len() + point_len::<E>(), 0);
+        } else {
+            self.bytes.extend_from_slice(&point.to_bytes(true));
+        }
+    }
+
+    pub(crate) fn scalar<E: Curve>(&mut self, scalar: &Scalar<E>) {
+        self.int(&scalar.to_bigint());
+    }
+
+    pub(crate) fn count(&mut self, count: usize) {
+        self.u16(u16::try_from(count).expect("list too long for the compact encoding"));
+    }
+}
+
+pub(crate) struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    pub(crate) fn new(bytes: &'a [u8]) -> Self {
+        Reader { bytes }
+    }
+
+    fn take(&mut self, len: usize) -> FsDkrResult<&'a [u8]> {
+        if self.bytes.len() < len {
+            return Err(invalid("truncated"));
+        }
+        let (head, tail) = self.bytes.split_at(len);
+        self.bytes = tail;
+        Ok(head)
+    }
+
+    pub(crate) fn u8(&mut self) -> FsDkrResult<u8> {
+        Ok(self.take(1)?[0])
+    }
+
+    pub(crate) fn u16(&mut self) -> FsDkrResult<u16> {
+        let bytes = self.take(2)?;
+        Ok(u16::from_be_bytes([bytes[0], bytes[1]]))
+    }
+
+    pub(crate) fn int(&mut self) -> FsDkrResult<BigInt> {
+        let header = self.u16()?;
+        let magnitude = self.take((header & !NEGATIVE) as usize)?;
+        if magnitude.first() == Some(&0) || (magnitude.is_empty() && header != 0) {
+            return Err(invalid("integer not minimally encoded"));
+        }
+        let value = BigInt::from_bytes(magnitude);
+        Ok(if header & NEGATIVE != 0 {
+            BigInt::zero() - value
+        } else {
+            value
+        })
+    }
+
+    pub(crate) fn point<E: Curve>(&mut self) -> FsDkrResult<Point<E>> {
+        let bytes = self.take(point_len::<E>())?;
+        if bytes.iter().all(|byte| *byte == 0) {
+            return Ok(Point::zero());
+        }
+        let point = Point::<E>::from_bytes(bytes).map_err(|_| invalid("invalid point"))?;
+        if &point.to_bytes(true)[..] != bytes {
+            return Err(invalid("point not compressed"));
+        }
+        Ok(point)
+    }
+
+    pub(crate) fn scalar<E: Curve>(&mut self) -> FsDkrResult<Scalar<E>> {
+        let value = self.int()?;
+        if value < BigInt::zero() || &value >= Scalar::<E>::group_order() {
+            return Err(invalid("scalar out of range"));
+        }
+        Ok(Scalar::from_bigint(&value))
+    }
+
+    /// A list count, which has to be `expected` when given.
+    pub(crate) fn count(&mut self, expected: Option<usize>) -> FsDkrResult<usize> {
+        let count = self.u16()? as usize;
+        match expected {
+            Some(expected) if expected != count => Err(invalid("unexpected list length")),
+            _ => Ok(count),
+        }
+    }
+
+    pub(crate) fn finish(self) -> FsDkrResult<()> {
+        if !self.bytes.is_empty() {
+            return Err(invalid("trailing data"));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use curv::elliptic::curves::Secp256k1;
+
+    #[test]
+    fn test_integers_round_trip_and_are_minimal() {
+        for value in [
+            BigInt::zero(),
+            BigInt::from(1),
+            BigInt::from(256),
+            BigInt::zero() - BigInt::from(5),
+        ]
+        .iter()
+        {
+            let mut writer = Writer::default();
+            writer.int(value);
+            let bytes = writer.into_bytes();
+            let mut reader = Reader::new(&bytes);
+            assert_eq!(&reader.int().unwrap(), value);
+            reader.finish().unwrap();
+        }
+
+        // a leading zero byte, a negative zero and trailing data are rejected
+        for bytes in [&[0, 2, 0, 1][..], &[0x80, 0], &[0, 1, 1, 0]].iter() {
+            let mut reader = Reader::new(bytes);
+            assert!(reader.int().and_then(|_| reader.finish()).is_err());
+        }
+    }
+
+    #[test]
+    fn test_points_are_compressed() {
+        let point = Point::<Secp256k1>::generator() * Scalar::random();
+        let mut writer = Writer::default();
+        writer.point(&point);
+        writer.point(&Point::<Secp256k1>::zero());
+        let bytes = writer.into_bytes();
+        assert_eq!(bytes.len(), 66);
+        let mut reader = Reader::new(&bytes);
+        assert_eq!(reader.point::<Secp256k1>().unwrap(), point);
+        assert!(reader.point::<Secp256k1>().unwrap().is_zero());
+        reader.finish().unwrap();
+    }
+}
@@ -0,0 +1,123 @@
+//! Evidence against a sender whose refresh message fails verification.
+//!
+//! A party whose collect rejects a message has to convince the others, or an arbiter, that the
+//! sender cheated. [RefreshMessage::collect_with_complaints] returns a [Complaint] for every
+//! sender whose message fails [RefreshMessage::verify]. The complaint carries the message
+//! itself, so [Complaint::verify] can re-run the failed check with nothing but the public data
+//! of the committee, without trusting the complainer and without having seen the broadcast.
+//! The [fingerprint](RefreshMessage::fingerprint) of the message is what the arbiter compares
+//! with the broadcast channel to make sure the complainer did not forge it.
+//!
+//! Refreshes that add parties are not covered: the public data of the committee does not hold
+//! the keys of the new receivers.
+
+use crate::add_party_message::JoinMessage;
+use crate::error::FsDkrError;
+use crate::observer::CommitteePublicData;
+use crate::refresh_message::{RefreshContext, RefreshMessage};
+use curv::cryptographic_primitives::hashing::Digest;
+use curv::elliptic::curves::Curve;
+use curv::BigInt;
+use multi_party_ecdsa::protocols::multi_party_ecdsa::gg_2020::state_machine::keygen::LocalKey;
+use paillier::DecryptionKey;
+use serde::{Deserialize, Serialize};
+
+/// A message that fails verification, with the reason, see the [module docs](self).
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Complaint<E: Curve, H: Digest + Clone, const M: usize> {
+    accused: u16,
+    session_id: Option<BigInt>,
+    reason: String,
+    message: RefreshMessage<E, H, M>,
+}
+
+impl<E: Curve, H: Digest + Clone, const M: usize> Complaint<E, H, M> {
+    /// The sender of the rejected message.
+    pub fn accused(&self) -> u16 {
+        self.accused
+    }
+
+    /// The session the message was verified in, if any.
+    pub fn session_id(&self) -> Option<&BigInt> {
+        self.session_id.as_ref()
+    }
+
+    /// The error the message was rejected with, as displayed.
+    pub fn reason(&self) -> &str {
+        &self.reason
+    }
+
+    pub fn message(&self) -> &RefreshMessage<E, H, M> {
+        &self.message
+    }
+
+    /// Re-runs the verification of the message against `committee`, the public data of the key
+    /// before the refresh, and confirms the accusation if it fails for the stated reason.
+    pub fn verify(&self, committee: &CommitteePublicData<E>) -> bool {
+        if self.message.party_index() != self.accused {
+            return false;
+        }
+        let context = Self::context(committee, &self.message, self.session_id.as_ref());
+        match self.message.verify(&context) {
+            Ok(_) => false,
+            Err(error) => error.to_string() == self.reason,
+        }
+    }
+
+    fn context(
+        committee: &CommitteePublicData<E>,
+        message: &RefreshMessage<E, H, M>,
+        session_id: Option<&BigInt>,
+    ) -> RefreshContext<E> {
+        let context = RefreshContext::for_observer(committee, message.recipient_count());
+        match session_id {
+            Some(session_id) => context.with_session_id(session_id.clone()),
+            None => context,
+        }
+    }
+}
+
+impl<E: Curve, H: Digest + Clone, const M: usize> RefreshMessage<E, H, M> {
+    /// Same as [RefreshMessage::collect], within the session `session_id` if any. When the
+    /// collect fails, returns the error with a [Complaint] for every sender whose message fails
+    /// verification on its own; a failure of the combination of valid messages comes without
+    /// complaints.
+    pub fn collect_with_complaints(
+        refresh_messages: &[Self],
+        old_key: &LocalKey<E>,
+        session_id: Option<&BigInt>,
+        new_dk: DecryptionKey,
+        join_messages: &[JoinMessage<E, H, M>],
+    ) -> Result<LocalKey<E>, (FsDkrError, Vec<Complaint<E, H, M>>)> {
+        let result = match session_id {
+            Some(session_id) => Self::collect_in_session(
+                refresh_messages,
+                old_key,
+                session_id,
+                new_dk,
+                join_messages,
+            ),
+            None => Self::collect(refresh_messages, old_key, new_dk, join_messages),
+        };
+        let error = match result {
+            Ok(new_key) => return Ok(new_key),
+            Err(error) => error,
+        };
+
+        let committee = CommitteePublicData::from_local_key(old_key);
+        let complaints = refresh_messages
+            .iter()
+            .filter_map(|msg| {
+                let context = Complaint::context(&committee, msg, session_id);
+                let reason = msg.verify(&context).err()?.to_string();
+                Some(Complaint {
+                    accused: msg.party_index,
+                    session_id: session_id.cloned(),
+                    reason,
+                    message: msg.clone(),
+                })
+            })
+            .collect();
+        Err((error, complaints))
+    }
+}
@@ -0,0 +1,219 @@
+//! Dealerless key generation with the refresh messages.
+//!
+//! A refresh message already deals a secret to a committee with a VSS, Paillier-encrypted
+//! sub-shares and the proofs that the sub-shares match the commitments. Key generation is a
+//! refresh in which every party deals a fresh random secret instead of its current share:
+//! * every party broadcasts a [JoinMessage] registering its Paillier key and ring-Pedersen
+//!   parameters, with its party index assigned, like a party joining a committee,
+//! * every party calls [dkg_distribute] and broadcasts the resulting message,
+//! * every party calls [collect_dkg], which checks the registrations and the messages like
+//!   [RefreshMessage::collect] and assembles a complete [LocalKey],
+//! * every party broadcasts [dkg_confirmation] of its key and checks the others with
+//!   [verify_dkg_confirmations].
+//!
+//! There is no public key to preserve, so the joint public key is the combination of the
+//! senders' constant-term commitments, and the confirmation round is what ensures that the
+//! whole committee derived the same key. Every party has to deal: the secrets are combined
+//! with [SharingBasis::LagrangeWeighted] over the whole committee. The key can then be signed
+//! with and refreshed like a key out of the GG20 keygen.
+
+use crate::add_party_message::JoinMessage;
+use crate::epoch::key_fingerprint;
+use crate::error::{FsDkrError, FsDkrResult};
+use crate::local_key::share_from_plaintext;
+use crate::refresh_message::{RefreshContext, RefreshMessage, RefreshParams, SharingBasis};
+use curv::cryptographic_primitives::hashing::Digest;
+use curv::cryptographic_primitives::secret_sharing::feldman_vss::{
+    ShamirSecretSharing, VerifiableSS,
+};
+use curv::elliptic::curves::{Curve, Point, Scalar};
+use curv::BigInt;
+use multi_party_ecdsa::protocols::multi_party_ecdsa::gg_2020::party_i::{Keys, SharedKeys};
+use multi_party_ecdsa::protocols::multi_party_ecdsa::gg_2020::state_machine::keygen::LocalKey;
+use paillier::{Decrypt, DecryptionKey, Paillier};
+use zeroize::Zeroize;
+
+/// Verifies `registrations` and returns them in party index order. The indices have to be
+/// exactly `1..=n`.
+fn sorted_registrations<E: Curve, H: Digest + Clone, const M: usize>(
+    registrations: &[JoinMessage<E, H, M>],
+) -> FsDkrResult<Vec<&JoinMessage<E, H, M>>> {
+    let mut sorted = registrations
+        .iter()
+        .map(|registration| Ok((registration.verify()?, registration)))
+        .collect::<FsDkrResult<Vec<_>>>()?;
+    sorted.sort_by_key(|(party_index, _)| *party_index);
+    let indices: Vec<u16> = sorted.iter().map(|(party_index, _)| *party_index).collect();
+    if indices != (1..=sorted.len() as u16).collect::<Vec<_>>() {
+        return Err(FsDkrError::InvalidQuorum { quorum: indices });
+    }
+    Ok(sorted
+        .into_iter()
+        .map(|(_, registration)| registration)
+        .collect())
+}
+
+/// The sharing every key generation message has to use.
+fn dkg_basis(n: u16) -> SharingBasis {
+    SharingBasis::LagrangeWeighted {
+        quorum: (1..=n).collect(),
+    }
+}
+
+/// Deals a fresh random secret to the committee registered by `registrations`, with threshold
+/// `t`, see the [module docs](self). Returns the message to broadcast and the Paillier
+/// decryption key the party uses once the key is generated.
+pub fn dkg_distribute<E: Curve, H: Digest + Clone, const M: usize>(
+    party_index: u16,
+    t: u16,
+    registrations: &[JoinMessage<E, H, M>],
+) -> FsDkrResult<(RefreshMessage<E, H, M>, DecryptionKey)> {
+    let registrations = sorted_registrations(registrations)?;
+    let n = registrations.len() as u16;
+    if party_index == 0 || party_index > n {
+        return Err(FsDkrError::NewPartyUnassignedIndexError);
+    }
+
+    // only the fields the distribution reads are meaningful: the secret to deal, the
+    // receivers' keys and the party's own ring-Pedersen parameters
+    let secret = Scalar::<E>::random();
+    let public_share = Point::<E>::generator() * &secret;
+    let dealer_key = LocalKey {
+        paillier_dk: DecryptionKey {
+            p: BigInt::from(0),
+            q: BigInt::from(0),
+        },
+        pk_vec: vec![public_share.clone(); n as usize],
+        keys_linear: SharedKeys {
+            y: public_share.clone(),
+            x_i: secret,
+        },
+        paillier_key_vec: registrations
+            .iter()
+            .map(|registration| registration.ek.clone())
+            .collect(),
+        y_sum_s: public_share.clone(),
+        h1_h2_n_tilde_vec: registrations
+            .iter()
+            .map(|registration| registration.dlog_statement.clone())
+            .collect(),
+        vss_scheme: VerifiableSS {
+            parameters: ShamirSecretSharing {
+                threshold: t,
+                share_count: n,
+            },
+            commitments: vec![public_share],
+        },
+        i: party_index,
+        t,
+        n,
+    };
+    let params = RefreshParams {
+        basis: dkg_basis(n),
+        ..RefreshParams::default()
+    };
+    RefreshMessage::prepare_unchecked(party_index, &dealer_key, n, &params)?.into_unbound()
+}
+
+/// Generates the key of `party_index` from the messages of the whole committee registered by
+/// `registrations`, see the [module docs](self). `paillier_keys` are the keys the party
+/// registered with and `new_dk` the one returned by its [dkg_distribute].
+pub fn collect_dkg<E: Curve, H: Digest + Clone, const M: usize>(
+    party_index: u16,
+    t: u16,
+    registrations: &[JoinMessage<E, H, M>],
+    refresh_messages: &[RefreshMessage<E, H, M>],
+    paillier_keys: &Keys,
+    new_dk: DecryptionKey,
+) -> FsDkrResult<LocalKey<E>> {
+    let registrations = sorted_registrations(registrations)?;
+    let n = registrations.len() as u16;
+    if party_index == 0
+        || party_index > n
+        || registrations[(party_index - 1) as usize].ek != paillier_keys.ek
+    {
+        return Err(FsDkrError::NewPartyUnassignedIndexError);
+    }
+
+    let context = RefreshContext::for_dkg(
+        registrations
+            .iter()
+            .map(|registration| (registration.ek.clone(), registration.dlog_statement.clone()))
+            .collect(),
+    );
+    let verified = RefreshMessage::verify_all(refresh_messages, &context)?;
+    for msg in verified.iter() {
+        if msg.basis != dkg_basis(n)
+            || msg.new_t() != t
+            || msg.old_party_index != msg.party_index
+            || msg.index_remap.is_some()
+            || msg.tweak_point.is_some()
+        {
+            return Err(FsDkrError::InvalidRefreshMessage {
+                party_index: msg.party_index,
+                reason: "not a key generation message".to_string(),
+            });
+        }
+    }
+
+    // every party of the committee has to have dealt exactly once
+    let (senders, li_vec) = RefreshMessage::senders_and_weights(&verified, t)?;
+    let (pk_vec, vss_scheme) = RefreshMessage::aggregate_public_data(&senders, &li_vec, t, n);
+    let public_key = vss_scheme.commitments[0].clone();
+
+    let ciphertext_sum =
+        RefreshMessage::get_ciphertext_sum(&senders, &li_vec, party_index, &paillier_keys.ek);
+    let mut share = Paillier::decrypt(&paillier_keys.dk, ciphertext_sum)
+        .0
+        .into_owned();
+    let x_i: Scalar<E> = share_from_plaintext(&share);
+    share.zeroize();
+    if Point::<E>::generator() * &x_i != pk_vec[(party_index - 1) as usize] {
+        return Err(FsDkrError::PublicShareValidationError);
+    }
+
+    Ok(LocalKey {
+        paillier_dk: new_dk,
+        pk_vec,
+        keys_linear: SharedKeys {
+            y: public_key.clone(),
+            x_i,
+        },
+        // the senders are sorted by party index
+        paillier_key_vec: senders.iter().map(|msg| msg.ek.clone()).collect(),
+        y_sum_s: public_key,
+        h1_h2_n_tilde_vec: registrations
+            .iter()
+            .map(|registration| registration.dlog_statement.clone())
+            .collect(),
+        vss_scheme,
+        i: party_index,
+        t,
+        n,
+    })
+}
+
+/// What a party broadcasts in the confirmation round of the key generation: the fingerprint of
+/// the public data of its key.
+pub fn dkg_confirmation<E: Curve, H: Digest + Clone>(local_key: &LocalKey<E>) -> BigInt {
+    key_fingerprint::<E, H>(local_key)
+}
+
+/// Checks that every party of the committee confirmed the same key as `local_key`.
+/// `confirmations` holds the [dkg_confirmation] of every other party, by party index.
+pub fn verify_dkg_confirmations<E: Curve, H: Digest + Clone>(
+    local_key: &LocalKey<E>,
+    confirmations: &[(u16, BigInt)],
+) -> FsDkrResult<()> {
+    let own = dkg_confirmation::<E, H>(local_key);
+    for party_index in (1..=local_key.n).filter(|party_index| *party_index != local_key.i) {
+        match confirmations
+            .iter()
+            .find(|(confirmed_by, _)| *confirmed_by == party_index)
+        {
+            Some((_, confirmation)) if confirmation == &own => {}
+            _ => return Err(FsDkrError::DkgConfirmationMismatch { party_index }),
+        }
+    }
+    Ok(())
+}
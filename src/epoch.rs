@@ -0,0 +1,282 @@
+//! Epoch tracking for refreshed keys.
+//!
+//! GG20 deployments cache presignatures (completed offline stages) that are bound to the
+//! shares they were computed from. Once a refresh replaces those shares, any cached
+//! presignature must never be used again. An [EpochGuard] describes a committed refresh
+//! (the new epoch number plus fingerprints of the key before and after), and a
+//! [PresignRegistry] refuses to release presignatures created under an older epoch once it
+//! has been advanced with that guard.
+
+use crate::error::{FsDkrError, FsDkrResult};
+use curv::arithmetic::Converter;
+use curv::cryptographic_primitives::hashing::{Digest, DigestExt};
+use curv::elliptic::curves::Curve;
+use curv::BigInt;
+use multi_party_ecdsa::protocols::multi_party_ecdsa::gg_2020::state_machine::keygen::LocalKey;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::sync::{RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+/// Computes a fingerprint of the public data of a [LocalKey].
+///
+/// Only data that is common to the whole committee is hashed (threshold parameters, joint
+/// public key, public shares and Paillier encryption keys), so every party of the same epoch
+/// computes the same fingerprint.
+pub fn key_fingerprint<E: Curve, H: Digest + Clone>(local_key: &LocalKey<E>) -> BigInt {
+    let mut hash = H::new()
+        .chain_bigint(&BigInt::from(local_key.t as i32))
+        .chain_bigint(&BigInt::from(local_key.n as i32))
+        .chain_bigint(&BigInt::from_bytes(&local_key.y_sum_s.to_bytes(true)));
+    for pk in local_key.pk_vec.iter() {
+        hash = hash.chain_bigint(&BigInt::from_bytes(&pk.to_bytes(true)));
+    }
+    for ek in local_key.paillier_key_vec.iter() {
+        hash = hash.chain_bigint(&ek.n);
+    }
+    hash.result_bigint()
+}
+
+/// Marks the transition from one key epoch to the next.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct EpochGuard {
+    epoch: u64,
+    old_fingerprint: BigInt,
+    new_fingerprint: BigInt,
+}
+
+impl EpochGuard {
+    pub fn new(epoch: u64, old_fingerprint: BigInt, new_fingerprint: BigInt) -> Self {
+        EpochGuard {
+            epoch,
+            old_fingerprint,
+            new_fingerprint,
+        }
+    }
+
+    /// Builds the guard for a refresh that turned `old_key` into `new_key`.
+    pub fn for_keys<E: Curve, H: Digest + Clone>(
+        epoch: u64,
+        old_key: &LocalKey<E>,
+        new_key: &LocalKey<E>,
+    ) -> Self {
+        Self::new(
+            epoch,
+            key_fingerprint::<E, H>(old_key),
+            key_fingerprint::<E, H>(new_key),
+        )
+    }
+
+    /// The epoch the key entered with this refresh.
+    pub fn epoch(&self) -> u64 {
+        self.epoch
+    }
+
+    pub fn old_fingerprint(&self) -> &BigInt {
+        &self.old_fingerprint
+    }
+
+    pub fn new_fingerprint(&self) -> &BigInt {
+        &self.new_fingerprint
+    }
+}
+
+struct RegistryState<T> {
+    epoch: u64,
+    fingerprint: Option<BigInt>,
+    entries: HashMap<T, u64>,
+}
+
+/// Keeps track of presignature identifiers together with the epoch they were created under.
+///
+/// The registry is safe to share between threads; [PresignRegistry::advance] is atomic with
+/// respect to lookups, so a presignature is either released before the epoch changes or
+/// rejected after it. Once a thread panicked while holding the lock, every call fails with
+/// [FsDkrError::LockPoisoned] and no presignature is usable anymore.
+pub struct PresignRegistry<T: Eq + Hash> {
+    state: RwLock<RegistryState<T>>,
+}
+
+impl<T: Eq + Hash> PresignRegistry<T> {
+    pub fn new(epoch: u64) -> Self {
+        PresignRegistry {
+            state: RwLock::new(RegistryState {
+                epoch,
+                fingerprint: None,
+                entries: HashMap::new(),
+            }),
+        }
+    }
+
+    fn read(&self) -> FsDkrResult<RwLockReadGuard<'_, RegistryState<T>>> {
+        self.state.read().map_err(|_| FsDkrError::LockPoisoned {
+            lock: "presignature registry",
+        })
+    }
+
+    fn write(&self) -> FsDkrResult<RwLockWriteGuard<'_, RegistryState<T>>> {
+        self.state.write().map_err(|_| FsDkrError::LockPoisoned {
+            lock: "presignature registry",
+        })
+    }
+
+    pub fn current_epoch(&self) -> FsDkrResult<u64> {
+        Ok(self.read()?.epoch)
+    }
+
+    /// Records a presignature created under `epoch`. Presignatures from an epoch older than the
+    /// current one are rejected straight away.
+    pub fn register(&self, id: T, epoch: u64) -> FsDkrResult<()> {
+        let mut state = self.write()?;
+        if epoch < state.epoch {
+            return Err(FsDkrError::StalePresignature {
+                created_epoch: epoch,
+                current_epoch: state.epoch,
+            });
+        }
+        state.entries.insert(id, epoch);
+        Ok(())
+    }
+
+    /// Returns true if `id` is known and still usable under the current epoch, false if the
+    /// registry is poisoned.
+    pub fn is_usable(&self, id: &T) -> bool {
+        let state = match self.read() {
+            Ok(state) => state,
+            Err(_) => return false,
+        };
+        matches!(state.entries.get(id), Some(epoch) if *epoch >= state.epoch)
+    }
+
+    /// Removes `id` from the registry and hands it out for signing. Every presignature can be
+    /// released only once.
+    pub fn release(&self, id: &T) -> FsDkrResult<()> {
+        let mut state = self.write()?;
+        let created_epoch = state
+            .entries
+            .remove(id)
+            .ok_or(FsDkrError::UnknownPresignature)?;
+        if created_epoch < state.epoch {
+            return Err(FsDkrError::StalePresignature {
+                created_epoch,
+                current_epoch: state.epoch,
+            });
+        }
+        Ok(())
+    }
+
+    /// Moves the registry to the epoch of `guard`, dropping every presignature created under an
+    /// older epoch. Returns the number of dropped presignatures.
+    pub fn advance(&self, guard: &EpochGuard) -> FsDkrResult<usize> {
+        let mut state = self.write()?;
+        if guard.epoch <= state.epoch {
+            return Err(FsDkrError::EpochRegression {
+                current_epoch: state.epoch,
+                new_epoch: guard.epoch,
+            });
+        }
+        if let Some(fingerprint) = &state.fingerprint {
+            if fingerprint != &guard.old_fingerprint {
+                return Err(FsDkrError::EpochFingerprintMismatch);
+            }
+        }
+
+        let new_epoch = guard.epoch;
+        let before = state.entries.len();
+        state.entries.retain(|_, epoch| *epoch >= new_epoch);
+        state.epoch = new_epoch;
+        state.fingerprint = Some(guard.new_fingerprint.clone());
+
+        Ok(before - state.entries.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use std::thread;
+
+    #[test]
+    fn test_stale_presignatures_after_advance() {
+        let registry = PresignRegistry::new(0);
+        registry.register("a", 0).unwrap();
+        registry.register("b", 0).unwrap();
+        assert!(registry.is_usable(&"a"));
+
+        let guard = EpochGuard::new(1, BigInt::from(1), BigInt::from(2));
+        assert_eq!(registry.advance(&guard).unwrap(), 2);
+        assert!(!registry.is_usable(&"a"));
+        assert!(registry.release(&"b").is_err());
+        assert!(registry.register("c", 0).is_err());
+
+        registry.register("d", 1).unwrap();
+        assert!(registry.release(&"d").is_ok());
+        assert!(registry.release(&"d").is_err());
+    }
+
+    #[test]
+    fn test_advance_rejects_regression_and_foreign_guard() {
+        let registry = PresignRegistry::<u64>::new(0);
+        registry
+            .advance(&EpochGuard::new(1, BigInt::from(1), BigInt::from(2)))
+            .unwrap();
+        assert!(registry
+            .advance(&EpochGuard::new(1, BigInt::from(2), BigInt::from(3)))
+            .is_err());
+        assert!(registry
+            .advance(&EpochGuard::new(2, BigInt::from(5), BigInt::from(6)))
+            .is_err());
+        assert!(registry
+            .advance(&EpochGuard::new(2, BigInt::from(2), BigInt::from(3)))
+            .is_ok());
+        assert_eq!(registry.current_epoch().unwrap(), 2);
+    }
+
+    #[test]
+    fn test_concurrent_release_during_advance() {
+        let registry = Arc::new(PresignRegistry::new(0));
+        for id in 0..1000u64 {
+            registry.register(id, 0).unwrap();
+        }
+
+        let releaser = {
+            let registry = registry.clone();
+            thread::spawn(move || {
+                (0..1000u64)
+                    .filter(|id| registry.release(id).is_ok())
+                    .count()
+            })
+        };
+        let dropped = registry
+            .advance(&EpochGuard::new(1, BigInt::from(1), BigInt::from(2)))
+            .unwrap();
+        let released = releaser.join().unwrap();
+
+        // every presignature was either handed out before the advance or dropped by it
+        assert_eq!(released + dropped, 1000);
+        assert!((0..1000u64).all(|id| !registry.is_usable(&id)));
+    }
+
+    #[test]
+    fn test_poisoned_registry_fails_closed() {
+        let registry = Arc::new(PresignRegistry::new(0));
+        registry.register("a", 0).unwrap();
+        let poisoner = registry.clone();
+        let _ = thread::spawn(move || {
+            let _state = poisoner.state.write().unwrap();
+            panic!("poisoning the registry");
+        })
+        .join();
+
+        assert!(!registry.is_usable(&"a"));
+        assert!(matches!(
+            registry.release(&"a"),
+            Err(FsDkrError::LockPoisoned { .. })
+        ));
+        assert!(matches!(
+            registry.current_epoch(),
+            Err(FsDkrError::LockPoisoned { .. })
+        ));
+    }
+}
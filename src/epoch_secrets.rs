@@ -0,0 +1,129 @@
+//! Pairwise transport keys for a new epoch, derived from the refresh.
+//!
+//! Once a refresh is committed the keys of the point-to-point channels are considered as tainted
+//! as the old shares. Instead of running another key agreement, every pair of parties derives
+//! fresh keys from what the refresh already gave them: the Diffie-Hellman value of party `i`
+//! and party `j` is `x_i·X_j = x_j·X_i`, where `x` are the new shares and `X` the new public
+//! shares. It goes through HKDF-SHA256, salted with the transcript digest of the refresh and
+//! bound to the epoch and the two indices, and yields one key per direction.
+
+use crate::refreshable_key::RefreshOutcome;
+use curv::arithmetic::Converter;
+use curv::elliptic::curves::{Curve, Point, Scalar};
+use hkdf::Hkdf;
+use sha2::Sha256;
+use std::collections::BTreeMap;
+use zeroize::Zeroize;
+
+const KEY_LEN: usize = 32;
+const INFO_LABEL: &[u8] = b"fs-dkr epoch transport keys";
+
+/// The keys of the channel with one peer.
+pub struct PeerKeys {
+    send: [u8; KEY_LEN],
+    receive: [u8; KEY_LEN],
+}
+
+impl PeerKeys {
+    /// Key for the messages sent to the peer.
+    pub fn send(&self) -> &[u8; KEY_LEN] {
+        &self.send
+    }
+
+    /// Key for the messages received from the peer.
+    pub fn receive(&self) -> &[u8; KEY_LEN] {
+        &self.receive
+    }
+}
+
+impl Drop for PeerKeys {
+    fn drop(&mut self) {
+        self.send.zeroize();
+        self.receive.zeroize();
+    }
+}
+
+/// The transport keys of one party for an epoch, zeroized on drop.
+pub struct EpochSecrets {
+    epoch: u64,
+    party_index: u16,
+    peers: BTreeMap<u16, PeerKeys>,
+}
+
+impl EpochSecrets {
+    pub fn epoch(&self) -> u64 {
+        self.epoch
+    }
+
+    pub fn party_index(&self) -> u16 {
+        self.party_index
+    }
+
+    pub fn peer(&self, peer_index: u16) -> Option<&PeerKeys> {
+        self.peers.get(&peer_index)
+    }
+}
+
+/// Derives the transport keys of party `own_index` with every other party, from its new share
+/// `own_share` and the new public shares `peer_public_shares` (the `pk_vec` of the refreshed
+/// key, indexed by party).
+pub fn derive_epoch_secrets<E: Curve>(
+    outcome: &RefreshOutcome,
+    own_index: u16,
+    own_share: &Scalar<E>,
+    peer_public_shares: &[Point<E>],
+) -> EpochSecrets {
+    let epoch = outcome.guard.epoch();
+    let salt = outcome.transcript_digest.to_bytes();
+    let peers = peer_public_shares
+        .iter()
+        .enumerate()
+        .map(|(k, public_share)| (k as u16 + 1, public_share))
+        .filter(|(peer_index, _)| *peer_index != own_index)
+        .map(|(peer_index, public_share)| {
+            let mut shared = (public_share * own_share).to_bytes(true).to_vec();
+            let hkdf = Hkdf::<Sha256>::new(Some(&salt), &shared);
+            shared.zeroize();
+
+            // the first key protects the messages from the lower index to the higher one
+            let (low, high) = if own_index < peer_index {
+                (own_index, peer_index)
+            } else {
+                (peer_index, own_index)
+            };
+            let mut info = INFO_LABEL.to_vec();
+            info.extend_from_slice(&epoch.to_be_bytes());
+            info.extend_from_slice(&low.to_be_bytes());
+            info.extend_from_slice(&high.to_be_bytes());
+            let mut okm = [0u8; 2 * KEY_LEN];
+            hkdf.expand(&info, &mut okm)
+                .expect("64 bytes is a valid HKDF-SHA256 output length");
+
+            let mut upward = [0u8; KEY_LEN];
+            let mut downward = [0u8; KEY_LEN];
+            upward.copy_from_slice(&okm[..KEY_LEN]);
+            downward.copy_from_slice(&okm[KEY_LEN..]);
+            okm.zeroize();
+            let keys = if own_index == low {
+                PeerKeys {
+                    send: upward,
+                    receive: downward,
+                }
+            } else {
+                PeerKeys {
+                    send: downward,
+                    receive: upward,
+                }
+            };
+            upward.zeroize();
+            downward.zeroize();
+            (peer_index, keys)
+        })
+        .collect();
+
+    EpochSecrets {
+        epoch,
+        party_index: own_index,
+        peers,
+    }
+}
@@ -1,3 +1,4 @@
+use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
 pub type FsDkrResult<T> = Result<T, FsDkrError>;
@@ -15,9 +16,9 @@ pub enum FsDkrError {
     #[error("Shares did not pass verification.")]
     PublicShareValidationError,
 
-    #[error("SizeMismatch error for the refresh message {refresh_message_index:?} - pdl proof length: {pdl_proof_len:?}, Points Commited Length: {points_commited_len:?}, Points Encrypted Length: {points_encrypted_len:?}")]
+    #[error("SizeMismatch error for the refresh message of party {party_index:?} - pdl proof length: {pdl_proof_len:?}, Points Commited Length: {points_commited_len:?}, Points Encrypted Length: {points_encrypted_len:?}")]
     SizeMismatchError {
-        refresh_message_index: usize,
+        party_index: u16,
         pdl_proof_len: usize,
         points_commited_len: usize,
         points_encrypted_len: usize,
@@ -33,13 +34,27 @@ pub enum FsDkrError {
     #[error("Ring Pedersen Proof Failed")]
     RingPedersenProofError,
 
-    #[error("Range Proof failed for party: {party_index:?}")]
-    RangeProof { party_index: usize },
+    #[error("PDLwSlack proof of party {party_index} for receiver {receiver} failed")]
+    PDLwSlackProofInvalid { party_index: u16, receiver: u16 },
+
+    #[error("The sub-share party {party_index} encrypted to receiver {receiver} does not match its commitment")]
+    SubShareMismatch { party_index: u16, receiver: u16 },
+
+    #[error("Range proof of party {party_index} for receiver {receiver} failed")]
+    RangeProof { party_index: u16, receiver: u16 },
 
-    #[error("The Paillier moduli size of party: {party_index:?} is {moduli_size:?} bits, when it should be 2047-2048 bits")]
+    #[error("The Paillier modulus of party {party_index} has {got_bits} bits, when at least {required_bits} are required")]
     ModuliTooSmall {
         party_index: u16,
-        moduli_size: usize,
+        got_bits: usize,
+        required_bits: usize,
+    },
+
+    #[error("The Paillier modulus of party {party_index} has {got_bits} bits, more than the {max_bits} accepted")]
+    ModulusTooLarge {
+        party_index: u16,
+        got_bits: usize,
+        max_bits: usize,
     },
 
     #[error("Paillier verification proof failed for party {party_index:?}")]
@@ -56,4 +71,209 @@ pub enum FsDkrError {
 
     #[error("Ring pedersen proof failed for party {party_index:?}")]
     RingPedersenProofValidation { party_index: u16 },
+
+    #[error(
+        "Epoch guard for epoch {new_epoch:?} does not advance the current epoch {current_epoch:?}"
+    )]
+    EpochRegression { current_epoch: u64, new_epoch: u64 },
+
+    #[error("Epoch guard does not continue the key lineage tracked by the registry")]
+    EpochFingerprintMismatch,
+
+    #[error("Presignature was created under epoch {created_epoch:?}, the current epoch is {current_epoch:?}")]
+    StalePresignature {
+        created_epoch: u64,
+        current_epoch: u64,
+    },
+
+    #[error("Unknown presignature")]
+    UnknownPresignature,
+
+    #[error("The refresh session has already been collected for this key")]
+    SessionReused,
+
+    #[error("Invalid local key: {reason}")]
+    InvalidLocalKey { reason: String },
+
+    #[error("Party index {party_index} of the key is outside 1..={n}")]
+    KeyIndexOutOfRange { party_index: u16, n: u16 },
+
+    #[error("Threshold {t} of the key is not below its {n} parties")]
+    KeyThresholdOutOfRange { t: u16, n: u16 },
+
+    #[error("The key has {len} entries in {field} for {n} parties")]
+    KeyVectorLength {
+        field: &'static str,
+        len: usize,
+        n: u16,
+    },
+
+    #[error("The Paillier decryption key of the key does not match the party's encryption key")]
+    KeyPaillierMismatch,
+
+    #[error("The secret share of the key is zero")]
+    KeyZeroShare,
+
+    #[error("Parties disagree on the BIP340 normalization of the refreshed key")]
+    Bip340NormalizationMismatch,
+
+    #[error("The joint public key changed across the refresh")]
+    PublicKeyChanged,
+
+    #[error("Collect of party {party_index} failed: {source}")]
+    CollectFailed {
+        party_index: u16,
+        source: Box<FsDkrError>,
+    },
+
+    #[error("Refusing to encrypt to weak receiver keys: {failures:?}")]
+    WeakReceiverKeys { failures: Vec<ReceiverKeyFailure> },
+
+    #[error("Invalid resharing quorum {quorum:?}")]
+    InvalidQuorum { quorum: Vec<u16> },
+
+    #[error("Refresh messages do not use the same sharing basis")]
+    SharingBasisMismatch,
+
+    #[error("Party {party_index:?} did not reshare its current share")]
+    ConstantTermMismatch { party_index: u16 },
+
+    #[error("The combined sharing does not preserve the joint public key")]
+    PublicKeyNotPreserved,
+
+    #[error("Index remap is not a bijection onto 1..=n, or does not cover the party")]
+    InvalidIndexRemap,
+
+    #[error("Refresh messages do not agree on the index remap")]
+    IndexRemapMismatch,
+
+    #[error("Rotation certificate does not verify against the public key")]
+    RotationCertificateInvalid,
+
+    #[error("Refresh messages do not agree on the key tweak")]
+    TweakMismatch,
+
+    #[error("Party {party_index:?} reshared with a different threshold")]
+    ThresholdMismatch { party_index: u16 },
+
+    #[error("Unknown word at position {position:?} of the mnemonic")]
+    MnemonicUnknownWord { position: usize },
+
+    #[error("Mnemonic checksum failed, check words {first_word:?} to {last_word:?}")]
+    MnemonicChecksum { first_word: usize, last_word: usize },
+
+    #[error("Malformed mnemonic")]
+    MnemonicMalformed,
+
+    #[error("The mnemonic was not wrapped with a passphrase, or needs one")]
+    MnemonicPassphrase,
+
+    #[error("The restored share does not belong to this key")]
+    MnemonicShareMismatch,
+
+    #[error("The refresh messages or the key differ from the verified ones")]
+    VerifiedSetMismatch,
+
+    #[error("Refresh message of party {party_index} is malformed: {reason}")]
+    InvalidRefreshMessage { party_index: u16, reason: String },
+
+    #[error("Refresh messages were verified for another key or session")]
+    RefreshContextMismatch,
+
+    #[error("Refresh message is not bound to the session")]
+    SessionBindingInvalid,
+
+    #[error("The revocation statement is not signed by the key")]
+    RevocationStatementInvalid,
+
+    #[error("The revocation statement is not canonically encoded")]
+    RevocationStatementMalformed,
+
+    #[error(
+        "The {field} of the revocation notice has length {len}, more than its encoding allows"
+    )]
+    RevocationFieldTooLong { field: &'static str, len: usize },
+
+    #[error("Anchoring the transcript digest failed: {reason}")]
+    AnchorFailed { reason: String },
+
+    #[error("The anchor receipt is missing or does not match the transcript digest")]
+    AnchorReceiptInvalid,
+
+    #[error("The key is leased to {leases} signing session(s)")]
+    KeyInUse { leases: usize },
+
+    #[error("A thread panicked while holding the lock of the {lock}")]
+    LockPoisoned { lock: &'static str },
+
+    #[error("The offline bundle does not match the digest recorded online")]
+    OfflineBundleTampered,
+
+    #[error("The decrypted share does not match the expected public share")]
+    OfflineShareMismatch,
+
+    #[error("The slice of party {party_index} is not committed to by its broadcast")]
+    MerklePathInvalid { party_index: u16 },
+
+    #[error("Party {party_index} sent more than one refresh message")]
+    DuplicatedRefreshMessage { party_index: u16 },
+
+    #[error("The refresh message of party {party_index} is not the one it distributed")]
+    OwnRefreshMessageMismatch { party_index: u16 },
+
+    #[error("Party {party_index} sent two different refresh messages")]
+    EquivocatingSender { party_index: u16 },
+
+    #[error("The refresh message of party {party_index} belongs to another session")]
+    SessionMismatch { party_index: u16 },
+
+    #[error("The signature on the refresh message of party {party_index} is invalid")]
+    SenderSignatureInvalid { party_index: u16 },
+
+    #[error("Message from party {party_index} could not be decoded: {reason}")]
+    MalformedMessage { party_index: u16, reason: String },
+
+    #[error("Invalid compact encoding: {reason}")]
+    InvalidEncoding { reason: String },
+
+    #[error(
+        "Refresh message has unsupported message version {found}, only {supported} is understood"
+    )]
+    UnsupportedVersion { found: u16, supported: u16 },
+
+    #[error("Party {party_index} derived a different key in the key generation")]
+    DkgConfirmationMismatch { party_index: u16 },
+
+    #[error("Party {party_index} did not acknowledge the refresh")]
+    RefreshAckMissing { party_index: u16 },
+
+    #[error("Party {party_index} acknowledged another share than the refresh gave it")]
+    RefreshAckMismatch { party_index: u16 },
+
+    #[error("{} senders failed verification, {tolerated} tolerated", failures.len())]
+    TooManyFailedSenders {
+        failures: Vec<(u16, FsDkrError)>,
+        tolerated: usize,
+    },
+}
+
+/// A receiver whose Paillier encryption key failed pre-flight validation in distribute.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReceiverKeyFailure {
+    pub party_index: u16,
+    pub reason: String,
+}
+
+/// A condition that did not fail a collect but should be looked at, see
+/// [RefreshMessage::collect_warnings](crate::refresh_message::RefreshMessage::collect_warnings).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Warning {
+    /// The new Paillier modulus of the sender has `bits` bits, one short of
+    /// [PAILLIER_KEY_SIZE](crate::PAILLIER_KEY_SIZE) and the smallest size collect accepts.
+    MarginalModulus { party_index: u16, bits: usize },
+    /// The messages of exactly `threshold + 1` distinct senders were combined: one more failure
+    /// would have aborted the refresh.
+    MinimalQuorum { received: usize, threshold: u16 },
+    /// Members of the current committee that sent no refresh message.
+    SilentParties { party_indices: Vec<u16> },
 }
@@ -0,0 +1,167 @@
+//! Export of the joint public key and address derivation.
+//!
+//! A refresh without a tweak never changes the joint public key, so every address derived from it must stay the
+//! same across epochs. [RefreshPublicData] captures the joint public key of a [LocalKey] and
+//! derives its SEC1 encodings and its Ethereum and Bitcoin addresses; comparing the data taken
+//! before and after a refresh with [RefreshPublicData::assert_unchanged] confirms nothing moved.
+//!
+//! This module is only available with the `export` feature.
+
+use crate::anchor::AnchorReceipt;
+use crate::error::{FsDkrError, FsDkrResult};
+use bech32::{ToBase32, Variant};
+use curv::elliptic::curves::{Curve, Point, Secp256k1};
+use multi_party_ecdsa::protocols::multi_party_ecdsa::gg_2020::state_machine::keygen::LocalKey;
+use ripemd160::Ripemd160;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use sha3::Keccak256;
+
+/// Bitcoin network an address is derived for.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BtcNetwork {
+    Bitcoin,
+    Testnet,
+    Regtest,
+}
+
+impl BtcNetwork {
+    fn hrp(self) -> &'static str {
+        match self {
+            BtcNetwork::Bitcoin => "bc",
+            BtcNetwork::Testnet => "tb",
+            BtcNetwork::Regtest => "bcrt",
+        }
+    }
+}
+
+/// Public data of a key that a refresh has to preserve.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(bound = "E: Curve")]
+pub struct RefreshPublicData<E: Curve> {
+    public_key: Point<E>,
+    /// `Δ·G` when the refresh moved the key by a tweak, see
+    /// [RefreshParams](crate::refresh_message::RefreshParams).
+    #[serde(default)]
+    tweak_point: Option<Point<E>>,
+    /// Receipt of the anchored transcript digest of the refresh, see [crate::anchor].
+    #[serde(default)]
+    anchor_receipt: Option<AnchorReceipt>,
+}
+
+impl<E: Curve> RefreshPublicData<E> {
+    pub fn from_local_key(local_key: &LocalKey<E>) -> Self {
+        RefreshPublicData {
+            public_key: local_key.y_sum_s.clone(),
+            tweak_point: None,
+            anchor_receipt: None,
+        }
+    }
+
+    /// Records that the refresh producing this key applied the tweak `tweak_point`.
+    pub fn with_tweak_point(mut self, tweak_point: Point<E>) -> Self {
+        self.tweak_point = Some(tweak_point);
+        self
+    }
+
+    pub fn tweak_point(&self) -> Option<&Point<E>> {
+        self.tweak_point.as_ref()
+    }
+
+    pub fn with_anchor_receipt(mut self, anchor_receipt: AnchorReceipt) -> Self {
+        self.anchor_receipt = Some(anchor_receipt);
+        self
+    }
+
+    pub fn anchor_receipt(&self) -> Option<&AnchorReceipt> {
+        self.anchor_receipt.as_ref()
+    }
+
+    /// The joint public key, which every refresh preserves unless it applies a tweak.
+    pub fn public_key(&self) -> &Point<E> {
+        &self.public_key
+    }
+
+    /// SEC1 encoding of the joint public key.
+    pub fn public_key_sec1(&self, compressed: bool) -> Vec<u8> {
+        self.public_key.to_bytes(compressed).to_vec()
+    }
+
+    /// Fails if the joint public key differs from the one in `old_public_data`, and therefore
+    /// any encoding or address derived from it. With a recorded tweak, the key is expected to
+    /// have moved by exactly the tweak.
+    pub fn assert_unchanged(&self, old_public_data: &Self) -> FsDkrResult<()> {
+        let expected = match &self.tweak_point {
+            Some(tweak_point) => &old_public_data.public_key + tweak_point,
+            None => old_public_data.public_key.clone(),
+        };
+        if self.public_key != expected {
+            return Err(FsDkrError::PublicKeyChanged);
+        }
+        Ok(())
+    }
+}
+
+impl RefreshPublicData<Secp256k1> {
+    /// EIP-55 checksummed Ethereum address of the joint public key.
+    pub fn eth_address(&self) -> String {
+        let uncompressed = self.public_key_sec1(false);
+        let hash = Keccak256::digest(&uncompressed[1..]);
+        let address: String = hash[12..].iter().map(|b| format!("{:02x}", b)).collect();
+
+        let checksum = Keccak256::digest(address.as_bytes());
+        let checksummed: String = address
+            .chars()
+            .enumerate()
+            .map(|(k, c)| {
+                let nibble = (checksum[k / 2] >> (4 * (1 - k % 2))) & 0x0f;
+                if nibble >= 8 {
+                    c.to_ascii_uppercase()
+                } else {
+                    c
+                }
+            })
+            .collect();
+        format!("0x{}", checksummed)
+    }
+
+    /// Native segwit (P2WPKH) address of the joint public key.
+    pub fn btc_p2wpkh_address(&self, network: BtcNetwork) -> String {
+        let hash160 = Ripemd160::digest(&Sha256::digest(&self.public_key_sec1(true)));
+        let mut data = vec![bech32::u5::try_from_u8(0).unwrap()];
+        data.extend(hash160.to_base32());
+        bech32::encode(network.hrp(), data, Variant::Bech32).unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // the public key of the secret key 1
+    fn generator_public_data() -> RefreshPublicData<Secp256k1> {
+        RefreshPublicData {
+            public_key: Point::<Secp256k1>::generator().to_point(),
+            tweak_point: None,
+            anchor_receipt: None,
+        }
+    }
+
+    #[test]
+    fn test_known_vectors() {
+        let public_data = generator_public_data();
+        assert_eq!(
+            public_data.public_key_sec1(true)[..3].to_vec(),
+            vec![0x02, 0x79, 0xbe]
+        );
+        assert_eq!(public_data.public_key_sec1(false).len(), 65);
+        assert_eq!(
+            public_data.eth_address(),
+            "0x7E5F4552091A69125d5DfCb7b8C2659029395Bdf"
+        );
+        assert_eq!(
+            public_data.btc_p2wpkh_address(BtcNetwork::Bitcoin),
+            "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4"
+        );
+    }
+}
@@ -0,0 +1,285 @@
+//! Fast, insecure [LocalKey] fixtures for tests and load generation.
+//!
+//! A real GG20 keygen spends most of its time generating Paillier and `N_tilde` primes.
+//! [fixture_committee] skips keygen: it derives the shares and the dlog statements from a seed
+//! and builds every modulus from a fixed pool of primes published in this file, so anyone can
+//! decrypt the ciphertexts sent to a fixture key. The moduli keep [crate::PAILLIER_KEY_SIZE]
+//! bits, since distribute and collect refuse smaller keys. [is_fixture_key] recognises the
+//! keys by their moduli.
+//!
+//! This module is only available with the `fast-insecure-fixtures` feature, which must never be
+//! enabled in a production build.
+
+use curv::arithmetic::{Converter, Modulo, One, Zero};
+use curv::cryptographic_primitives::hashing::{Digest, DigestExt};
+use curv::cryptographic_primitives::secret_sharing::feldman_vss::{
+    ShamirSecretSharing, VerifiableSS,
+};
+use curv::elliptic::curves::{Curve, Point, Scalar};
+use curv::BigInt;
+use multi_party_ecdsa::protocols::multi_party_ecdsa::gg_2020::party_i::SharedKeys;
+use multi_party_ecdsa::protocols::multi_party_ecdsa::gg_2020::state_machine::keygen::LocalKey;
+use paillier::{DecryptionKey, EncryptionKey};
+use sha2::Sha256;
+use zk_paillier::zkproofs::DLogStatement;
+
+/// Every party uses four primes of the pool, two for its Paillier key and two for `N_tilde`.
+const PRIMES_PER_PARTY: usize = 4;
+
+/// Largest committee [fixture_committee] can deal.
+pub const MAX_FIXTURE_PARTIES: u16 = (FIXTURE_PRIMES.len() / PRIMES_PER_PARTY) as u16;
+
+/// Deals a committee of `n` parties with threshold `t`. The same seed always gives the same
+/// keys.
+///
+/// # Panics
+///
+/// If `n` exceeds [MAX_FIXTURE_PARTIES] or `t` is not below `n`.
+pub fn fixture_committee<E: Curve>(t: u16, n: u16, seed: u64) -> Vec<LocalKey<E>> {
+    assert!(
+        n <= MAX_FIXTURE_PARTIES,
+        "fixture committees have at most {} parties",
+        MAX_FIXTURE_PARTIES
+    );
+    assert!(t < n, "the threshold has to be below the number of parties");
+
+    let derive = |label: &str, k: usize| {
+        Sha256::new()
+            .chain_bigint(&BigInt::from_bytes(label.as_bytes()))
+            .chain_bigint(&BigInt::from_bytes(&seed.to_be_bytes()))
+            .chain_bigint(&BigInt::from(k as u64))
+            .result_bigint()
+    };
+
+    // f(x) = a_0 + a_1 x + ... + a_t x^t
+    let coefficients: Vec<Scalar<E>> = (0..=t as usize)
+        .map(|k| Scalar::<E>::from_bigint(&derive("coefficient", k)))
+        .collect();
+    let vss_scheme = VerifiableSS {
+        parameters: ShamirSecretSharing {
+            threshold: t,
+            share_count: n,
+        },
+        commitments: coefficients
+            .iter()
+            .map(|a| Point::<E>::generator() * a)
+            .collect(),
+    };
+    let shares: Vec<Scalar<E>> = (1..=n)
+        .map(|i| {
+            let x = Scalar::<E>::from(i);
+            coefficients
+                .iter()
+                .rev()
+                .fold(Scalar::<E>::zero(), |acc, a| acc * &x + a)
+        })
+        .collect();
+    let y = Point::<E>::generator() * &coefficients[0];
+    let pk_vec: Vec<Point<E>> = shares
+        .iter()
+        .map(|share| Point::<E>::generator() * share)
+        .collect();
+
+    // rotate the pool by the seed, so different seeds use different moduli
+    let pool = prime_pool();
+    let offset = (seed as usize % MAX_FIXTURE_PARTIES as usize) * PRIMES_PER_PARTY;
+    let prime = |k: usize| pool[(offset + k) % pool.len()].clone();
+
+    let paillier_dks: Vec<DecryptionKey> = (0..n as usize)
+        .map(|k| DecryptionKey {
+            p: prime(PRIMES_PER_PARTY * k),
+            q: prime(PRIMES_PER_PARTY * k + 1),
+        })
+        .collect();
+    let paillier_key_vec: Vec<EncryptionKey> = paillier_dks
+        .iter()
+        .map(|dk| {
+            let n = &dk.p * &dk.q;
+            EncryptionKey { nn: &n * &n, n }
+        })
+        .collect();
+    let h1_h2_n_tilde_vec: Vec<DLogStatement> = (0..n as usize)
+        .map(|k| {
+            let p = prime(PRIMES_PER_PARTY * k + 2);
+            let q = prime(PRIMES_PER_PARTY * k + 3);
+            let n_tilde = &p * &q;
+            let one = BigInt::one();
+            let phi = (&p - &one) * (&q - &one);
+            let r = derive("h1", k);
+            let h1 = BigInt::mod_mul(&r, &r, &n_tilde);
+            let xhi = BigInt::modulus(&derive("xhi", k), &phi);
+            DLogStatement {
+                g: h1.clone(),
+                ni: BigInt::mod_pow(&h1, &xhi, &n_tilde),
+                N: n_tilde,
+            }
+        })
+        .collect();
+
+    paillier_dks
+        .into_iter()
+        .enumerate()
+        .map(|(k, paillier_dk)| LocalKey {
+            paillier_dk,
+            pk_vec: pk_vec.clone(),
+            keys_linear: SharedKeys {
+                y: y.clone(),
+                x_i: shares[k].clone(),
+            },
+            paillier_key_vec: paillier_key_vec.clone(),
+            y_sum_s: y.clone(),
+            h1_h2_n_tilde_vec: h1_h2_n_tilde_vec.clone(),
+            vss_scheme: vss_scheme.clone(),
+            i: k as u16 + 1,
+            t,
+            n,
+        })
+        .collect()
+}
+
+/// Whether any Paillier key of `local_key` was built from the fixture pool. Services that
+/// might be handed a fixture key by mistake can refuse it with this check.
+pub fn is_fixture_key<E: Curve>(local_key: &LocalKey<E>) -> bool {
+    let pool = prime_pool();
+    local_key
+        .paillier_key_vec
+        .iter()
+        .any(|ek| pool.iter().any(|p| BigInt::modulus(&ek.n, p).is_zero()))
+}
+
+fn prime_pool() -> Vec<BigInt> {
+    FIXTURE_PRIMES
+        .iter()
+        .map(|p| BigInt::from_hex(p).unwrap())
+        .collect()
+}
+
+/// Public 1024-bit primes. Never use them for anything but fixtures.
+const FIXTURE_PRIMES: [&str; 96] = [
+    "ca338d0d479aaf725433fdcc1a7995c49fd862b77368197603001a909f4cfd69b90d81151e43b4a3b854a6dc72445069ff0c6b1aa219741a027cfe606db83e36d773d199b53221aa9e3db8e87d01c157d53e0d9eab25806f029e87aa73ace9c5d163b386af6c4a56ffbcadc1d489cd462d954d4744bcb00ff623678d1242f873",
+    "c16612f7ffbc879a877bb77efc867d68c62a2519456d68676b6b08cd0a9872fa5a5f2cc04dd34919c7e9c4cfadec3b7a72dfa10f1017a9e8de46eed171ab8b237fd12e782fff9bc0260af602fc7db01a5752bfb507e3940a4923c7aba7d8fa5e37d2bef16a2895fb5163b4f1ddfe0576cb2f954f1c1f69d1050bbb387eb7a137",
+    "fb670c12e3c1eb585750e1c1c7325c9df121d0c7cb1ce08a1b2c39c00f71f3f5e4b9da2b5fc5e2607d906a963ae5b98396cce7945118ff3f63f11a93d9a2e5b62530e7e50093a4e694a5556fb33696510d3c909ad00d1a6f462ad42e7807a98ab54a905de79d593580a3b284e14d775d1237f5ee8dd986fb100b275f986f33c1",
+    "ebaceb38b2786101f812fb012d39898d17350e0fb569f71a61dacc261e29ab01d1799fd26ff9d104ad2a645961b549a62c3525d3f38fe8759504b22b065ef8577eba017fe247ea1a378d30f40c84e500c6cf97b1296eb4f5d2774e6e52225f73db41bf6b5b7b06412bff3acf53760a57fc1273d4cbb60f2b50979dedd0043209",
+    "d391035c9d154a803520554e9d60538aa0a254ea5b7638255610e8717ad81f1d0f621c30266eff45c664e01d718ef067dfa9509dcbe6bda07b1e22de068ec55ea58a71c254ac6d80cd65a6bf6e05144d938999a285cf499780f93a5c36b8d801415b5a5cdd5b17e63dca3812e4179a3afb1a58e5023406b8c5e28dfc1e918b45",
+    "f8c85eab91510ed015ac01650206def1c0251e61a70b9bbe9d41eeabbbf805748e0782db2c02b67552dcaceaf9ad97e09a1aecceff34070c4529bb1a10d6d8571e0b9a2d4686d2a00166d8f84673b0ec2abf50f209ba844ac1fe4c7c30e43e6f0e3eb7d52874f6bd34ffa1294c67c21cb7958a04716517b14d22c751911d516d",
+    "d596ff1bf0c9faa22979ae233647af58d78ff5fb30301d8943feb7a1369f3f18c8607ce5cb81e6650859e67ae4ecba9800eb1694e107531fd22b30cd5b9af0f93dd359fcdbb9cdedcf6241d4333c4e6a8c82bc9541d314f0139f8f6f4b901b1b05b66b47a0ead1437a51a59ea79e5b6a43c0c5f5041334b189414c62d2b2ba1b",
+    "d638905aefd46fd05498d9b2a274dd3b1f9cb91d4b664f7996c8333136d9b230da16eb846ccb9e822c7631d397d1438185d2221acc3de3594d471135be29ff0fd4d19a50b45b53650bdb2fa4b946570a0c13fdec33d2c6de96852b17dd441b92b070503d81323919d0bc9bc112720674a8a61bb395efd79027bd7fcb4cbcbe23",
+    "c30215d4096a96ed5803b3a8804907d862fa48a0842f55dc318a18e4da61f458e7300e5e074d39e21162f120a5306ee74b1dbe080017b0d7eaf0209b204fac496f48a9c783afff56ab04a7657dbbcaa086037e94b024dae1d1075e798b216bcda19bd60b586035c94ca63b6cff8e89d46569b00a9d75fc64671db7fa63b10ecd",
+    "c3b9e0e6867ec1a6a2fd86c1653f10ea45934a7a397a222c1c76629b5c6fe1a049ccfd614818c95449b239ce2c56abe567e6acbc720c9c21c613e473956e035eec27d9a5324ad1e9d964f6e414e4f91d59b3bc54f0734ba4ff256a9ef8c45048fb8b3ab96d32d80b54a30109ac584c9f5d8ad01c7efdce3febe4082d2b6d980d",
+    "cfe1d11549cf664c38ddd2831cac02829d9b3c7fbd8eb2138d46402e25f11945ca2b3eb83aff61c129f7e83361ca37fd682fcb4526a91bbdec7fca027451106f5a6cd0ca324667ddc8a75e541d56095bb216305f041a18b17bc555af65976c161597c4a1244f8175fca6c6d467805e8d4fd05c68c7439b84e159d7ad60eefc67",
+    "f4f94b96b023f99481ae5749e233613fcefc11783522d0a295182f02787c99a4f83ffdabd693ec0c38963f1781b7b391fe02f52d68e77cc78226c02260245053d0ef1646d0e757850da3a84fe1c7c65d3952b0da3dd6aeda0a26a0e906fc3d1a4fbf3cc695a19ec3fdafd6cf337f1f960f3ca9424df07cebc716423cdec40f8f",
+    "c1611858f57e1df78d0fa7944b782ec37d9a14f44094e93d8ce30fb6aeb2806bf3416b9ae26b74ffa57bc0fce2a033dc087035b42ec69e7a5fb425f725e2d7b18ae1458cd71d5c9491cb3b5843a45676d457d4575444d0246acf5a24757a1d4061e8f97c4052746bcfd50954aac9239e83e1cb708f96d2dfd828ce4ef82772ff",
+    "d768c174f4368ba826d7515d79b69b14483efed7bf1c3033dfdc6252155604d7307d8820e56fedc2fcaadc6d2aaf005f599c04cece951ac58b96af679728cc0a592c6a79ee2f47b5a51bb95f865900edc8e75ad805558500a1d2a4cc762ba4ba126bb25becbf3faf094134b85ae33ce87d2e2fb37cca217676a653132a439759",
+    "f7becc1a9b2b66e121fc98103fb2655967e5b03cab518f591b052c5bed965f820cbe4adc4e0070f94fe166531a2d1896f734912ec39412f0b952cab13e286e378011d0ac5aecad22e40167bc5b0feaf33cfd13b7cc44be98eb9de44ef2b7159f1665f66c569ee949a1b6369a63b78e5e5bea4228e79f1d156f667991bbacb633",
+    "c870ce12fdb2d21b49186664b751c2d8ca4a0920a04a5a64843bb4761a0bda090b21a5318fd78ed04839cc807efdce9afa191d561efb00b08dd66113571e1e960534800a1356fba02f3f2532da50fb682445938f54d7b83925ef20286ba7628b07ccfc5ef5d0e60a5267f592a3dc0b56b1c5045647a920c5eceff49360da5f55",
+    "fce9f2c38bc62e4e06d0822133b199fdec1809bfff91228bd5ce18d9d28ebd367b5860ae500c120734f0154431725f876aeb7c36dfbd78d06cc8fb3a844898b343f55c29a6d849df4d722eb33c234966a7f67d04b78d366a135be9c6f462b51f5a500a97cd3fd10cc4ec08dd70c320db110da94195f82fe8f32777521e0b1cf5",
+    "e516a8b9ed8691396f754c9a6c237b55ca29a5b993d1bb3a0f5ca88f94155d292eaad7944c9ad5d9305383ba892be72f18755ef78b39b69cb55a4aee692bccd625898dc12ae82f23e0de41261408de78c6c9e44e23d8f65fd6f8c4091e9d5e2b06b43bd2e710806c133d849670a1d8f42a602c659d4b5a7c9fd850cf69f49bdb",
+    "e9ca762b7457ba5d7451796bb445ba7aad28af795d76dbf4511ae97b4640c4f90bbf1863b3ca0dc0a71f92363b8b87c09c0467eef369c4cc5a1a477075fa13cfa756a135217cb42102f14c2179f3edb22d6f14f748144c5636cf7606d00339f98410869a5b41d8f53830db9c32f7333b0be07f37cbd984bb3bb3995d3190f4c3",
+    "c78ea5676812e38166261521392a4ac01e355a0bbfb014f6b94a40ceadbd66908bceb7718d3b2e542be195650c74f1bfc0b1d8245c311db91c4e0518f5ec7824130e3609383d34bfa5642a221585831c994c0b7503614ec3c4ac5ac882f9adda810ebd383f16d6010084dc43ea668bea466f7d1061779bee42deba5e6d3c4a83",
+    "c2b537843cf1d87d3b70570d2d355cd54fdddc68e6a1203df531d52813c3e87161ff668f1db136f78df2bb0389711ada4c8b4a2f2c52648325107f847710cec94348900f37c8af41e13feeca00b5e9200343c5983ad61624f9bb26abd4bc60a46cd0045354eeb726ea25fa090204cc42a47bfa4df326dc25bc4ba6e51dde77c1",
+    "ee652ed3d7793daae56d6ce813ba9580e5b3de4e1d22fba4bff8e40290fddd272aab4bc52e8a3adb9168d85f73f1a1d4dae0bfca45eb08d416be4bb195b8ff95a1b2f829b78347d5e5d469d1b59908397ab47ceb87dbacd645bd046bded0fe2efcfe08998264a6ed939d3e361dafdd930ac8f009ed5bfb80a9cc0d5d7879c7e3",
+    "d61106d0aadd4a02685f77afb431d3b86b32035ebc2c998332cc68e8b9c34ccc62a2679ddad72a55b7d066fbc66df7eb835360916b0d52c1ea8b41d4e2678c112e3be66084d19a1beb0df63bd3021843469acfba2409df7ba8162eca9cf576429147ab8ab8cb606fe5c6bbc66ca053fbd730e2cc4785d70ee632397b2ea07eb1",
+    "dab9ca3a0285cd41f5271318c53df8df326dde7e51c377d7f57af0d4ca2fde84dcfa8723516acfe0b9b9d6eec5c5c387b9a5db9f2564baade39f4d9a2387d3bb58cea35a89cdf1ace6c4607e9876088ed4271e88e470a5f4141ca2e6124166c4f076035e98ffe73c0de00857868a544bdfe3c2455ae1fcbbb176e1d7cd6e31d3",
+    "eba3b391cca750ef76abe12ce50b1ada0cb082ad20cce78c66a91f876df04a31f7d564a86de0d361850c13f38f9776b1602bbbbfd1d9c084f22ac4bcedccb23bd8b0613d02b6fbc49b0c458b04c0a6734cc67a695b5d974211487e550eb0b4d9aa73657c9a973da78b71dd5492b939f5bd28852cf1c92d3a41c12b5c13c1fa33",
+    "de2a4fb3aecc0ebb62ab858fa324005feb007c8766e692e17cec074a0fd344f2940344e173f479201f53b2a3babff764b8a16d8db01972a493426bb363ec47c1fcac5fbeeed6710e8563c4c80fd3b6d74b5909b298723c87f86dc979d848a00de741b8c31fc1a747b84290d58eeadb8e8727b40c45e53d663bf5bb9bb079f33d",
+    "ca116a4af78bc02cbd3ab827c342dfe5c07e8761339973c3cc200bdee13a7362bb2a64d497229e5c2edd5d852faf13d7f683f24a0d8f85a1fee2a6176d0ecb454abaa4231fad04f3537e085b0d35cd298176befbbf2f24a014a8f28e4ffc0807e5211c297ab101e8df1432e62328cb2f7a5cc06ab91e6be3ced40579f2d3520b",
+    "dfff16281085638a996f99c7947ef1854990d21cb84a0f0d7798691675f39c5a7c327fa259940c0b8f56fe10b4b765717511fcb574422ffdbe7deee1d5a254635d2eaa8afb7f070fea4080ffad89cea1138731c822f83ce3b2b2302fa0734c7722fe157ffbaeb1ceb1c44de01f7c96d6845c3e3de36d99c74db885ea2667b71f",
+    "e20713dbf22f7d25b64d1bf8512fbb51b65dc3024ee7e8fa644fdb30ccc21fd495f402aa6b3fd6c2279425be2c20c66d20857ad03b8a024bdc2ef8044d0cfc9fd68bbec0a7a00c54a702aea78e649165af77282f13885d2bc7c4d150d8ed770a6ab28627fd7ffd7f2d8c219995f7b85a9039398db3875160dbe030d6fbc3b17d",
+    "ce96f9f5447cf890386570645cf15243a49df2bb95c82f0fefad2d6d347fe81d73d83d4189344e332edc3ebd759b05910292d96f9faa4d1d14b8e056809b5a0ff5b72554de30d49b148b1cfcc8eb8828092c471f13a6a6e954ed567d0122bc7f9592ce4f8cb91993984319765af106c4f7072f369f7751ee18a36ed81643b6c1",
+    "df3de6e398be5bc8d143d199766f697d77798ede310ae203c724a7ff2f6308c0ab192c176a5c4b842f003106217aff6aadefda837b9be7d5acbf0b59d97f470ac20357720808d741cae85d3cbf83290495bdd4259c6fe246107d6628b8227d33d6348aa8f881a32971585fcc34b13a43de747ae4125e58d10e87a793c5ceb8cd",
+    "cef42ce99da4f835f4bf51aa8a86b832874f33bbbe23f91293faa3b880aaab85c120e68a2b3d3a8269ab26e2f057ca9279a5fc5dded6429eb549668dbb77761a7c5afd8d655a275c070a4fc475b6e5291fe0eb6e7f114dddec21bf5862bd3439103228526e75d3afd811b93221617279bc0dd28a7132d70b997cfa8dc8aa18d5",
+    "f80ac505238f6c1cc2c62682ce8486e04c3ba6d14045431fc08b305dc52befe23615fc61557c45aa388545d0875918c782b0f6b3474c78cf861a93b1be84f21a9c8c1e6fb8e0938871da4f99360b1bb21097a5b4218e568971aed4aaebddb54a40b4911ac1b3c0c54e2d1311a30ad22392a5ec4e7c2ccd696db75599e2f660a5",
+    "ea3f7f343e1bd072c2ae1852fa4064cca5d83a5d4df21627989a675d8308410288873c1be47a9b1b84c88f188dd99ceac0a38a462de3f3af4b515ba032d128f8568eadd76805d3bf01e52b65f54b17cabd5f50b3d0917fb04a9d48ea84566d26cddb69cc4265c3769148c6b00e8905b1f5433f87b08131b6d3c3e274c0284379",
+    "e7f43ec4bbeeb2a928341cb43a9d04d44329d74358eea27f01cd522b615ee74b9482c70855b675052adb307eed04d128be60dcd8b38437701959824f47823365ba70cb2674d3d74a22bfb93f42007d8cca2e74f0dc7e91f6dd972e6ccb5c5c36fa89da62bddaa15661ebde7dc8ddad57529d7abaf55de52d149a445fef057945",
+    "c86da15525e275a424176dbbca508e29ef59284d912fc84067c1d80184fc60a2c8fc3e8f6c66edb5144a0896d9bdd0f85b2c487c7a17ee17b5de1e657a57651750cce682142ba74c99ca83117a963a63f041ae370bdc3275c2427550c137507fed1504c9ce684cb98c7bfc86d9777c5d021a871db131be09b53aa4c5e845781d",
+    "ecd1c13ecc749b1b9a1d14471c9f467d77e62ebd77d50825b11f321dfbcd0d180c6f719168ca48a36a8b4cc6d666b34d9801b7ae234783bc7c9e04e532857dca4cdf4111621c1bf69f50b3961c7193b5351b7f5a028c5b59068a18d2a13634709c271dfd79c983915d0d2f9b5b8ece923d95a10811501864f6cb3847ca05966f",
+    "e9cd7c3f2167fb7afd9051237f0c51bfdcf89b0a3a0b10aac5eb4826d07ca46da5c6f4eca874d9966eb1b343dfa907a5821d5f7fd121d7764cc7f8b204c1d9608f5de7d57e0c3d7b56d928ce86338f924a05b19b02942b7628901070f8c36f4987d4c4ca98aa608eba5aca98e1f481a3f3e42082cf4d347d787dfbcc71f7f383",
+    "c162ca725e2d21cfdb10629f28cd94ffa0752aaaae533e25e8ccf105a36859ab80ea2b0752a50371947ea51629a5674a7d99c1d1ccdcaeb9810e7b2967b50effee79b6a4656b3e05e9c44ebfa5088f25c3f15751ccf47979cf9c4224958fac563bc862a843e1f81a89cc4422dfa68a3b80047556930b41ae1fa3bb758e86e84f",
+    "d22e6e851dc9ca40a8dfed95e5df6b841c854021251b26136f7fefbea70694597e14e9d97dfe471dc2199764e105d005350c841cb433f38c21983c0dad391445362a3095090e6488c611217788325e08a04436e007ddab29c697542e274d9d81a4767414831e6bda42d85f750de8fbcd803497f1c362583fed34edb058364853",
+    "df1e79718846f977810c6ad80273bda0b6829a2542951172c34e4c3c684f619b46dc4ca688bdd7bea2802e35a6feb1866f75fddcc74bde5fea4e67ae5a2209cb3758807c860a789d5e1548d87def68428f21083d4fcadcc08e4d49f07b9c9ed97bf02179814c8289387d38f0e27fa53fcdb827a5858e2c88a32f953b4a94c8f9",
+    "f9d86c8691acd4e036a976158e5065d60a29175e0162da497a9ec5f8b92a076e0a9174365d4404805fd9238ee9ce5433e9ffb4cc8a3e4b0bdf71254d38861556427a8da467ec35d9f11b7198ecf7597424115ce60f78f55f054b42f11ce463b5811cd0c8e888e25580df9d0551dead9b8ec2e08341440f3c19fbbf79d96eb9b1",
+    "dd0f7b26f54472ab823b8bf39ba9eefd25ccf0add68e6112df6cf5009e7eb23a569b506bd6d8b510a22019e3ad6f947b6853397d8dbf65fd43f83fdf58fc6c756def3c870fcc6b5b91a92590a4606af6ec9bf6fea6c061121cf0db0b14a5991b1e03a2f036662a8ee073bc32eb03ac21aaba336026746ae2d6a769e4e64ea7d5",
+    "fb8f62a126f14ae1d43b7316d3fdcd138b6e052701f953eb2caa516929d669118984a94ee24a3662db95905f91825ce6fd52c5fbfe072f1e8fc9cf4d629ce84c130ad602ac5dfc21fbcda6912cae3a3ead8d2530a001cb9a71b49f393ecb7d9458809026c8017cd3081ea8e2217fb14ba6211152a4ed9d5bcdf4e83cc5d2c1a5",
+    "e075e8bccfe3c80d9afc44c99490302310222ad0d6cfc5ac79a0fc4e2430ef27a6cc49767fc6f730876b5ba2090cde1d545c2f4c242ad8b0813891d204c3c9b5369b1c79fe9997d90f06a006ff694de2e1deedd927e3c3af772ddab04536f1b96a94a170fce43c01ff27778f22b871af7748ede9bf2133955e15ea9cb69259f5",
+    "d53c02a1d45e87e5af358adb0e6231b4477df5741bfb2029e1cc5d07037a9db7e953ab21e6e909451849935eca2dbed68e72126860d5f1082b978aa5a0fc54222f078addfdd3fa26d102b034914cfa8e8d5afb5686f375c0b79f599cd2907d6ac7fd528ab079183a58f3dbd0cbd7d9914a16f3f58baed5ec025719252d252e4b",
+    "e1fa1b59540898dea3e59a179a654dbc30fd4146a15f89e478e2b22c1b129696b90326be44e904bd32b079c527804ea0fbd32af91d48a870a93db92c39fa2ea0fb1e28c9262298791918576588546d6e9e50e56dccb469861963fcc62b9d5d89a32cf156dccf74215fd542646a6308af804d8db465cbff2368a246e59fb4fced",
+    "ddfdf4bca5f3d96cdc2931e4f3bc22ec228d51d9c871fdb49337a6e62e88356c14675011b0ee762a73c02d5c05f5132dc7a4cdd56bdfcd23487184abecbc1d72955100c2d52d0b9957237be6cb6430dfcf7228f661731d4b7c2438754c0716d07183400605fdd92a2eb04bea1a5031c9b39eef25ff027ab8a7aac1fded4c736f",
+    "d864942aa3b277046b7e71e377bdcd1d53636b9c467125f17a38a2a2e7fc0e733698f345fde7408b29eb8cd2d768ed933f05df93c0edccfea62b3a44bf64db46a70703f3eebc50d1998a53623c1fccc94c60d22d42144cbce668dc86ae3433d1cc64f5767e3aead2b8c2cafb312a5c8aae07dd2c9d0cfd5f5e4eb79108a3e4e5",
+    "d901a3e6a6cdcabd568061a447f3598092ed8fb49dcd16cb3d2a0f7c0ca14f02f7943612b40eafdaa5bedc26d3cc8308432394f744e296d9cc40d42045df9e6e03a93fe2be8ea250adfaff881479eb8c6040f3d6e783f86afe9a0c5c54887d5ec7f875e519647d07d3a3e8bd3410ef7a7b4eaca7ca82d9f24afd1016809b40a7",
+    "c9bf687bfbbae925845f50e94c7dc80ea1c79b918f3372529b6a9fff54b0db43d545c3f695db16551d0e24abd4acab30cb013794beac70d7ce52b3697fc21b392b4088d32c1a236097a45305af56bef037ad1c32072a2b36a3e1468a41a7f8c8a3a33ee7b8eb3c8aa577ab1d2831605b885a6247379e055e44d6e5d56a6d9b9f",
+    "e40f91fec06bc788301e8985bb5f65da12a46842abd8614a6a14373a8d7fd8aa9471335ca5a2e2cb9efac6feb49b7ff2769e44ee75ff52b675a6939790809604da19ff6f9c893f1214a414340153679d35860dc183c4c18a4cea1024877d61e368bef77d6b77d824abdf829ad8ef97adc9dde24e958b331f02a34248d4d3ac25",
+    "dac089fa127a3f4b3dd85b503f2094d93ef060ed9e1ff3f40ab96a02fa841ad42ab1f2300c33ea49ea6084213ab7c4bbfd1b335d2b0d8d499de2cdb1754e8560603bbe29d4045019d6091da1cc205088011fb8a23ffdca5d7d6e9b8cf3280b3418bff233ad50f429e10e385ba3388d26c45bc02af38c93d5a2abb4fa012fd385",
+    "ded18e275cc5c75930136f5b66e0561ba38bffd983f12fad381c0112befa37184c7956a847c8767f4f9168e1250c0d59908ed9816952d85e9689be176344b7c0cf1831a0b335ab5869d4ea1a15488006c3a6a7f8e3e6817ddcb17450f2457b2b94048c60d8ce1663b3be14bdd0abb446d1fbf07e946c121ea5a348a286e86cbf",
+    "e66b2e8ca92033b6f6333c3c5965f0bc070a298ec861ffea4724b5f7fcd8be2294886c10ae13eeccdbb044fd156aa1b094d26d642dade119dab8b0e2843467d56462419c5cf781c2f042cc232fadac22e1e6420dc21343cc9fc5e9e9e2cc3106b672130354594a77edec83ba2bf0d8a856e264c638323e6ed0994446ede5488d",
+    "d2dfa234fd9ae05f34cfed7ccffa05a78899217e0f3771955a30498c62d374f5c8f3d15797519251f7fe803156dfc9060ddd8c1b0665b4d1263fa5157129fa9a1c72b35c6837b3df8b7b2bc3ab9aa2437154a195d485a2fa52ae03198ce27ca999f14990d387a3d6ec1acbee37d2f4d572d273bb6359e42d34cec8797d310ddb",
+    "c18077123a8e8b3f6a0f091e9cc017db05f65c6f6e2e612ee0e3059ef250cdeece42a18d8366b73053a36b471a598b878ff3e6d0c974c5124b8fdf5e2ef162826470c4acceb8936312ed1b073e8b0d00cd1532b4922f26354161e48a77679f075c3943d66bd096c8c9355f7685f13aabb8c8e520bca895d657c3f0c54eeb3891",
+    "f448fb38b3d82263aa43f3354210facb4b0f203cbf61cc5f585308d8e547b22e1cdfcd29c566cd0156fb18ec70927e4f94f6b8f8d021e7802b58ddb042f6730c9b39cde26492bbd86e08deb89724e35782ac79b11feb17afd6f4bf923cc561d11bd21d6e2a79aac81850acb361d2f0388a9564f1b7bd1610cd93eb9ab5ce0e1f",
+    "ce927b2ccc2d38fd795bf5a965f10f62411d83013aa0197f5960b2a8383a1bcebcf2cae60a625548690515bbd746009d488f7df5983b479f2a59cf45438263ecbcfdc8d2b3d9c61c08397101381be9b37de1739df5ee2353ff1a1f93fca76484f141fde301a6bc2a04641df1a0f8613377dbd096153ee05b67091e7096e00477",
+    "cafa8e1b968e75a8161f2b66652509b7fc6ac8777a4ec19952ef0ab16c2e5da8351ed48b2c7640be1eee13bc92cf98e92ee2b3472efe59a6dd2dc9d17b6351bd36027bebc6bb0a6a54a9ca41ccf1f94fbef03968461a3803d6a3624618d19ea4606b66ad497946131f2d4c6157119513f0d4de734a36ffc0e901a1715b4ed25f",
+    "d7ef3b7c1c01e50f38f6eda3df913ad24fe3a3be5cb5b28f0182c88df87a0711a6b6c6a4762450d2f934723952908c23d201a56fc02c42cb9f0dc234b2f6b9f795e01305fe5c214f8e93d47e5c82c932a59adfac5fee2d4d27c30f9d206bf0fb1d9e06fe2b3f4abf28d83d7d32fa822b3c0fb3aba0bcb9ac7c6b68d01ceba7dd",
+    "ed21fdbc72b5888fe3e83c7bbf4232aa12e53bf0574cf7287797c52816106585d8f6e324064112ce63cd5534911c63b0a9090ea11f328c170d1f3488692aca089497a41ec6b4b5219d6adbaea150c8847ff57d087b00419da97aa74a25a265e120fcac8b1e6e5f67d42fac2827283870b6ce6fb02e2c600ed9b631de7a55168b",
+    "f59bc52d1e43deb553dafb6aa158616fe21c403cf257b75171aead0e865011baea03863521c38485761cfc185e5345948b0084d64a8ba73d29d84d48b8bc45f3f898b39d9129625b6c7426ee5c6417080aa0be5ed247178e275b0bca07af32c695c601b091aa0c622a00b1ce4a8880f62c7bb6a4505fbb8b4ff453714d84847d",
+    "c6e71a73047ba0bdd0451cfd1717d3c602dcbfe54d1e5594973f9173cd659a3cb839b41ce1ab5e064070625898c48fe3227c8b629da879c7ddd3b2559c3f57c74d06fd93875cbea3710aaeae23bcd5804bee0d4ab68973e6fa7e0846e452e8fed063f3dc7baae4bcbfada9e16004a1ac858a4aba37885b6ed6654dda48cb4879",
+    "c14abcdf71326f6148d82d92f7086afaccbb7c2309760a2296942cd688aa453cc445fa5931988dcb6ac26beacc0a95dc69fc3e3ef66905dd0857e6ffd2b3f4e310e4bdf6ca9ed54d101d439d68be6e45a3a40cb9770cbbd88eae38d2d63ffa617f05172b24dc91509054080bdbac7c08e815fca01f8894c2e09fe2f6c5ce6e95",
+    "f25e6e93a73c3c9a80aa92fa132cbbecc0036ff4724878d74aa81bd71ae76e3e42288ba2eb89e60f07608f3a0afd32999191aab05f9bbb6b3d9179ead32faab18aeaec5cf6c5b562590e16564cb8b6e80b70bf6608479a5308bd34f2cf743040e7aa073895831321a559d08ae1b8f26703d88f9fd564cb14fe2651dc42f331af",
+    "df4ff317a7d59d0118a2722798958de06bc748119f60a7bdfde895a57665ac87d68cc1e05f6a6f46dde2e06939beaa8186fc6f17d1fece2f51c1f09ed5ff0adab761c415b5e1a3898f4e1ce8f40e48bc36cd42c411414155b9f539b69f6bd26dac04eb4555d2922eceae61fd575fee7c6160c102eb59177eadb1e8e6fe5383ff",
+    "dd156566a09d8186bd46d0b3e7c2f4a994247bbbbb9589adf7db856c129901098f1c22ad385908fe7eabcb5553ade1d66b44d41aa5f3ff60d007a0371e9b69e219495c0dd58b19e69284ff16876e035bcc5986322d7a29cdc51aa1b2cfe15ba31f5f5862fac30733fb2e45096c6782b58cf4cf0151d5a30b0478f2542dcdcbed",
+    "dd4f086b7bcff015ffc96791833e98204496cbeaaa2db4e1441bb8d972df9648633545055a4e3378929a63ea0ebd2e85f98fc21d1f7629365b5d6966af4c60444a6ee43744041fdbe29051143b6bb81d62dac5a03b41718ba7f68e918853b5b16625229561b96b445c023e62f1c864b1eb91fc0a2297799256b9d467ff80163d",
+    "c1130d21aee078679b14d75c6e2cf22703d5e09cdbd229d3503ee4ec4d96514d5e1c6cb21f41860aea52d4d06d6bc76d6a2c3f7a61eda0a6609585744c9a50aa4c4a203f28f678a8e9cde1618a229409a67095fff7ca26374a95eb9b9a9adacd2cfa28ae4ec57d38a4d550cda1893946e4ca95acdc7baaf2ff3980c9911594f7",
+    "d92cfda6c8f5bf1dbb67489064cbd2935412afefbe4c005784a72c5fe7e8e2b510bb520c806b51c5fbb45910e9f2d5226bf66f5da542f03083386661bf7428e5dd075141ab8885c1911fb7608c4cf15b36aebd660228128ee478a7c5853065db0d8133d2a1d795545b5fa95a11ddf6ce022d5c813442afe4b89f9a0681a45ae9",
+    "de243ad6964afc826d810abdf9e60537ec7527a4a81523e8cbf8b3158bc8e8c240041769a97eeed54a6d7ba0c5e46d50fd57e4852ebe825704bd66225475ddb0dce640fa083ce15d08a3d7160e8c000b69a77c178b2fe6131710fe122750612f96af66213431987d8dc176c1ccf40f68450c8df1ef4b4b46a5eff62f877b88b3",
+    "fee87592f7daa00e63a64257d63d297d83f451329c9567a610447c3fa4815247c941c6b5d01a3d6355ffa95565e394990f63de3d9cfcf6a60a73e7b68c00e7310261051748d30156ba9c519dea591b240eb20970bc01994756a43ca3b8ba23457d4b6ae8213694e30a25e3a39e88461e413f92e9fa31433913fa12ad4a53f561",
+    "fc942723a07502cf4d49f6509e3b737d21cd427fdc1d999fcb6639ef52b3f50782a9206cb56be090d1af7024295b0af2c42872ab67f7cb630904acaa0046228b35b895be2c080990beb7a6b983a351f597b167ac5e41bd02e98924a4e9fb5ffad1bed0b44f6296236ed74e545162e372a4717a00f736243db2c495e576ea2721",
+    "f1547705ae141d4e0841276f4ff34e6d32590cfe357d0af08806ab9d91b8460fa193113a1325954b5518e802f6513514fb8ff576dec26030bc37fe7b367f7648bfe8bc03a11cd464c8b74bb11c9e6fe26aba186f529bde00c03272ca09cef55f50a45b1600332124734966a8770764b05abf03eb6ecd183b170bf908dc54f54b",
+    "c6f673d3cf030f762a037d45523971836f45f4ed4516f278ab685352a75f4a89283cfbec7096db16215f9a8d8f7196389876e73f3910128e70022b2f10b2e7334c7844baf05b8aa5fe2af230e4f370f3f30c28eeaa8de670f65431f797ef6ddfc4b3df884f0ce00fa2de31df1c86f485e4057249163d7997625be54088d63d57",
+    "d6576be8585e51733b2335068c4b8f278b23cb3a008a42af7580b5f70cdc1a823290c39d457c7ca97868e279ffa1b88684df77bffd0fe3f2ebad8416090de90d0dd60e32f25e1e7d807d506e5c4da1713fb44c8232f1a5586ed0c3979c46595f5d05e7f1bb78d53b98dcc2f7a9aae0d8eaa75fc16cd6a022ae57ad7b61583cbb",
+    "c93aa90faba15c3410915bba74225d830105d16ee91a28344a00d269a41a03595f6231c33faddc2eeae704db4e9988c506c4929b6a144b6962a1c4f53479459162c12ed36312e82c9e93cbb551dd8c31bbfb58089b2bf319776dbc5aeefc70d87957a39e796dc6ebc194005cdf93f2ca86e784bc74aacf4abf72c2f1a80dc97b",
+    "c5d3efe61eccbcb0ac798d2a35de46d684ceced2d45c93157e1995dccce35271bb079fc6874b74a48c2feca9d2986de09f78d20515163b7a8afda8b8319892737f280633a15bc8250cebf2db8e6f34f8b4d9d163de7a3024bae0cc7068a005cfa724438414eb5f76401b944012a350c9195ced8e2fbb8221a67eda12464c5f3f",
+    "c05f9ce5c14e00fc2d1faa3f20a0f58dd7ec65782ed82e219c8e9445c6d7abf466d1d81d3680293dc5593926a1db76595c78a0d3c89cd3f05f569e974f27c9ae0a3e52b4a5d4e096a47208bfff080e3ac78294f17733863840aecdb55161be447fa41dff4d9dfcddcdd230b6a4d47b3c1296ca15eeaceffa7ae2a0bc5b70278b",
+    "d8614e5f238b16be29f3fb58d20908ad0906e9547e6c30e535691f6ddc283ddee8a7157f30a239213c7742cd294d870f71bf6d4456e0b3711175cda9f0925cbc7c2cc48011f22a90b85a86dab77755c1f80e0b248261ff697038077ba2367f0da4800e286d14c4376d5d44d5d8ac0bbfc9540467bb71da3f1c050ac5b0661c3b",
+    "fb437e11bf79cb94644e1235873b63fb09299cd3c68c0b188b87420708b281d9c2a8c1f3ef27f1cf9e273bda10c18b285eb0540d4903f6650cca6868722a765259b256f9a979540a018d6de24d787af4d6c7d4569437a3470f0b40abad9810f5356f5ffeccb5843280b849e56880848b5d17fe90146114b8d287a9cb3a145b35",
+    "f1432204845ca3efd80b84c91932a5b4c03af071f166642e33cb65d1810523fc4ab8a12c4c03d2f9efaed4cfd57badcafff4337f3f8d242849d04c765c1996f2fdd091f44fe73fe93d89ef71c9819fffaac28a8ef0650a6504ae3b94471dcae361a7455591c6c69c2ab9a0ba3ef8557ee81b05051ce0ea815a6182c6076d157d",
+    "e5dec248c6ed68e7718455b0f6d69bc6d90ef3b382b3bc727b29e49d703282aa7abded76f068fc3f457d38c3baf8a392c5c528bc4ceb12b90ef4ed1a1b031c10dcc41073ea6c13170575775c6e500333ae2f29a230cd22f3464a7982007e5df886df692fc1cac4949e5053ddb3d7d1915b3974ff287e95de409fe0c320611cfb",
+    "cf2780ef33c75248acd8e72ff3725c0bdb81f910ec4bd19eb295e883bfc0f0817eeb7c6e0bdb0b76a82b6e90499bf61c668e994c880af87f0ba114f6bccb7722abdb86b9c9fb4841d9bd16ee69c7eac29e3e58cb9e01ce60e709c80b145c71b6726c4b6d6f79c3895e0c8324d16e2fae40052a213034a93bec603da04c85b915",
+    "d79276bb9790a930db9e85d29a1d2cd3f0716cd8fd5ef84e1565429a9159d2e342211dd9482cec79f3a862b0eaceba221b08d8dad91cfa84d96dfe9e4f4118a027a713b9ea6e0bbd8963318d21f5a389c399c6d4c49f582d1641e292e7aad9e19c651905fa823ec7d1a6ddd31286b2304736cff6e77c0e4e25e68aa2b6cc398d",
+    "f20fb089b8df623117020dba5e7b3d8685395d5d15bff4e9b49947087b779cfc22c20329f931f23794a1de9169b6b12405a7d39a57bd514ac81f0f81d7ddf1794172c2adadc4127af74565dba9a02885e687f92fc3f132d44685b4305c5a7234e5d426bfae56299ec405e95dfa01030671a8c5f8a00b2735315247894c37389b",
+    "f3d8e921ec7c11c990e4ab803fae3444ba3c43c8a994a7a0f072a676716b91711d9742736ee84257826d2da698924796d1c0eb2736dd3af69e3b4a6c07a68ea19d0692a3bee2d4cb2cb9afede94adff794677769eb100bb1568ef0c01ab58323e574ef7b17dcc0f8cda63d13a903d7f65760b776e3fa5420b1d2e3155a0ebf09",
+    "fe4f31e7c9681995aee3b3f125a316a0776dc38251ea9094e1cecae9d52175efec7c79f4835be7e7656d11ddea734e3701acd0918e8402bda35eb231dc3660e9daee18e04e197988375dbf8d5eeb3c4f3d808105790771047ed72b0397ea234f58916adab8d36afb75546fc3ba4f579b30fe8ea469e9fe67caa3487d9ba5189f",
+    "ef2700b1a2b20c167229a01f36299f09d34d43a92571f6d56f044ab22372d6426f99fa4f6c8a8102fa9ad651d5bedb7df6a5e10d496a516e04c7400525e42daeeb056d990c550c2b001785476163dcb3d5627f206d0a5ac003412dcbd3533876b6a78f1ff79fab9b7e7bafa9195dbf30038b530c5ce9fe22840b185db089a6bd",
+    "d7ec22d55258f01c3fc17434762a66cd2e162aad6507b00da8bc5f1dab7e404def1f1887763d6f32aadd5cef82cbefe8d460712e9598912b1c687442ed0665cd0892dbad9a707ab27de96e1f6177b4084850a7da84944adfd19ad118b820e5f09783d83ef4d8de0605537934320b42f31f9a70b9ebcc45e340084656377268ff",
+    "f1fc99a75394e3ace40ccbd83dc3f0c2201552331ba02a5b4ec93360f2801a6b1b6806cb24a3403efda9d41777c9a11c0d7d8b485ceb867a815b3b12ba23d9adec4f9a7984bc3880ca1357baf6f5669d632e36cc7e8d25d48e7bd590d6f31b8846f00f1a4fcf7b49ddc2d4491b98f8dc2db9e40edca6226f10d754fded038bd9",
+    "ed8748894d7222aa3197ddcbcb13b7760c227d9b5a332d93e98c78175cb891c616f0ae720279a26c75b2ee2afc9324d6ed9804e9e34480db35dbf5725896ca6d33a7601a8853e1c9d855620d48800b94ef8cf2a51b53516ae959fc4eeb4589cb6ce56ad6e87a5900dd941c051cbd7188afeb3d8f5c15a847edb8ef6e31eae37d",
+    "fd7199ca5db4cabaf1477fd344ceaa194b4c8e2967cbef8bd63f1f1580cbb160a6672605e9d84c6a4e5cf421d6994cf7500e580cafe138fee519f5b69519d93442026b88d9b32083bad76a35cf8971a6909d43d9ccc7dc9e12148859e5b2fe00b7c68aea90636d2925fc5755ce619d2752c9930477aef8087ed60bd889c557c5",
+    "f4832977accb4630e9eb1c82eb270ddfb625b19b661f76ac11a2ca528ba4aa5bc2528d86d6ef8d3ed079113d1dee20c6e8abc887239b55b60308a6ae7ba7dcfe936111bd3af5b5bb68e518db9642d26d1dad8a0a7e060458ed02abc5594ac62b01a19a0f522c569a0204f3fd757bef539e8bad86b20a1ed3effad886c1b6d57f",
+    "dbf8fda0faa11e95561360ab775497e44669ffe72a260ee409a5bf4fd1aa6d07178a1d40c2909bb625bb147c07a6dc962dcd97f5f71750a96c73faa397ea1fa9f80bf160a74e790bb4d29d7559fca5e95df674af76ab0997be246695ea3b7160d69694ade26da3698578e2bbdb0eec25dbc04eed4dde5f679cb15b9417a5d249",
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::local_key::verify_local_key;
+    use crate::rehearsal::deal_committee;
+    use curv::arithmetic::BitManipulation;
+    use curv::elliptic::curves::Secp256k1;
+
+    #[test]
+    fn test_fixture_committee() {
+        let keys = fixture_committee::<Secp256k1>(2, 5, 7);
+        for key in keys.iter() {
+            verify_local_key(key).unwrap();
+            assert!(is_fixture_key(key));
+            assert_eq!(
+                key.paillier_key_vec[key.i as usize - 1].n.bit_length(),
+                crate::PAILLIER_KEY_SIZE
+            );
+        }
+
+        let again = fixture_committee::<Secp256k1>(2, 5, 7);
+        assert_eq!(again[0].keys_linear.x_i, keys[0].keys_linear.x_i);
+        let other_seed = fixture_committee::<Secp256k1>(2, 5, 8);
+        assert_ne!(other_seed[0].y_sum_s, keys[0].y_sum_s);
+        assert_ne!(other_seed[0].paillier_key_vec, keys[0].paillier_key_vec);
+
+        assert!(!is_fixture_key(&deal_committee::<Secp256k1>(1, 2)[0]));
+    }
+}
@@ -0,0 +1,93 @@
+//! Dense renumbering of a committee.
+//!
+//! After joins and removals the active parties of a committee can hold sparse indices, while the
+//! shares are evaluation points of the VSS polynomial, so the indices cannot simply be edited in
+//! a [LocalKey](multi_party_ecdsa::protocols::multi_party_ecdsa::gg_2020::state_machine::keygen::LocalKey).
+//! An [IndexRemap] is applied by a refresh instead (see
+//! [RefreshMessage::remap](crate::refresh_message::RefreshMessage::remap)): the senders reshare
+//! from their old evaluation points to the new ones.
+
+use crate::error::{FsDkrError, FsDkrResult};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+
+/// Bijection from the old indices of the active parties onto `1..=len`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IndexRemap {
+    old_to_new: BTreeMap<u16, u16>,
+}
+
+impl IndexRemap {
+    pub fn new(old_to_new: HashMap<u16, u16>) -> FsDkrResult<Self> {
+        let remap = IndexRemap {
+            old_to_new: old_to_new.into_iter().collect(),
+        };
+        remap.validate()?;
+        Ok(remap)
+    }
+
+    /// Maps the active parties, given by their old indices, onto `1..=len` in increasing order.
+    pub fn densify(old_indices: &[u16]) -> FsDkrResult<Self> {
+        let mut sorted = old_indices.to_vec();
+        sorted.sort_unstable();
+        Self::new(
+            sorted
+                .into_iter()
+                .enumerate()
+                .map(|(k, old)| (old, k as u16 + 1))
+                .collect(),
+        )
+    }
+
+    /// Checks the map is a bijection from nonzero old indices onto `1..=len`. A deserialized
+    /// map has to be validated again before use.
+    pub fn validate(&self) -> FsDkrResult<()> {
+        let mut new_indices: Vec<u16> = self.old_to_new.values().cloned().collect();
+        new_indices.sort_unstable();
+        let dense = new_indices
+            .iter()
+            .enumerate()
+            .all(|(k, new)| *new as usize == k + 1);
+        if self.old_to_new.is_empty() || self.old_to_new.contains_key(&0) || !dense {
+            return Err(FsDkrError::InvalidIndexRemap);
+        }
+        Ok(())
+    }
+
+    pub fn get(&self, old_index: u16) -> Option<u16> {
+        self.old_to_new.get(&old_index).cloned()
+    }
+
+    /// Number of parties of the renumbered committee.
+    pub fn len(&self) -> u16 {
+        self.old_to_new.len() as u16
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.old_to_new.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (u16, u16)> + '_ {
+        self.old_to_new.iter().map(|(old, new)| (*old, *new))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_remap_must_be_dense_bijection() {
+        let remap = IndexRemap::densify(&[9, 1, 4, 7]).unwrap();
+        assert_eq!(remap.get(1), Some(1));
+        assert_eq!(remap.get(9), Some(4));
+        assert_eq!(remap.len(), 4);
+
+        let not_injective: HashMap<u16, u16> = vec![(1, 1), (4, 1)].into_iter().collect();
+        assert!(IndexRemap::new(not_injective).is_err());
+        let not_dense: HashMap<u16, u16> = vec![(1, 1), (4, 3)].into_iter().collect();
+        assert!(IndexRemap::new(not_dense).is_err());
+        assert!(IndexRemap::densify(&[0, 1]).is_err());
+        assert!(IndexRemap::densify(&[]).is_err());
+    }
+}
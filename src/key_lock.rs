@@ -0,0 +1,213 @@
+//! Coordination of refreshes with in-flight signing sessions.
+//!
+//! A GG20 signing session has to run entirely on shares of one epoch: if a refresh is committed
+//! while a session is mid-round, some parties finish it with the old shares and others with the
+//! new ones. [KeyLock] owns a [RefreshableLocalKey]; a signing session takes a [SigningLease]
+//! for its whole duration, and [KeyLock::collect] only commits the refresh once no lease is
+//! held, either failing right away or waiting, see [CommitPolicy]. While a commit is waiting,
+//! new leases wait for it, so a busy signer can not starve the refresh. Leases taken after the
+//! commit get the new epoch.
+//!
+//! If a thread panics while holding the lock, e.g. in the middle of a commit, the state of the
+//! lock can not be trusted anymore and every later call fails with
+//! [FsDkrError::LockPoisoned].
+//!
+//! This only coordinates the threads of one process holding the same [KeyLock]. Coordinating
+//! the other parties of the committee, e.g. agreeing that no session is in flight anywhere, is
+//! up to the application.
+
+use crate::add_party_message::JoinMessage;
+use crate::error::{FsDkrError, FsDkrResult};
+use crate::local_key::erase_secrets;
+use crate::refresh_message::RefreshMessage;
+use crate::refreshable_key::{RefreshOutcome, RefreshableLocalKey};
+use curv::cryptographic_primitives::hashing::Digest;
+use curv::elliptic::curves::Curve;
+use curv::BigInt;
+use multi_party_ecdsa::protocols::multi_party_ecdsa::gg_2020::state_machine::keygen::LocalKey;
+use paillier::DecryptionKey;
+use std::sync::{Condvar, Mutex, MutexGuard, PoisonError};
+use std::time::{Duration, Instant};
+
+/// What [KeyLock::collect] does when signing leases are held.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CommitPolicy {
+    /// Fail with [FsDkrError::KeyInUse] without touching the key.
+    Fail,
+    /// Wait up to the given time for the leases to be released, then fail.
+    Wait(Duration),
+}
+
+struct LockState<E: Curve> {
+    key: RefreshableLocalKey<E>,
+    leases: usize,
+    committing: bool,
+}
+
+/// A [RefreshableLocalKey] shared between signing sessions and refreshes.
+pub struct KeyLock<E: Curve> {
+    state: Mutex<LockState<E>>,
+    changed: Condvar,
+}
+
+impl<E: Curve> KeyLock<E> {
+    pub fn new(key: RefreshableLocalKey<E>) -> Self {
+        KeyLock {
+            state: Mutex::new(LockState {
+                key,
+                leases: 0,
+                committing: false,
+            }),
+            changed: Condvar::new(),
+        }
+    }
+
+    fn lock(&self) -> FsDkrResult<MutexGuard<'_, LockState<E>>> {
+        self.state.lock().map_err(poisoned)
+    }
+
+    pub fn epoch(&self) -> FsDkrResult<u64> {
+        Ok(self.lock()?.key.epoch())
+    }
+
+    /// Number of signing leases currently held.
+    pub fn active_leases(&self) -> FsDkrResult<usize> {
+        Ok(self.lock()?.leases)
+    }
+
+    pub fn into_inner(self) -> FsDkrResult<RefreshableLocalKey<E>> {
+        Ok(self.state.into_inner().map_err(poisoned)?.key)
+    }
+
+    /// Takes a lease for a signing session, on the key of the current epoch. Waits while a
+    /// refresh is being committed.
+    pub fn lease(&self) -> FsDkrResult<SigningLease<'_, E>> {
+        let mut state = self.lock()?;
+        while state.committing {
+            state = self.changed.wait(state).map_err(poisoned)?;
+        }
+        state.leases += 1;
+        Ok(SigningLease {
+            lock: self,
+            epoch: state.key.epoch(),
+            local_key: state.key.inner().clone(),
+        })
+    }
+
+    /// Same as [RefreshableLocalKey::collect], committing only once no signing lease is held.
+    /// The refresh is verified and committed without releasing the lock, so no lease can be
+    /// taken in between.
+    pub fn collect<H: Digest + Clone, const M: usize>(
+        &self,
+        session_id: &BigInt,
+        refresh_messages: &[RefreshMessage<E, H, M>],
+        new_dk: DecryptionKey,
+        join_messages: &[JoinMessage<E, H, M>],
+        policy: CommitPolicy,
+    ) -> FsDkrResult<RefreshOutcome> {
+        let mut state = self.lock()?;
+        if state.leases > 0 {
+            let deadline = match policy {
+                CommitPolicy::Fail => {
+                    return Err(FsDkrError::KeyInUse {
+                        leases: state.leases,
+                    })
+                }
+                CommitPolicy::Wait(timeout) => Instant::now() + timeout,
+            };
+            state.committing = true;
+            while state.leases > 0 {
+                let now = Instant::now();
+                if now >= deadline {
+                    state.committing = false;
+                    self.changed.notify_all();
+                    return Err(FsDkrError::KeyInUse {
+                        leases: state.leases,
+                    });
+                }
+                state = self
+                    .changed
+                    .wait_timeout(state, deadline - now)
+                    .map_err(poisoned)?
+                    .0;
+            }
+        }
+
+        let result = state
+            .key
+            .collect(session_id, refresh_messages, new_dk, join_messages);
+        state.committing = false;
+        self.changed.notify_all();
+        result
+    }
+}
+
+fn poisoned<T>(_: PoisonError<T>) -> FsDkrError {
+    FsDkrError::LockPoisoned {
+        lock: "signing key",
+    }
+}
+
+/// A signing session's hold on a [KeyLock], released on drop.
+pub struct SigningLease<'a, E: Curve> {
+    lock: &'a KeyLock<E>,
+    epoch: u64,
+    local_key: LocalKey<E>,
+}
+
+impl<'a, E: Curve> SigningLease<'a, E> {
+    /// The epoch of the key the lease was taken on.
+    pub fn epoch(&self) -> u64 {
+        self.epoch
+    }
+
+    /// The key to sign with for the duration of the lease.
+    pub fn local_key(&self) -> &LocalKey<E> {
+        &self.local_key
+    }
+}
+
+impl<'a, E: Curve> Drop for SigningLease<'a, E> {
+    fn drop(&mut self) {
+        erase_secrets(&mut self.local_key);
+        // the lease count is still released on a poisoned lock, a waiting commit fails anyway
+        let mut state = self
+            .lock
+            .state
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        state.leases -= 1;
+        self.lock.changed.notify_all();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulation::keygen_then_refresh;
+    use curv::elliptic::curves::Secp256k1;
+    use sha2::Sha256;
+    use std::sync::Arc;
+    use std::thread;
+
+    #[test]
+    fn test_poisoned_lock_fails_closed() {
+        let keys =
+            keygen_then_refresh::<Secp256k1, Sha256, { crate::M_SECURITY }>(1, 2, 0).unwrap();
+        let lock = Arc::new(KeyLock::new(RefreshableLocalKey::new(keys[0].clone())));
+
+        // a signer panicking while it holds the lock
+        let signer = {
+            let lock = Arc::clone(&lock);
+            thread::spawn(move || {
+                let _lease = lock.lease().unwrap();
+                let _state = lock.state.lock().unwrap();
+                panic!("signing session crashed");
+            })
+        };
+        assert!(signer.join().is_err());
+
+        assert!(matches!(lock.lease(), Err(FsDkrError::LockPoisoned { .. })));
+        assert!(matches!(lock.epoch(), Err(FsDkrError::LockPoisoned { .. })));
+    }
+}
@@ -0,0 +1,159 @@
+//! The refresh as a `round_based` state machine.
+//!
+//! [KeyRefresh] runs a refresh of the whole committee the way the GG20 `Keygen` runs key
+//! generation, so it can be driven by any `round_based` transport, or by
+//! [Simulation](round_based::dev::Simulation) in tests. The protocol has a single round: every
+//! party broadcasts the message of [RefreshMessage::distribute] and, once it holds the message
+//! of every party, outputs the key of [RefreshMessage::collect]. A message that is not a
+//! broadcast, comes from another party than it claims or repeats a sender is rejected without
+//! aborting the protocol; a failed collect is critical.
+
+use crate::error::{FsDkrError, FsDkrResult};
+use crate::refresh_message::RefreshMessage;
+use curv::cryptographic_primitives::hashing::Digest;
+use curv::elliptic::curves::Curve;
+use multi_party_ecdsa::protocols::multi_party_ecdsa::gg_2020::state_machine::keygen::LocalKey;
+use paillier::DecryptionKey;
+use round_based::{IsCritical, Msg, StateMachine};
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+impl IsCritical for FsDkrError {
+    /// Only the errors about a single incoming message can be recovered from, by dropping it.
+    fn is_critical(&self) -> bool {
+        !matches!(
+            self,
+            FsDkrError::MalformedMessage { .. } | FsDkrError::DuplicatedRefreshMessage { .. }
+        )
+    }
+}
+
+#[derive(Debug)]
+enum Round<E: Curve> {
+    Collecting(DecryptionKey),
+    Failed,
+    Finished(Box<LocalKey<E>>),
+    Gone,
+}
+
+/// A party of a refresh of its whole committee, see the [module docs](self).
+#[derive(Debug)]
+pub struct KeyRefresh<E: Curve, H: Digest + Clone, const M: usize> {
+    local_key: LocalKey<E>,
+    round: Round<E>,
+    received: BTreeMap<u16, RefreshMessage<E, H, M>>,
+    msgs_queue: Vec<Msg<RefreshMessage<E, H, M>>>,
+}
+
+impl<E: Curve, H: Digest + Clone, const M: usize> KeyRefresh<E, H, M> {
+    /// Distributes the refresh of `local_key` and queues the message to broadcast.
+    pub fn new(local_key: LocalKey<E>) -> FsDkrResult<Self> {
+        let (message, new_dk) = RefreshMessage::distribute(local_key.i, &local_key, local_key.n)?;
+        let mut received = BTreeMap::new();
+        received.insert(local_key.i, message.clone());
+        Ok(KeyRefresh {
+            msgs_queue: vec![Msg {
+                sender: local_key.i,
+                receiver: None,
+                body: message,
+            }],
+            local_key,
+            round: Round::Collecting(new_dk),
+            received,
+        })
+    }
+}
+
+impl<E: Curve, H: Digest + Clone, const M: usize> StateMachine for KeyRefresh<E, H, M> {
+    type MessageBody = RefreshMessage<E, H, M>;
+    type Err = FsDkrError;
+    type Output = LocalKey<E>;
+
+    fn handle_incoming(&mut self, msg: Msg<Self::MessageBody>) -> FsDkrResult<()> {
+        let malformed = |reason: &str| FsDkrError::MalformedMessage {
+            party_index: msg.sender,
+            reason: reason.to_string(),
+        };
+        if !matches!(self.round, Round::Collecting(_)) {
+            return Err(malformed("received after the refresh round"));
+        }
+        if msg.receiver.is_some() {
+            return Err(malformed("not broadcast"));
+        }
+        if msg.sender == 0 || msg.sender > self.local_key.n || msg.body.party_index != msg.sender {
+            return Err(malformed("sent by another party than it claims"));
+        }
+        if self.received.contains_key(&msg.sender) {
+            return Err(FsDkrError::DuplicatedRefreshMessage {
+                party_index: msg.sender,
+            });
+        }
+        self.received.insert(msg.sender, msg.body);
+        Ok(())
+    }
+
+    fn message_queue(&mut self) -> &mut Vec<Msg<Self::MessageBody>> {
+        &mut self.msgs_queue
+    }
+
+    fn wants_to_proceed(&self) -> bool {
+        matches!(self.round, Round::Collecting(_))
+            && self.received.len() == self.local_key.n as usize
+    }
+
+    fn proceed(&mut self) -> FsDkrResult<()> {
+        if !self.wants_to_proceed() {
+            return Ok(());
+        }
+        let new_dk = match std::mem::replace(&mut self.round, Round::Failed) {
+            Round::Collecting(new_dk) => new_dk,
+            _ => unreachable!("checked by wants_to_proceed"),
+        };
+        let messages: Vec<_> = self.received.values().cloned().collect();
+        let new_key = RefreshMessage::collect(&messages, &self.local_key, new_dk, &[])?;
+        self.round = Round::Finished(Box::new(new_key));
+        Ok(())
+    }
+
+    fn round_timeout(&self) -> Option<Duration> {
+        None
+    }
+
+    fn round_timeout_reached(&mut self) -> Self::Err {
+        panic!("no timeout was set")
+    }
+
+    fn is_finished(&self) -> bool {
+        matches!(self.round, Round::Finished(_) | Round::Gone)
+    }
+
+    fn pick_output(&mut self) -> Option<FsDkrResult<Self::Output>> {
+        match std::mem::replace(&mut self.round, Round::Gone) {
+            Round::Finished(new_key) => Some(Ok(*new_key)),
+            round => {
+                self.round = round;
+                None
+            }
+        }
+    }
+
+    fn current_round(&self) -> u16 {
+        match self.round {
+            Round::Collecting(_) | Round::Failed => 1,
+            Round::Finished(_) => 2,
+            Round::Gone => 3,
+        }
+    }
+
+    fn total_rounds(&self) -> Option<u16> {
+        Some(1)
+    }
+
+    fn party_ind(&self) -> u16 {
+        self.local_key.i
+    }
+
+    fn parties(&self) -> u16 {
+        self.local_key.n
+    }
+}
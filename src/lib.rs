@@ -13,15 +13,65 @@
 //!
 //! * [refresh_message]: crate::refresh_message
 //!
+//! Insecure fixture keys for tests are only available with the `fast-insecure-fixtures` feature;
+//! without it they can not be constructed:
+//!
+#![cfg_attr(
+    not(feature = "fast-insecure-fixtures"),
+    doc = "```compile_fail\nfs_dkr::fixtures::fixture_committee::<curv::elliptic::curves::Secp256k1>(1, 3, 0);\n```"
+)]
+
+#[macro_use]
+mod trace;
 
 pub mod add_party_message;
+pub mod air_gapped;
+pub mod anchor;
+pub mod batch_refresh;
+pub mod bip340;
+pub mod certified_rotation;
+pub mod compact;
+pub mod complaint;
+pub mod dkg;
+pub mod epoch;
+pub mod epoch_secrets;
 pub mod error;
+#[cfg(feature = "export")]
+pub mod export;
+#[cfg(feature = "fast-insecure-fixtures")]
+pub mod fixtures;
+pub mod index_remap;
+pub mod key_lock;
+pub mod key_refresh;
+pub mod light_distribution;
+pub mod local_key;
+#[cfg(feature = "mnemonic")]
+pub mod mnemonic;
+pub mod observer;
+pub mod paillier_rotation;
+pub mod prepared_distribution;
 pub mod range_proofs;
+pub mod refresh_ack;
 pub mod refresh_message;
+pub mod refresh_session;
+pub mod refresh_summary;
+pub mod refreshable_key;
+pub mod rehearsal;
+pub mod revocation;
 pub mod ring_pedersen_proof;
+pub mod signed_message;
+pub mod signing_context;
+#[cfg(any(test, feature = "simulation"))]
+pub mod simulation;
+#[cfg(any(test, feature = "test-utils"))]
+pub mod test_utils;
+pub mod wire;
 pub mod zk_pdl_with_slack;
 
 mod test;
 
 pub const PAILLIER_KEY_SIZE: usize = 2048;
+/// Largest Paillier modulus, in bits, a refresh accepts, see
+/// [RefreshParams::paillier_modulus_bits](refresh_message::RefreshParams::paillier_modulus_bits).
+pub const MAX_PAILLIER_KEY_SIZE: usize = 4096;
 pub const M_SECURITY: usize = 256;
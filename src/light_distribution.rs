@@ -0,0 +1,236 @@
+//! Distribution for bandwidth-constrained receivers.
+//!
+//! In a regular [RefreshMessage] every receiver gets the ciphertexts and proofs of all the
+//! other receivers, which only matters to them. [RefreshMessage::into_light] splits a message
+//! into a [LightBroadcast], holding everything common to all the receivers plus a Merkle root
+//! over the `(receiver, ciphertext)` pairs, and one [ReceiverSlice] per receiver with its
+//! ciphertext, its proofs and its Merkle path. The broadcast goes over the broadcast channel and
+//! each slice point-to-point; since every receiver checks its slice against the same broadcast
+//! root, the sender can not hand different receivers inconsistent ciphertexts without being
+//! caught. The committed sub-shares are derived from the broadcast commitments, so
+//! [RefreshMessage::collect_light] checks the slice, its proofs and the combination of the
+//! commitments exactly like [RefreshMessage::collect], for its own slice only.
+//!
+//! Light collects do not support join messages or session bindings.
+
+use crate::error::{FsDkrError, FsDkrResult};
+use crate::range_proofs::AliceProof;
+use crate::refresh_message::{RefreshContext, RefreshMessage, RefreshMessageParts};
+use crate::zk_pdl_with_slack::PDLwSlackProof;
+use curv::cryptographic_primitives::hashing::{Digest, DigestExt};
+use curv::elliptic::curves::Curve;
+use curv::BigInt;
+use multi_party_ecdsa::protocols::multi_party_ecdsa::gg_2020::state_machine::keygen::LocalKey;
+use paillier::DecryptionKey;
+use serde::{Deserialize, Serialize};
+
+/// The part of a refresh message every receiver gets, see the [module docs](self).
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct LightBroadcast<E: Curve, H: Digest + Clone, const M: usize> {
+    /// The message without its per-receiver vectors.
+    header: RefreshMessageParts<E, H, M>,
+    merkle_root: BigInt,
+}
+
+impl<E: Curve, H: Digest + Clone, const M: usize> LightBroadcast<E, H, M> {
+    pub fn party_index(&self) -> u16 {
+        self.header.party_index
+    }
+
+    pub fn merkle_root(&self) -> &BigInt {
+        &self.merkle_root
+    }
+
+    /// The full message as seen by `slice.receiver`: its own entries are the ones of the slice,
+    /// the entries of the other receivers are copies of them and must not be checked.
+    fn expand(&self, slice: &ReceiverSlice<E, H>) -> FsDkrResult<RefreshMessage<E, H, M>> {
+        let new_n = self
+            .header
+            .coefficients_committed_vec
+            .parameters
+            .share_count as usize;
+        let mut parts = self.header.clone();
+        parts.points_encrypted_vec = vec![slice.ciphertext.clone(); new_n];
+        parts.pdl_proof_vec = vec![slice.pdl_proof.clone(); new_n];
+        parts.range_proofs = vec![slice.range_proof.clone(); new_n];
+        RefreshMessage::new(parts)
+    }
+}
+
+/// The part of a refresh message meant for one receiver, see the [module docs](self).
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ReceiverSlice<E: Curve, H: Digest + Clone> {
+    pub(crate) sender: u16,
+    pub(crate) receiver: u16,
+    pub(crate) ciphertext: BigInt,
+    pdl_proof: PDLwSlackProof<E, H>,
+    range_proof: AliceProof<E, H>,
+    path: Vec<BigInt>,
+}
+
+impl<E: Curve, H: Digest + Clone> ReceiverSlice<E, H> {
+    pub fn sender(&self) -> u16 {
+        self.sender
+    }
+
+    pub fn receiver(&self) -> u16 {
+        self.receiver
+    }
+
+    /// Whether the slice is the one committed to by `root` for its receiver, among `n`.
+    fn verify_path(&self, n: usize, root: &BigInt) -> bool {
+        if self.receiver == 0 || self.receiver as usize > n {
+            return false;
+        }
+        let mut hash = leaf::<H>(self.receiver, &self.ciphertext);
+        let mut index = self.receiver as usize - 1;
+        let mut len = n;
+        let mut path = self.path.iter();
+        while len > 1 {
+            if index ^ 1 < len {
+                let sibling = match path.next() {
+                    Some(sibling) => sibling,
+                    None => return false,
+                };
+                hash = if index % 2 == 0 {
+                    node::<H>(&hash, sibling)
+                } else {
+                    node::<H>(sibling, &hash)
+                };
+            }
+            index /= 2;
+            len = (len + 1) / 2;
+        }
+        path.next().is_none() && &hash == root
+    }
+}
+
+fn leaf<H: Digest + Clone>(receiver: u16, ciphertext: &BigInt) -> BigInt {
+    H::new()
+        .chain_bigint(&BigInt::from(0))
+        .chain_bigint(&BigInt::from(receiver as i32))
+        .chain_bigint(ciphertext)
+        .result_bigint()
+}
+
+fn node<H: Digest + Clone>(left: &BigInt, right: &BigInt) -> BigInt {
+    H::new()
+        .chain_bigint(&BigInt::from(1))
+        .chain_bigint(left)
+        .chain_bigint(right)
+        .result_bigint()
+}
+
+/// The levels of the Merkle tree over `leaves`, from the leaves to the root. The last node of an
+/// odd level is promoted to the next one as is.
+fn merkle_levels<H: Digest + Clone>(leaves: Vec<BigInt>) -> Vec<Vec<BigInt>> {
+    let mut levels = vec![leaves];
+    while levels.last().unwrap().len() > 1 {
+        let next = levels
+            .last()
+            .unwrap()
+            .chunks(2)
+            .map(|pair| match pair {
+                [left, right] => node::<H>(left, right),
+                [single] => single.clone(),
+                _ => unreachable!(),
+            })
+            .collect();
+        levels.push(next);
+    }
+    levels
+}
+
+impl<E: Curve, H: Digest + Clone, const M: usize> RefreshMessage<E, H, M> {
+    /// Splits the message into the broadcast part and one slice per receiver, in receiver order.
+    pub fn into_light(self) -> (LightBroadcast<E, H, M>, Vec<ReceiverSlice<E, H>>) {
+        let mut header = self.into_parts();
+        let ciphertexts = std::mem::take(&mut header.points_encrypted_vec);
+        let pdl_proofs = std::mem::take(&mut header.pdl_proof_vec);
+        let range_proofs = std::mem::take(&mut header.range_proofs);
+
+        let leaves = ciphertexts
+            .iter()
+            .enumerate()
+            .map(|(k, ciphertext)| leaf::<H>(k as u16 + 1, ciphertext))
+            .collect();
+        let levels = merkle_levels::<H>(leaves);
+        let merkle_root = levels.last().unwrap()[0].clone();
+
+        let slices = ciphertexts
+            .into_iter()
+            .zip(pdl_proofs)
+            .zip(range_proofs)
+            .enumerate()
+            .map(|(k, ((ciphertext, pdl_proof), range_proof))| {
+                let mut index = k;
+                let mut path = Vec::new();
+                for level in levels.iter().take(levels.len() - 1) {
+                    if let Some(sibling) = level.get(index ^ 1) {
+                        path.push(sibling.clone());
+                    }
+                    index /= 2;
+                }
+                ReceiverSlice {
+                    sender: header.party_index,
+                    receiver: k as u16 + 1,
+                    ciphertext,
+                    pdl_proof,
+                    range_proof,
+                    path,
+                }
+            })
+            .collect();
+
+        (
+            LightBroadcast {
+                header,
+                merkle_root,
+            },
+            slices,
+        )
+    }
+
+    /// Same as [RefreshMessage::collect] from the broadcasts of the senders and the slices they
+    /// sent to this party. Every slice is checked against the Merkle root of its sender's
+    /// broadcast before anything else. Returns the refreshed key, `old_key` is left untouched.
+    pub fn collect_light(
+        broadcasts: &[LightBroadcast<E, H, M>],
+        slices: &[ReceiverSlice<E, H>],
+        old_key: &LocalKey<E>,
+        new_dk: DecryptionKey,
+    ) -> FsDkrResult<LocalKey<E>> {
+        let messages = broadcasts
+            .iter()
+            .map(|broadcast| {
+                let sender = broadcast.party_index();
+                let slice = slices
+                    .iter()
+                    .find(|slice| slice.sender == sender)
+                    .ok_or_else(|| FsDkrError::InvalidRefreshMessage {
+                        party_index: sender,
+                        reason: "no slice for this receiver".to_string(),
+                    })?;
+                let new_n = broadcast
+                    .header
+                    .coefficients_committed_vec
+                    .parameters
+                    .share_count as usize;
+                if slice.receiver != old_key.i || !slice.verify_path(new_n, &broadcast.merkle_root)
+                {
+                    return Err(FsDkrError::MerklePathInvalid {
+                        party_index: sender,
+                    });
+                }
+                broadcast.expand(slice)
+            })
+            .collect::<FsDkrResult<Vec<_>>>()?;
+
+        let context = RefreshContext::new(old_key, old_key.n).own_receiver_only(old_key.i);
+        let verified = Self::verify_all(&messages, &context)?;
+        let verified_set = Self::verify_combination_in(&verified, old_key, &[], &context)?;
+        let mut new_key = old_key.clone();
+        Self::decrypt_and_update(&verified, &mut new_key, new_dk, &[], verified_set)?;
+        Ok(new_key)
+    }
+}
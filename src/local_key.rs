@@ -0,0 +1,184 @@
+//! Consistency checks for a [LocalKey].
+//!
+//! A key produced by [crate::refresh_message::RefreshMessage::collect] has to be a valid input
+//! for the next refresh and for GG20 signing. [verify_local_key] checks every public field of the
+//! key against the others and the secret share against its public counterpart.
+//! [validate_local_key] is the cheaper subset distribute and collect run on every key they get.
+//! [erase_secrets] wipes the secrets of a key that was refreshed.
+
+use crate::error::{FsDkrError, FsDkrResult};
+use curv::arithmetic::{Modulo, Zero};
+use curv::elliptic::curves::{Curve, Point, Scalar};
+use curv::BigInt;
+use multi_party_ecdsa::protocols::multi_party_ecdsa::gg_2020::state_machine::keygen::LocalKey;
+use zeroize::Zeroize;
+
+/// Lagrange coefficient of the evaluation point `j` over the evaluation points `points`,
+/// evaluated at `x`. Evaluation points are party indices, i.e. start at 1.
+pub(crate) fn lagrange_coefficient_at<E: Curve>(
+    x: &Scalar<E>,
+    j: u16,
+    points: &[u16],
+) -> Scalar<E> {
+    let xj = Scalar::<E>::from(j);
+    points
+        .iter()
+        .filter(|m| **m != j)
+        .fold(Scalar::<E>::from(1u16), |acc, m| {
+            let xm = Scalar::<E>::from(*m);
+            let denominator = (&xj - &xm).invert().unwrap();
+            acc * (x - &xm) * denominator
+        })
+}
+
+/// The share decrypted from a weighted sum of sub-share ciphertexts. The plaintext exceeds the
+/// curve order, so it is reduced here rather than left to the conversion to a scalar.
+pub(crate) fn share_from_plaintext<E: Curve>(plaintext: &BigInt) -> Scalar<E> {
+    let mut reduced = BigInt::modulus(plaintext, Scalar::<E>::group_order());
+    let share = Scalar::<E>::from(&reduced);
+    reduced.zeroize();
+    share
+}
+
+/// Interpolates the polynomial "in the exponent" going through `(points[k], values[k])` and
+/// evaluates it at `x`.
+pub(crate) fn interpolate_at<E: Curve>(
+    x: &Scalar<E>,
+    points: &[u16],
+    values: &[Point<E>],
+) -> Point<E> {
+    points
+        .iter()
+        .zip(values)
+        .fold(Point::<E>::zero(), |acc, (j, value)| {
+            acc + value * &lagrange_coefficient_at(x, *j, points)
+        })
+}
+
+/// Checks the parts of `local_key` that distribute and collect index into: the party index and
+/// the threshold fit the committee, and there is a Paillier key and a ring-Pedersen statement
+/// for every party. Holds for the public part of a key as well, see [crate::air_gapped].
+pub(crate) fn validate_key_layout<E: Curve>(local_key: &LocalKey<E>) -> FsDkrResult<()> {
+    let n = local_key.n;
+    if local_key.i == 0 || local_key.i > n {
+        return Err(FsDkrError::KeyIndexOutOfRange {
+            party_index: local_key.i,
+            n,
+        });
+    }
+    if local_key.t >= n {
+        return Err(FsDkrError::KeyThresholdOutOfRange { t: local_key.t, n });
+    }
+    let lengths = [
+        ("paillier_key_vec", local_key.paillier_key_vec.len()),
+        ("h1_h2_n_tilde_vec", local_key.h1_h2_n_tilde_vec.len()),
+    ];
+    for &(field, len) in lengths.iter() {
+        if len != n as usize {
+            return Err(FsDkrError::KeyVectorLength { field, len, n });
+        }
+    }
+    Ok(())
+}
+
+/// The checks distribute and collect run on `local_key` before using it: its layout fits the
+/// committee, the Paillier decryption key matches the party's own encryption key and the
+/// secret share is not zero. Much cheaper than [verify_local_key], which also checks the public
+/// shares.
+pub fn validate_local_key<E: Curve>(local_key: &LocalKey<E>) -> FsDkrResult<()> {
+    validate_key_layout(local_key)?;
+    let dk = &local_key.paillier_dk;
+    if &dk.p * &dk.q != local_key.paillier_key_vec[local_key.i as usize - 1].n {
+        return Err(FsDkrError::KeyPaillierMismatch);
+    }
+    if local_key.keys_linear.x_i.is_zero() {
+        return Err(FsDkrError::KeyZeroShare);
+    }
+    Ok(())
+}
+
+/// Checks that `local_key` is internally consistent:
+/// * everything [validate_local_key] checks,
+/// * the public shares have `n` entries and the VSS parameters match `t` and `n`,
+/// * the public shares lie on a polynomial of degree `t` whose free coefficient is the joint
+///   public key,
+/// * the secret share matches the party's public share.
+pub fn verify_local_key<E: Curve>(local_key: &LocalKey<E>) -> FsDkrResult<()> {
+    let invalid = |reason: &str| {
+        Err(FsDkrError::InvalidLocalKey {
+            reason: reason.to_string(),
+        })
+    };
+
+    validate_local_key(local_key)?;
+    let n = local_key.n as usize;
+    let t = local_key.t as usize;
+    if local_key.pk_vec.len() != n {
+        return Err(FsDkrError::KeyVectorLength {
+            field: "pk_vec",
+            len: local_key.pk_vec.len(),
+            n: local_key.n,
+        });
+    }
+    if local_key.vss_scheme.parameters.threshold as usize != t
+        || local_key.vss_scheme.parameters.share_count as usize != n
+    {
+        return invalid("VSS parameters do not match t and n");
+    }
+    if local_key.keys_linear.y != local_key.y_sum_s {
+        return invalid("keys_linear.y is not the joint public key");
+    }
+
+    // the first t+1 public shares define the polynomial, every other share has to lie on it
+    let base_points: Vec<u16> = (1..=t as u16 + 1).collect();
+    let base_values = &local_key.pk_vec[..t + 1];
+    if interpolate_at(&Scalar::<E>::zero(), &base_points, base_values) != local_key.y_sum_s {
+        return invalid("public shares do not interpolate to the joint public key");
+    }
+    for (k, pk) in local_key.pk_vec.iter().enumerate().skip(t + 1) {
+        let x = Scalar::<E>::from(k as u16 + 1);
+        if &interpolate_at(&x, &base_points, base_values) != pk {
+            return invalid("public shares do not lie on a polynomial of degree t");
+        }
+    }
+
+    let i = local_key.i as usize - 1;
+    if Point::<E>::generator() * &local_key.keys_linear.x_i != local_key.pk_vec[i] {
+        return invalid("secret share does not match the public share");
+    }
+
+    Ok(())
+}
+
+/// Overwrites the secrets of `local_key`, the secret share and the Paillier decryption key,
+/// with zeros. Meant for the old key once the refreshed key is stored: a refresh only protects
+/// against an adversary that does not also get hold of the old shares. The key is unusable
+/// afterwards, see [secrets_erased].
+pub fn erase_secrets<E: Curve>(local_key: &mut LocalKey<E>) {
+    local_key.keys_linear.x_i = Scalar::<E>::zero();
+    local_key.paillier_dk.p.zeroize();
+    local_key.paillier_dk.q.zeroize();
+}
+
+/// Whether the secrets of `local_key` were erased by [erase_secrets].
+pub fn secrets_erased<E: Curve>(local_key: &LocalKey<E>) -> bool {
+    local_key.keys_linear.x_i.is_zero()
+        && local_key.paillier_dk.p.is_zero()
+        && local_key.paillier_dk.q.is_zero()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use curv::elliptic::curves::Secp256k1;
+
+    #[test]
+    fn test_share_from_plaintext_reduces() {
+        let share = Scalar::<Secp256k1>::random();
+        let q = Scalar::<Secp256k1>::group_order();
+        for k in [0u32, 1, 5, 1 << 20].iter() {
+            let plaintext = share.to_bigint() + q * &BigInt::from(*k);
+            assert_eq!(share_from_plaintext::<Secp256k1>(&plaintext), share);
+        }
+    }
+}
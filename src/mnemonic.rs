@@ -0,0 +1,291 @@
+//! Word mnemonic backup of a refreshed share.
+//!
+//! A [ShareBackup] holds a party's share together with the minimal metadata needed to put it
+//! back into the right committee: party index, epoch and a prefix of the key fingerprint. It is
+//! encoded with the BIP39 English word list, 11 bits per word. Every 7 data words are followed by
+//! a checksum word, so a mistyped word is always detected and located to within one group of 8
+//! words.
+//!
+//! When a passphrase is given the share is XORed before encoding with a key derived from the
+//! passphrase by PBKDF2-HMAC-SHA512 (RFC 8018) with [KDF_ROUNDS] iterations, salted with a
+//! domain separator and the metadata; the metadata stays readable. A wrong passphrase yields a
+//! different share, which [ShareBackup::verify_against] rejects.
+//!
+//! This module is only available with the `mnemonic` feature.
+
+use crate::epoch::key_fingerprint;
+use crate::error::{FsDkrError, FsDkrResult};
+use crate::refreshable_key::RefreshOutcome;
+use bip39::Language;
+use curv::arithmetic::Converter;
+use curv::elliptic::curves::{Curve, Point, Scalar};
+use curv::BigInt;
+use hmac::Hmac;
+use multi_party_ecdsa::protocols::multi_party_ecdsa::gg_2020::state_machine::keygen::LocalKey;
+use sha2::{Digest, Sha256, Sha512};
+
+/// 2 since the passphrase is stretched with PBKDF2.
+const VERSION: u8 = 2;
+const FLAG_PASSPHRASE: u8 = 0x80;
+const PAYLOAD_LEN: usize = 1 + 2 + 8 + FINGERPRINT_PREFIX_LEN + SHARE_LEN;
+const FINGERPRINT_PREFIX_LEN: usize = 4;
+const SHARE_LEN: usize = 32;
+const DATA_WORDS_PER_GROUP: usize = 7;
+/// PBKDF2 iterations for the passphrase, the OWASP recommendation for HMAC-SHA512.
+pub const KDF_ROUNDS: u32 = 210_000;
+const KDF_SALT_PREFIX: &[u8] = b"fs-dkr/mnemonic";
+
+/// A share and the metadata needed to restore it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ShareBackup<E: Curve> {
+    pub party_index: u16,
+    pub epoch: u64,
+    pub fingerprint_prefix: [u8; FINGERPRINT_PREFIX_LEN],
+    pub share: Scalar<E>,
+}
+
+impl<E: Curve> ShareBackup<E> {
+    pub fn from_local_key(local_key: &LocalKey<E>, epoch: u64) -> Self {
+        ShareBackup {
+            party_index: local_key.i,
+            epoch,
+            fingerprint_prefix: fingerprint_prefix(local_key),
+            share: local_key.keys_linear.x_i.clone(),
+        }
+    }
+
+    /// Checks that the restored share belongs to `local_key`'s committee and matches the
+    /// party's public share. A wrong passphrase is detected here.
+    pub fn verify_against(&self, local_key: &LocalKey<E>) -> FsDkrResult<()> {
+        let public_share = local_key
+            .pk_vec
+            .get((self.party_index as usize).wrapping_sub(1))
+            .ok_or(FsDkrError::MnemonicMalformed)?;
+        if self.fingerprint_prefix != fingerprint_prefix(local_key)
+            || &(Point::<E>::generator() * &self.share) != public_share
+        {
+            return Err(FsDkrError::MnemonicShareMismatch);
+        }
+        Ok(())
+    }
+
+    pub fn to_mnemonic(&self, passphrase: Option<&str>) -> Vec<&'static str> {
+        let mut payload = Vec::with_capacity(PAYLOAD_LEN);
+        let flags = if passphrase.is_some() {
+            VERSION | FLAG_PASSPHRASE
+        } else {
+            VERSION
+        };
+        payload.push(flags);
+        payload.extend_from_slice(&self.party_index.to_be_bytes());
+        payload.extend_from_slice(&self.epoch.to_be_bytes());
+        payload.extend_from_slice(&self.fingerprint_prefix);
+        let mut share = padded(&self.share.to_bigint().to_bytes(), SHARE_LEN);
+        if let Some(passphrase) = passphrase {
+            wrap_share(&mut share, passphrase, &payload);
+        }
+        payload.extend_from_slice(&share);
+
+        let word_list = Language::English.word_list();
+        let data_words = bytes_to_words(&payload);
+        let mut words = Vec::new();
+        for (group_index, group) in data_words.chunks(DATA_WORDS_PER_GROUP).enumerate() {
+            words.extend(group.iter().map(|w| word_list[*w as usize]));
+            words.push(word_list[group_checksum(group_index, group) as usize]);
+        }
+        words
+    }
+
+    /// Decodes a mnemonic produced by [ShareBackup::to_mnemonic]. Unknown words are reported with
+    /// their position, checksum failures with the range of the group holding the mistake.
+    /// Positions start at 1.
+    pub fn from_mnemonic(words: &[&str], passphrase: Option<&str>) -> FsDkrResult<Self> {
+        let indices = words
+            .iter()
+            .enumerate()
+            .map(|(k, word)| {
+                Language::English
+                    .find_word(&word.to_lowercase())
+                    .ok_or(FsDkrError::MnemonicUnknownWord { position: k + 1 })
+            })
+            .collect::<FsDkrResult<Vec<u16>>>()?;
+
+        let mut data_words = Vec::new();
+        for (group_index, group) in indices.chunks(DATA_WORDS_PER_GROUP + 1).enumerate() {
+            let first_word = group_index * (DATA_WORDS_PER_GROUP + 1) + 1;
+            let checksum_error = FsDkrError::MnemonicChecksum {
+                first_word,
+                last_word: first_word + group.len() - 1,
+            };
+            let (checksum, data) = group.split_last().ok_or(FsDkrError::MnemonicMalformed)?;
+            if data.is_empty() || group_checksum(group_index, data) != *checksum {
+                return Err(checksum_error);
+            }
+            data_words.extend_from_slice(data);
+        }
+
+        let payload = words_to_bytes(&data_words, PAYLOAD_LEN)?;
+        let flags = payload[0];
+        if flags & !FLAG_PASSPHRASE != VERSION {
+            return Err(FsDkrError::MnemonicMalformed);
+        }
+        let mut share = payload[PAYLOAD_LEN - SHARE_LEN..].to_vec();
+        match (flags & FLAG_PASSPHRASE != 0, passphrase) {
+            (true, Some(passphrase)) => {
+                wrap_share(&mut share, passphrase, &payload[..PAYLOAD_LEN - SHARE_LEN])
+            }
+            (false, None) => {}
+            _ => return Err(FsDkrError::MnemonicPassphrase),
+        }
+
+        let mut fingerprint_prefix = [0u8; FINGERPRINT_PREFIX_LEN];
+        fingerprint_prefix.copy_from_slice(&payload[11..11 + FINGERPRINT_PREFIX_LEN]);
+        let mut epoch = [0u8; 8];
+        epoch.copy_from_slice(&payload[3..11]);
+        Ok(ShareBackup {
+            party_index: u16::from_be_bytes([payload[1], payload[2]]),
+            epoch: u64::from_be_bytes(epoch),
+            fingerprint_prefix,
+            share: Scalar::<E>::from(&BigInt::from_bytes(&share)),
+        })
+    }
+}
+
+impl RefreshOutcome {
+    /// Paper backup of `new_key`, the key this refresh produced, for the epoch it entered. `H`
+    /// is the hash the refresh was run with. The outcome itself holds no secret, so the key is
+    /// passed in and checked against the fingerprint of the outcome first.
+    pub fn share_mnemonic<E: Curve, H: Digest + Clone>(
+        &self,
+        new_key: &LocalKey<E>,
+        passphrase: Option<&str>,
+    ) -> FsDkrResult<Vec<&'static str>> {
+        if &key_fingerprint::<E, H>(new_key) != self.guard.new_fingerprint() {
+            return Err(FsDkrError::MnemonicShareMismatch);
+        }
+        Ok(ShareBackup::from_local_key(new_key, self.guard.epoch()).to_mnemonic(passphrase))
+    }
+}
+
+fn fingerprint_prefix<E: Curve>(local_key: &LocalKey<E>) -> [u8; FINGERPRINT_PREFIX_LEN] {
+    let fingerprint = padded(&key_fingerprint::<E, Sha256>(local_key).to_bytes(), 32);
+    let mut prefix = [0u8; FINGERPRINT_PREFIX_LEN];
+    prefix.copy_from_slice(&fingerprint[..FINGERPRINT_PREFIX_LEN]);
+    prefix
+}
+
+fn padded(bytes: &[u8], len: usize) -> Vec<u8> {
+    let mut out = vec![0u8; len.saturating_sub(bytes.len())];
+    out.extend_from_slice(bytes);
+    out
+}
+
+/// XORs `share` with a key derived from `passphrase` by PBKDF2-HMAC-SHA512, salted with the
+/// metadata.
+fn wrap_share(share: &mut [u8], passphrase: &str, metadata: &[u8]) {
+    let salt = [KDF_SALT_PREFIX, metadata].concat();
+    let mut key = [0u8; SHARE_LEN];
+    pbkdf2::pbkdf2::<Hmac<Sha512>>(passphrase.as_bytes(), &salt, KDF_ROUNDS, &mut key);
+    share.iter_mut().zip(key.iter()).for_each(|(b, k)| *b ^= k);
+}
+
+/// Weighted sum of the group's words with odd weights, which are invertible modulo 2048, so any
+/// single mistyped word in a group changes the checksum.
+fn group_checksum(group_index: usize, group: &[u16]) -> u16 {
+    let sum = group
+        .iter()
+        .enumerate()
+        .fold(group_index as u32 * 1031, |acc, (k, word)| {
+            acc + (2 * k as u32 + 1) * *word as u32
+        });
+    (sum % 2048) as u16
+}
+
+fn bytes_to_words(bytes: &[u8]) -> Vec<u16> {
+    let bits = bytes.len() * 8;
+    (0..(bits + 10) / 11)
+        .map(|w| {
+            (0..11).fold(0u16, |acc, b| {
+                let bit = w * 11 + b;
+                let set = bit < bits && bytes[bit / 8] & (0x80 >> (bit % 8)) != 0;
+                (acc << 1) | set as u16
+            })
+        })
+        .collect()
+}
+
+fn words_to_bytes(words: &[u16], len: usize) -> FsDkrResult<Vec<u8>> {
+    if words.len() != (len * 8 + 10) / 11 {
+        return Err(FsDkrError::MnemonicMalformed);
+    }
+    let mut bytes = vec![0u8; len];
+    for (w, word) in words.iter().enumerate() {
+        for b in 0..11 {
+            let bit = w * 11 + b;
+            if word & (0x400 >> b) != 0 {
+                if bit >= len * 8 {
+                    return Err(FsDkrError::MnemonicMalformed);
+                }
+                bytes[bit / 8] |= 0x80 >> (bit % 8);
+            }
+        }
+    }
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use curv::elliptic::curves::Secp256k1;
+
+    fn backup() -> ShareBackup<Secp256k1> {
+        ShareBackup {
+            party_index: 3,
+            epoch: 7,
+            fingerprint_prefix: [1, 2, 3, 4],
+            share: Scalar::from(&BigInt::from(123456789)),
+        }
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let backup = backup();
+        let words = backup.to_mnemonic(None);
+        assert_eq!(words.len(), 40);
+        assert_eq!(ShareBackup::from_mnemonic(&words, None).unwrap(), backup);
+
+        let words = backup.to_mnemonic(Some("correct horse"));
+        assert_eq!(
+            ShareBackup::from_mnemonic(&words, Some("correct horse")).unwrap(),
+            backup
+        );
+        let wrong = ShareBackup::<Secp256k1>::from_mnemonic(&words, Some("battery staple"));
+        assert_ne!(wrong.unwrap().share, backup.share);
+        assert!(ShareBackup::<Secp256k1>::from_mnemonic(&words, None).is_err());
+    }
+
+    #[test]
+    fn test_corrupted_word_is_located() {
+        let words = backup().to_mnemonic(None);
+        let word_list = Language::English.word_list();
+        for position in [0usize, 12, 39].iter() {
+            let mut corrupted = words.clone();
+            let index = Language::English.find_word(corrupted[*position]).unwrap();
+            corrupted[*position] = word_list[(index as usize + 1) % 2048];
+            match ShareBackup::<Secp256k1>::from_mnemonic(&corrupted, None) {
+                Err(FsDkrError::MnemonicChecksum {
+                    first_word,
+                    last_word,
+                }) => assert!(first_word <= position + 1 && position + 1 <= last_word),
+                _ => panic!("corrupted word at {} was not detected", position),
+            }
+        }
+
+        let mut unknown = words;
+        unknown[5] = "notaword";
+        match ShareBackup::<Secp256k1>::from_mnemonic(&unknown, None) {
+            Err(FsDkrError::MnemonicUnknownWord { position }) => assert_eq!(position, 6),
+            _ => panic!("unknown word was not detected"),
+        }
+    }
+}
@@ -0,0 +1,48 @@
+//! Verification of a refresh by a party holding no share.
+//!
+//! A watchtower following the broadcast channel can confirm that a valid refresh of the same
+//! joint public key took place without a [LocalKey]: the members publish the
+//! [CommitteePublicData] of their key, and the observer runs
+//! [RefreshMessage::verify_refresh] on the messages of the refresh. That performs every check
+//! of [RefreshMessage::collect] that does not need a decryption key, including the proofs of
+//! the ciphertexts of every receiver and the preservation of the public key.
+//!
+//! [RefreshMessage::verify_refresh]: crate::refresh_message::RefreshMessage::verify_refresh
+//! [RefreshMessage::collect]: crate::refresh_message::RefreshMessage::collect
+
+use curv::elliptic::curves::{Curve, Point};
+use multi_party_ecdsa::protocols::multi_party_ecdsa::gg_2020::state_machine::keygen::LocalKey;
+use paillier::EncryptionKey;
+use serde::{Deserialize, Serialize};
+use zk_paillier::zkproofs::DLogStatement;
+
+/// The public part of the key of a committee, common to all its members.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(bound = "E: Curve")]
+pub struct CommitteePublicData<E: Curve> {
+    pub(crate) t: u16,
+    pub(crate) public_key: Point<E>,
+    pub(crate) pk_vec: Vec<Point<E>>,
+    pub(crate) paillier_key_vec: Vec<EncryptionKey>,
+    pub(crate) h1_h2_n_tilde_vec: Vec<DLogStatement>,
+}
+
+impl<E: Curve> CommitteePublicData<E> {
+    pub fn from_local_key(local_key: &LocalKey<E>) -> Self {
+        CommitteePublicData {
+            t: local_key.t,
+            public_key: local_key.y_sum_s.clone(),
+            pk_vec: local_key.pk_vec.clone(),
+            paillier_key_vec: local_key.paillier_key_vec.clone(),
+            h1_h2_n_tilde_vec: local_key.h1_h2_n_tilde_vec.clone(),
+        }
+    }
+
+    pub fn t(&self) -> u16 {
+        self.t
+    }
+
+    pub fn public_key(&self) -> &Point<E> {
+        &self.public_key
+    }
+}
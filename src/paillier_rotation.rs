@@ -0,0 +1,105 @@
+//! Rotation of the Paillier keys alone.
+//!
+//! A policy may require rotating the homomorphic encryption keys more often than the shares,
+//! and a full refresh is far more expensive than that. Every party broadcasts a
+//! [PaillierRotationMessage] with a fresh encryption key and the proof that it was generated
+//! correctly, and [apply_rotation] moves the key of the party to the new Paillier keys of the
+//! committee. The share, the public key and the VSS data are left as they are, so the rotated
+//! key signs and refreshes like before.
+
+use crate::error::{FsDkrError, FsDkrResult};
+use crate::refresh_message::validate_receiver_keys;
+use curv::elliptic::curves::Curve;
+use multi_party_ecdsa::protocols::multi_party_ecdsa::gg_2020::state_machine::keygen::LocalKey;
+use paillier::{DecryptionKey, EncryptionKey, KeyGeneration, Paillier};
+use serde::{Deserialize, Serialize};
+use zeroize::Zeroize;
+use zk_paillier::zkproofs::{NiCorrectKeyProof, SALT_STRING};
+
+/// The new Paillier encryption key of one party, see the [module docs](self).
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PaillierRotationMessage {
+    pub(crate) party_index: u16,
+    pub(crate) ek: EncryptionKey,
+    pub(crate) dk_correctness_proof: NiCorrectKeyProof,
+}
+
+impl PaillierRotationMessage {
+    /// Generates the new Paillier key pair of the party holding `local_key`. Returns the message
+    /// to broadcast and the decryption key to pass to [apply_rotation].
+    pub fn distribute<E: Curve>(local_key: &LocalKey<E>) -> (Self, DecryptionKey) {
+        let (ek, dk) = Paillier::keypair_with_modulus_size(crate::PAILLIER_KEY_SIZE).keys();
+        let dk_correctness_proof = NiCorrectKeyProof::proof(&dk, None);
+        let message = PaillierRotationMessage {
+            party_index: local_key.i,
+            ek,
+            dk_correctness_proof,
+        };
+        (message, dk)
+    }
+
+    pub fn party_index(&self) -> u16 {
+        self.party_index
+    }
+
+    fn verify(&self) -> FsDkrResult<()> {
+        if self
+            .dk_correctness_proof
+            .verify(&self.ek, SALT_STRING)
+            .is_err()
+        {
+            return Err(FsDkrError::PaillierVerificationError {
+                party_index: self.party_index,
+            });
+        }
+        Ok(())
+    }
+}
+
+/// Moves `old_key` to the Paillier keys of `messages`, which have to hold exactly one message
+/// of every party of the committee, the party's own made along with `new_dk`. The new keys
+/// are checked like the receivers' keys of a refresh and must not reuse a modulus of the
+/// committee, old or new.
+pub fn apply_rotation<E: Curve>(
+    old_key: &LocalKey<E>,
+    messages: &[PaillierRotationMessage],
+    new_dk: DecryptionKey,
+) -> FsDkrResult<LocalKey<E>> {
+    let mut sorted: Vec<&PaillierRotationMessage> = messages.iter().collect();
+    sorted.sort_by_key(|msg| msg.party_index);
+    for pair in sorted.windows(2) {
+        if pair[0].party_index == pair[1].party_index {
+            return Err(FsDkrError::DuplicatedRefreshMessage {
+                party_index: pair[1].party_index,
+            });
+        }
+    }
+    let indices: Vec<u16> = sorted.iter().map(|msg| msg.party_index).collect();
+    if indices != (1..=old_key.n).collect::<Vec<_>>() {
+        return Err(FsDkrError::InvalidQuorum { quorum: indices });
+    }
+    for msg in sorted.iter() {
+        msg.verify()?;
+    }
+
+    let paillier_key_vec: Vec<EncryptionKey> = sorted.iter().map(|msg| msg.ek.clone()).collect();
+    validate_receiver_keys(
+        &paillier_key_vec,
+        old_key.n,
+        &old_key.paillier_key_vec,
+        crate::PAILLIER_KEY_SIZE,
+    )?;
+    if &new_dk.p * &new_dk.q != paillier_key_vec[(old_key.i - 1) as usize].n {
+        return Err(FsDkrError::InvalidLocalKey {
+            reason: "Paillier decryption key does not match the party's new encryption key"
+                .to_string(),
+        });
+    }
+
+    let mut new_key = old_key.clone();
+    new_key.paillier_dk.p.zeroize();
+    new_key.paillier_dk.q.zeroize();
+    new_key.paillier_dk = new_dk;
+    new_key.paillier_key_vec = paillier_key_vec;
+    Ok(new_key)
+}
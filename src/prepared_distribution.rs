@@ -0,0 +1,185 @@
+//! Distribute split into an offline and an online phase.
+//!
+//! The expensive part of [RefreshMessage::distribute] (sampling the polynomial, encrypting the
+//! sub-shares, proving the ciphertexts and generating the new Paillier key) does not depend on
+//! anything learned during the refresh session. [RefreshMessage::prepare] does all of it ahead of
+//! time and returns a [PreparedDistribution], which also holds a copy of the sender's current
+//! share. [RefreshMessage::finalize] later binds the message to the session with a Schnorr
+//! signature under that share, which only costs a couple of scalar multiplications.
+//!
+//! The proofs themselves do not cover the session, the binding does. Its key has to be the
+//! share the message reshares: the constant term of the committed polynomial is that share in
+//! the exponent (tweaked and Lagrange-weighted as the message says), and the receivers already
+//! check it against the current public share of the sender. So only the sender can bind its
+//! message to a session, and a message bound to one session can not be re-bound to another.
+
+use crate::compact::{Reader, Writer};
+use crate::error::{FsDkrError, FsDkrResult};
+use crate::refresh_message::{RefreshMessage, RefreshMessageParts};
+use curv::arithmetic::{Converter, Zero};
+use curv::cryptographic_primitives::hashing::{Digest, DigestExt};
+use curv::elliptic::curves::{Curve, Point, Scalar};
+use curv::BigInt;
+use paillier::DecryptionKey;
+use serde::{Deserialize, Serialize};
+use zeroize::Zeroize;
+
+/// Signature binding a refresh message to a session, see [RefreshMessage::finalize]. The
+/// binding key is the current public share of the sender.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SessionBinding<E: Curve> {
+    session_id: BigInt,
+    binding_key: Point<E>,
+    commitment: Point<E>,
+    response: Scalar<E>,
+}
+
+impl<E: Curve> SessionBinding<E> {
+    pub(crate) fn sign<H: Digest + Clone>(
+        session_id: &BigInt,
+        share: &Scalar<E>,
+        message_fingerprint: &[u8; 32],
+    ) -> Self {
+        let binding_key = Point::<E>::generator() * share;
+        let nonce = Scalar::<E>::random();
+        let commitment = Point::<E>::generator() * &nonce;
+        let challenge =
+            Self::challenge::<H>(session_id, &binding_key, &commitment, message_fingerprint);
+        SessionBinding {
+            session_id: session_id.clone(),
+            binding_key,
+            commitment,
+            response: nonce + challenge * share,
+        }
+    }
+
+    fn challenge<H: Digest + Clone>(
+        session_id: &BigInt,
+        binding_key: &Point<E>,
+        commitment: &Point<E>,
+        message_fingerprint: &[u8; 32],
+    ) -> Scalar<E> {
+        let e = H::new()
+            .chain_bigint(session_id)
+            .chain_bigint(&BigInt::from_bytes(&binding_key.to_bytes(true)))
+            .chain_bigint(&BigInt::from_bytes(&commitment.to_bytes(true)))
+            .chain(message_fingerprint)
+            .result_bigint();
+        Scalar::<E>::from(&e)
+    }
+
+    pub fn session_id(&self) -> &BigInt {
+        &self.session_id
+    }
+
+    pub fn binding_key(&self) -> &Point<E> {
+        &self.binding_key
+    }
+
+    /// Checks that the binding is for `session_id` and signs the message with
+    /// [binding fingerprint](RefreshMessage::binding_fingerprint) `message_fingerprint`.
+    pub fn verify<H: Digest + Clone>(
+        &self,
+        session_id: &BigInt,
+        message_fingerprint: &[u8; 32],
+    ) -> FsDkrResult<()> {
+        let challenge = Self::challenge::<H>(
+            &self.session_id,
+            &self.binding_key,
+            &self.commitment,
+            message_fingerprint,
+        );
+        if &self.session_id != session_id
+            || Point::<E>::generator() * &self.response
+                != &self.commitment + &self.binding_key * &challenge
+        {
+            return Err(FsDkrError::SessionBindingInvalid);
+        }
+        Ok(())
+    }
+
+    pub(crate) fn write_compact(&self, writer: &mut Writer) {
+        writer.int(&self.session_id);
+        writer.point(&self.binding_key);
+        writer.point(&self.commitment);
+        writer.scalar(&self.response);
+    }
+
+    pub(crate) fn read_compact(reader: &mut Reader) -> FsDkrResult<Self> {
+        Ok(SessionBinding {
+            session_id: reader.int()?,
+            binding_key: reader.point()?,
+            commitment: reader.point()?,
+            response: reader.scalar()?,
+        })
+    }
+}
+
+/// The output of [RefreshMessage::prepare]: a refresh message waiting to be bound to a session,
+/// with the new Paillier decryption key and the share that signs the binding. The secrets are
+/// zeroized on drop, but a serialized bundle holds the share and has to be stored like the key.
+#[derive(Serialize, Deserialize)]
+pub struct PreparedDistribution<E: Curve, H: Digest + Clone, const M: usize> {
+    parts: RefreshMessageParts<E, H, M>,
+    secrets: PreparedSecrets<E>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct PreparedSecrets<E: Curve> {
+    dk: DecryptionKey,
+    share: Scalar<E>,
+}
+
+impl<E: Curve> Drop for PreparedSecrets<E> {
+    fn drop(&mut self) {
+        self.dk.p.zeroize();
+        self.dk.q.zeroize();
+        self.share = Scalar::<E>::zero();
+    }
+}
+
+impl<E: Curve> PreparedSecrets<E> {
+    fn take_dk(&mut self) -> DecryptionKey {
+        std::mem::replace(
+            &mut self.dk,
+            DecryptionKey {
+                p: BigInt::zero(),
+                q: BigInt::zero(),
+            },
+        )
+    }
+}
+
+impl<E: Curve, H: Digest + Clone, const M: usize> PreparedDistribution<E, H, M> {
+    /// `share` is the current share of the sender, before any tweak or Lagrange weight.
+    pub(crate) fn new(
+        parts: RefreshMessageParts<E, H, M>,
+        dk: DecryptionKey,
+        share: Scalar<E>,
+    ) -> Self {
+        PreparedDistribution {
+            parts,
+            secrets: PreparedSecrets { dk, share },
+        }
+    }
+
+    /// The message without a session binding, as [RefreshMessage::distribute] returns it.
+    pub(crate) fn into_unbound(mut self) -> FsDkrResult<(RefreshMessage<E, H, M>, DecryptionKey)> {
+        let dk = self.secrets.take_dk();
+        Ok((RefreshMessage::new(self.parts)?, dk))
+    }
+
+    /// The message bound to `session_id`.
+    pub(crate) fn into_bound(
+        mut self,
+        session_id: &BigInt,
+    ) -> FsDkrResult<(RefreshMessage<E, H, M>, DecryptionKey)> {
+        let mut refresh_message = RefreshMessage::new(self.parts)?;
+        refresh_message.session_binding = Some(SessionBinding::sign::<H>(
+            session_id,
+            &self.secrets.share,
+            &refresh_message.binding_fingerprint(),
+        ));
+        Ok((refresh_message, self.secrets.take_dk()))
+    }
+}
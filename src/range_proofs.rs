@@ -11,6 +11,8 @@
 
 // TODO: Verify this matches (if possible) range proofs from multi-party-ecdsa
 
+use crate::compact::{Reader, Writer};
+use crate::error::FsDkrResult;
 use curv::arithmetic::traits::*;
 use curv::cryptographic_primitives::hashing::{Digest, DigestExt};
 use curv::elliptic::curves::Point;
@@ -104,7 +106,7 @@ pub struct AliceProof<E: Curve, H: Digest + Clone> {
     s: BigInt,
     s1: BigInt,
     s2: BigInt,
-    _phantom: PhantomData<(E, H)>,
+    _phantom: PhantomData<fn() -> (E, H)>,
 }
 
 impl<E: Curve, H: Digest + Clone> AliceProof<E, H> {
@@ -200,6 +202,23 @@ impl<E: Curve, H: Digest + Clone> AliceProof<E, H> {
             _phantom: PhantomData,
         }
     }
+
+    pub(crate) fn write_compact(&self, writer: &mut Writer) {
+        for value in [&self.z, &self.e, &self.s, &self.s1, &self.s2].iter() {
+            writer.int(value);
+        }
+    }
+
+    pub(crate) fn read_compact(reader: &mut Reader) -> FsDkrResult<Self> {
+        Ok(AliceProof {
+            z: reader.int()?,
+            e: reader.int()?,
+            s: reader.int()?,
+            s1: reader.int()?,
+            s2: reader.int()?,
+            _phantom: PhantomData,
+        })
+    }
 }
 
 /// Represents first round of the interactive version of the proof
@@ -669,6 +688,29 @@ pub(crate) mod tests {
         assert!(alice_proof.verify(&cipher, &ek, &dlog_statement));
     }
 
+    #[test]
+    fn alice_zkp_rejects_plaintext_out_of_range() {
+        let (dlog_statement, ek, _) = generate_init();
+
+        // a plaintext beyond the slack of the proof, large enough to wrap around N once the
+        // receiver sums the ciphertexts
+        let a = Scalar::<Secp256k1>::group_order().pow(3) + FE::random().to_bigint();
+        let r = BigInt::from_paillier_key(&ek);
+        let cipher = Paillier::encrypt_with_chosen_randomness(
+            &ek,
+            RawPlaintext::from(a.clone()),
+            &Randomness::from(&r),
+        )
+        .0
+        .clone()
+        .into_owned();
+
+        let alice_proof =
+            AliceProof::<Secp256k1, Sha256>::generate(&a, &cipher, &ek, &dlog_statement, &r);
+
+        assert!(!alice_proof.verify(&cipher, &ek, &dlog_statement));
+    }
+
     #[test]
     fn bob_zkp() {
         let (dlog_statement, ek, _) = generate_init();
@@ -0,0 +1,69 @@
+//! Optional acknowledgment round after a refresh.
+//!
+//! Collect succeeds or fails for every party on its own, so a party whose collect failed goes
+//! unnoticed by the others, which have already moved to their new keys. After storing its new
+//! key, every party broadcasts a [RefreshAck]: its new public share with a Schnorr proof of
+//! knowledge of the share. [verify_acks] checks that every party of the new committee
+//! acknowledged the share the refresh messages give it, and names the first party that did
+//! not, so the committee can decide to re-run the refresh or go back to the old keys.
+
+use crate::error::{FsDkrError, FsDkrResult};
+use crate::observer::CommitteePublicData;
+use crate::refresh_message::RefreshMessage;
+use curv::cryptographic_primitives::hashing::Digest;
+use curv::cryptographic_primitives::proofs::sigma_dlog::DLogProof;
+use curv::elliptic::curves::{Curve, Point};
+use multi_party_ecdsa::protocols::multi_party_ecdsa::gg_2020::state_machine::keygen::LocalKey;
+use serde::{Deserialize, Serialize};
+
+/// Acknowledgment of the new share of one party, see the [module docs](self).
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct RefreshAck<E: Curve, H: Digest + Clone> {
+    pub(crate) party_index: u16,
+    pub(crate) proof: DLogProof<E, H>,
+}
+
+impl<E: Curve, H: Digest + Clone> RefreshAck<E, H> {
+    /// Acknowledges the share of `new_key`, the key returned by the collect.
+    pub fn new(new_key: &LocalKey<E>) -> Self {
+        RefreshAck {
+            party_index: new_key.i,
+            proof: DLogProof::prove(&new_key.keys_linear.x_i),
+        }
+    }
+
+    pub fn party_index(&self) -> u16 {
+        self.party_index
+    }
+
+    pub fn public_share(&self) -> &Point<E> {
+        &self.proof.pk
+    }
+}
+
+/// Checks `acks` against the public shares `refresh_messages` give the parties. `committee` is
+/// the public data of the key before the refresh, which the acknowledging parties still hold,
+/// see [CommitteePublicData::from_local_key]. The refresh messages are verified again like
+/// [RefreshMessage::verify_refresh] does, so an observer can check the acks as well.
+pub fn verify_acks<E: Curve, H: Digest + Clone, const M: usize>(
+    acks: &[RefreshAck<E, H>],
+    refresh_messages: &[RefreshMessage<E, H, M>],
+    committee: &CommitteePublicData<E>,
+) -> FsDkrResult<()> {
+    let pk_vec = RefreshMessage::refreshed_public_shares(refresh_messages, committee)?;
+    for (k, public_share) in pk_vec.iter().enumerate() {
+        let party_index = k as u16 + 1;
+        let mut party_acks = acks.iter().filter(|ack| ack.party_index == party_index);
+        let ack = party_acks
+            .next()
+            .ok_or(FsDkrError::RefreshAckMissing { party_index })?;
+        if party_acks.next().is_some()
+            || ack.public_share() != public_share
+            || DLogProof::verify(&ack.proof).is_err()
+        {
+            return Err(FsDkrError::RefreshAckMismatch { party_index });
+        }
+    }
+    Ok(())
+}
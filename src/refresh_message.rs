@@ -1,9 +1,18 @@
 use crate::add_party_message::JoinMessage;
-use crate::error::{FsDkrError, FsDkrResult};
+use crate::air_gapped::OfflineBundle;
+use crate::compact::{Reader, Writer, COMPACT_FORMAT_VERSION};
+use crate::epoch::key_fingerprint;
+use crate::error::{FsDkrError, FsDkrResult, ReceiverKeyFailure, Warning};
+use crate::index_remap::IndexRemap;
+use crate::local_key::{
+    lagrange_coefficient_at, share_from_plaintext, validate_key_layout, validate_local_key,
+};
+use crate::observer::CommitteePublicData;
+use crate::prepared_distribution::{PreparedDistribution, SessionBinding};
 use crate::range_proofs::AliceProof;
 use crate::zk_pdl_with_slack::{PDLwSlackProof, PDLwSlackStatement, PDLwSlackWitness};
-use curv::arithmetic::{BitManipulation, Samplable, Zero};
-use curv::cryptographic_primitives::hashing::Digest;
+use curv::arithmetic::{BasicOps, BitManipulation, Converter, Modulo, Roots, Samplable, Zero};
+use curv::cryptographic_primitives::hashing::{Digest, DigestExt};
 use curv::cryptographic_primitives::secret_sharing::feldman_vss::{
     ShamirSecretSharing, VerifiableSS,
 };
@@ -16,23 +25,375 @@ use paillier::{
     Add, Decrypt, Encrypt, EncryptWithChosenRandomness, EncryptionKey, KeyGeneration, Mul,
     Paillier, Randomness, RawCiphertext, RawPlaintext,
 };
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
 use serde::{Deserialize, Serialize};
-use std::borrow::Borrow;
-use std::collections::HashMap;
+use sha2::Sha256;
+use std::collections::{BTreeMap, HashMap};
+use std::convert::TryFrom;
 use std::fmt::Debug;
+use std::hash::{Hash, Hasher};
+use std::ops::Deref;
+use std::sync::{Arc, OnceLock};
 use zeroize::Zeroize;
 use zk_paillier::zkproofs::{DLogStatement, NiCorrectKeyProof, SALT_STRING};
 
 use crate::ring_pedersen_proof::{RingPedersenProof, RingPedersenStatement};
 
+/// The secret each sender reshares in [RefreshMessage::distribute_with_basis].
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub enum SharingBasis {
+    /// Every sender reshares its linear share `x_i`, and collect weights the contributions of
+    /// the `t+1` senders with the lowest old index by their Lagrange coefficients.
+    Linear,
+    /// Every member of `quorum` (old party indices) reshares `λ_i·x_i`, its Lagrange-weighted
+    /// share for that quorum, and collect adds up the contributions of exactly that quorum.
+    LagrangeWeighted { quorum: Vec<u16> },
+}
+
+impl Default for SharingBasis {
+    fn default() -> Self {
+        SharingBasis::Linear
+    }
+}
+
+/// Optional parameters of [RefreshMessage::distribute_with_params]. All the senders of a refresh
+/// have to use the same parameters.
+#[derive(Clone, Debug)]
+pub struct RefreshParams<E: Curve> {
+    pub basis: SharingBasis,
+    /// Additive tweak `Δ` moving the key to `y + Δ·G`, e.g. a non-hardened child key. Every
+    /// sender adds the full tweak to its share before resharing it; the contributions are
+    /// combined with weights that sum to one, so the new shares are shares of `x + Δ`.
+    pub tweak: Option<Scalar<E>>,
+    /// Operational metadata, e.g. a change ticket or a reason code, at most
+    /// [MAX_METADATA_LEN] bytes. It is covered by the message digest and the transcript digest
+    /// but not used by collect; senders may attach different metadata.
+    pub metadata: Vec<u8>,
+    /// Threshold of the new sharing, the current one if `None`. It has to be at least 1 and at
+    /// most half the new number of parties. The current threshold still applies to the
+    /// senders: more than `t` of them are needed to collect.
+    pub new_t: Option<u16>,
+    /// Size of the new Paillier modulus of the sender, at most [crate::MAX_PAILLIER_KEY_SIZE].
+    /// Receivers reject moduli below the size they require, see
+    /// [CollectOptions::with_min_modulus_bits].
+    pub paillier_modulus_bits: usize,
+    /// Generates the new Paillier key from safe primes, which is much slower.
+    pub use_safe_primes: bool,
+    /// Size of the Paillier moduli of the receivers below which distribute refuses to encrypt
+    /// to them, see [validate_encryption_keys].
+    pub min_receiver_modulus_bits: usize,
+}
+
+impl<E: Curve> Default for RefreshParams<E> {
+    fn default() -> Self {
+        RefreshParams {
+            basis: SharingBasis::Linear,
+            tweak: None,
+            metadata: Vec::new(),
+            new_t: None,
+            paillier_modulus_bits: crate::PAILLIER_KEY_SIZE,
+            use_safe_primes: false,
+            min_receiver_modulus_bits: crate::PAILLIER_KEY_SIZE,
+        }
+    }
+}
+
+/// Options of [RefreshMessage::collect_with_options].
+#[derive(Clone, Debug, Default)]
+pub struct CollectOptions {
+    max_failures: usize,
+    min_modulus_bits: Option<usize>,
+}
+
+impl CollectOptions {
+    /// Leaves out up to `max_failures` senders whose message fails verification, as long as the
+    /// remaining senders are still more than the threshold.
+    pub fn tolerate_failures(max_failures: usize) -> Self {
+        CollectOptions {
+            max_failures,
+            ..CollectOptions::default()
+        }
+    }
+
+    /// Rejects new Paillier moduli shorter than `bits`, instead of
+    /// [PAILLIER_KEY_SIZE](crate::PAILLIER_KEY_SIZE).
+    pub fn with_min_modulus_bits(mut self, bits: usize) -> Self {
+        self.min_modulus_bits = Some(bits);
+        self
+    }
+
+    pub fn max_failures(&self) -> usize {
+        self.max_failures
+    }
+
+    pub fn min_modulus_bits(&self) -> usize {
+        self.min_modulus_bits.unwrap_or(crate::PAILLIER_KEY_SIZE)
+    }
+}
+
+/// Token of a refresh message set that passed all the checks of
+/// [RefreshMessage::collect_verify_only], carrying the public data of the refreshed key.
+#[derive(Clone, Debug)]
+pub struct VerifiedRefreshSet<E: Curve> {
+    messages_digest: BigInt,
+    context_digest: BigInt,
+    key_fingerprint: BigInt,
+    party_index: u16,
+    public_key: Point<E>,
+    pk_vec: Vec<Point<E>>,
+    vss_scheme: VerifiableSS<E>,
+    new_n: u16,
+}
+
+impl<E: Curve> VerifiedRefreshSet<E> {
+    /// The joint public key of the refreshed key.
+    pub fn public_key(&self) -> &Point<E> {
+        &self.public_key
+    }
+
+    pub fn pk_vec(&self) -> &[Point<E>] {
+        &self.pk_vec
+    }
+
+    pub fn vss_scheme(&self) -> &VerifiableSS<E> {
+        &self.vss_scheme
+    }
+
+    pub fn messages_digest(&self) -> &BigInt {
+        &self.messages_digest
+    }
+}
+
+/// Token of a refresh message set that passed all the checks of
+/// [RefreshMessage::verify_public], which only need the public data of the committee. Clones
+/// share the verified messages, so a node verifies once and finalizes for every party it
+/// holds a key of, see [VerifiedRefresh::finalize].
+#[derive(Clone, Debug)]
+pub struct VerifiedRefresh<E: Curve, H: Digest + Clone, const M: usize> {
+    messages: Arc<[VerifiedRefreshMessage<E, H, M>]>,
+    context_digest: BigInt,
+    old_t: u16,
+    old_public_key: Point<E>,
+    public_key: Point<E>,
+    pk_vec: Vec<Point<E>>,
+    vss_scheme: VerifiableSS<E>,
+    new_n: u16,
+}
+
+impl<E: Curve, H: Digest + Clone, const M: usize> VerifiedRefresh<E, H, M> {
+    /// The joint public key of the refreshed key.
+    pub fn public_key(&self) -> &Point<E> {
+        &self.public_key
+    }
+
+    pub fn pk_vec(&self) -> &[Point<E>] {
+        &self.pk_vec
+    }
+
+    pub fn vss_scheme(&self) -> &VerifiableSS<E> {
+        &self.vss_scheme
+    }
+
+    /// Decrypts the new share of `old_key` and returns the refreshed key, without verifying
+    /// any proof again. `old_key` has to be a key of the committee the messages were verified
+    /// for, and is left untouched.
+    pub fn finalize(
+        self,
+        old_key: &LocalKey<E>,
+        new_dk: DecryptionKey,
+    ) -> FsDkrResult<LocalKey<E>> {
+        validate_local_key(old_key)?;
+        if RefreshContext::new(old_key, self.new_n).digest::<H>() != self.context_digest
+            || old_key.t != self.old_t
+            || old_key.y_sum_s != self.old_public_key
+        {
+            return Err(FsDkrError::RefreshContextMismatch);
+        }
+        let messages: Vec<&RefreshMessage<E, H, M>> =
+            self.messages.iter().map(|msg| msg.message()).collect();
+        let verified_set = VerifiedRefreshSet {
+            messages_digest: RefreshMessage::messages_digest(&messages, &[]),
+            context_digest: self.context_digest,
+            key_fingerprint: key_fingerprint::<E, H>(old_key),
+            party_index: old_key.i,
+            public_key: self.public_key,
+            pk_vec: self.pk_vec,
+            vss_scheme: self.vss_scheme,
+            new_n: self.new_n,
+        };
+        let mut new_key = old_key.clone();
+        RefreshMessage::decrypt_and_update(
+            &self.messages,
+            &mut new_key,
+            new_dk,
+            &[],
+            verified_set,
+        )?;
+        Ok(new_key)
+    }
+}
+
+/// Checks that the new Paillier modulus of `party_index` has at least `required_bits` bits, up
+/// to the one bit key generation may fall short by, and at most
+/// [MAX_PAILLIER_KEY_SIZE](crate::MAX_PAILLIER_KEY_SIZE).
+pub(crate) fn check_modulus_size(
+    party_index: u16,
+    ek: &EncryptionKey,
+    required_bits: usize,
+) -> FsDkrResult<()> {
+    let got_bits = ek.n.bit_length();
+    if got_bits + 1 < required_bits {
+        return Err(FsDkrError::ModuliTooSmall {
+            party_index,
+            got_bits,
+            required_bits,
+        });
+    }
+    if got_bits > crate::MAX_PAILLIER_KEY_SIZE {
+        return Err(FsDkrError::ModulusTooLarge {
+            party_index,
+            got_bits,
+            max_bits: crate::MAX_PAILLIER_KEY_SIZE,
+        });
+    }
+    Ok(())
+}
+
+/// Bound of the trial division in [validate_encryption_keys].
+const SMALL_FACTOR_BOUND: u32 = 1 << 16;
+
+/// The primes below [SMALL_FACTOR_BOUND], sieved on first use.
+fn small_primes() -> &'static [u32] {
+    static PRIMES: OnceLock<Vec<u32>> = OnceLock::new();
+    PRIMES.get_or_init(|| {
+        let bound = SMALL_FACTOR_BOUND as usize;
+        let mut composite = vec![false; bound];
+        let mut primes = Vec::new();
+        for p in 2..bound {
+            if !composite[p] {
+                primes.push(p as u32);
+                for multiple in (p * p..bound).step_by(p) {
+                    composite[multiple] = true;
+                }
+            }
+        }
+        primes
+    })
+}
+
+/// Why the modulus `n` is unfit for Paillier encryption regardless of its size, if it is.
+fn weak_modulus_reason(n: &BigInt, primes: &[u32]) -> Option<&'static str> {
+    if !n.test_bit(0) {
+        return Some("even modulus");
+    }
+    if primes
+        .iter()
+        .any(|p| BigInt::modulus(n, &BigInt::from(*p)).is_zero())
+    {
+        return Some("modulus with a small factor");
+    }
+    // an odd perfect power is a perfect k-th power for some odd prime k, below the bit length
+    // of `n` since its root is at least 3
+    let bits = n.bit_length() as u32;
+    let perfect_power = primes
+        .iter()
+        .skip(1)
+        .take_while(|k| **k < bits)
+        .any(|k| &n.nth_root(*k).pow(*k) == n);
+    if perfect_power {
+        return Some("modulus is a perfect power");
+    }
+    None
+}
+
+/// [RefreshMessage::validate_receiver_keys], which does not depend on the message type.
+pub(crate) fn validate_receiver_keys(
+    paillier_key_vec: &[EncryptionKey],
+    new_n: u16,
+    burned_eks: &[EncryptionKey],
+    min_bits: usize,
+) -> FsDkrResult<()> {
+    let primes = small_primes();
+    let mut failures = Vec::new();
+    let mut fail = |k: usize, reason: &str| {
+        failures.push(ReceiverKeyFailure {
+            party_index: k as u16 + 1,
+            reason: reason.to_string(),
+        })
+    };
+
+    for k in 0..new_n as usize {
+        let ek = match paillier_key_vec.get(k) {
+            Some(ek) => ek,
+            None => {
+                fail(k, "missing encryption key");
+                continue;
+            }
+        };
+        let n_length = ek.n.bit_length();
+        if n_length > crate::MAX_PAILLIER_KEY_SIZE || n_length + 1 < min_bits {
+            fail(k, "modulus size out of range");
+        } else if let Some(reason) = weak_modulus_reason(&ek.n, primes) {
+            fail(k, reason);
+        }
+        let shared = paillier_key_vec
+            .iter()
+            .take(new_n as usize)
+            .enumerate()
+            .any(|(other, other_ek)| other != k && other_ek.n == ek.n);
+        if shared {
+            fail(k, "modulus shared with another receiver");
+        }
+        if burned_eks.iter().any(|burned| burned.n == ek.n) {
+            fail(k, "burned encryption key");
+        }
+    }
+
+    if failures.is_empty() {
+        Ok(())
+    } else {
+        Err(FsDkrError::WeakReceiverKeys { failures })
+    }
+}
+
+/// Checks every key of `eks`, the key of party `k + 1` at position `k`, before anything is
+/// encrypted to it: the modulus has to be odd, of at least `min_bits` bits (up to the one bit
+/// key generation may fall short by) and at most
+/// [MAX_PAILLIER_KEY_SIZE](crate::MAX_PAILLIER_KEY_SIZE), not a perfect power, without a prime
+/// factor below 2^16 and distinct from the others. All the failing keys are reported at once in
+/// [FsDkrError::WeakReceiverKeys].
+pub fn validate_encryption_keys(eks: &[EncryptionKey], min_bits: usize) -> FsDkrResult<()> {
+    validate_receiver_keys(eks, eks.len() as u16, &[], min_bits)
+}
+
+/// Version of the [RefreshMessage] wire format. Version 2 stopped sending the committed
+/// sub-shares, which are derived from the coefficient commitments instead. The version is the
+/// first field of every encoding, and a message of any other version is rejected with
+/// [FsDkrError::UnsupportedVersion] before anything else is looked at.
+pub const REFRESH_MESSAGE_VERSION: u16 = 2;
+
+/// Largest metadata a [RefreshMessage] can carry, see [RefreshParams::metadata].
+pub const MAX_METADATA_LEN: usize = 256;
+
 // Everything here can be broadcasted
+/// A refresh message as it comes out of the network or of deserialization. Building one, with
+/// [RefreshMessage::new] or through serde, only checks that it is well formed: the vectors have
+/// one entry per receiver, the sender is one of the receivers and the commitments match the
+/// threshold. None of its proofs are checked until [RefreshMessage::verify] turns it into a
+/// [VerifiedRefreshMessage].
 #[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(try_from = "RefreshMessageParts<E, H, M>")]
 pub struct RefreshMessage<E: Curve, H: Digest + Clone, const M: usize> {
+    version: u16,
     pub(crate) old_party_index: u16,
     pub(crate) party_index: u16,
     pdl_proof_vec: Vec<PDLwSlackProof<E, H>>,
     range_proofs: Vec<AliceProof<E, H>>,
     coefficients_committed_vec: VerifiableSS<E>,
+    /// The sub-shares committed by `coefficients_committed_vec`, derived when the message is
+    /// built rather than sent.
+    #[serde(skip_serializing)]
     pub(crate) points_committed_vec: Vec<Point<E>>,
     points_encrypted_vec: Vec<BigInt>,
     dk_correctness_proof: NiCorrectKeyProof,
@@ -42,197 +403,1377 @@ pub struct RefreshMessage<E: Curve, H: Digest + Clone, const M: usize> {
     pub(crate) public_key: Point<E>,
     pub(crate) ring_pedersen_statement: RingPedersenStatement<E, H>,
     pub(crate) ring_pedersen_proof: RingPedersenProof<E, H, M>,
+    pub(crate) basis: SharingBasis,
+    pub(crate) index_remap: Option<IndexRemap>,
+    pub(crate) tweak_point: Option<Point<E>>,
+    pub(crate) session_binding: Option<SessionBinding<E>>,
+    metadata: Vec<u8>,
     #[serde(skip)]
     pub hash_choice: HashChoice<H>,
 }
 
+/// The content of a [RefreshMessage], before [RefreshMessage::new] checks that it is structurally
+/// valid. A [RefreshMessage] is deserialized through this type, so it can not be built from
+/// inconsistent data.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct RefreshMessageParts<E: Curve, H: Digest + Clone, const M: usize> {
+    /// Has to be [REFRESH_MESSAGE_VERSION]. Messages from before the version was introduced
+    /// read as version 0.
+    #[serde(default)]
+    pub version: u16,
+    pub old_party_index: u16,
+    pub party_index: u16,
+    pub pdl_proof_vec: Vec<PDLwSlackProof<E, H>>,
+    pub range_proofs: Vec<AliceProof<E, H>>,
+    pub coefficients_committed_vec: VerifiableSS<E>,
+    pub points_encrypted_vec: Vec<BigInt>,
+    pub dk_correctness_proof: NiCorrectKeyProof,
+    pub dlog_statement: DLogStatement,
+    pub ek: EncryptionKey,
+    pub remove_party_indices: Vec<u16>,
+    pub public_key: Point<E>,
+    pub ring_pedersen_statement: RingPedersenStatement<E, H>,
+    pub ring_pedersen_proof: RingPedersenProof<E, H, M>,
+    #[serde(default)]
+    pub basis: SharingBasis,
+    #[serde(default)]
+    pub index_remap: Option<IndexRemap>,
+    #[serde(default)]
+    pub tweak_point: Option<Point<E>>,
+    #[serde(default)]
+    pub session_binding: Option<SessionBinding<E>>,
+    #[serde(default)]
+    pub metadata: Vec<u8>,
+}
+
+impl<E: Curve, H: Digest + Clone, const M: usize> TryFrom<RefreshMessageParts<E, H, M>>
+    for RefreshMessage<E, H, M>
+{
+    type Error = FsDkrError;
+
+    fn try_from(parts: RefreshMessageParts<E, H, M>) -> FsDkrResult<Self> {
+        RefreshMessage::new(parts)
+    }
+}
+
+/// Messages are equal when their [fingerprints](RefreshMessage::fingerprint) are.
+impl<E: Curve, H: Digest + Clone, const M: usize> PartialEq for RefreshMessage<E, H, M> {
+    fn eq(&self, other: &Self) -> bool {
+        // compares every byte, whichever differs
+        self.fingerprint()
+            .iter()
+            .zip(other.fingerprint().iter())
+            .fold(0u8, |diff, (a, b)| diff | (a ^ b))
+            == 0
+    }
+}
+
+impl<E: Curve, H: Digest + Clone, const M: usize> Eq for RefreshMessage<E, H, M> {}
+
+impl<E: Curve, H: Digest + Clone, const M: usize> Hash for RefreshMessage<E, H, M> {
+    fn hash<S: Hasher>(&self, state: &mut S) {
+        self.fingerprint().hash(state);
+    }
+}
+
+/// What a [RefreshMessage] is verified against: the receivers' Paillier keys and ring-Pedersen
+/// parameters, the current public shares and, optionally, the id of the refresh session. Within
+/// a session every message has to be bound to it, see [RefreshMessage::finalize].
+#[derive(Clone, Debug)]
+pub struct RefreshContext<E: Curve> {
+    new_n: u16,
+    /// The receivers whose ciphertexts are checked; a new party only knows its own keys.
+    receivers: Vec<Option<(EncryptionKey, DLogStatement)>>,
+    old_pk_vec: Option<Vec<Point<E>>>,
+    session_id: Option<BigInt>,
+    /// Not part of the digest: a local policy, not something the senders agree on.
+    min_modulus_bits: usize,
+}
+
+impl<E: Curve> RefreshContext<E> {
+    /// The context of a member of the current committee holding `local_key`, for a refresh to
+    /// `new_n` parties.
+    pub fn new(local_key: &LocalKey<E>, new_n: u16) -> Self {
+        RefreshContext {
+            new_n,
+            receivers: local_key
+                .paillier_key_vec
+                .iter()
+                .zip(local_key.h1_h2_n_tilde_vec.iter())
+                .take(new_n as usize)
+                .map(|(ek, dlog_statement)| Some((ek.clone(), dlog_statement.clone())))
+                .collect(),
+            old_pk_vec: Some(local_key.pk_vec.clone()),
+            session_id: None,
+            min_modulus_bits: crate::PAILLIER_KEY_SIZE,
+        }
+    }
+
+    /// The context of an observer of the committee described by `committee`, for a refresh to
+    /// `new_n` parties.
+    pub fn for_observer(committee: &CommitteePublicData<E>, new_n: u16) -> Self {
+        RefreshContext {
+            new_n,
+            receivers: committee
+                .paillier_key_vec
+                .iter()
+                .zip(committee.h1_h2_n_tilde_vec.iter())
+                .take(new_n as usize)
+                .map(|(ek, dlog_statement)| Some((ek.clone(), dlog_statement.clone())))
+                .collect(),
+            old_pk_vec: Some(committee.pk_vec.clone()),
+            session_id: None,
+            min_modulus_bits: crate::PAILLIER_KEY_SIZE,
+        }
+    }
+
+    /// The context of a party joining at `party_index`, which can only check its own ciphertexts.
+    pub fn for_new_party(
+        party_index: u16,
+        ek: &EncryptionKey,
+        dlog_statement: &DLogStatement,
+        new_n: u16,
+    ) -> Self {
+        let receivers = (1..=new_n)
+            .map(|i| {
+                if i == party_index {
+                    Some((ek.clone(), dlog_statement.clone()))
+                } else {
+                    None
+                }
+            })
+            .collect();
+        RefreshContext {
+            new_n,
+            receivers,
+            old_pk_vec: None,
+            session_id: None,
+            min_modulus_bits: crate::PAILLIER_KEY_SIZE,
+        }
+    }
+
+    /// The context of a key generation among the parties holding `receivers`, in party index
+    /// order, see [crate::dkg]. There are no current public shares to check against.
+    pub(crate) fn for_dkg(receivers: Vec<(EncryptionKey, DLogStatement)>) -> Self {
+        RefreshContext {
+            new_n: receivers.len() as u16,
+            receivers: receivers.into_iter().map(Some).collect(),
+            old_pk_vec: None,
+            session_id: None,
+            min_modulus_bits: crate::PAILLIER_KEY_SIZE,
+        }
+    }
+
+    /// Stops checking the ciphertexts of every receiver but `party_index`.
+    pub(crate) fn own_receiver_only(mut self, party_index: u16) -> Self {
+        for (k, receiver) in self.receivers.iter_mut().enumerate() {
+            if k + 1 != party_index as usize {
+                *receiver = None;
+            }
+        }
+        self
+    }
+
+    pub fn with_session_id(mut self, session_id: BigInt) -> Self {
+        self.session_id = Some(session_id);
+        self
+    }
+
+    /// Rejects new Paillier moduli shorter than `bits`, see [CollectOptions::with_min_modulus_bits].
+    pub fn with_min_modulus_bits(mut self, bits: usize) -> Self {
+        self.min_modulus_bits = bits;
+        self
+    }
+
+    pub fn new_n(&self) -> u16 {
+        self.new_n
+    }
+
+    pub fn digest<H: Digest + Clone>(&self) -> BigInt {
+        let mut hash = H::new().chain_bigint(&BigInt::from(self.new_n as i32));
+        for receiver in self.receivers.iter() {
+            hash = match receiver {
+                Some((ek, dlog_statement)) => hash
+                    .chain_bigint(&ek.n)
+                    .chain_bigint(&dlog_statement.N)
+                    .chain_bigint(&dlog_statement.g)
+                    .chain_bigint(&dlog_statement.ni),
+                None => hash.chain_bigint(&BigInt::zero()),
+            };
+        }
+        for pk in self.old_pk_vec.iter().flatten() {
+            hash = hash.chain_bigint(&BigInt::from_bytes(&pk.to_bytes(true)));
+        }
+        if let Some(session_id) = &self.session_id {
+            hash = hash.chain_bigint(session_id);
+        }
+        hash.result_bigint()
+    }
+}
+
+/// A [RefreshMessage] that passed [RefreshMessage::verify]. Only verified messages are combined
+/// into a new key. The message remembers the [RefreshContext] it was verified in, so it can not
+/// be applied to another key or in another session.
+///
+/// Unverified messages are rejected at compile time:
+///
+/// ```compile_fail
+/// use curv::elliptic::curves::Secp256k1;
+/// use fs_dkr::refresh_message::{DecryptionKey, RefreshMessage};
+/// use multi_party_ecdsa::protocols::multi_party_ecdsa::gg_2020::state_machine::keygen::LocalKey;
+/// use sha2::Sha256;
+///
+/// fn forgot_to_verify(
+///     messages: &[RefreshMessage<Secp256k1, Sha256, 256>],
+///     local_key: &mut LocalKey<Secp256k1>,
+///     new_dk: DecryptionKey,
+/// ) {
+///     RefreshMessage::apply_refresh(messages, local_key, new_dk, &[], None);
+/// }
+/// ```
+#[derive(Clone, Debug)]
+pub struct VerifiedRefreshMessage<E: Curve, H: Digest + Clone, const M: usize> {
+    message: RefreshMessage<E, H, M>,
+    context_digest: BigInt,
+}
+
+impl<E: Curve, H: Digest + Clone, const M: usize> VerifiedRefreshMessage<E, H, M> {
+    pub fn message(&self) -> &RefreshMessage<E, H, M> {
+        &self.message
+    }
+
+    /// Digest of the [RefreshContext] the message was verified in.
+    pub fn context_digest(&self) -> &BigInt {
+        &self.context_digest
+    }
+
+    /// Gives the message back, dropping the proof that it was verified.
+    pub fn into_unverified(self) -> RefreshMessage<E, H, M> {
+        self.message
+    }
+}
+
+impl<E: Curve, H: Digest + Clone, const M: usize> Deref for VerifiedRefreshMessage<E, H, M> {
+    type Target = RefreshMessage<E, H, M>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.message
+    }
+}
+
 impl<E: Curve, H: Digest + Clone, const M: usize> RefreshMessage<E, H, M> {
+    /// Builds a message from `parts`, checking that the per-receiver vectors all have one entry
+    /// per receiver, that the sender's new index is one of the receivers, that the commitments match the threshold and the number of receivers, that
+    /// no commitment is the point at infinity, and that the ciphertexts and the Paillier key are
+    /// well-formed integers. The committed sub-shares are derived from the commitments here.
+    pub fn new(parts: RefreshMessageParts<E, H, M>) -> FsDkrResult<Self> {
+        let invalid = |reason: &str| FsDkrError::InvalidRefreshMessage {
+            party_index: parts.party_index,
+            reason: reason.to_string(),
+        };
+        if parts.version != REFRESH_MESSAGE_VERSION {
+            return Err(FsDkrError::UnsupportedVersion {
+                found: parts.version,
+                supported: REFRESH_MESSAGE_VERSION,
+            });
+        }
+        if parts.party_index == 0 || parts.old_party_index == 0 {
+            return Err(invalid("party index 0"));
+        }
+        if parts.metadata.len() > MAX_METADATA_LEN {
+            return Err(invalid("metadata too long"));
+        }
+
+        let new_n = parts.points_encrypted_vec.len();
+        if new_n == 0 {
+            return Err(invalid("no receivers"));
+        }
+        if parts.party_index as usize > new_n {
+            return Err(invalid("party index out of range"));
+        }
+        if parts.pdl_proof_vec.len() != new_n || parts.range_proofs.len() != new_n {
+            return Err(invalid("per-receiver vectors of different lengths"));
+        }
+
+        if parts
+            .points_encrypted_vec
+            .iter()
+            .any(|ciphertext| ciphertext <= &BigInt::zero())
+        {
+            return Err(invalid("ciphertext out of range"));
+        }
+        if parts.ek.n <= BigInt::zero() || parts.ek.nn != &parts.ek.n * &parts.ek.n {
+            return Err(invalid("malformed Paillier key"));
+        }
+
+        let vss = &parts.coefficients_committed_vec;
+        if vss.commitments.is_empty()
+            || vss.commitments.len() != vss.parameters.threshold as usize + 1
+            || vss.parameters.share_count as usize != new_n
+        {
+            return Err(invalid("commitments do not match the sharing parameters"));
+        }
+        let is_zero = |point: &Point<E>| point.is_zero();
+        if vss.commitments.iter().any(is_zero)
+            || parts.public_key.is_zero()
+            || parts.tweak_point.as_ref().map_or(false, is_zero)
+        {
+            return Err(invalid("point at infinity"));
+        }
+
+        let points_committed_vec = (1..=new_n as u16)
+            .map(|i| vss.get_point_commitment(i))
+            .collect();
+
+        Ok(RefreshMessage {
+            version: parts.version,
+            old_party_index: parts.old_party_index,
+            party_index: parts.party_index,
+            pdl_proof_vec: parts.pdl_proof_vec,
+            range_proofs: parts.range_proofs,
+            coefficients_committed_vec: parts.coefficients_committed_vec,
+            points_committed_vec,
+            points_encrypted_vec: parts.points_encrypted_vec,
+            dk_correctness_proof: parts.dk_correctness_proof,
+            dlog_statement: parts.dlog_statement,
+            ek: parts.ek,
+            remove_party_indices: parts.remove_party_indices,
+            public_key: parts.public_key,
+            ring_pedersen_statement: parts.ring_pedersen_statement,
+            ring_pedersen_proof: parts.ring_pedersen_proof,
+            basis: parts.basis,
+            index_remap: parts.index_remap,
+            tweak_point: parts.tweak_point,
+            session_binding: parts.session_binding,
+            metadata: parts.metadata,
+            hash_choice: HashChoice::new(),
+        })
+    }
+
+    /// The content of the message, dropping the committed sub-shares derived by
+    /// [RefreshMessage::new].
+    pub fn into_parts(self) -> RefreshMessageParts<E, H, M> {
+        RefreshMessageParts {
+            version: self.version,
+            old_party_index: self.old_party_index,
+            party_index: self.party_index,
+            pdl_proof_vec: self.pdl_proof_vec,
+            range_proofs: self.range_proofs,
+            coefficients_committed_vec: self.coefficients_committed_vec,
+            points_encrypted_vec: self.points_encrypted_vec,
+            dk_correctness_proof: self.dk_correctness_proof,
+            dlog_statement: self.dlog_statement,
+            ek: self.ek,
+            remove_party_indices: self.remove_party_indices,
+            public_key: self.public_key,
+            ring_pedersen_statement: self.ring_pedersen_statement,
+            ring_pedersen_proof: self.ring_pedersen_proof,
+            basis: self.basis,
+            index_remap: self.index_remap,
+            tweak_point: self.tweak_point,
+            session_binding: self.session_binding,
+            metadata: self.metadata,
+        }
+    }
+
+    /// The compact canonical encoding of the message, see [crate::compact].
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.encode(self.session_binding.as_ref())
+    }
+
+    /// [RefreshMessage::to_bytes] with `session_binding` in place of the binding of the message.
+    fn encode(&self, session_binding: Option<&SessionBinding<E>>) -> Vec<u8> {
+        let mut writer = Writer::default();
+        writer.u8(COMPACT_FORMAT_VERSION);
+        writer.u16(self.version);
+        writer.u16(self.old_party_index);
+        writer.u16(self.party_index);
+        let vss = &self.coefficients_committed_vec;
+        writer.u16(self.points_encrypted_vec.len() as u16);
+        writer.u16(vss.parameters.threshold);
+        for commitment in vss.commitments.iter() {
+            writer.point(commitment);
+        }
+        for ciphertext in self.points_encrypted_vec.iter() {
+            writer.int(ciphertext);
+        }
+        for proof in self.pdl_proof_vec.iter() {
+            proof.write_compact(&mut writer);
+        }
+        for proof in self.range_proofs.iter() {
+            proof.write_compact(&mut writer);
+        }
+        writer.count(self.dk_correctness_proof.sigma_vec.len());
+        for sigma in self.dk_correctness_proof.sigma_vec.iter() {
+            writer.int(sigma);
+        }
+        writer.int(&self.dlog_statement.N);
+        writer.int(&self.dlog_statement.g);
+        writer.int(&self.dlog_statement.ni);
+        writer.int(&self.ek.n);
+        writer.count(self.remove_party_indices.len());
+        for index in self.remove_party_indices.iter() {
+            writer.u16(*index);
+        }
+        writer.point(&self.public_key);
+        self.ring_pedersen_statement.write_compact(&mut writer);
+        self.ring_pedersen_proof.write_compact(&mut writer);
+        match &self.basis {
+            SharingBasis::Linear => writer.u8(0),
+            SharingBasis::LagrangeWeighted { quorum } => {
+                writer.u8(1);
+                writer.count(quorum.len());
+                for index in quorum.iter() {
+                    writer.u16(*index);
+                }
+            }
+        }
+        match &self.index_remap {
+            None => writer.u8(0),
+            Some(remap) => {
+                writer.u8(1);
+                writer.count(remap.len() as usize);
+                for (old_index, new_index) in remap.iter() {
+                    writer.u16(old_index);
+                    writer.u16(new_index);
+                }
+            }
+        }
+        match &self.tweak_point {
+            None => writer.u8(0),
+            Some(tweak_point) => {
+                writer.u8(1);
+                writer.point(tweak_point);
+            }
+        }
+        match session_binding {
+            None => writer.u8(0),
+            Some(binding) => {
+                writer.u8(1);
+                binding.write_compact(&mut writer);
+            }
+        }
+        writer.count(self.metadata.len());
+        for byte in self.metadata.iter() {
+            writer.u8(*byte);
+        }
+        writer.into_bytes()
+    }
+
+    /// Decodes a message of [RefreshMessage::to_bytes] that has to have `new_n` receivers, and
+    /// checks it with [RefreshMessage::new].
+    pub fn from_bytes(bytes: &[u8], new_n: u16) -> FsDkrResult<Self> {
+        let invalid = |reason: &str| FsDkrError::InvalidEncoding {
+            reason: reason.to_string(),
+        };
+        let mut reader = Reader::new(bytes);
+        if reader.u8()? != COMPACT_FORMAT_VERSION {
+            return Err(invalid("unsupported format version"));
+        }
+        let version = reader.u16()?;
+        if version != REFRESH_MESSAGE_VERSION {
+            return Err(FsDkrError::UnsupportedVersion {
+                found: version,
+                supported: REFRESH_MESSAGE_VERSION,
+            });
+        }
+        let old_party_index = reader.u16()?;
+        let party_index = reader.u16()?;
+        if reader.u16()? != new_n {
+            return Err(invalid("unexpected number of receivers"));
+        }
+        let threshold = reader.u16()?;
+        if threshold >= new_n {
+            return Err(invalid("threshold not below the number of receivers"));
+        }
+        let commitments = (0..=threshold)
+            .map(|_| reader.point())
+            .collect::<FsDkrResult<_>>()?;
+        let points_encrypted_vec = (0..new_n)
+            .map(|_| reader.int())
+            .collect::<FsDkrResult<_>>()?;
+        let pdl_proof_vec = (0..new_n)
+            .map(|_| PDLwSlackProof::read_compact(&mut reader))
+            .collect::<FsDkrResult<_>>()?;
+        let range_proofs = (0..new_n)
+            .map(|_| AliceProof::read_compact(&mut reader))
+            .collect::<FsDkrResult<_>>()?;
+        let sigma_vec = (0..reader.count(None)?)
+            .map(|_| reader.int())
+            .collect::<FsDkrResult<_>>()?;
+        let dlog_statement = DLogStatement {
+            N: reader.int()?,
+            g: reader.int()?,
+            ni: reader.int()?,
+        };
+        let ek = EncryptionKey::from(&reader.int()?);
+        let remove_party_indices = (0..reader.count(None)?)
+            .map(|_| reader.u16())
+            .collect::<FsDkrResult<_>>()?;
+        let public_key = reader.point()?;
+        let ring_pedersen_statement = RingPedersenStatement::read_compact(&mut reader)?;
+        let ring_pedersen_proof = RingPedersenProof::read_compact(&mut reader)?;
+        let basis = match reader.u8()? {
+            0 => SharingBasis::Linear,
+            1 => SharingBasis::LagrangeWeighted {
+                quorum: (0..reader.count(None)?)
+                    .map(|_| reader.u16())
+                    .collect::<FsDkrResult<_>>()?,
+            },
+            _ => return Err(invalid("unknown sharing basis")),
+        };
+        let index_remap = match reader.u8()? {
+            0 => None,
+            1 => {
+                let old_to_new = (0..reader.count(None)?)
+                    .map(|_| Ok((reader.u16()?, reader.u16()?)))
+                    .collect::<FsDkrResult<HashMap<_, _>>>()?;
+                Some(IndexRemap::new(old_to_new)?)
+            }
+            _ => return Err(invalid("invalid option tag")),
+        };
+        let tweak_point = match reader.u8()? {
+            0 => None,
+            1 => Some(reader.point()?),
+            _ => return Err(invalid("invalid option tag")),
+        };
+        let session_binding = match reader.u8()? {
+            0 => None,
+            1 => Some(SessionBinding::read_compact(&mut reader)?),
+            _ => return Err(invalid("invalid option tag")),
+        };
+        let metadata = (0..reader.count(None)?)
+            .map(|_| reader.u8())
+            .collect::<FsDkrResult<_>>()?;
+        reader.finish()?;
+
+        RefreshMessage::new(RefreshMessageParts {
+            version,
+            old_party_index,
+            party_index,
+            pdl_proof_vec,
+            range_proofs,
+            coefficients_committed_vec: VerifiableSS {
+                parameters: ShamirSecretSharing {
+                    threshold,
+                    share_count: new_n,
+                },
+                commitments,
+            },
+            points_encrypted_vec,
+            dk_correctness_proof: NiCorrectKeyProof { sigma_vec },
+            dlog_statement,
+            ek,
+            remove_party_indices,
+            public_key,
+            ring_pedersen_statement,
+            ring_pedersen_proof,
+            basis,
+            index_remap,
+            tweak_point,
+            session_binding,
+            metadata,
+        })
+    }
+
+    /// Checks the encryption keys of the first `new_n` receivers before anything is encrypted to
+    /// them, see [validate_encryption_keys], with moduli of at least `min_bits` bits. A modulus
+    /// also must not be among `burned_eks`. All the failing receivers are reported at once.
+    pub fn validate_receiver_keys(
+        paillier_key_vec: &[EncryptionKey],
+        new_n: u16,
+        burned_eks: &[EncryptionKey],
+        min_bits: usize,
+    ) -> FsDkrResult<()> {
+        validate_receiver_keys(paillier_key_vec, new_n, burned_eks, min_bits)
+    }
+
     pub fn distribute(
         old_party_index: u16,
-        local_key: &mut LocalKey<E>,
+        local_key: &LocalKey<E>,
+        new_n: u16,
+    ) -> FsDkrResult<(RefreshMessage<E, H, M>, DecryptionKey)> {
+        Self::distribute_with_basis(old_party_index, local_key, new_n, SharingBasis::Linear)
+    }
+
+    /// Same as [RefreshMessage::distribute], resharing the secret selected by `basis`. With
+    /// [SharingBasis::LagrangeWeighted] only the members of the quorum distribute, and every
+    /// one of them has to use the same quorum.
+    pub fn distribute_with_basis(
+        old_party_index: u16,
+        local_key: &LocalKey<E>,
         new_n: u16,
+        basis: SharingBasis,
     ) -> FsDkrResult<(RefreshMessage<E, H, M>, DecryptionKey)> {
-        assert!(local_key.t <= new_n / 2);
-        let secret = local_key.keys_linear.x_i.clone();
+        let params = RefreshParams {
+            basis,
+            ..RefreshParams::default()
+        };
+        Self::distribute_with_params(old_party_index, local_key, new_n, &params)
+    }
+
+    /// Same as [RefreshMessage::distribute], with the sharing basis and key tweak of `params`.
+    pub fn distribute_with_params(
+        old_party_index: u16,
+        local_key: &LocalKey<E>,
+        new_n: u16,
+        params: &RefreshParams<E>,
+    ) -> FsDkrResult<(RefreshMessage<E, H, M>, DecryptionKey)> {
+        Self::prepare(old_party_index, local_key, new_n, params)?.into_unbound()
+    }
+
+    /// Offline phase of [RefreshMessage::distribute_with_params]: does all the expensive work
+    /// ahead of the session, see [crate::prepared_distribution]. The sub-shares, the encryption
+    /// randomness and every integer copy of them made for the proofs are wiped once the proofs
+    /// are produced.
+    pub fn prepare(
+        old_party_index: u16,
+        local_key: &LocalKey<E>,
+        new_n: u16,
+        params: &RefreshParams<E>,
+    ) -> FsDkrResult<PreparedDistribution<E, H, M>> {
+        validate_local_key(local_key)?;
+        Self::prepare_unchecked(old_party_index, local_key, new_n, params)
+    }
+
+    /// [RefreshMessage::prepare] for a dealer that holds no Paillier decryption key yet, see
+    /// [crate::dkg].
+    pub(crate) fn prepare_unchecked(
+        old_party_index: u16,
+        local_key: &LocalKey<E>,
+        new_n: u16,
+        params: &RefreshParams<E>,
+    ) -> FsDkrResult<PreparedDistribution<E, H, M>> {
+        let new_t = params.new_t.unwrap_or(local_key.t);
+        if new_t == 0 || new_t > new_n / 2 {
+            return Err(FsDkrError::InvalidRefreshMessage {
+                party_index: local_key.i,
+                reason: "new threshold out of range".to_string(),
+            });
+        }
+        if params.metadata.len() > MAX_METADATA_LEN {
+            return Err(FsDkrError::InvalidRefreshMessage {
+                party_index: local_key.i,
+                reason: "metadata too long".to_string(),
+            });
+        }
+        if params.paillier_modulus_bits < crate::PAILLIER_KEY_SIZE
+            || params.paillier_modulus_bits > crate::MAX_PAILLIER_KEY_SIZE
+        {
+            return Err(FsDkrError::InvalidRefreshMessage {
+                party_index: local_key.i,
+                reason: "Paillier modulus size out of range".to_string(),
+            });
+        }
+        trace_span!("distribute", sender = local_key.i, new_n = new_n);
+        let basis = params.basis.clone();
+        let tweaked_share = match &params.tweak {
+            Some(tweak) => &local_key.keys_linear.x_i + tweak,
+            None => local_key.keys_linear.x_i.clone(),
+        };
+        let secret = match &basis {
+            SharingBasis::Linear => tweaked_share,
+            SharingBasis::LagrangeWeighted { quorum } => {
+                Self::validate_quorum(quorum, local_key.t)?;
+                if !quorum.contains(&old_party_index) {
+                    return Err(FsDkrError::InvalidQuorum {
+                        quorum: quorum.clone(),
+                    });
+                }
+                lagrange_coefficient_at(&Scalar::<E>::zero(), old_party_index, quorum)
+                    * &tweaked_share
+            }
+        };
         // secret share old key
-        if new_n <= local_key.t {
+        if new_n <= new_t {
             return Err(FsDkrError::NewPartyUnassignedIndexError);
         }
-        let (vss_scheme, secret_shares) = VerifiableSS::<E>::share(local_key.t, new_n, &secret);
-
-        local_key.vss_scheme = vss_scheme.clone();
+        Self::validate_receiver_keys(
+            &local_key.paillier_key_vec,
+            new_n,
+            &[],
+            params.min_receiver_modulus_bits,
+        )?;
+        if local_key.h1_h2_n_tilde_vec.len() < new_n as usize
+            || local_key.i == 0
+            || local_key.i as usize > local_key.h1_h2_n_tilde_vec.len()
+        {
+            return Err(FsDkrError::InvalidLocalKey {
+                reason: "per-party vectors shorter than the committee".to_string(),
+            });
+        }
+        let (vss_scheme, secret_shares) = {
+            trace_span!("share_polynomial", sender = local_key.i, new_t = new_t);
+            VerifiableSS::<E>::share(new_t, new_n, &secret)
+        };
 
         // commit to points on the polynomial
         let points_committed_vec: Vec<_> = (0..secret_shares.len())
             .map(|i| Point::<E>::generator() * &secret_shares[i].clone().into())
             .collect();
 
-        // encrypt points on the polynomial using Paillier keys
-        let (points_encrypted_vec, randomness_vec): (Vec<_>, Vec<_>) = (0..secret_shares.len())
-            .map(|i| {
-                let randomness = BigInt::sample_below(&local_key.paillier_key_vec[i].n);
-                let ciphertext = Paillier::encrypt_with_chosen_randomness(
-                    &local_key.paillier_key_vec[i],
-                    RawPlaintext::from(secret_shares[i].to_bigint()),
-                    &Randomness::from(randomness.clone()),
-                )
-                .0
-                .into_owned();
-                (ciphertext, randomness)
-            })
-            .unzip();
+        // encrypt points on the polynomial using Paillier keys, with proofs for each
+        // {point_committed, point_encrypted} pair
+        let (points_encrypted_vec, pdl_proof_vec, range_proofs) =
+            Self::encrypt_sub_shares(local_key, &secret_shares[..], &points_committed_vec);
 
-        // generate PDL proofs for each {point_committed, point_encrypted} pair
-        let pdl_proof_vec: Vec<_> = (0..secret_shares.len())
-            .map(|i| {
-                let witness = PDLwSlackWitness {
-                    x: secret_shares[i].clone(),
-                    r: randomness_vec[i].clone(),
-                };
-                let statement = PDLwSlackStatement {
-                    ciphertext: points_encrypted_vec[i].clone(),
-                    ek: local_key.paillier_key_vec[i].clone(),
-                    Q: points_committed_vec[i].clone(),
-                    G: Point::<E>::generator().to_point(),
-                    h1: local_key.h1_h2_n_tilde_vec[i].g.clone(),
-                    h2: local_key.h1_h2_n_tilde_vec[i].ni.clone(),
-                    N_tilde: local_key.h1_h2_n_tilde_vec[i].N.clone(),
-                };
-                PDLwSlackProof::prove(&witness, &statement)
-            })
+        let (ek, dk, dk_correctness_proof) = {
+            trace_span!(
+                "paillier_keygen",
+                sender = local_key.i,
+                bits = params.paillier_modulus_bits,
+                safe_primes = params.use_safe_primes,
+            );
+            let keypair = if params.use_safe_primes {
+                Paillier::keypair_safe_primes_with_modulus_size(params.paillier_modulus_bits)
+            } else {
+                Paillier::keypair_with_modulus_size(params.paillier_modulus_bits)
+            };
+            let (ek, dk) = keypair.keys();
+            let dk_correctness_proof = NiCorrectKeyProof::proof(&dk, None);
+            (ek, dk, dk_correctness_proof)
+        };
+
+        let (ring_pedersen_statement, ring_pedersen_proof) = {
+            trace_span!("prove_ring_pedersen", sender = local_key.i);
+            let (statement, witness) = RingPedersenStatement::generate();
+            let proof = RingPedersenProof::prove(&witness, &statement);
+            (statement, proof)
+        };
+        let parts = RefreshMessageParts {
+            version: REFRESH_MESSAGE_VERSION,
+            old_party_index,
+            party_index: local_key.i,
+            pdl_proof_vec,
+            range_proofs,
+            coefficients_committed_vec: vss_scheme,
+            points_encrypted_vec,
+            dk_correctness_proof,
+            dlog_statement: local_key.h1_h2_n_tilde_vec[(local_key.i - 1) as usize].clone(),
+            ek,
+            remove_party_indices: Vec::new(),
+            public_key: local_key.y_sum_s.clone(),
+            ring_pedersen_statement,
+            ring_pedersen_proof,
+            basis,
+            index_remap: None,
+            tweak_point: params
+                .tweak
+                .as_ref()
+                .map(|tweak| Point::<E>::generator() * tweak),
+            session_binding: None,
+            metadata: params.metadata.clone(),
+        };
+        Ok(PreparedDistribution::new(
+            parts,
+            dk,
+            local_key.keys_linear.x_i.clone(),
+        ))
+    }
+
+    /// The ciphertext of the sub-share of every receiver with its PDL and range proofs, in
+    /// receiver order. With the `parallel` feature the receivers are handled on the rayon
+    /// thread pool; the output is the same either way.
+    pub(crate) fn encrypt_sub_shares(
+        local_key: &LocalKey<E>,
+        secret_shares: &[Scalar<E>],
+        points_committed_vec: &[Point<E>],
+    ) -> (
+        Vec<BigInt>,
+        Vec<PDLwSlackProof<E, H>>,
+        Vec<AliceProof<E, H>>,
+    ) {
+        let encrypt = |i: usize| {
+            Self::encrypt_sub_share(local_key, i, &secret_shares[i], &points_committed_vec[i])
+        };
+        #[cfg(feature = "parallel")]
+        let encrypted: Vec<_> = (0..secret_shares.len())
+            .into_par_iter()
+            .map(encrypt)
             .collect();
+        #[cfg(not(feature = "parallel"))]
+        let encrypted: Vec<_> = (0..secret_shares.len()).map(encrypt).collect();
 
-        let range_proofs = (0..secret_shares.len())
-            .map(|i| {
-                AliceProof::generate(
-                    &secret_shares[i].to_bigint(),
-                    &points_encrypted_vec[i],
-                    &local_key.paillier_key_vec[i],
-                    &local_key.h1_h2_n_tilde_vec[i],
-                    &randomness_vec[i],
-                )
-            })
+        let mut points_encrypted_vec = Vec::with_capacity(encrypted.len());
+        let mut pdl_proof_vec = Vec::with_capacity(encrypted.len());
+        let mut range_proofs = Vec::with_capacity(encrypted.len());
+        for (ciphertext, pdl_proof, range_proof) in encrypted {
+            points_encrypted_vec.push(ciphertext);
+            pdl_proof_vec.push(pdl_proof);
+            range_proofs.push(range_proof);
+        }
+        (points_encrypted_vec, pdl_proof_vec, range_proofs)
+    }
+
+    /// Encrypts `share` to the receiver at position `i` and proves the ciphertext. The
+    /// randomness is sampled here, so every receiver gets its own even across threads.
+    pub(crate) fn encrypt_sub_share(
+        local_key: &LocalKey<E>,
+        i: usize,
+        share: &Scalar<E>,
+        point_committed: &Point<E>,
+    ) -> (BigInt, PDLwSlackProof<E, H>, AliceProof<E, H>) {
+        let ek = &local_key.paillier_key_vec[i];
+        let dlog_statement = &local_key.h1_h2_n_tilde_vec[i];
+        let mut randomness = BigInt::sample_below(&ek.n);
+        let mut plaintext = share.to_bigint();
+        let ciphertext = {
+            trace_span!("encrypt", sender = local_key.i, receiver = i + 1);
+            Paillier::encrypt_with_chosen_randomness(
+                ek,
+                RawPlaintext::from(&plaintext),
+                &Randomness::from(&randomness),
+            )
+            .0
+            .into_owned()
+        };
+
+        let pdl_proof = {
+            trace_span!("prove_pdl", sender = local_key.i, receiver = i + 1);
+            let witness = PDLwSlackWitness {
+                x: share.clone(),
+                r: randomness.clone(),
+            };
+            let statement = PDLwSlackStatement {
+                ciphertext: ciphertext.clone(),
+                ek: ek.clone(),
+                Q: point_committed.clone(),
+                G: Point::<E>::generator().to_point(),
+                h1: dlog_statement.g.clone(),
+                h2: dlog_statement.ni.clone(),
+                N_tilde: dlog_statement.N.clone(),
+            };
+            PDLwSlackProof::prove(&witness, &statement)
+        };
+
+        let range_proof = {
+            trace_span!("prove_range", sender = local_key.i, receiver = i + 1);
+            AliceProof::generate(&plaintext, &ciphertext, ek, dlog_statement, &randomness)
+        };
+        // the witness wipes itself on drop
+        plaintext.zeroize();
+        randomness.zeroize();
+        (ciphertext, pdl_proof, range_proof)
+    }
+
+    /// Online phase: binds the message prepared by [RefreshMessage::prepare] to `session_id`,
+    /// signing the binding with the share of the sender. Consumes the prepared bundle, so it can
+    /// only be bound to one session, and nobody else can bind the message to another one.
+    pub fn finalize(
+        prepared: PreparedDistribution<E, H, M>,
+        session_id: &BigInt,
+    ) -> FsDkrResult<(RefreshMessage<E, H, M>, DecryptionKey)> {
+        prepared.into_bound(session_id)
+    }
+
+    pub fn validate_collect(refresh_messages: &[Self], t: u16, n: u16) -> FsDkrResult<()> {
+        // check we got at least threshold t refresh messages
+        if refresh_messages.len() <= t.into() {
+            return Err(FsDkrError::PartiesThresholdViolation {
+                threshold: t,
+                refreshed_keys: refresh_messages.len(),
+            });
+        }
+
+        // every message is consistent by construction (see [RefreshMessage::new]), the messages
+        // only have to agree on the number of receivers
+        let reference_len = refresh_messages[0].points_committed_vec.len();
+
+        for refresh_message in refresh_messages.iter() {
+            let pdl_proof_len = refresh_message.pdl_proof_vec.len();
+            let points_commited_len = refresh_message.points_committed_vec.len();
+            let points_encrypted_len = refresh_message.points_encrypted_vec.len();
+
+            if points_commited_len != reference_len || points_commited_len < n as usize {
+                return Err(FsDkrError::SizeMismatchError {
+                    party_index: refresh_message.party_index,
+                    pdl_proof_len,
+                    points_commited_len,
+                    points_encrypted_len,
+                });
+            }
+        }
+
+        Ok(())
+    }
+
+    /// The metadata the sender attached, see [RefreshParams::metadata].
+    pub fn metadata(&self) -> &[u8] {
+        &self.metadata
+    }
+
+    pub fn party_index(&self) -> u16 {
+        self.party_index
+    }
+
+    /// The number of receivers, which every per-receiver vector of the message matches.
+    pub fn recipient_count(&self) -> u16 {
+        self.points_encrypted_vec.len() as u16
+    }
+
+    /// The commitments to the coefficients of the sender's sharing polynomial.
+    pub fn vss_commitments(&self) -> &VerifiableSS<E> {
+        &self.coefficients_committed_vec
+    }
+
+    /// The commitments to the sub-share of every receiver, derived from the coefficients.
+    pub fn committed_points(&self) -> &[Point<E>] {
+        &self.points_committed_vec
+    }
+
+    /// The sub-share of every receiver, encrypted under the receiver's Paillier key.
+    pub fn encrypted_shares(&self) -> &[BigInt] {
+        &self.points_encrypted_vec
+    }
+
+    pub fn pdl_proofs(&self) -> &[PDLwSlackProof<E, H>] {
+        &self.pdl_proof_vec
+    }
+
+    pub fn range_proofs(&self) -> &[AliceProof<E, H>] {
+        &self.range_proofs
+    }
+
+    /// The threshold of the sender's sharing, see [RefreshParams::new_t].
+    pub fn new_t(&self) -> u16 {
+        self.coefficients_committed_vec.parameters.threshold
+    }
+
+    /// SHA-256 of the [compact encoding](RefreshMessage::to_bytes) of the message, which covers
+    /// every field including the proofs. Stable across versions of the crate as long as the
+    /// encoding is.
+    pub fn fingerprint(&self) -> [u8; 32] {
+        Sha256::digest(&self.to_bytes()).into()
+    }
+
+    /// What the session binding signs: the [fingerprint](RefreshMessage::fingerprint) of the
+    /// message with the binding left out, so every other field, the proofs included, is covered.
+    pub fn binding_fingerprint(&self) -> [u8; 32] {
+        Sha256::digest(&self.encode(None)).into()
+    }
+
+    /// Hash of the sharing the message contributes: the sender, the commitments, the
+    /// ciphertexts, the basis and tweak, the new Paillier key, the public key and the metadata.
+    /// It does not cover the proofs nor the ring-Pedersen parameters; to tell messages apart use
+    /// [RefreshMessage::fingerprint].
+    pub fn message_digest(&self) -> BigInt {
+        let point = |p: &Point<E>| BigInt::from_bytes(&p.to_bytes(true));
+        let mut hash = H::new()
+            .chain_bigint(&BigInt::from(self.old_party_index as i32))
+            .chain_bigint(&BigInt::from(self.party_index as i32));
+        for commitment in self.coefficients_committed_vec.commitments.iter() {
+            hash = hash.chain_bigint(&point(commitment));
+        }
+        for ciphertext in self.points_encrypted_vec.iter() {
+            hash = hash.chain_bigint(ciphertext);
+        }
+        if let SharingBasis::LagrangeWeighted { quorum } = &self.basis {
+            for index in quorum.iter() {
+                hash = hash.chain_bigint(&BigInt::from(*index as i32));
+            }
+        }
+        if let Some(tweak_point) = &self.tweak_point {
+            hash = hash.chain_bigint(&point(tweak_point));
+        }
+        if !self.metadata.is_empty() {
+            hash = hash
+                .chain_bigint(&BigInt::from(self.metadata.len() as u64))
+                .chain(&self.metadata);
+        }
+        hash.chain_bigint(&self.ek.n)
+            .chain_bigint(&point(&self.public_key))
+            .result_bigint()
+    }
+
+    /// Order-independent hash of the [fingerprints](RefreshMessage::fingerprint) of all the
+    /// refresh messages and of the encryption keys of all the join messages.
+    pub(crate) fn messages_digest(
+        refresh_messages: &[&Self],
+        join_messages: &[JoinMessage<E, H, M>],
+    ) -> BigInt {
+        let mut sorted: Vec<&Self> = refresh_messages.to_vec();
+        sorted.sort_by_key(|msg| (msg.party_index, msg.old_party_index));
+        let mut hash = H::new();
+        for msg in sorted {
+            hash = hash.chain(&msg.fingerprint());
+        }
+        let mut joins: Vec<_> = join_messages
+            .iter()
+            .map(|join_message| (join_message.party_index, join_message.ek.n.clone()))
+            .collect();
+        joins.sort_by(|a, b| a.0.cmp(&b.0));
+        for (party_index, n) in joins {
+            hash = hash
+                .chain_bigint(&BigInt::from(party_index.unwrap_or_default() as i32))
+                .chain_bigint(&n);
+        }
+        hash.result_bigint()
+    }
+
+    /// Hash of the public content of a set of refresh messages. The messages are ordered by
+    /// sender index first and exact copies count once, so the digest depends neither on the
+    /// order they were received in nor on a transport delivering one twice.
+    pub fn transcript_digest(refresh_messages: &[Self]) -> BigInt {
+        Self::transcript_digest_of(&refresh_messages.iter().collect::<Vec<_>>())
+    }
+
+    /// [RefreshMessage::transcript_digest] of borrowed messages.
+    pub(crate) fn transcript_digest_of(refresh_messages: &[&Self]) -> BigInt {
+        let mut sorted: Vec<([u8; 32], &Self)> = refresh_messages
+            .iter()
+            .map(|msg| (msg.fingerprint(), *msg))
             .collect();
+        sorted.sort_by_key(|(fingerprint, msg)| (msg.party_index, *fingerprint));
+        sorted.dedup_by_key(|(fingerprint, _)| *fingerprint);
 
-        let (ek, dk) = Paillier::keypair_with_modulus_size(crate::PAILLIER_KEY_SIZE).keys();
-        let dk_correctness_proof = NiCorrectKeyProof::proof(&dk, None);
+        let mut hash = H::new();
+        for (_, msg) in sorted {
+            hash = hash
+                .chain_bigint(&BigInt::from(msg.old_party_index as i32))
+                .chain_bigint(&BigInt::from(msg.party_index as i32));
+            for commitment in msg.coefficients_committed_vec.commitments.iter() {
+                hash = hash.chain_bigint(&BigInt::from_bytes(&commitment.to_bytes(true)));
+            }
+            for ciphertext in msg.points_encrypted_vec.iter() {
+                hash = hash.chain_bigint(ciphertext);
+            }
+            hash = hash
+                .chain_bigint(&msg.ek.n)
+                .chain_bigint(&BigInt::from_bytes(&msg.public_key.to_bytes(true)));
+            if !msg.metadata.is_empty() {
+                hash = hash
+                    .chain_bigint(&BigInt::from(msg.metadata.len() as u64))
+                    .chain(&msg.metadata);
+            }
+        }
+        hash.result_bigint()
+    }
 
-        let (ring_pedersen_statement, ring_pedersen_witness) = RingPedersenStatement::generate();
+    /// All the senders have to renumber the committee the same way, and every sender's new index
+    /// has to be the image of its old index.
+    pub(crate) fn validate_index_remap(refresh_messages: &[&Self], new_n: u16) -> FsDkrResult<()> {
+        let index_remap = match refresh_messages.first() {
+            Some(msg) => &msg.index_remap,
+            None => return Ok(()),
+        };
+        if refresh_messages
+            .iter()
+            .any(|msg| &msg.index_remap != index_remap)
+        {
+            return Err(FsDkrError::IndexRemapMismatch);
+        }
+        if let Some(index_remap) = index_remap {
+            index_remap.validate()?;
+            let consistent = index_remap.len() == new_n
+                && refresh_messages
+                    .iter()
+                    .all(|msg| index_remap.get(msg.old_party_index) == Some(msg.party_index));
+            if !consistent {
+                return Err(FsDkrError::IndexRemapMismatch);
+            }
+        }
+        Ok(())
+    }
 
-        let ring_pedersen_proof =
-            RingPedersenProof::prove(&ring_pedersen_witness, &ring_pedersen_statement);
-        Ok((
-            RefreshMessage {
-                old_party_index,
-                party_index: local_key.i,
-                pdl_proof_vec,
-                range_proofs,
-                coefficients_committed_vec: vss_scheme,
-                points_committed_vec,
-                points_encrypted_vec,
-                dk_correctness_proof,
-                dlog_statement: local_key.h1_h2_n_tilde_vec[(local_key.i - 1) as usize].clone(),
-                ek,
-                remove_party_indices: Vec::new(),
-                public_key: local_key.y_sum_s.clone(),
-                ring_pedersen_statement,
-                ring_pedersen_proof,
-                hash_choice: HashChoice::new(),
-            },
-            dk,
-        ))
+    /// A quorum has to hold more than `threshold` distinct party indices.
+    fn validate_quorum(quorum: &[u16], threshold: u16) -> FsDkrResult<()> {
+        let mut sorted = quorum.to_vec();
+        sorted.sort_unstable();
+        sorted.dedup();
+        if sorted.len() != quorum.len() || quorum.len() <= threshold as usize || sorted[0] == 0 {
+            return Err(FsDkrError::InvalidQuorum {
+                quorum: quorum.to_vec(),
+            });
+        }
+        Ok(())
     }
 
-    pub fn validate_collect(refresh_messages: &[Self], t: u16, n: u16) -> FsDkrResult<()> {
-        // check we got at least threshold t refresh messages
-        if refresh_messages.len() <= t.into() {
-            return Err(FsDkrError::PartiesThresholdViolation {
-                threshold: t,
-                refreshed_keys: refresh_messages.len(),
+    /// Picks the senders whose contributions are combined into the new shares, together with the
+    /// weight of each contribution. Every party has to combine the same set, so the choice must
+    /// not depend on the order the messages were received in.
+    ///
+    /// With [SharingBasis::Linear] these are the `t+1` messages with the lowest old party index,
+    /// weighted by their Lagrange coefficients. With [SharingBasis::LagrangeWeighted] the
+    /// senders already weighted their shares, so every message must come from a distinct member
+    /// of the quorum and all the quorum members must be present. In both cases a party can only
+    /// send one message.
+    pub(crate) fn senders_and_weights(
+        refresh_messages: &[VerifiedRefreshMessage<E, H, M>],
+        threshold: u16,
+    ) -> FsDkrResult<(Vec<&VerifiedRefreshMessage<E, H, M>>, Vec<Scalar<E>>)> {
+        let basis = refresh_messages
+            .first()
+            .map(|msg| msg.basis.clone())
+            .unwrap_or_default();
+        if refresh_messages.iter().any(|msg| msg.basis != basis) {
+            return Err(FsDkrError::SharingBasisMismatch);
+        }
+
+        // a party sends one message, under both its old and its new index
+        let mut senders: Vec<_> = refresh_messages.iter().collect();
+        senders.sort_by_key(|msg| msg.party_index);
+        if let Some(pair) = senders
+            .windows(2)
+            .find(|pair| pair[0].party_index == pair[1].party_index)
+        {
+            return Err(FsDkrError::DuplicatedRefreshMessage {
+                party_index: pair[0].party_index,
             });
         }
+        senders.sort_by_key(|msg| msg.old_party_index);
+        if let Some(pair) = senders
+            .windows(2)
+            .find(|pair| pair[0].old_party_index == pair[1].old_party_index)
+        {
+            return Err(FsDkrError::DuplicatedRefreshMessage {
+                party_index: pair[1].party_index,
+            });
+        }
+        match basis {
+            SharingBasis::Linear => {
+                senders.truncate(threshold as usize + 1);
+                let indices: Vec<u16> = senders.iter().map(|msg| msg.old_party_index).collect();
+                let li_vec = indices
+                    .iter()
+                    .map(|index| lagrange_coefficient_at(&Scalar::<E>::zero(), *index, &indices))
+                    .collect();
+                Ok((senders, li_vec))
+            }
+            SharingBasis::LagrangeWeighted { mut quorum } => {
+                Self::validate_quorum(&quorum, threshold)?;
+                quorum.sort_unstable();
+                let indices: Vec<u16> = senders.iter().map(|msg| msg.old_party_index).collect();
+                if indices != quorum {
+                    return Err(FsDkrError::InvalidQuorum { quorum });
+                }
+                let li_vec = vec![Scalar::<E>::from(1u16); senders.len()];
+                Ok((senders, li_vec))
+            }
+        }
+    }
 
-        // check all vectors are of same length
-        let reference_len = refresh_messages[0].pdl_proof_vec.len();
+    /// The tweak point all the senders agree on, if any.
+    pub(crate) fn agreed_tweak_point(refresh_messages: &[&Self]) -> FsDkrResult<Option<Point<E>>> {
+        let tweak_point = refresh_messages
+            .first()
+            .and_then(|msg| msg.tweak_point.clone());
+        if refresh_messages
+            .iter()
+            .any(|msg| msg.tweak_point != tweak_point)
+        {
+            return Err(FsDkrError::TweakMismatch);
+        }
+        Ok(tweak_point)
+    }
 
-        for (k, refresh_message) in refresh_messages.iter().enumerate() {
-            let pdl_proof_len = refresh_message.pdl_proof_vec.len();
-            let points_commited_len = refresh_message.points_committed_vec.len();
-            let points_encrypted_len = refresh_message.points_encrypted_vec.len();
+    /// The threshold all the senders reshared with.
+    pub(crate) fn agreed_new_t(refresh_messages: &[&Self]) -> FsDkrResult<u16> {
+        let new_t = refresh_messages
+            .first()
+            .map(|msg| msg.new_t())
+            .unwrap_or_default();
+        match refresh_messages.iter().find(|msg| msg.new_t() != new_t) {
+            Some(msg) => Err(FsDkrError::ThresholdMismatch {
+                party_index: msg.party_index,
+            }),
+            None => Ok(new_t),
+        }
+    }
 
-            if !(pdl_proof_len == reference_len
-                && points_commited_len == reference_len
-                && points_encrypted_len == reference_len)
-            {
-                return Err(FsDkrError::SizeMismatchError {
-                    refresh_message_index: k,
-                    pdl_proof_len,
-                    points_commited_len,
-                    points_encrypted_len,
-                });
-            }
+    /// The joint public key of the refreshed key: the current one, moved by the tweak if any.
+    pub(crate) fn target_public_key(
+        public_key: &Point<E>,
+        tweak_point: Option<&Point<E>>,
+    ) -> Point<E> {
+        match tweak_point {
+            Some(tweak_point) => public_key + tweak_point,
+            None => public_key.clone(),
         }
+    }
 
-        for refresh_message in refresh_messages.iter() {
-            for i in 0..n as usize {
-                //TODO: we should handle the case of t<i<n
-                if refresh_message
-                    .coefficients_committed_vec
-                    .validate_share_public(&refresh_message.points_committed_vec[i], i as u16 + 1)
-                    .is_err()
-                {
-                    return Err(FsDkrError::PublicShareValidationError);
-                }
+    /// The free coefficient a sender commits to: its public share, moved by the tweak if any and
+    /// weighted by its Lagrange coefficient under [SharingBasis::LagrangeWeighted].
+    pub(crate) fn expected_constant_term(&self, old_pk_vec: &[Point<E>]) -> Option<Point<E>> {
+        let public_share = old_pk_vec.get(self.old_party_index.checked_sub(1)? as usize)?;
+        Some(self.constant_term_for(public_share))
+    }
+
+    /// The constant term of the polynomial of a sender with public share `public_share`, after
+    /// the tweak and the Lagrange weight of the message.
+    fn constant_term_for(&self, public_share: &Point<E>) -> Point<E> {
+        let public_share = Self::target_public_key(public_share, self.tweak_point.as_ref());
+        match &self.basis {
+            SharingBasis::Linear => public_share,
+            SharingBasis::LagrangeWeighted { quorum } => {
+                &public_share
+                    * &lagrange_coefficient_at(&Scalar::<E>::zero(), self.old_party_index, quorum)
             }
         }
+    }
 
+    /// Checks that the message is bound to `session_id` by the holder of the share it reshares,
+    /// see [crate::prepared_distribution].
+    pub(crate) fn verify_session_binding(&self, session_id: &BigInt) -> FsDkrResult<()> {
+        let binding = self
+            .session_binding
+            .as_ref()
+            .ok_or(FsDkrError::SessionBindingInvalid)?;
+        binding.verify::<H>(session_id, &self.binding_fingerprint())?;
+        if Some(&self.constant_term_for(binding.binding_key()))
+            != self.coefficients_committed_vec.commitments.first()
+        {
+            return Err(FsDkrError::SessionBindingInvalid);
+        }
         Ok(())
     }
 
     pub(crate) fn get_ciphertext_sum<'a>(
-        refresh_messages: &'a [Self],
+        senders: &[&VerifiedRefreshMessage<E, H, M>],
+        li_vec: &[Scalar<E>],
         party_index: u16,
-        parameters: &'a ShamirSecretSharing,
         ek: &'a EncryptionKey,
-    ) -> (RawCiphertext<'a>, Vec<Scalar<E>>) {
-        // TODO: check we have large enough qualified set , at least t+1
-        //decrypt the new share
-        // we first homomorphically add all ciphertext encrypted using our encryption key
-        let ciphertext_vec: Vec<_> = (0..refresh_messages.len())
-            .map(|k| refresh_messages[k].points_encrypted_vec[(party_index - 1) as usize].clone())
+    ) -> RawCiphertext<'a> {
+        // we first homomorphically add all ciphertext encrypted using our encryption key,
+        // optimization - one decryption
+        let ciphertext_vec_at_indices_mapped: Vec<_> = senders
+            .iter()
+            .zip(li_vec)
+            .map(|(msg, li)| {
+                Paillier::mul(
+                    ek,
+                    RawCiphertext::from(
+                        msg.points_encrypted_vec[(party_index - 1) as usize].clone(),
+                    ),
+                    RawPlaintext::from(li.to_bigint()),
+                )
+            })
             .collect();
 
-        let indices: Vec<u16> = (0..(parameters.threshold + 1) as usize)
-            .map(|i| refresh_messages[i].old_party_index - 1)
-            .collect();
+        ciphertext_vec_at_indices_mapped.iter().fold(
+            Paillier::encrypt(ek, RawPlaintext::from(BigInt::zero())),
+            |acc, x| Paillier::add(ek, acc, x.clone()),
+        )
+    }
 
-        // optimization - one decryption
-        let li_vec: Vec<_> = (0..parameters.threshold as usize + 1)
+    /// The first sender whose sub-share for `local_key` does not decrypt to the point it
+    /// committed to, as [FsDkrError::SubShareMismatch]. Costs a decryption per sender, so it is
+    /// only run once the combined share failed to match.
+    pub(crate) fn blame_sub_share(
+        senders: &[&Self],
+        local_key: &LocalKey<E>,
+    ) -> Option<FsDkrError> {
+        let receiver = local_key.i;
+        let k = (receiver - 1) as usize;
+        senders.iter().find_map(|msg| {
+            let mut plaintext = Paillier::decrypt(
+                &local_key.paillier_dk,
+                RawCiphertext::from(msg.points_encrypted_vec[k].clone()),
+            )
+            .0
+            .into_owned();
+            let sub_share: Scalar<E> = share_from_plaintext(&plaintext);
+            plaintext.zeroize();
+            if Point::<E>::generator() * &sub_share == msg.points_committed_vec[k] {
+                None
+            } else {
+                Some(FsDkrError::SubShareMismatch {
+                    party_index: msg.party_index,
+                    receiver,
+                })
+            }
+        })
+    }
+
+    /// Combines the qualified senders' commitments into the public shares of all the parties and
+    /// the VSS scheme of the new sharing.
+    pub(crate) fn aggregate_public_data(
+        senders: &[&VerifiedRefreshMessage<E, H, M>],
+        li_vec: &[Scalar<E>],
+        new_t: u16,
+        new_n: u16,
+    ) -> (Vec<Point<E>>, VerifiableSS<E>) {
+        let pk_vec = (0..new_n as usize)
             .map(|i| {
-                VerifiableSS::<E>::map_share_to_new_params(
-                    parameters.clone().borrow(),
-                    indices[i],
-                    &indices,
-                )
+                senders
+                    .iter()
+                    .zip(li_vec)
+                    .fold(Point::<E>::zero(), |acc, (msg, li)| {
+                        acc + &msg.points_committed_vec[i] * li
+                    })
             })
             .collect();
 
-        let ciphertext_vec_at_indices_mapped: Vec<_> = (0..(parameters.threshold + 1) as usize)
-            .map(|i| {
-                Paillier::mul(
-                    ek,
-                    RawCiphertext::from(ciphertext_vec[i].clone()),
-                    RawPlaintext::from(li_vec[i].to_bigint()),
-                )
+        let commitments = (0..new_t as usize + 1)
+            .map(|j| {
+                senders
+                    .iter()
+                    .zip(li_vec)
+                    .fold(Point::<E>::zero(), |acc, (msg, li)| {
+                        acc + &msg.coefficients_committed_vec.commitments[j] * li
+                    })
             })
             .collect();
 
-        let ciphertext_sum = ciphertext_vec_at_indices_mapped.iter().fold(
-            Paillier::encrypt(ek, RawPlaintext::from(BigInt::zero())),
-            |acc, x| Paillier::add(ek, acc, x.clone()),
-        );
+        let vss_scheme = VerifiableSS {
+            parameters: ShamirSecretSharing {
+                threshold: new_t,
+                share_count: new_n,
+            },
+            commitments,
+        };
+
+        (pk_vec, vss_scheme)
+    }
+
+    /// Renumbers the committee while refreshing it: every active party calls this with the same
+    /// `remap`, which moves the per-party data of `key` to the new indices and reshares the key
+    /// from the party's old evaluation point. The parties missing from `remap` leave the
+    /// committee. The returned message is collected with [RefreshMessage::collect] as usual.
+    pub fn remap(key: &mut LocalKey<E>, remap: &IndexRemap) -> FsDkrResult<(Self, DecryptionKey)> {
+        remap.validate()?;
+        let old_party_index = key.i;
+        let new_party_index = remap
+            .get(old_party_index)
+            .ok_or(FsDkrError::InvalidIndexRemap)?;
+
+        let mut paillier_key_vec = key.paillier_key_vec.clone();
+        let mut h1_h2_n_tilde_vec = key.h1_h2_n_tilde_vec.clone();
+        paillier_key_vec.truncate(remap.len() as usize);
+        h1_h2_n_tilde_vec.truncate(remap.len() as usize);
+        for (old, new) in remap.iter() {
+            let old = old as usize - 1;
+            let new = new as usize - 1;
+            match (
+                key.paillier_key_vec.get(old),
+                key.h1_h2_n_tilde_vec.get(old),
+            ) {
+                (Some(ek), Some(dlog_statement)) if new < paillier_key_vec.len() => {
+                    paillier_key_vec[new] = ek.clone();
+                    h1_h2_n_tilde_vec[new] = dlog_statement.clone();
+                }
+                _ => return Err(FsDkrError::InvalidIndexRemap),
+            }
+        }
+        key.paillier_key_vec = paillier_key_vec;
+        key.h1_h2_n_tilde_vec = h1_h2_n_tilde_vec;
+        key.i = new_party_index;
+        key.n = remap.len();
+
+        let (mut refresh_message, dk) =
+            RefreshMessage::distribute(old_party_index, key, remap.len())?;
+        refresh_message.index_remap = Some(remap.clone());
+        Ok((refresh_message, dk))
+    }
 
-        (ciphertext_sum, li_vec)
+    /// Refreshes the key to the `survivors` only, given by their current indices: they are
+    /// renumbered `1..=survivors.len()` in increasing order, and the removed parties receive no
+    /// share of the new epoch. A shorthand for [RefreshMessage::remap] with
+    /// [IndexRemap::densify], called by every survivor.
+    pub fn remove_parties(
+        key: &mut LocalKey<E>,
+        survivors: &[u16],
+    ) -> FsDkrResult<(Self, DecryptionKey)> {
+        Self::remap(key, &IndexRemap::densify(survivors)?)
     }
 
     pub fn replace(
@@ -317,129 +1858,615 @@ impl<E: Curve, H: Digest + Clone, const M: usize> RefreshMessage<E, H, M> {
         RefreshMessage::distribute(old_party_index, key, new_n as u16)
     }
 
-    pub fn collect(
+    /// Same as [RefreshMessage::collect], leaving out the senders whose message fails
+    /// verification if `options` tolerates that many failures. Returns the refreshed key and the
+    /// senders left out with the reason, by sender index.
+    ///
+    /// Every message is checked against the ciphertexts of all the receivers, and has to be
+    /// distributed to the `n` parties of `old_key` whatever order the messages arrive in, so all
+    /// the honest parties leave out the same senders and combine the same contributions.
+    pub fn collect_with_options(
         refresh_messages: &[Self],
-        mut local_key: &mut LocalKey<E>,
+        old_key: &LocalKey<E>,
         new_dk: DecryptionKey,
         join_messages: &[JoinMessage<E, H, M>],
-    ) -> FsDkrResult<()> {
-        let new_n = refresh_messages.len() + join_messages.len();
-        RefreshMessage::validate_collect(refresh_messages, local_key.t, new_n as u16)?;
+        options: &CollectOptions,
+    ) -> FsDkrResult<(LocalKey<E>, Vec<(u16, FsDkrError)>)> {
+        Self::collect_excluding(
+            refresh_messages,
+            old_key,
+            new_dk,
+            join_messages,
+            options,
+            None,
+            Vec::new(),
+        )
+    }
 
-        for refresh_message in refresh_messages.iter() {
-            for i in 0..(new_n as usize) {
-                let statement = PDLwSlackStatement {
-                    ciphertext: refresh_message.points_encrypted_vec[i].clone(),
-                    ek: local_key.paillier_key_vec[i].clone(),
-                    Q: refresh_message.points_committed_vec[i].clone(),
-                    G: Point::<E>::generator().to_point(),
-                    h1: local_key.h1_h2_n_tilde_vec[i].g.clone(),
-                    h2: local_key.h1_h2_n_tilde_vec[i].ni.clone(),
-                    N_tilde: local_key.h1_h2_n_tilde_vec[i].N.clone(),
-                };
-                refresh_message.pdl_proof_vec[i].verify(&statement)?;
-                if !refresh_message.range_proofs[i].verify(
-                    &statement.ciphertext,
-                    &statement.ek,
-                    &local_key.h1_h2_n_tilde_vec[i],
-                ) {
-                    return Err(FsDkrError::RangeProof { party_index: i });
-                }
+    /// [RefreshMessage::collect_with_options] for senders of which `excluded` are already known
+    /// to have failed, e.g. because their message could not be decoded. They count towards the
+    /// tolerated failures. With `session_id`, the messages are collected within that session,
+    /// see [RefreshMessage::collect_in_session].
+    pub(crate) fn collect_excluding(
+        refresh_messages: &[Self],
+        old_key: &LocalKey<E>,
+        new_dk: DecryptionKey,
+        join_messages: &[JoinMessage<E, H, M>],
+        options: &CollectOptions,
+        session_id: Option<&BigInt>,
+        mut excluded: Vec<(u16, FsDkrError)>,
+    ) -> FsDkrResult<(LocalKey<E>, Vec<(u16, FsDkrError)>)> {
+        let mut context = RefreshContext::new(old_key, old_key.n)
+            .with_min_modulus_bits(options.min_modulus_bits());
+        if let Some(session_id) = session_id {
+            context = context.with_session_id(session_id.clone());
+        }
+        let mut sorted = Self::unique_messages(refresh_messages)?;
+        sorted.sort_by_key(|msg| msg.party_index);
+
+        let mut verified = Vec::new();
+        for msg in sorted {
+            match msg.verify(&context) {
+                Ok(verified_message) => verified.push(verified_message),
+                Err(error) => excluded.push((msg.party_index, error)),
             }
         }
+        excluded.sort_by_key(|(party_index, _)| *party_index);
+        if excluded.len() > options.max_failures {
+            return Err(FsDkrError::TooManyFailedSenders {
+                failures: excluded,
+                tolerated: options.max_failures,
+            });
+        }
 
-        // Verify ring-pedersen parameters
-        for refresh_message in refresh_messages.iter() {
-            RingPedersenProof::verify(
-                &refresh_message.ring_pedersen_proof,
-                &refresh_message.ring_pedersen_statement,
-            )?;
+        let mut new_key = old_key.clone();
+        Self::apply_refresh(&verified, &mut new_key, new_dk, join_messages, session_id)?;
+        Ok((new_key, excluded))
+    }
+
+    /// Verifies `refresh_messages` against `old_key` and returns the refreshed key, see
+    /// [RefreshMessage::verify] and [RefreshMessage::apply_refresh]. `old_key` is left untouched
+    /// whatever the outcome, so a failed collect can be retried with another set of messages;
+    /// once the refreshed key is stored, the caller zeroizes the Paillier decryption key of
+    /// `old_key`.
+    pub fn collect(
+        refresh_messages: &[Self],
+        old_key: &LocalKey<E>,
+        new_dk: DecryptionKey,
+        join_messages: &[JoinMessage<E, H, M>],
+    ) -> FsDkrResult<LocalKey<E>> {
+        trace_span!(
+            "collect",
+            party_index = old_key.i,
+            senders = refresh_messages.len()
+        );
+        validate_local_key(old_key)?;
+        let context = RefreshContext::new(old_key, old_key.n);
+        let verified = Self::verify_all(refresh_messages, &context)?;
+        let mut new_key = old_key.clone();
+        Self::apply_refresh(&verified, &mut new_key, new_dk, join_messages, None)?;
+        Ok(new_key)
+    }
+
+    /// Same as [RefreshMessage::collect] within the session `session_id`: every message has to
+    /// be bound to it with [RefreshMessage::finalize], so messages from another session, earlier
+    /// refreshes included, are rejected.
+    pub fn collect_in_session(
+        refresh_messages: &[Self],
+        old_key: &LocalKey<E>,
+        session_id: &BigInt,
+        new_dk: DecryptionKey,
+        join_messages: &[JoinMessage<E, H, M>],
+    ) -> FsDkrResult<LocalKey<E>> {
+        trace_span!(
+            "collect",
+            party_index = old_key.i,
+            senders = refresh_messages.len()
+        );
+        validate_local_key(old_key)?;
+        let context = RefreshContext::new(old_key, old_key.n).with_session_id(session_id.clone());
+        let verified = Self::verify_all(refresh_messages, &context)?;
+        let mut new_key = old_key.clone();
+        Self::apply_refresh(
+            &verified,
+            &mut new_key,
+            new_dk,
+            join_messages,
+            Some(session_id),
+        )?;
+        Ok(new_key)
+    }
+
+    /// Same as [RefreshMessage::collect] for the party that distributed `own_message`, whether
+    /// or not the transport echoed it back in `refresh_messages`. A missing echo is replaced by
+    /// `own_message`, and a message in `refresh_messages` that claims to come from the party has
+    /// to be `own_message`, so a message forged in its name can not take its place. With `None`
+    /// this is [RefreshMessage::collect].
+    pub fn collect_with_own_message(
+        refresh_messages: &[Self],
+        old_key: &LocalKey<E>,
+        own_message: Option<&Self>,
+        new_dk: DecryptionKey,
+        join_messages: &[JoinMessage<E, H, M>],
+    ) -> FsDkrResult<LocalKey<E>> {
+        let own_message = match own_message {
+            Some(own_message) => own_message,
+            None => return Self::collect(refresh_messages, old_key, new_dk, join_messages),
+        };
+        let mismatch = FsDkrError::OwnRefreshMessageMismatch {
+            party_index: old_key.i,
+        };
+        if own_message.old_party_index != old_key.i {
+            return Err(mismatch);
+        }
+        let own_fingerprint = own_message.fingerprint();
+        let mut echoed = false;
+        for msg in refresh_messages.iter().filter(|msg| {
+            msg.party_index == own_message.party_index
+                || msg.old_party_index == own_message.old_party_index
+        }) {
+            if msg.fingerprint() != own_fingerprint {
+                return Err(mismatch);
+            }
+            echoed = true;
+        }
+        if echoed {
+            return Self::collect(refresh_messages, old_key, new_dk, join_messages);
         }
+        let mut messages = refresh_messages.to_vec();
+        messages.push(own_message.clone());
+        Self::collect(&messages, old_key, new_dk, join_messages)
+    }
+
+    /// The conditions of a refresh of `local_key` with `refresh_messages` that collect accepts
+    /// but that are close to failing it. Every sender counts once, however many copies of its
+    /// message arrived.
+    pub fn collect_warnings(refresh_messages: &[Self], local_key: &LocalKey<E>) -> Vec<Warning> {
+        let all: Vec<&Self> = refresh_messages.iter().collect();
+        Self::warnings_for(refresh_messages, &all, local_key)
+    }
 
-        for join_message in join_messages.iter() {
-            RingPedersenProof::verify(
-                &join_message.ring_pedersen_proof,
-                &join_message.ring_pedersen_statement,
-            )?;
+    /// [RefreshMessage::collect_warnings] of a collect that combined the messages of `accepted`
+    /// out of `refresh_messages`; the senders left out count towards neither the quorum nor the
+    /// moduli.
+    pub(crate) fn warnings_for(
+        refresh_messages: &[Self],
+        accepted: &[&Self],
+        local_key: &LocalKey<E>,
+    ) -> Vec<Warning> {
+        let mut senders: BTreeMap<u16, &Self> = BTreeMap::new();
+        for msg in accepted {
+            senders.entry(msg.party_index).or_insert(msg);
+        }
+        let mut warnings: Vec<Warning> = senders
+            .values()
+            .filter(|msg| msg.ek.n.bit_length() == crate::PAILLIER_KEY_SIZE - 1)
+            .map(|msg| Warning::MarginalModulus {
+                party_index: msg.party_index,
+                bits: msg.ek.n.bit_length(),
+            })
+            .collect();
+        if senders.len() == local_key.t as usize + 1 {
+            warnings.push(Warning::MinimalQuorum {
+                received: senders.len(),
+                threshold: local_key.t,
+            });
         }
+        let silent: Vec<u16> = (1..=local_key.n)
+            .filter(|i| !refresh_messages.iter().any(|msg| msg.old_party_index == *i))
+            .collect();
+        if !silent.is_empty() {
+            warnings.push(Warning::SilentParties {
+                party_indices: silent,
+            });
+        }
+        warnings
+    }
 
-        let old_ek = local_key.paillier_key_vec[(local_key.i - 1) as usize].clone();
-        let (cipher_text_sum, li_vec) = RefreshMessage::get_ciphertext_sum(
-            refresh_messages,
-            local_key.i,
-            &local_key.vss_scheme.parameters,
-            &old_ek,
-        );
+    /// Checks everything about this message that does not depend on the other messages: the
+    /// shares it commits to, the proofs of the ciphertexts of every receiver `context` knows the
+    /// keys of, its ring-Pedersen parameters, its new Paillier key and, when `context` knows the
+    /// current public shares, that the sender reshared its own share.
+    pub fn verify(
+        &self,
+        context: &RefreshContext<E>,
+    ) -> FsDkrResult<VerifiedRefreshMessage<E, H, M>> {
+        trace_span!("verify_message", sender = self.party_index);
+        let new_n = context.new_n as usize;
+        if self.points_committed_vec.len() != new_n {
+            return Err(FsDkrError::InvalidRefreshMessage {
+                party_index: self.party_index,
+                reason: "wrong number of receivers".to_string(),
+            });
+        }
 
-        for refresh_message in refresh_messages.iter() {
-            if refresh_message
-                .dk_correctness_proof
-                .verify(&refresh_message.ek, SALT_STRING)
-                .is_err()
-            {
-                return Err(FsDkrError::PaillierVerificationError {
-                    party_index: refresh_message.party_index,
+        // the committed sub-shares are derived from the commitments, so only the ciphertexts
+        // have to be checked against them
+        for i in 0..new_n {
+            let (ek, dlog_statement) = match context.receivers.get(i) {
+                Some(Some(receiver)) => receiver,
+                Some(None) => continue,
+                None => {
+                    return Err(FsDkrError::InvalidRefreshMessage {
+                        party_index: self.party_index,
+                        reason: "unknown receiver".to_string(),
+                    })
+                }
+            };
+            let statement = PDLwSlackStatement {
+                ciphertext: self.points_encrypted_vec[i].clone(),
+                ek: ek.clone(),
+                Q: self.points_committed_vec[i].clone(),
+                G: Point::<E>::generator().to_point(),
+                h1: dlog_statement.g.clone(),
+                h2: dlog_statement.ni.clone(),
+                N_tilde: dlog_statement.N.clone(),
+            };
+            let receiver = i as u16 + 1;
+            trace_span!(
+                "verify_ciphertext",
+                sender = self.party_index,
+                receiver = receiver
+            );
+            if self.pdl_proof_vec[i].verify(&statement).is_err() {
+                return Err(FsDkrError::PDLwSlackProofInvalid {
+                    party_index: self.party_index,
+                    receiver,
                 });
             }
-            let n_length = refresh_message.ek.n.bit_length();
-            if n_length > crate::PAILLIER_KEY_SIZE || n_length < crate::PAILLIER_KEY_SIZE - 1 {
-                return Err(FsDkrError::ModuliTooSmall {
-                    party_index: refresh_message.party_index,
-                    moduli_size: n_length,
+            if !self.range_proofs[i].verify(&statement.ciphertext, &statement.ek, dlog_statement) {
+                return Err(FsDkrError::RangeProof {
+                    party_index: self.party_index,
+                    receiver,
                 });
             }
+        }
 
-            // if the proof checks, we add the new paillier public key to the key
-            local_key.paillier_key_vec[(refresh_message.party_index - 1) as usize] =
-                refresh_message.ek.clone();
+        RingPedersenProof::verify(&self.ring_pedersen_proof, &self.ring_pedersen_statement)
+            .map_err(|_| FsDkrError::RingPedersenProofValidation {
+                party_index: self.party_index,
+            })?;
+
+        if self
+            .dk_correctness_proof
+            .verify(&self.ek, SALT_STRING)
+            .is_err()
+        {
+            return Err(FsDkrError::PaillierVerificationError {
+                party_index: self.party_index,
+            });
         }
+        check_modulus_size(self.party_index, &self.ek, context.min_modulus_bits)?;
 
-        for join_message in join_messages {
-            let party_index = join_message.get_party_index()?;
+        // within a session, the message has to be bound to it by its sender
+        if let Some(session_id) = &context.session_id {
+            self.verify_session_binding(session_id)?;
+        }
 
-            if join_message
-                .dk_correctness_proof
-                .verify(&join_message.ek, SALT_STRING)
-                .is_err()
-            {
-                return Err(FsDkrError::PaillierVerificationError { party_index });
+        // the sender has to reshare the share it holds in the current key
+        if let Some(old_pk_vec) = &context.old_pk_vec {
+            let expected = self.expected_constant_term(old_pk_vec);
+            if expected.as_ref() != self.coefficients_committed_vec.commitments.first() {
+                return Err(FsDkrError::ConstantTermMismatch {
+                    party_index: self.old_party_index,
+                });
             }
+        }
 
-            // creating an inverse dlog statement
-            let dlog_statement_base_h2 = DLogStatement {
-                N: join_message.dlog_statement.N.clone(),
-                g: join_message.dlog_statement.ni.clone(),
-                ni: join_message.dlog_statement.g.clone(),
-            };
-            if join_message
-                .composite_dlog_proof_base_h1
-                .verify(&join_message.dlog_statement)
-                .is_err()
-                || join_message
-                    .composite_dlog_proof_base_h2
-                    .verify(&dlog_statement_base_h2)
-                    .is_err()
-            {
-                return Err(FsDkrError::DLogProofValidation { party_index });
-            }
+        Ok(VerifiedRefreshMessage {
+            message: self.clone(),
+            context_digest: context.digest::<H>(),
+        })
+    }
 
-            let n_length = join_message.ek.n.bit_length();
-            if n_length > crate::PAILLIER_KEY_SIZE || n_length < crate::PAILLIER_KEY_SIZE - 1 {
-                return Err(FsDkrError::ModuliTooSmall {
-                    party_index: join_message.get_party_index()?,
-                    moduli_size: n_length,
-                });
+    /// [RefreshMessage::verify] on every message, stopping at the first failure. A message
+    /// delivered more than once is verified once, see [RefreshMessage::unique_messages].
+    pub fn verify_all(
+        refresh_messages: &[Self],
+        context: &RefreshContext<E>,
+    ) -> FsDkrResult<Vec<VerifiedRefreshMessage<E, H, M>>> {
+        Self::unique_messages(refresh_messages)?
+            .into_iter()
+            .map(|msg| msg.verify(context))
+            .collect()
+    }
+
+    /// `refresh_messages` without the exact copies a transport may deliver more than once. Two
+    /// messages of the same sender with different fingerprints are an equivocation.
+    pub(crate) fn unique_messages(refresh_messages: &[Self]) -> FsDkrResult<Vec<&Self>> {
+        let mut fingerprints: HashMap<u16, [u8; 32]> = HashMap::new();
+        let mut unique = Vec::with_capacity(refresh_messages.len());
+        for msg in refresh_messages {
+            let fingerprint = msg.fingerprint();
+            match fingerprints.get(&msg.party_index) {
+                Some(seen) if *seen == fingerprint => continue,
+                Some(_) => {
+                    return Err(FsDkrError::EquivocatingSender {
+                        party_index: msg.party_index,
+                    })
+                }
+                None => {
+                    fingerprints.insert(msg.party_index, fingerprint);
+                    unique.push(msg);
+                }
             }
+        }
+        Ok(unique)
+    }
 
-            // if the proof checks, we add the new paillier public key to the key
-            local_key.paillier_key_vec[(party_index - 1) as usize] = join_message.ek.clone();
+    /// Combines verified messages into the new share of `local_key`, after the checks that
+    /// involve all the messages. The messages have to be verified against `local_key` as it is
+    /// now, within the session `session_id` if any.
+    pub fn apply_refresh(
+        verified: &[VerifiedRefreshMessage<E, H, M>],
+        local_key: &mut LocalKey<E>,
+        new_dk: DecryptionKey,
+        join_messages: &[JoinMessage<E, H, M>],
+        session_id: Option<&BigInt>,
+    ) -> FsDkrResult<()> {
+        let verified_set =
+            Self::verify_combination(verified, local_key, join_messages, session_id)?;
+        Self::decrypt_and_update(verified, local_key, new_dk, join_messages, verified_set)
+    }
+
+    /// Runs every check of [RefreshMessage::collect] without decrypting anything or modifying
+    /// `local_key`. The returned [VerifiedRefreshSet] is bound to the exact messages and to the
+    /// key, and is handed to [RefreshMessage::collect_verified] once the go-ahead arrives.
+    pub fn collect_verify_only(
+        refresh_messages: &[Self],
+        local_key: &LocalKey<E>,
+        join_messages: &[JoinMessage<E, H, M>],
+    ) -> FsDkrResult<VerifiedRefreshSet<E>> {
+        let context = RefreshContext::new(local_key, local_key.n);
+        let verified = Self::verify_all(refresh_messages, &context)?;
+        Self::verify_combination(&verified, local_key, join_messages, None)
+    }
+
+    /// Second step of [RefreshMessage::collect]: decrypts the new share and returns the refreshed
+    /// key, without verifying the messages again. Fails if `refresh_messages`, `join_messages` or
+    /// `old_key` are not the ones `verified` was produced for; `old_key` is left untouched.
+    pub fn collect_verified(
+        refresh_messages: &[Self],
+        old_key: &LocalKey<E>,
+        new_dk: DecryptionKey,
+        join_messages: &[JoinMessage<E, H, M>],
+        verified: VerifiedRefreshSet<E>,
+    ) -> FsDkrResult<LocalKey<E>> {
+        let messages = Self::unique_messages(refresh_messages)?;
+        if verified.messages_digest != Self::messages_digest(&messages, join_messages)
+            || verified.key_fingerprint != key_fingerprint::<E, H>(old_key)
+            || verified.party_index != old_key.i
+        {
+            return Err(FsDkrError::VerifiedSetMismatch);
+        }
+
+        // the set vouches for exactly these messages
+        let verified_messages: Vec<_> = messages
+            .into_iter()
+            .map(|msg| VerifiedRefreshMessage {
+                message: msg.clone(),
+                context_digest: verified.context_digest.clone(),
+            })
+            .collect();
+        let mut new_key = old_key.clone();
+        Self::decrypt_and_update(
+            &verified_messages,
+            &mut new_key,
+            new_dk,
+            join_messages,
+            verified,
+        )?;
+        Ok(new_key)
+    }
+
+    /// Online half of a collect split for an air-gapped party, see [crate::air_gapped]: verifies
+    /// the messages like [RefreshMessage::collect] and returns the bundle to finish the collect
+    /// offline. Only the public data of `local_key` is used.
+    pub fn collect_online(
+        refresh_messages: &[Self],
+        local_key: &LocalKey<E>,
+        join_messages: &[JoinMessage<E, H, M>],
+    ) -> FsDkrResult<OfflineBundle<E>> {
+        validate_key_layout(local_key)?;
+        let context = RefreshContext::new(local_key, local_key.n);
+        let verified = Self::verify_all(refresh_messages, &context)?;
+        let verified_set = Self::verify_combination(&verified, local_key, join_messages, None)?;
+
+        let old_ek = &local_key.paillier_key_vec[(local_key.i - 1) as usize];
+        let (senders, li_vec) = Self::senders_and_weights(&verified, local_key.t)?;
+        let ciphertext_sum = Self::get_ciphertext_sum(&senders, &li_vec, local_key.i, old_ek)
+            .0
+            .into_owned();
+
+        let mut paillier_key_vec = local_key.paillier_key_vec.clone();
+        for refresh_message in verified.iter() {
+            paillier_key_vec[(refresh_message.party_index - 1) as usize] =
+                refresh_message.ek.clone();
+        }
+        for join_message in join_messages {
+            let party_index = join_message.get_party_index()?;
+            paillier_key_vec[(party_index - 1) as usize] = join_message.ek.clone();
+        }
+
+        Ok(OfflineBundle {
+            party_index: local_key.i,
+            t: verified_set.vss_scheme.parameters.threshold,
+            ciphertext_sum,
+            paillier_key_vec,
+            h1_h2_n_tilde_vec: local_key.h1_h2_n_tilde_vec.clone(),
+            public_key: verified_set.public_key,
+            pk_vec: verified_set.pk_vec,
+            vss_scheme: verified_set.vss_scheme,
+        })
+    }
+
+    /// The checks of [RefreshMessage::collect] that involve all the messages at once.
+    fn verify_combination(
+        verified: &[VerifiedRefreshMessage<E, H, M>],
+        local_key: &LocalKey<E>,
+        join_messages: &[JoinMessage<E, H, M>],
+        session_id: Option<&BigInt>,
+    ) -> FsDkrResult<VerifiedRefreshSet<E>> {
+        // the messages have to be verified against this very key and session
+        let mut context = RefreshContext::new(local_key, local_key.n);
+        if let Some(session_id) = session_id {
+            context = context.with_session_id(session_id.clone());
+        }
+        Self::verify_combination_in(verified, local_key, join_messages, &context)
+    }
+
+    /// [RefreshMessage::verify_combination] for messages verified against `context`.
+    pub(crate) fn verify_combination_in(
+        verified: &[VerifiedRefreshMessage<E, H, M>],
+        local_key: &LocalKey<E>,
+        join_messages: &[JoinMessage<E, H, M>],
+        context: &RefreshContext<E>,
+    ) -> FsDkrResult<VerifiedRefreshSet<E>> {
+        let messages: Vec<&Self> = verified.iter().map(|msg| msg.message()).collect();
+        let new_n = context.new_n;
+        let context_digest = context.digest::<H>();
+        if verified
+            .iter()
+            .any(|msg| msg.context_digest != context_digest)
+        {
+            return Err(FsDkrError::RefreshContextMismatch);
+        }
+
+        let (public_key, pk_vec, vss_scheme) =
+            Self::combine_public(verified, local_key.t, &local_key.y_sum_s, new_n)?;
+        for join_message in join_messages {
+            join_message.verify()?;
+        }
+
+        Ok(VerifiedRefreshSet {
+            messages_digest: Self::messages_digest(&messages, join_messages),
+            context_digest,
+            key_fingerprint: key_fingerprint::<E, H>(local_key),
+            party_index: local_key.i,
+            public_key,
+            pk_vec,
+            vss_scheme,
+            new_n,
+        })
+    }
+
+    /// The checks of the combination of `verified` that only involve public data: more than `t`
+    /// distinct senders, agreeing on the remap, the tweak and the new threshold, whose combined
+    /// sharing preserves `public_key` up to the tweak. Returns the joint public key, the public
+    /// shares and the VSS scheme of the new sharing.
+    fn combine_public(
+        verified: &[VerifiedRefreshMessage<E, H, M>],
+        t: u16,
+        public_key: &Point<E>,
+        new_n: u16,
+    ) -> FsDkrResult<(Point<E>, Vec<Point<E>>, VerifiableSS<E>)> {
+        trace_span!("verify_vss", senders = verified.len(), new_n = new_n);
+        let messages: Vec<&Self> = verified.iter().map(|msg| msg.message()).collect();
+        // check we got at least threshold t refresh messages
+        if verified.len() <= t.into() {
+            return Err(FsDkrError::PartiesThresholdViolation {
+                threshold: t,
+                refreshed_keys: verified.len(),
+            });
         }
+        Self::validate_index_remap(&messages, new_n)?;
+        let tweak_point = Self::agreed_tweak_point(&messages)?;
+        let new_t = Self::agreed_new_t(&messages)?;
+        let (senders, li_vec) = Self::senders_and_weights(verified, t)?;
 
-        let new_share = Paillier::decrypt(&local_key.paillier_dk, cipher_text_sum)
+        // the combined sharing has to preserve the joint public key, up to the agreed tweak
+        let (pk_vec, vss_scheme) = Self::aggregate_public_data(&senders, &li_vec, new_t, new_n);
+        let public_key = Self::target_public_key(public_key, tweak_point.as_ref());
+        if vss_scheme.commitments[0] != public_key {
+            return Err(FsDkrError::PublicKeyNotPreserved);
+        }
+        Ok((public_key, pk_vec, vss_scheme))
+    }
+
+    /// Every check of [RefreshMessage::collect] that only needs public data, for an observer
+    /// holding no share of the key of `committee`, see [crate::observer]. Refreshes that add or
+    /// renumber parties are not covered.
+    pub fn verify_refresh(
+        refresh_messages: &[Self],
+        committee: &CommitteePublicData<E>,
+    ) -> FsDkrResult<()> {
+        Self::refreshed_public_shares(refresh_messages, committee)?;
+        Ok(())
+    }
+
+    /// [RefreshMessage::verify_refresh], returning the public shares the refresh gives the
+    /// parties.
+    pub(crate) fn refreshed_public_shares(
+        refresh_messages: &[Self],
+        committee: &CommitteePublicData<E>,
+    ) -> FsDkrResult<Vec<Point<E>>> {
+        Ok(Self::verify_public(refresh_messages, committee)?.pk_vec)
+    }
+
+    /// Verification half of [RefreshMessage::collect]: the checks of
+    /// [RefreshMessage::verify_refresh], which touch no secret and can run away from the
+    /// Paillier decryption keys. The returned [VerifiedRefresh] is finalized by every member of
+    /// `committee`.
+    pub fn verify_public(
+        refresh_messages: &[Self],
+        committee: &CommitteePublicData<E>,
+    ) -> FsDkrResult<VerifiedRefresh<E, H, M>> {
+        let new_n = committee.pk_vec.len() as u16;
+        let context = RefreshContext::for_observer(committee, new_n);
+        let verified = Self::verify_all(refresh_messages, &context)?;
+        let (public_key, pk_vec, vss_scheme) =
+            Self::combine_public(&verified, committee.t, &committee.public_key, new_n)?;
+        Ok(VerifiedRefresh {
+            messages: verified.into(),
+            context_digest: context.digest::<H>(),
+            old_t: committee.t,
+            old_public_key: committee.public_key.clone(),
+            public_key,
+            pk_vec,
+            vss_scheme,
+            new_n,
+        })
+    }
+
+    /// Decrypts the new share from `verified` and moves `local_key` to the data in
+    /// `verified_set`, which has to be the result of checking `verified` against `local_key`.
+    pub(crate) fn decrypt_and_update(
+        verified: &[VerifiedRefreshMessage<E, H, M>],
+        local_key: &mut LocalKey<E>,
+        new_dk: DecryptionKey,
+        join_messages: &[JoinMessage<E, H, M>],
+        verified_set: VerifiedRefreshSet<E>,
+    ) -> FsDkrResult<()> {
+        validate_local_key(local_key)?;
+        trace_span!("decrypt", party_index = local_key.i);
+        let old_ek = local_key.paillier_key_vec[(local_key.i - 1) as usize].clone();
+        let (senders, li_vec) = Self::senders_and_weights(verified, local_key.t)?;
+        let cipher_text_sum = Self::get_ciphertext_sum(&senders, &li_vec, local_key.i, &old_ek);
+
+        let mut new_share = Paillier::decrypt(&local_key.paillier_dk, cipher_text_sum)
             .0
             .into_owned();
+        let new_share_fe: Scalar<E> = share_from_plaintext(&new_share);
+        new_share.zeroize();
+        // the share has to match the public share the commitments give the party; if it does
+        // not, the sender at fault is looked for
+        if Point::<E>::generator() * &new_share_fe
+            != verified_set.pk_vec[(local_key.i - 1) as usize]
+        {
+            let messages: Vec<_> = senders.iter().map(|msg| msg.message()).collect();
+            return Err(Self::blame_sub_share(&messages, local_key)
+                .unwrap_or(FsDkrError::PublicShareValidationError));
+        }
 
-        let new_share_fe: Scalar<E> = Scalar::<E>::from(&new_share);
+        // the proofs checked, we add the new paillier public keys to the key
+        for refresh_message in verified.iter() {
+            local_key.paillier_key_vec[(refresh_message.party_index - 1) as usize] =
+                refresh_message.ek.clone();
+        }
+        for join_message in join_messages {
+            let party_index = join_message.get_party_index()?;
+            local_key.paillier_key_vec[(party_index - 1) as usize] = join_message.ek.clone();
+        }
 
         // zeroize the old dk key
         local_key.paillier_dk.q.zeroize();
@@ -447,20 +2474,16 @@ impl<E: Curve, H: Digest + Clone, const M: usize> RefreshMessage<E, H, M> {
         local_key.paillier_dk = new_dk;
 
         // update old key and output new key
-        local_key.keys_linear.x_i = new_share_fe.clone();
-        local_key.keys_linear.y = Point::<E>::generator() * new_share_fe;
-
-        // update local key list of local public keys (X_i = g^x_i is updated by adding all committed points to that party)
-        for i in 0..refresh_messages.len() + join_messages.len() as usize {
-            local_key.pk_vec.insert(
-                i,
-                refresh_messages[0].points_committed_vec[i].clone() * li_vec[0].clone(),
-            );
-            for j in 1..local_key.t as usize + 1 {
-                local_key.pk_vec[i] = local_key.pk_vec[i].clone()
-                    + refresh_messages[j].points_committed_vec[i].clone() * li_vec[j].clone();
-            }
-        }
+        local_key.keys_linear.x_i = new_share_fe;
+        local_key.keys_linear.y = verified_set.public_key.clone();
+        local_key.y_sum_s = verified_set.public_key;
+
+        // update local key list of local public keys (X_i = g^x_i is updated by adding all committed
+        // points to that party) and the VSS scheme of the new sharing
+        local_key.pk_vec = verified_set.pk_vec;
+        local_key.t = verified_set.vss_scheme.parameters.threshold;
+        local_key.vss_scheme = verified_set.vss_scheme;
+        local_key.n = verified_set.new_n;
 
         Ok(())
     }
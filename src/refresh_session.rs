@@ -0,0 +1,188 @@
+//! One refresh session among several in flight.
+//!
+//! A party may take part in refreshes of different keys at the same time, or retry a refresh
+//! after a timeout, and the free-standing [RefreshMessage::distribute] and
+//! [RefreshMessage::collect] do not keep the messages of one apart from the other. A
+//! [RefreshSession] holds what the party expects of one session: its id, the threshold, the
+//! size of the committee, the senders and the joint public key. [RefreshSession::handle]
+//! rejects a message bound to another session, from another sender or with other parameters
+//! as soon as it arrives, and [RefreshSession::try_complete] collects once every sender has
+//! been heard from. A session is `Send`, so it can be driven by its own task.
+
+use crate::error::{FsDkrError, FsDkrResult};
+use crate::refresh_message::{RefreshMessage, RefreshParams};
+use curv::cryptographic_primitives::hashing::Digest;
+use curv::elliptic::curves::{Curve, Point};
+use curv::BigInt;
+use multi_party_ecdsa::protocols::multi_party_ecdsa::gg_2020::state_machine::keygen::LocalKey;
+use paillier::DecryptionKey;
+use std::collections::BTreeMap;
+
+/// Progress of a [RefreshSession].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SessionStatus {
+    /// The senders whose message has not arrived yet, in increasing order.
+    Waiting { missing: Vec<u16> },
+    /// Every sender was heard from, see [RefreshSession::try_complete].
+    QuorumReached,
+}
+
+/// A refresh session of one party, see the [module docs](self).
+#[derive(Debug)]
+pub struct RefreshSession<E: Curve, H: Digest + Clone, const M: usize> {
+    session_id: BigInt,
+    t: u16,
+    n: u16,
+    participants: Vec<u16>,
+    public_key: Point<E>,
+    received: BTreeMap<u16, RefreshMessage<E, H, M>>,
+    new_dk: Option<DecryptionKey>,
+}
+
+impl<E: Curve, H: Digest + Clone, const M: usize> RefreshSession<E, H, M> {
+    /// A session `session_id` of the `t`-out-of-`n` key with joint public key `public_key`, in
+    /// which the parties `participants` send a message. `new_dk` is the decryption key the
+    /// party got when it distributed its own message for the session.
+    pub fn new(
+        session_id: BigInt,
+        t: u16,
+        n: u16,
+        mut participants: Vec<u16>,
+        public_key: Point<E>,
+        new_dk: DecryptionKey,
+    ) -> FsDkrResult<Self> {
+        if t == 0 || t >= n {
+            return Err(FsDkrError::KeyThresholdOutOfRange { t, n });
+        }
+        participants.sort_unstable();
+        participants.dedup();
+        if participants.len() <= t as usize {
+            return Err(FsDkrError::PartiesThresholdViolation {
+                threshold: t,
+                refreshed_keys: participants.len(),
+            });
+        }
+        Ok(RefreshSession {
+            session_id,
+            t,
+            n,
+            participants,
+            public_key,
+            received: BTreeMap::new(),
+            new_dk: Some(new_dk),
+        })
+    }
+
+    /// Starts the session `session_id` in which the whole committee of `local_key` refreshes
+    /// it. Returns the session, which already holds the message of the party, and the message
+    /// to broadcast.
+    pub fn start(
+        local_key: &LocalKey<E>,
+        session_id: BigInt,
+    ) -> FsDkrResult<(Self, RefreshMessage<E, H, M>)> {
+        let prepared = RefreshMessage::prepare(
+            local_key.i,
+            local_key,
+            local_key.n,
+            &RefreshParams::default(),
+        )?;
+        let (message, new_dk) = RefreshMessage::finalize(prepared, &session_id)?;
+        let mut session = Self::new(
+            session_id,
+            local_key.t,
+            local_key.n,
+            (1..=local_key.n).collect(),
+            local_key.y_sum_s.clone(),
+            new_dk,
+        )?;
+        session.handle(message.clone())?;
+        Ok((session, message))
+    }
+
+    pub fn session_id(&self) -> &BigInt {
+        &self.session_id
+    }
+
+    pub fn status(&self) -> SessionStatus {
+        let missing: Vec<u16> = self
+            .participants
+            .iter()
+            .filter(|sender| !self.received.contains_key(sender))
+            .copied()
+            .collect();
+        if missing.is_empty() {
+            SessionStatus::QuorumReached
+        } else {
+            SessionStatus::Waiting { missing }
+        }
+    }
+
+    /// Accepts the message `msg` of one of the senders. A message bound to another session is
+    /// rejected with [FsDkrError::SessionMismatch], a second copy of a message with
+    /// [FsDkrError::DuplicatedRefreshMessage] and a different second message with
+    /// [FsDkrError::EquivocatingSender]. The proofs are only checked by
+    /// [RefreshSession::try_complete].
+    pub fn handle(&mut self, msg: RefreshMessage<E, H, M>) -> FsDkrResult<SessionStatus> {
+        let party_index = msg.party_index;
+        let invalid = |reason: &str| FsDkrError::InvalidRefreshMessage {
+            party_index,
+            reason: reason.to_string(),
+        };
+        if self.new_dk.is_none() {
+            return Err(FsDkrError::MalformedMessage {
+                party_index,
+                reason: "received after the session completed".to_string(),
+            });
+        }
+        if self.participants.binary_search(&party_index).is_err() {
+            return Err(invalid("not a participant of the session"));
+        }
+
+        match &msg.session_binding {
+            Some(binding) if binding.session_id() == &self.session_id => {}
+            _ => return Err(FsDkrError::SessionMismatch { party_index }),
+        }
+        msg.verify_session_binding(&self.session_id)?;
+
+        if msg.recipient_count() != self.n {
+            return Err(invalid("wrong number of receivers"));
+        }
+        if msg.new_t() != self.t {
+            return Err(invalid("wrong threshold"));
+        }
+        if msg.public_key != self.public_key {
+            return Err(invalid("wrong public key"));
+        }
+
+        if let Some(seen) = self.received.get(&party_index) {
+            return Err(if seen.fingerprint() == msg.fingerprint() {
+                FsDkrError::DuplicatedRefreshMessage { party_index }
+            } else {
+                FsDkrError::EquivocatingSender { party_index }
+            });
+        }
+        self.received.insert(party_index, msg);
+        Ok(self.status())
+    }
+
+    /// Collects the session for `old_key` once every sender was heard from, see
+    /// [RefreshMessage::collect_in_session]. Returns `None` before that, and after the session
+    /// completed: the new decryption key is used up by the first attempt, successful or not.
+    pub fn try_complete(&mut self, old_key: &LocalKey<E>) -> Option<FsDkrResult<LocalKey<E>>> {
+        if self.status() != SessionStatus::QuorumReached {
+            return None;
+        }
+        let new_dk = self.new_dk.take()?;
+        if old_key.t != self.t || old_key.y_sum_s != self.public_key {
+            return Some(Err(FsDkrError::RefreshContextMismatch));
+        }
+        let messages: Vec<_> = self.received.values().cloned().collect();
+        Some(RefreshMessage::collect_in_session(
+            &messages,
+            old_key,
+            &self.session_id,
+            new_dk,
+            &[],
+        ))
+    }
+}
@@ -0,0 +1,107 @@
+//! Audit record of a refresh.
+//!
+//! [RefreshMessage::collect_with_summary] returns, next to the refreshed key, a
+//! [RefreshSummary] of what the refresh accepted: the senders and the fingerprint of each of
+//! their messages, the session, the public shares of every party and the time the collect
+//! took. It holds no secret, so it can be logged or persisted as is.
+
+use crate::add_party_message::JoinMessage;
+use crate::error::FsDkrResult;
+use crate::refresh_message::RefreshMessage;
+use curv::cryptographic_primitives::hashing::Digest;
+use curv::elliptic::curves::{Curve, Point};
+use curv::BigInt;
+use multi_party_ecdsa::protocols::multi_party_ecdsa::gg_2020::state_machine::keygen::LocalKey;
+use paillier::DecryptionKey;
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
+
+/// Public record of a refresh collected by one party, see the [module docs](self).
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(bound = "E: Curve")]
+pub struct RefreshSummary<E: Curve> {
+    party_index: u16,
+    session_id: Option<BigInt>,
+    senders: Vec<u16>,
+    fingerprints: Vec<(u16, [u8; 32])>,
+    public_key: Point<E>,
+    pk_vec: Vec<Point<E>>,
+    duration: Duration,
+}
+
+impl<E: Curve> RefreshSummary<E> {
+    /// The index of the party in the refreshed committee.
+    pub fn party_index(&self) -> u16 {
+        self.party_index
+    }
+
+    /// The session the messages were bound to, if the collect was run within one.
+    pub fn session_id(&self) -> Option<&BigInt> {
+        self.session_id.as_ref()
+    }
+
+    /// The senders whose message was accepted, in increasing order.
+    pub fn senders(&self) -> &[u16] {
+        &self.senders
+    }
+
+    /// The [fingerprint](RefreshMessage::fingerprint) of the accepted message of every sender.
+    pub fn fingerprints(&self) -> &[(u16, [u8; 32])] {
+        &self.fingerprints
+    }
+
+    /// The joint public key of the refreshed key.
+    pub fn public_key(&self) -> &Point<E> {
+        &self.public_key
+    }
+
+    /// The new public share of every party.
+    pub fn pk_vec(&self) -> &[Point<E>] {
+        &self.pk_vec
+    }
+
+    /// The time the collect took, verification included.
+    pub fn duration(&self) -> Duration {
+        self.duration
+    }
+}
+
+impl<E: Curve, H: Digest + Clone, const M: usize> RefreshMessage<E, H, M> {
+    /// [RefreshMessage::collect], or [RefreshMessage::collect_in_session] when `session_id` is
+    /// given, returning a [RefreshSummary] of the refresh with the new key.
+    pub fn collect_with_summary(
+        refresh_messages: &[Self],
+        old_key: &LocalKey<E>,
+        session_id: Option<&BigInt>,
+        new_dk: DecryptionKey,
+        join_messages: &[JoinMessage<E, H, M>],
+    ) -> FsDkrResult<(LocalKey<E>, RefreshSummary<E>)> {
+        let started = Instant::now();
+        let new_key = match session_id {
+            Some(session_id) => Self::collect_in_session(
+                refresh_messages,
+                old_key,
+                session_id,
+                new_dk,
+                join_messages,
+            )?,
+            None => Self::collect(refresh_messages, old_key, new_dk, join_messages)?,
+        };
+
+        let mut fingerprints: Vec<_> = Self::unique_messages(refresh_messages)?
+            .into_iter()
+            .map(|msg| (msg.party_index, msg.fingerprint()))
+            .collect();
+        fingerprints.sort_unstable();
+        let summary = RefreshSummary {
+            party_index: new_key.i,
+            session_id: session_id.cloned(),
+            senders: fingerprints.iter().map(|(sender, _)| *sender).collect(),
+            fingerprints,
+            public_key: new_key.y_sum_s.clone(),
+            pk_vec: new_key.pk_vec.clone(),
+            duration: started.elapsed(),
+        };
+        Ok((new_key, summary))
+    }
+}
@@ -0,0 +1,479 @@
+//! A [LocalKey] bundled with the metadata of its refresh lifecycle.
+//!
+//! [RefreshableLocalKey] keeps the current epoch, the last collected session, the digest of the
+//! previous refresh transcript and the list of retired Paillier keys next to the key itself, so
+//! the metadata and the key shares are always updated together. Every refresh runs in a session:
+//! the messages are bound to it by their senders, see [crate::prepared_distribution], so a
+//! message captured in one session, of this epoch or an earlier one, is rejected in any other.
+//! The raw [LocalKey] entry points on [RefreshMessage] remain available.
+
+use crate::add_party_message::JoinMessage;
+use crate::anchor::{anchor_digest, Anchor, AnchorPolicy, AnchorReceipt};
+use crate::bip340::{normalize_for_bip340, Bip340Normalization, KeyParity};
+use crate::certified_rotation::{rotation_digest, SelfCertifiedRotation};
+use crate::epoch::EpochGuard;
+use crate::error::{FsDkrError, FsDkrResult, Warning};
+use crate::local_key::erase_secrets;
+use crate::refresh_message::{CollectOptions, RefreshMessage, RefreshParams};
+use crate::signing_context::SigningContext;
+use crate::wire::{decode_message, Codec, DecodeLimits};
+use curv::cryptographic_primitives::hashing::Digest;
+use curv::elliptic::curves::{Curve, Secp256k1};
+use curv::BigInt;
+use multi_party_ecdsa::protocols::multi_party_ecdsa::gg_2020::party_i::SignatureRecid;
+use multi_party_ecdsa::protocols::multi_party_ecdsa::gg_2020::state_machine::keygen::LocalKey;
+use paillier::{DecryptionKey, EncryptionKey};
+use serde::{Deserialize, Serialize};
+
+/// Public result of a successful collect through [RefreshableLocalKey].
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RefreshOutcome {
+    pub guard: EpochGuard,
+    pub transcript_digest: BigInt,
+    /// Set when BIP340 normalization is enabled, to be compared across the committee with
+    /// [crate::bip340::verify_normalizations].
+    pub bip340: Option<Bip340Normalization>,
+    /// The metadata of every sender, by sender index, see
+    /// [RefreshParams::metadata](crate::refresh_message::RefreshParams::metadata).
+    #[serde(default)]
+    pub metadata: Vec<(u16, Vec<u8>)>,
+    /// Senders left out of the refresh and why, see [CollectOptions::tolerate_failures].
+    #[serde(default)]
+    pub excluded: Vec<(u16, String)>,
+    /// Conditions the refresh passed only narrowly, see [RefreshMessage::collect_warnings].
+    #[serde(default)]
+    pub warnings: Vec<Warning>,
+    /// Set when the transcript digest was anchored, see [crate::anchor].
+    #[serde(default)]
+    pub anchor_receipt: Option<AnchorReceipt>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(bound = "E: Curve")]
+pub struct RefreshableLocalKey<E: Curve> {
+    inner: LocalKey<E>,
+    epoch: u64,
+    last_session: Option<BigInt>,
+    prev_transcript_digest: Option<BigInt>,
+    burned_eks: Vec<EncryptionKey>,
+    #[serde(default)]
+    bip340: bool,
+    #[serde(default)]
+    anchor_receipt: Option<AnchorReceipt>,
+}
+
+impl<E: Curve> RefreshableLocalKey<E> {
+    /// Wraps a key fresh out of keygen, at epoch 0.
+    pub fn new(inner: LocalKey<E>) -> Self {
+        RefreshableLocalKey {
+            inner,
+            epoch: 0,
+            last_session: None,
+            prev_transcript_digest: None,
+            burned_eks: Vec::new(),
+            bip340: false,
+            anchor_receipt: None,
+        }
+    }
+
+    pub fn inner(&self) -> &LocalKey<E> {
+        &self.inner
+    }
+
+    pub fn into_inner(self) -> LocalKey<E> {
+        self.inner
+    }
+
+    pub fn epoch(&self) -> u64 {
+        self.epoch
+    }
+
+    pub fn last_session(&self) -> Option<&BigInt> {
+        self.last_session.as_ref()
+    }
+
+    pub fn prev_transcript_digest(&self) -> Option<&BigInt> {
+        self.prev_transcript_digest.as_ref()
+    }
+
+    /// Receipt of the anchored transcript digest of the last refresh, if it was anchored.
+    pub fn anchor_receipt(&self) -> Option<&AnchorReceipt> {
+        self.anchor_receipt.as_ref()
+    }
+
+    /// Signing constants of `quorum` for the current epoch, see [crate::signing_context].
+    pub fn signing_context(&self, quorum: &[u16]) -> FsDkrResult<SigningContext<E>> {
+        SigningContext::new(&self.inner, self.epoch, quorum)
+    }
+
+    /// Paillier encryption keys that were rotated out and must never be encrypted to again.
+    pub fn burned_eks(&self) -> &[EncryptionKey] {
+        &self.burned_eks
+    }
+
+    /// Public key export and address derivation for the current epoch.
+    #[cfg(feature = "export")]
+    pub fn public_data(&self) -> crate::export::RefreshPublicData<E> {
+        let public_data = crate::export::RefreshPublicData::from_local_key(&self.inner);
+        match &self.anchor_receipt {
+            Some(receipt) => public_data.with_anchor_receipt(receipt.clone()),
+            None => public_data,
+        }
+    }
+
+    /// Paper backup of the current share, see [crate::mnemonic] and
+    /// [RefreshOutcome::share_mnemonic]. The share is wrapped with `passphrase` when one is
+    /// given.
+    #[cfg(feature = "mnemonic")]
+    pub fn share_mnemonic(&self, passphrase: Option<&str>) -> Vec<&'static str> {
+        crate::mnemonic::ShareBackup::from_local_key(&self.inner, self.epoch)
+            .to_mnemonic(passphrase)
+    }
+
+    /// Restores the share of a mnemonic produced by [RefreshableLocalKey::share_mnemonic] into
+    /// this key, after checking it belongs to the same committee, party and epoch.
+    #[cfg(feature = "mnemonic")]
+    pub fn restore_share_from_mnemonic(
+        &mut self,
+        words: &[&str],
+        passphrase: Option<&str>,
+    ) -> FsDkrResult<()> {
+        let backup = crate::mnemonic::ShareBackup::<E>::from_mnemonic(words, passphrase)?;
+        if backup.party_index != self.inner.i || backup.epoch != self.epoch {
+            return Err(FsDkrError::MnemonicShareMismatch);
+        }
+        backup.verify_against(&self.inner)?;
+        self.inner.keys_linear.x_i = backup.share;
+        Ok(())
+    }
+
+    /// Parity of the y coordinate of the joint public key.
+    pub fn parity(&self) -> KeyParity {
+        KeyParity::of(&self.inner.y_sum_s)
+    }
+
+    pub fn bip340_normalization(&self) -> bool {
+        self.bip340
+    }
+
+    /// When enabled, every collected key is normalized with [normalize_for_bip340] before it is
+    /// stored, so the shares always correspond to the even-y point of the x-only key. Enabling it
+    /// does not touch the current key, which is normalized by the next collect.
+    pub fn set_bip340_normalization(&mut self, enabled: bool) {
+        self.bip340 = enabled;
+    }
+
+    /// Same as [RefreshMessage::distribute] with the message bound to `session_id`, see
+    /// [RefreshMessage::finalize], additionally refusing to encrypt to any receiver key that was
+    /// burned by an earlier refresh.
+    pub fn distribute<H: Digest + Clone, const M: usize>(
+        &self,
+        session_id: &BigInt,
+        new_n: u16,
+    ) -> FsDkrResult<(RefreshMessage<E, H, M>, DecryptionKey)> {
+        self.distribute_with_params(session_id, new_n, &RefreshParams::default())
+    }
+
+    /// Same as [RefreshableLocalKey::distribute], with the parameters of `params`.
+    pub fn distribute_with_params<H: Digest + Clone, const M: usize>(
+        &self,
+        session_id: &BigInt,
+        new_n: u16,
+        params: &RefreshParams<E>,
+    ) -> FsDkrResult<(RefreshMessage<E, H, M>, DecryptionKey)> {
+        RefreshMessage::<E, H, M>::validate_receiver_keys(
+            &self.inner.paillier_key_vec,
+            new_n,
+            &self.burned_eks,
+            params.min_receiver_modulus_bits,
+        )?;
+        let prepared = RefreshMessage::prepare(self.inner.i, &self.inner, new_n, params)?;
+        RefreshMessage::finalize(prepared, session_id)
+    }
+
+    /// Same as [RefreshMessage::collect_in_session], additionally advancing the epoch metadata.
+    /// On failure neither the key nor the metadata is modified.
+    pub fn collect<H: Digest + Clone, const M: usize>(
+        &mut self,
+        session_id: &BigInt,
+        refresh_messages: &[RefreshMessage<E, H, M>],
+        new_dk: DecryptionKey,
+        join_messages: &[JoinMessage<E, H, M>],
+    ) -> FsDkrResult<RefreshOutcome> {
+        Ok(self
+            .collect_pending(session_id, refresh_messages, new_dk, join_messages)?
+            .commit())
+    }
+
+    /// Verifies the refresh messages and computes the refreshed key without storing it. The key
+    /// is only replaced once the returned [PendingRefresh] is committed; dropping it leaves the
+    /// key and the metadata untouched.
+    pub fn collect_pending<H: Digest + Clone, const M: usize>(
+        &mut self,
+        session_id: &BigInt,
+        refresh_messages: &[RefreshMessage<E, H, M>],
+        new_dk: DecryptionKey,
+        join_messages: &[JoinMessage<E, H, M>],
+    ) -> FsDkrResult<PendingRefresh<'_, E>> {
+        self.collect_pending_with_options(
+            session_id,
+            refresh_messages,
+            new_dk,
+            join_messages,
+            &CollectOptions::default(),
+        )
+    }
+
+    /// Same as [RefreshableLocalKey::collect_pending] with the options of
+    /// [RefreshMessage::collect_with_options]. The senders left out are listed in the outcome.
+    pub fn collect_pending_with_options<H: Digest + Clone, const M: usize>(
+        &mut self,
+        session_id: &BigInt,
+        refresh_messages: &[RefreshMessage<E, H, M>],
+        new_dk: DecryptionKey,
+        join_messages: &[JoinMessage<E, H, M>],
+        options: &CollectOptions,
+    ) -> FsDkrResult<PendingRefresh<'_, E>> {
+        self.collect_pending_excluding(
+            session_id,
+            refresh_messages,
+            new_dk,
+            join_messages,
+            options,
+            None,
+        )
+    }
+
+    /// Same as [RefreshableLocalKey::collect], from the raw bytes each sender sent, by sender
+    /// index, see [RefreshableLocalKey::collect_pending_from_bytes].
+    #[allow(clippy::too_many_arguments)]
+    pub fn collect_from_bytes<H: Digest + Clone, C: Codec, const M: usize>(
+        &mut self,
+        session_id: &BigInt,
+        blobs: &[(u16, Vec<u8>)],
+        codec: &C,
+        limits: &DecodeLimits,
+        new_dk: DecryptionKey,
+        options: &CollectOptions,
+    ) -> FsDkrResult<RefreshOutcome> {
+        Ok(self
+            .collect_pending_from_bytes::<H, C, M>(
+                session_id, blobs, codec, limits, new_dk, options,
+            )?
+            .commit())
+    }
+
+    /// Same as [RefreshableLocalKey::collect_pending], from the raw bytes each sender sent, by
+    /// sender index. Every blob is decoded with [decode_message], so the senders whose bytes
+    /// could not be decoded fail with [FsDkrError::MalformedMessage] while the ones whose message
+    /// fails verification fail with the verification error. Both count towards the failures
+    /// `options` tolerates; past them, the collect fails with [FsDkrError::TooManyFailedSenders]
+    /// listing every failed sender.
+    #[allow(clippy::too_many_arguments)]
+    pub fn collect_pending_from_bytes<H: Digest + Clone, C: Codec, const M: usize>(
+        &mut self,
+        session_id: &BigInt,
+        blobs: &[(u16, Vec<u8>)],
+        codec: &C,
+        limits: &DecodeLimits,
+        new_dk: DecryptionKey,
+        options: &CollectOptions,
+    ) -> FsDkrResult<PendingRefresh<'_, E>> {
+        let mut refresh_messages: Vec<RefreshMessage<E, H, M>> = Vec::new();
+        let mut malformed = Vec::new();
+        for (sender, bytes) in blobs.iter() {
+            match decode_message(*sender, bytes, codec, limits) {
+                Ok(message) => refresh_messages.push(message),
+                Err(error) => malformed.push((*sender, error)),
+            }
+        }
+        self.collect_pending_excluding(
+            session_id,
+            &refresh_messages,
+            new_dk,
+            &[],
+            options,
+            Some(malformed),
+        )
+    }
+
+    /// Collects reporting every failed sender, `excluded` included, unless `excluded` is `None`
+    /// and no failure is tolerated.
+    fn collect_pending_excluding<H: Digest + Clone, const M: usize>(
+        &mut self,
+        session_id: &BigInt,
+        refresh_messages: &[RefreshMessage<E, H, M>],
+        new_dk: DecryptionKey,
+        join_messages: &[JoinMessage<E, H, M>],
+        options: &CollectOptions,
+        excluded: Option<Vec<(u16, FsDkrError)>>,
+    ) -> FsDkrResult<PendingRefresh<'_, E>> {
+        if self.last_session.as_ref() == Some(session_id) {
+            return Err(FsDkrError::SessionReused);
+        }
+
+        let (mut new_key, excluded) = if options.max_failures() == 0 && excluded.is_none() {
+            let new_key = RefreshMessage::collect_in_session(
+                refresh_messages,
+                &self.inner,
+                session_id,
+                new_dk,
+                join_messages,
+            )?;
+            (new_key, Vec::new())
+        } else {
+            let (new_key, excluded) = RefreshMessage::collect_excluding(
+                refresh_messages,
+                &self.inner,
+                new_dk,
+                join_messages,
+                options,
+                Some(session_id),
+                excluded.unwrap_or_default(),
+            )?;
+            let excluded = excluded
+                .into_iter()
+                .map(|(party_index, error)| (party_index, error.to_string()))
+                .collect();
+            (new_key, excluded)
+        };
+        let bip340 = if self.bip340 {
+            Some(normalize_for_bip340::<E, H>(&mut new_key))
+        } else {
+            None
+        };
+
+        let guard = EpochGuard::for_keys::<E, H>(self.epoch + 1, &self.inner, &new_key);
+        let certificate_digest = rotation_digest::<H>(&guard);
+        // the warnings, the transcript and the metadata cover the contributions every party
+        // combined: a copy delivered twice counts once and the senders left out do not count
+        let accepted: Vec<_> = RefreshMessage::unique_messages(refresh_messages)?
+            .into_iter()
+            .filter(|msg| {
+                !excluded
+                    .iter()
+                    .any(|(party_index, _)| *party_index == msg.party_index)
+            })
+            .collect();
+        let warnings = RefreshMessage::warnings_for(refresh_messages, &accepted, &self.inner);
+        let transcript_digest = RefreshMessage::transcript_digest_of(&accepted);
+        let mut metadata: Vec<_> = accepted
+            .iter()
+            .map(|msg| (msg.party_index, msg.metadata().to_vec()))
+            .collect();
+        metadata.sort();
+
+        Ok(PendingRefresh {
+            key: self,
+            new_key,
+            session_id: session_id.clone(),
+            certificate_digest,
+            outcome: RefreshOutcome {
+                guard,
+                transcript_digest,
+                bip340,
+                metadata,
+                excluded,
+                warnings,
+                anchor_receipt: None,
+            },
+        })
+    }
+}
+
+/// A verified refresh of a [RefreshableLocalKey] that is not stored yet.
+///
+/// The old key stays available until [PendingRefresh::commit], so the old shares can still sign,
+/// e.g. the [SelfCertifiedRotation] of the refresh. Committing replaces the key, wipes the old
+/// Paillier decryption key and consumes the pending refresh, so nothing can be certified with
+/// the old shares afterwards:
+///
+/// ```compile_fail
+/// use curv::elliptic::curves::Secp256k1;
+/// use fs_dkr::refreshable_key::PendingRefresh;
+/// use multi_party_ecdsa::protocols::multi_party_ecdsa::gg_2020::party_i::SignatureRecid;
+/// use sha2::Sha256;
+///
+/// fn certify_after_commit(pending: PendingRefresh<'_, Secp256k1>, signature: SignatureRecid) {
+///     pending.commit();
+///     pending.certify::<Sha256>(signature);
+/// }
+/// ```
+pub struct PendingRefresh<'a, E: Curve> {
+    key: &'a mut RefreshableLocalKey<E>,
+    new_key: LocalKey<E>,
+    session_id: BigInt,
+    certificate_digest: BigInt,
+    outcome: RefreshOutcome,
+}
+
+impl<'a, E: Curve> PendingRefresh<'a, E> {
+    /// The key before the refresh.
+    pub fn old_key(&self) -> &LocalKey<E> {
+        &self.key.inner
+    }
+
+    pub fn new_key(&self) -> &LocalKey<E> {
+        &self.new_key
+    }
+
+    pub fn outcome(&self) -> &RefreshOutcome {
+        &self.outcome
+    }
+
+    /// The message a quorum signs with the old shares to certify the refresh.
+    pub fn certificate_digest(&self) -> &BigInt {
+        &self.certificate_digest
+    }
+
+    /// Stores the refreshed key and advances the epoch metadata.
+    pub fn commit(self) -> RefreshOutcome {
+        let key = self.key;
+        for ek in key.inner.paillier_key_vec.iter() {
+            if !self.new_key.paillier_key_vec.contains(ek) && !key.burned_eks.contains(ek) {
+                key.burned_eks.push(ek.clone());
+            }
+        }
+
+        erase_secrets(&mut key.inner);
+        key.inner = self.new_key;
+        key.epoch = self.outcome.guard.epoch();
+        key.last_session = Some(self.session_id);
+        key.prev_transcript_digest = Some(self.outcome.transcript_digest.clone());
+        key.anchor_receipt = self.outcome.anchor_receipt.clone();
+
+        self.outcome
+    }
+
+    /// Publishes the transcript digest to `anchor`, then commits with the receipt stored in the
+    /// outcome and next to the key. If publishing fails, [AnchorPolicy::Required] returns the
+    /// error without storing the refreshed key, and [AnchorPolicy::BestEffort] commits without
+    /// a receipt.
+    pub fn commit_anchored(
+        mut self,
+        anchor: &dyn Anchor,
+        policy: AnchorPolicy,
+    ) -> FsDkrResult<RefreshOutcome> {
+        match anchor.publish(anchor_digest(&self.outcome.transcript_digest)) {
+            Ok(receipt) => self.outcome.anchor_receipt = Some(receipt),
+            Err(error) if policy == AnchorPolicy::Required => return Err(error),
+            Err(_) => {}
+        }
+        Ok(self.commit())
+    }
+}
+
+impl<'a> PendingRefresh<'a, Secp256k1> {
+    /// Bundles the signature of [PendingRefresh::certificate_digest] produced with the old
+    /// shares into a certificate of the refresh.
+    pub fn certify<H: Digest + Clone>(
+        &self,
+        signature: SignatureRecid,
+    ) -> FsDkrResult<SelfCertifiedRotation> {
+        SelfCertifiedRotation::new::<H>(
+            self.outcome.guard.clone(),
+            signature,
+            &self.key.inner.y_sum_s,
+        )
+    }
+}
@@ -0,0 +1,197 @@
+//! Refresh rehearsals over a throwaway key.
+//!
+//! [rehearsal_fixture] deals a committee with the same shape as a real key (threshold, number
+//! of parties, indices) but a fresh random secret known to the dealer. The keys are wrapped in
+//! [RehearsalKey], which has no conversion into a [LocalKey], so a rehearsal key can not be
+//! passed where a real key is expected by accident; the refresh itself runs through the normal
+//! distribute and collect code. [RehearsalReport] collects per-party timings and failures.
+
+use crate::add_party_message::generate_dlog_statement_proofs;
+use crate::error::{FsDkrError, FsDkrResult};
+use crate::refresh_message::RefreshMessage;
+use curv::cryptographic_primitives::hashing::Digest;
+use curv::cryptographic_primitives::secret_sharing::feldman_vss::VerifiableSS;
+use curv::elliptic::curves::{Curve, Point, Scalar};
+use multi_party_ecdsa::protocols::multi_party_ecdsa::gg_2020::party_i::SharedKeys;
+use multi_party_ecdsa::protocols::multi_party_ecdsa::gg_2020::state_machine::keygen::LocalKey;
+use paillier::{KeyGeneration, Paillier};
+use std::fmt;
+use std::time::{Duration, Instant};
+
+/// A [LocalKey] dealt for a rehearsal. Its secret is known to the dealer, so it must never
+/// protect anything.
+#[derive(Clone)]
+pub struct RehearsalKey<E: Curve>(LocalKey<E>);
+
+impl<E: Curve> RehearsalKey<E> {
+    /// The dealt key, to run the refresh code on.
+    pub fn rehearsal_local_key(&self) -> &LocalKey<E> {
+        &self.0
+    }
+
+    pub fn rehearsal_local_key_mut(&mut self) -> &mut LocalKey<E> {
+        &mut self.0
+    }
+}
+
+impl<E: Curve> fmt::Debug for RehearsalKey<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "RehearsalKey {{ REHEARSAL ONLY, i: {}, t: {}, n: {} }}",
+            self.0.i, self.0.t, self.0.n
+        )
+    }
+}
+
+/// Deals a throwaway committee with the threshold and number of parties of `real_key`.
+pub fn rehearsal_fixture<E: Curve>(real_key: &LocalKey<E>) -> Vec<RehearsalKey<E>> {
+    deal_committee(real_key.t, real_key.n)
+        .into_iter()
+        .map(RehearsalKey)
+        .collect()
+}
+
+/// Deals a committee of `n` parties with threshold `t` from a random secret.
+pub(crate) fn deal_committee<E: Curve>(t: u16, n: u16) -> Vec<LocalKey<E>> {
+    let secret = Scalar::<E>::random();
+    let y = Point::<E>::generator() * &secret;
+    let (vss_scheme, shares) = VerifiableSS::<E>::share(t, n, &secret);
+    let pk_vec: Vec<Point<E>> = shares
+        .iter()
+        .map(|share| Point::<E>::generator() * share)
+        .collect();
+    let paillier_keys: Vec<_> = (0..n)
+        .map(|_| Paillier::keypair_with_modulus_size(crate::PAILLIER_KEY_SIZE).keys())
+        .collect();
+    let paillier_key_vec: Vec<_> = paillier_keys.iter().map(|(ek, _)| ek.clone()).collect();
+    let h1_h2_n_tilde_vec: Vec<_> = (0..n).map(|_| generate_dlog_statement_proofs().0).collect();
+
+    paillier_keys
+        .into_iter()
+        .enumerate()
+        .map(|(k, (_, paillier_dk))| LocalKey {
+            paillier_dk,
+            pk_vec: pk_vec.clone(),
+            keys_linear: SharedKeys {
+                y: y.clone(),
+                x_i: shares[k].clone(),
+            },
+            paillier_key_vec: paillier_key_vec.clone(),
+            y_sum_s: y.clone(),
+            h1_h2_n_tilde_vec: h1_h2_n_tilde_vec.clone(),
+            vss_scheme: vss_scheme.clone(),
+            i: k as u16 + 1,
+            t,
+            n,
+        })
+        .collect()
+}
+
+/// Outcome of a rehearsal for one party.
+#[derive(Clone, Debug)]
+pub struct PartyReport {
+    pub party_index: u16,
+    pub distribute: Option<Duration>,
+    pub collect: Option<Duration>,
+    pub failure: Option<FsDkrError>,
+}
+
+impl PartyReport {
+    /// Both phases ran and nothing failed.
+    pub fn is_green(&self) -> bool {
+        self.distribute.is_some() && self.collect.is_some() && self.failure.is_none()
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct RehearsalReport {
+    pub parties: Vec<PartyReport>,
+}
+
+impl RehearsalReport {
+    fn party(&mut self, party_index: u16) -> &mut PartyReport {
+        match self
+            .parties
+            .iter()
+            .position(|p| p.party_index == party_index)
+        {
+            Some(k) => &mut self.parties[k],
+            None => {
+                self.parties.push(PartyReport {
+                    party_index,
+                    distribute: None,
+                    collect: None,
+                    failure: None,
+                });
+                self.parties.last_mut().unwrap()
+            }
+        }
+    }
+
+    /// Records the distribute phase of a party, e.g. as measured over a real transport.
+    pub fn record_distribute(
+        &mut self,
+        party_index: u16,
+        elapsed: Duration,
+        result: FsDkrResult<()>,
+    ) {
+        let party = self.party(party_index);
+        party.distribute = Some(elapsed);
+        if let Err(e) = result {
+            party.failure = Some(e);
+        }
+    }
+
+    /// Records the collect phase of a party.
+    pub fn record_collect(&mut self, party_index: u16, elapsed: Duration, result: FsDkrResult<()>) {
+        let party = self.party(party_index);
+        party.collect = Some(elapsed);
+        if let Err(e) = result {
+            party.failure = Some(e);
+        }
+    }
+
+    pub fn all_green(&self) -> bool {
+        !self.parties.is_empty() && self.parties.iter().all(PartyReport::is_green)
+    }
+}
+
+/// Runs a full refresh of `keys` in-process and reports the timing of every party.
+pub fn rehearse<E: Curve, H: Digest + Clone, const M: usize>(
+    keys: &mut [RehearsalKey<E>],
+) -> RehearsalReport {
+    let mut report = RehearsalReport::default();
+    let new_n = keys.len() as u16;
+
+    let mut messages = Vec::new();
+    let mut new_dks = Vec::new();
+    for key in keys.iter() {
+        let start = Instant::now();
+        let result = RefreshMessage::<E, H, M>::distribute(key.0.i, &key.0, new_n);
+        let elapsed = start.elapsed();
+        match result {
+            Ok((message, new_dk)) => {
+                messages.push(message);
+                new_dks.push(Some(new_dk));
+                report.record_distribute(key.0.i, elapsed, Ok(()));
+            }
+            Err(e) => {
+                new_dks.push(None);
+                report.record_distribute(key.0.i, elapsed, Err(e));
+            }
+        }
+    }
+
+    for (key, new_dk) in keys.iter_mut().zip(new_dks) {
+        let party_index = key.0.i;
+        if let Some(new_dk) = new_dk {
+            let start = Instant::now();
+            let result = RefreshMessage::collect(&messages, &key.0, new_dk, &[])
+                .map(|new_key| key.0 = new_key);
+            report.record_collect(party_index, start.elapsed(), result);
+        }
+    }
+
+    report
+}
@@ -0,0 +1,353 @@
+//! Revocation statements published after an emergency refresh.
+//!
+//! When a refresh is run because shares are suspected to be compromised, the refreshed committee
+//! announces that signatures produced under the superseded epochs after a given time must not be
+//! trusted. The [RevocationNotice] is built from the [RefreshOutcome] of the refresh, a quorum
+//! signs its digest with the *new* shares, and the resulting [RevocationStatement] can be checked
+//! by anyone against the joint public key, which the refresh leaves unchanged.
+//!
+//! Both types have a canonical byte encoding, so the published statement is the same whatever
+//! produced it:
+//!
+//! ```text
+//! version (1) | fingerprint length (2) | fingerprint | superseded epoch (8) | effective at (8)
+//!   | number of reason codes (2) | (reason code length (2) | reason code)* [| r (32) | s (32) | recid (1)]
+//! ```
+//!
+//! All integers are big-endian. The signature is only present in a [RevocationStatement].
+
+use crate::error::{FsDkrError, FsDkrResult};
+use crate::refreshable_key::RefreshOutcome;
+use curv::arithmetic::Converter;
+use curv::cryptographic_primitives::hashing::{Digest, DigestExt};
+use curv::elliptic::curves::{Point, Scalar, Secp256k1};
+use curv::BigInt;
+use multi_party_ecdsa::protocols::multi_party_ecdsa::gg_2020::party_i::{verify, SignatureRecid};
+use serde::{Deserialize, Serialize};
+use std::convert::TryFrom;
+
+const VERSION: u8 = 1;
+const SCALAR_LEN: usize = 32;
+
+/// The unsigned content of a [RevocationStatement].
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "NoticeFields")]
+pub struct RevocationNotice {
+    key_fingerprint: BigInt,
+    superseded_epoch: u64,
+    effective_at: u64,
+    reason_codes: Vec<String>,
+}
+
+/// A deserialized [RevocationNotice], checked by [RevocationNotice::new].
+#[derive(Deserialize)]
+struct NoticeFields {
+    key_fingerprint: BigInt,
+    superseded_epoch: u64,
+    effective_at: u64,
+    reason_codes: Vec<String>,
+}
+
+impl TryFrom<NoticeFields> for RevocationNotice {
+    type Error = FsDkrError;
+
+    fn try_from(fields: NoticeFields) -> FsDkrResult<Self> {
+        RevocationNotice::new(
+            fields.key_fingerprint,
+            fields.superseded_epoch,
+            fields.effective_at,
+            fields.reason_codes,
+        )
+    }
+}
+
+impl RevocationNotice {
+    /// `effective_at` is a unix timestamp in seconds: signatures produced after it under an epoch
+    /// up to and including `superseded_epoch` must not be trusted. The fingerprint, the list of
+    /// reason codes and every reason code are length-prefixed with two bytes in the canonical
+    /// encoding; a longer one fails with [FsDkrError::RevocationFieldTooLong].
+    pub fn new(
+        key_fingerprint: BigInt,
+        superseded_epoch: u64,
+        effective_at: u64,
+        reason_codes: Vec<String>,
+    ) -> FsDkrResult<Self> {
+        check_len("key fingerprint", key_fingerprint.to_bytes().len())?;
+        check_len("reason codes", reason_codes.len())?;
+        for reason_code in reason_codes.iter() {
+            check_len("reason code", reason_code.len())?;
+        }
+        Ok(RevocationNotice {
+            key_fingerprint,
+            superseded_epoch,
+            effective_at,
+            reason_codes,
+        })
+    }
+
+    /// Fingerprint of the refreshed key, see [crate::epoch::key_fingerprint].
+    pub fn key_fingerprint(&self) -> &BigInt {
+        &self.key_fingerprint
+    }
+
+    pub fn superseded_epoch(&self) -> u64 {
+        self.superseded_epoch
+    }
+
+    pub fn effective_at(&self) -> u64 {
+        self.effective_at
+    }
+
+    pub fn reason_codes(&self) -> &[String] {
+        &self.reason_codes
+    }
+
+    /// The message a quorum of the refreshed committee signs.
+    pub fn digest<H: Digest + Clone>(&self) -> BigInt {
+        H::new().chain(&self.canonical_bytes()).result_bigint()
+    }
+
+    pub fn canonical_bytes(&self) -> Vec<u8> {
+        let mut bytes = vec![VERSION];
+        push_field(&mut bytes, &self.key_fingerprint.to_bytes());
+        bytes.extend_from_slice(&self.superseded_epoch.to_be_bytes());
+        bytes.extend_from_slice(&self.effective_at.to_be_bytes());
+        push_len(&mut bytes, self.reason_codes.len());
+        for reason_code in self.reason_codes.iter() {
+            push_field(&mut bytes, reason_code.as_bytes());
+        }
+        bytes
+    }
+
+    pub fn from_canonical_bytes(bytes: &[u8]) -> FsDkrResult<Self> {
+        let mut reader = Reader(bytes);
+        let notice = reader.notice()?;
+        reader.finish()?;
+        Ok(notice)
+    }
+}
+
+impl RefreshOutcome {
+    /// Builds the notice revoking every epoch before the one this refresh entered, see
+    /// [RevocationNotice::new].
+    pub fn revocation_notice(
+        &self,
+        effective_at: u64,
+        reason_codes: Vec<String>,
+    ) -> FsDkrResult<RevocationNotice> {
+        RevocationNotice::new(
+            self.guard.new_fingerprint().clone(),
+            self.guard.epoch().saturating_sub(1),
+            effective_at,
+            reason_codes,
+        )
+    }
+}
+
+/// A [RevocationNotice] signed by a quorum of the refreshed committee.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RevocationStatement {
+    notice: RevocationNotice,
+    signature: SignatureRecid,
+}
+
+impl RevocationStatement {
+    /// Bundles `signature` over the digest of `notice`, checking it against `public_key`.
+    pub fn new<H: Digest + Clone>(
+        notice: RevocationNotice,
+        signature: SignatureRecid,
+        public_key: &Point<Secp256k1>,
+    ) -> FsDkrResult<Self> {
+        let statement = RevocationStatement { notice, signature };
+        statement.verify::<H>(public_key)?;
+        Ok(statement)
+    }
+
+    pub fn notice(&self) -> &RevocationNotice {
+        &self.notice
+    }
+
+    pub fn signature(&self) -> &SignatureRecid {
+        &self.signature
+    }
+
+    /// Checks that the statement was signed by the key with joint public key `public_key`.
+    pub fn verify<H: Digest + Clone>(&self, public_key: &Point<Secp256k1>) -> FsDkrResult<()> {
+        verify(&self.signature, public_key, &self.notice.digest::<H>())
+            .map_err(|_| FsDkrError::RevocationStatementInvalid)
+    }
+
+    pub fn canonical_bytes(&self) -> Vec<u8> {
+        let mut bytes = self.notice.canonical_bytes();
+        bytes.extend_from_slice(&self.signature.r.to_bytes());
+        bytes.extend_from_slice(&self.signature.s.to_bytes());
+        bytes.push(self.signature.recid);
+        bytes
+    }
+
+    /// Parses a statement published with [RevocationStatement::canonical_bytes]. The signature
+    /// is not checked, see [RevocationStatement::verify].
+    pub fn from_canonical_bytes(bytes: &[u8]) -> FsDkrResult<Self> {
+        let mut reader = Reader(bytes);
+        let notice = reader.notice()?;
+        let r = reader.scalar()?;
+        let s = reader.scalar()?;
+        let recid = reader.take(1)?[0];
+        reader.finish()?;
+        Ok(RevocationStatement {
+            notice,
+            signature: SignatureRecid { r, s, recid },
+        })
+    }
+}
+
+fn check_len(field: &'static str, len: usize) -> FsDkrResult<()> {
+    if len > u16::MAX as usize {
+        return Err(FsDkrError::RevocationFieldTooLong { field, len });
+    }
+    Ok(())
+}
+
+/// [RevocationNotice::new] checked that every length fits.
+fn push_len(bytes: &mut Vec<u8>, len: usize) {
+    let len = u16::try_from(len).expect("length checked by RevocationNotice::new");
+    bytes.extend_from_slice(&len.to_be_bytes());
+}
+
+fn push_field(bytes: &mut Vec<u8>, field: &[u8]) {
+    push_len(bytes, field.len());
+    bytes.extend_from_slice(field);
+}
+
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> FsDkrResult<&'a [u8]> {
+        if self.0.len() < len {
+            return Err(FsDkrError::RevocationStatementMalformed);
+        }
+        let (head, tail) = self.0.split_at(len);
+        self.0 = tail;
+        Ok(head)
+    }
+
+    fn u16(&mut self) -> FsDkrResult<u16> {
+        let bytes = self.take(2)?;
+        Ok(u16::from_be_bytes([bytes[0], bytes[1]]))
+    }
+
+    fn u64(&mut self) -> FsDkrResult<u64> {
+        let mut bytes = [0u8; 8];
+        bytes.copy_from_slice(self.take(8)?);
+        Ok(u64::from_be_bytes(bytes))
+    }
+
+    fn field(&mut self) -> FsDkrResult<&'a [u8]> {
+        let len = self.u16()? as usize;
+        self.take(len)
+    }
+
+    fn scalar(&mut self) -> FsDkrResult<Scalar<Secp256k1>> {
+        Scalar::from_bytes(self.take(SCALAR_LEN)?)
+            .map_err(|_| FsDkrError::RevocationStatementMalformed)
+    }
+
+    fn notice(&mut self) -> FsDkrResult<RevocationNotice> {
+        if self.take(1)?[0] != VERSION {
+            return Err(FsDkrError::RevocationStatementMalformed);
+        }
+        let key_fingerprint = BigInt::from_bytes(self.field()?);
+        let superseded_epoch = self.u64()?;
+        let effective_at = self.u64()?;
+        let reason_codes = (0..self.u16()?)
+            .map(|_| {
+                String::from_utf8(self.field()?.to_vec())
+                    .map_err(|_| FsDkrError::RevocationStatementMalformed)
+            })
+            .collect::<FsDkrResult<Vec<_>>>()?;
+        Ok(RevocationNotice {
+            key_fingerprint,
+            superseded_epoch,
+            effective_at,
+            reason_codes,
+        })
+    }
+
+    fn finish(&self) -> FsDkrResult<()> {
+        if !self.0.is_empty() {
+            return Err(FsDkrError::RevocationStatementMalformed);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_canonical_bytes_round_trip() {
+        let notice = RevocationNotice::new(
+            BigInt::from(0x1234_5678),
+            4,
+            1_700_000_000,
+            vec!["key-compromise".to_string(), "".to_string()],
+        )
+        .unwrap();
+        let bytes = notice.canonical_bytes();
+        assert_eq!(
+            RevocationNotice::from_canonical_bytes(&bytes).unwrap(),
+            notice
+        );
+
+        let mut trailing = bytes.clone();
+        trailing.push(0);
+        assert!(RevocationNotice::from_canonical_bytes(&trailing).is_err());
+        assert!(RevocationNotice::from_canonical_bytes(&bytes[..bytes.len() - 1]).is_err());
+    }
+
+    #[test]
+    fn test_oversized_fields_rejected() {
+        let too_long = "x".repeat(u16::MAX as usize + 1);
+        assert!(matches!(
+            RevocationNotice::new(BigInt::from(1), 4, 0, vec![too_long]),
+            Err(FsDkrError::RevocationFieldTooLong {
+                field: "reason code",
+                ..
+            })
+        ));
+        assert!(matches!(
+            RevocationNotice::new(
+                BigInt::from(1),
+                4,
+                0,
+                vec![String::new(); u16::MAX as usize + 1]
+            ),
+            Err(FsDkrError::RevocationFieldTooLong {
+                field: "reason codes",
+                ..
+            })
+        ));
+        assert!(matches!(
+            RevocationNotice::new(
+                BigInt::from_bytes(&vec![0xff; u16::MAX as usize + 1]),
+                4,
+                0,
+                vec![]
+            ),
+            Err(FsDkrError::RevocationFieldTooLong {
+                field: "key fingerprint",
+                ..
+            })
+        ));
+
+        // deserialization goes through the same checks
+        let json = serde_json::json!({
+            "key_fingerprint": BigInt::from(1),
+            "superseded_epoch": 4,
+            "effective_at": 0,
+            "reason_codes": vec!["x".repeat(u16::MAX as usize + 1)],
+        });
+        assert!(serde_json::from_value::<RevocationNotice>(json).is_err());
+    }
+}
@@ -22,6 +22,7 @@ use paillier::{KeyGeneration, Paillier};
 use serde::{Deserialize, Serialize};
 use std::marker::PhantomData;
 
+use crate::compact::{Reader, Writer};
 use crate::error::FsDkrError;
 use crate::error::FsDkrResult;
 
@@ -70,6 +71,27 @@ impl<E: Curve, H: Digest + Clone> RingPedersenStatement<E, H> {
             },
         )
     }
+
+    /// `φ(N)` is not written, see [crate::compact].
+    pub(crate) fn write_compact(&self, writer: &mut Writer) {
+        writer.int(&self.S);
+        writer.int(&self.T);
+        writer.int(&self.N);
+    }
+
+    pub(crate) fn read_compact(reader: &mut Reader) -> FsDkrResult<Self> {
+        let S = reader.int()?;
+        let T = reader.int()?;
+        let N = reader.int()?;
+        Ok(RingPedersenStatement {
+            ek: EncryptionKey::from(&N),
+            S,
+            T,
+            N,
+            phi: BigInt::zero(),
+            phantom: PhantomData,
+        })
+    }
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -151,6 +173,22 @@ impl<E: Curve, H: Digest + Clone, const M: usize> RingPedersenProof<E, H, M> {
         }
         Ok(())
     }
+
+    pub(crate) fn write_compact(&self, writer: &mut Writer) {
+        for value in self.A.iter().chain(self.Z.iter()) {
+            writer.int(value);
+        }
+    }
+
+    pub(crate) fn read_compact(reader: &mut Reader) -> FsDkrResult<Self> {
+        let A = (0..M).map(|_| reader.int()).collect::<FsDkrResult<_>>()?;
+        let Z = (0..M).map(|_| reader.int()).collect::<FsDkrResult<_>>()?;
+        Ok(RingPedersenProof {
+            A,
+            Z,
+            phantom: PhantomData,
+        })
+    }
 }
 
 #[cfg(test)]
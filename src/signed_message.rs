@@ -0,0 +1,116 @@
+//! Refresh messages signed by their sender.
+//!
+//! Collect trusts the sender index a message claims, and relies on an authenticated broadcast
+//! channel for it to be true. Where the transport does not authenticate the senders, a
+//! [SignedRefreshMessage] carries a Schnorr signature over the
+//! [fingerprint](RefreshMessage::fingerprint) of the message under the current share of the
+//! sender. Every party checks it against the public share `X_i` of the sender in the key being
+//! refreshed, so only the holder of share `i` can send a message as party `i`.
+//! [RefreshMessage::collect_signed] checks all the signatures before anything else.
+
+use crate::add_party_message::JoinMessage;
+use crate::error::{FsDkrError, FsDkrResult};
+use crate::refresh_message::RefreshMessage;
+use curv::arithmetic::Converter;
+use curv::cryptographic_primitives::hashing::{Digest, DigestExt};
+use curv::elliptic::curves::{Curve, Point, Scalar};
+use curv::BigInt;
+use multi_party_ecdsa::protocols::multi_party_ecdsa::gg_2020::state_machine::keygen::LocalKey;
+use paillier::DecryptionKey;
+use serde::{Deserialize, Serialize};
+
+/// A refresh message with the signature of its sender, see the [module docs](self).
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SignedRefreshMessage<E: Curve, H: Digest + Clone, const M: usize> {
+    message: RefreshMessage<E, H, M>,
+    commitment: Point<E>,
+    response: Scalar<E>,
+}
+
+impl<E: Curve, H: Digest + Clone, const M: usize> SignedRefreshMessage<E, H, M> {
+    /// Signs `message` with the share of `local_key`, the key the message was distributed
+    /// from.
+    pub fn sign(message: RefreshMessage<E, H, M>, local_key: &LocalKey<E>) -> FsDkrResult<Self> {
+        if message.old_party_index != local_key.i {
+            return Err(FsDkrError::InvalidRefreshMessage {
+                party_index: local_key.i,
+                reason: "signed by another party than the sender".to_string(),
+            });
+        }
+        let secret = &local_key.keys_linear.x_i;
+        let public_share = Point::<E>::generator() * secret;
+        let nonce = Scalar::<E>::random();
+        let commitment = Point::<E>::generator() * &nonce;
+        let challenge = Self::challenge(&message, &public_share, &commitment);
+        Ok(SignedRefreshMessage {
+            response: nonce + challenge * secret,
+            message,
+            commitment,
+        })
+    }
+
+    fn challenge(
+        message: &RefreshMessage<E, H, M>,
+        public_share: &Point<E>,
+        commitment: &Point<E>,
+    ) -> Scalar<E> {
+        let e = H::new()
+            .chain_bigint(&BigInt::from(message.old_party_index as i32))
+            .chain_bigint(&BigInt::from_bytes(&public_share.to_bytes(true)))
+            .chain_bigint(&BigInt::from_bytes(&commitment.to_bytes(true)))
+            .chain(&message.fingerprint())
+            .result_bigint();
+        Scalar::<E>::from(&e)
+    }
+
+    pub fn message(&self) -> &RefreshMessage<E, H, M> {
+        &self.message
+    }
+
+    pub fn into_message(self) -> RefreshMessage<E, H, M> {
+        self.message
+    }
+
+    /// Checks the signature against `pk_vec`, the public shares of the key being refreshed, and
+    /// returns the message.
+    pub fn verify(&self, pk_vec: &[Point<E>]) -> FsDkrResult<&RefreshMessage<E, H, M>> {
+        let party_index = self.message.old_party_index;
+        let public_share = (party_index as usize)
+            .checked_sub(1)
+            .and_then(|k| pk_vec.get(k))
+            .ok_or(FsDkrError::SenderSignatureInvalid { party_index })?;
+        let challenge = Self::challenge(&self.message, public_share, &self.commitment);
+        if Point::<E>::generator() * &self.response != &self.commitment + public_share * &challenge
+        {
+            return Err(FsDkrError::SenderSignatureInvalid { party_index });
+        }
+        Ok(&self.message)
+    }
+}
+
+impl<E: Curve, H: Digest + Clone, const M: usize> RefreshMessage<E, H, M> {
+    /// [RefreshMessage::distribute], with the message signed by `local_key`.
+    pub fn distribute_signed(
+        local_key: &LocalKey<E>,
+        new_n: u16,
+    ) -> FsDkrResult<(SignedRefreshMessage<E, H, M>, DecryptionKey)> {
+        let (message, new_dk) = Self::distribute(local_key.i, local_key, new_n)?;
+        Ok((SignedRefreshMessage::sign(message, local_key)?, new_dk))
+    }
+
+    /// [RefreshMessage::collect] of signed messages. Every signature is checked against the
+    /// public shares of `old_key` first, and a message that fails is rejected with
+    /// [FsDkrError::SenderSignatureInvalid].
+    pub fn collect_signed(
+        signed_messages: &[SignedRefreshMessage<E, H, M>],
+        old_key: &LocalKey<E>,
+        new_dk: DecryptionKey,
+        join_messages: &[JoinMessage<E, H, M>],
+    ) -> FsDkrResult<LocalKey<E>> {
+        let messages = signed_messages
+            .iter()
+            .map(|signed| signed.verify(&old_key.pk_vec).map(Clone::clone))
+            .collect::<FsDkrResult<Vec<_>>>()?;
+        Self::collect(&messages, old_key, new_dk, join_messages)
+    }
+}
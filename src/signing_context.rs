@@ -0,0 +1,102 @@
+//! Per-quorum constants of GG20 signing, for inspection.
+//!
+//! Every signing session converts the shares of its quorum to additive shares with the Lagrange
+//! coefficients of the quorum, and the public data it checks against follows from the public
+//! shares the same way. [RefreshableLocalKey::signing_context] computes these constants for a
+//! quorum of the current epoch, e.g. to check which public share each party contributes or that
+//! the quorum reconstructs the joint public key after a refresh.
+//!
+//! The context is informational only: the GG20 offline stage computes its own coefficients and
+//! does not take a [SigningContext], and nothing is cached, so a context never outlives the
+//! epoch it reports in [SigningContext::epoch] unless the caller keeps it.
+//!
+//! [RefreshableLocalKey::signing_context]: crate::refreshable_key::RefreshableLocalKey::signing_context
+
+use crate::error::{FsDkrError, FsDkrResult};
+use crate::local_key::lagrange_coefficient_at;
+use curv::elliptic::curves::{Curve, Point, Scalar};
+use multi_party_ecdsa::protocols::multi_party_ecdsa::gg_2020::state_machine::keygen::LocalKey;
+
+/// The constants of signing with one quorum of one epoch, see the [module docs](self).
+#[derive(Clone, Debug)]
+pub struct SigningContext<E: Curve> {
+    epoch: u64,
+    quorum: Vec<u16>,
+    lagrange_coefficients: Vec<Scalar<E>>,
+    weighted_public_shares: Vec<Point<E>>,
+    aggregate_public_key: Point<E>,
+}
+
+impl<E: Curve> SigningContext<E> {
+    /// The context of `local_key` at `epoch` for `quorum`, which has to hold more than `t`
+    /// distinct indices of the committee, among which the party's own.
+    pub(crate) fn new(local_key: &LocalKey<E>, epoch: u64, quorum: &[u16]) -> FsDkrResult<Self> {
+        let mut sorted = quorum.to_vec();
+        sorted.sort_unstable();
+        sorted.dedup();
+        if sorted.len() != quorum.len()
+            || sorted.len() <= local_key.t as usize
+            || sorted[0] == 0
+            || sorted[sorted.len() - 1] > local_key.n
+            || !sorted.contains(&local_key.i)
+        {
+            return Err(FsDkrError::InvalidQuorum {
+                quorum: quorum.to_vec(),
+            });
+        }
+
+        let zero = Scalar::<E>::zero();
+        let lagrange_coefficients: Vec<Scalar<E>> = sorted
+            .iter()
+            .map(|j| lagrange_coefficient_at(&zero, *j, &sorted))
+            .collect();
+        let weighted_public_shares: Vec<Point<E>> = sorted
+            .iter()
+            .zip(lagrange_coefficients.iter())
+            .map(|(j, lambda)| &local_key.pk_vec[(*j - 1) as usize] * lambda)
+            .collect();
+        let aggregate_public_key = weighted_public_shares
+            .iter()
+            .fold(Point::<E>::zero(), |acc, share| acc + share);
+
+        Ok(SigningContext {
+            epoch,
+            quorum: sorted,
+            lagrange_coefficients,
+            weighted_public_shares,
+            aggregate_public_key,
+        })
+    }
+
+    /// The epoch of the key the context was computed from.
+    pub fn epoch(&self) -> u64 {
+        self.epoch
+    }
+
+    /// The party indices of the quorum, in increasing order.
+    pub fn quorum(&self) -> &[u16] {
+        &self.quorum
+    }
+
+    fn position(&self, party_index: u16) -> Option<usize> {
+        self.quorum.binary_search(&party_index).ok()
+    }
+
+    /// Lagrange coefficient at zero of `party_index` over the quorum.
+    pub fn lagrange_coefficient(&self, party_index: u16) -> Option<&Scalar<E>> {
+        self.position(party_index)
+            .map(|k| &self.lagrange_coefficients[k])
+    }
+
+    /// Public share of `party_index` weighted by its Lagrange coefficient, i.e. the public
+    /// counterpart of its additive share.
+    pub fn weighted_public_share(&self, party_index: u16) -> Option<&Point<E>> {
+        self.position(party_index)
+            .map(|k| &self.weighted_public_shares[k])
+    }
+
+    /// Sum of the weighted public shares, the joint public key for a consistent key.
+    pub fn aggregate_public_key(&self) -> &Point<E> {
+        &self.aggregate_public_key
+    }
+}
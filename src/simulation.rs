@@ -0,0 +1,124 @@
+//! Whole-committee refreshes in a single process, for tests of downstream crates.
+//!
+//! [run_refresh] plays every party of a committee: each distributes, then each collects the
+//! messages of all the others. [keygen_then_refresh] runs the key generation of [crate::dkg] and
+//! a number of successive refreshes on top of it. Both work on any curve, and check after every
+//! refresh that the shares still reconstruct the same secret.
+//!
+//! This module is only available with the `simulation` feature.
+
+use crate::add_party_message::JoinMessage;
+use crate::dkg::{collect_dkg, dkg_confirmation, dkg_distribute, verify_dkg_confirmations};
+use crate::error::{FsDkrError, FsDkrResult};
+use crate::refresh_message::RefreshMessage;
+use curv::cryptographic_primitives::hashing::Digest;
+use curv::cryptographic_primitives::secret_sharing::feldman_vss::{
+    ShamirSecretSharing, VerifiableSS,
+};
+use curv::elliptic::curves::{Curve, Scalar};
+use multi_party_ecdsa::protocols::multi_party_ecdsa::gg_2020::state_machine::keygen::LocalKey;
+
+/// Refreshes the keys of every party of a committee, given in party index order. A failed
+/// collect is reported as [FsDkrError::CollectFailed] with the index of the party.
+pub fn run_refresh<E: Curve, H: Digest + Clone, const M: usize>(
+    keys: &[LocalKey<E>],
+) -> FsDkrResult<Vec<LocalKey<E>>> {
+    let mut broadcast_vec = Vec::with_capacity(keys.len());
+    let mut new_dks = Vec::with_capacity(keys.len());
+    for key in keys {
+        let (message, new_dk) =
+            RefreshMessage::<E, H, M>::distribute(key.i, key, keys.len() as u16)?;
+        broadcast_vec.push(message);
+        new_dks.push(new_dk);
+    }
+
+    let new_keys = keys
+        .iter()
+        .zip(new_dks)
+        .map(|(key, new_dk)| {
+            RefreshMessage::collect(&broadcast_vec, key, new_dk, &[]).map_err(|error| {
+                FsDkrError::CollectFailed {
+                    party_index: key.i,
+                    source: Box::new(error),
+                }
+            })
+        })
+        .collect::<FsDkrResult<Vec<_>>>()?;
+    if reconstruct(keys)? != reconstruct(&new_keys)? {
+        return Err(FsDkrError::PublicKeyChanged);
+    }
+    Ok(new_keys)
+}
+
+/// Generates the keys of a `t`-out-of-`n` committee with [crate::dkg] and refreshes them
+/// `rounds` times, see [run_refresh]. Returns the keys of the last refresh.
+pub fn keygen_then_refresh<E: Curve, H: Digest + Clone, const M: usize>(
+    t: u16,
+    n: u16,
+    rounds: usize,
+) -> FsDkrResult<Vec<LocalKey<E>>> {
+    if t == 0 || t >= n {
+        return Err(FsDkrError::KeyThresholdOutOfRange { t, n });
+    }
+    let (mut registrations, paillier_keys): (Vec<JoinMessage<E, H, M>>, Vec<_>) =
+        (0..n).map(|_| JoinMessage::distribute()).unzip();
+    for (party_index, registration) in (1..=n).zip(registrations.iter_mut()) {
+        registration.set_party_index(party_index);
+    }
+    let (dkg_messages, new_dks): (Vec<_>, Vec<_>) = (1..=n)
+        .map(|party_index| dkg_distribute(party_index, t, &registrations))
+        .collect::<FsDkrResult<Vec<_>>>()?
+        .into_iter()
+        .unzip();
+    let mut keys = (1..=n)
+        .zip(paillier_keys.iter().zip(new_dks))
+        .map(|(party_index, (paillier_keys, new_dk))| {
+            collect_dkg(
+                party_index,
+                t,
+                &registrations,
+                &dkg_messages,
+                paillier_keys,
+                new_dk,
+            )
+        })
+        .collect::<FsDkrResult<Vec<_>>>()?;
+    let confirmations: Vec<_> = keys
+        .iter()
+        .map(|key| (key.i, dkg_confirmation::<E, H>(key)))
+        .collect();
+    for key in keys.iter() {
+        verify_dkg_confirmations::<E, H>(key, &confirmations)?;
+    }
+
+    for _ in 0..rounds {
+        keys = run_refresh::<E, H, M>(&keys)?;
+    }
+    Ok(keys)
+}
+
+/// The secret the first `t + 1` keys reconstruct. Fails with
+/// [FsDkrError::PartiesThresholdViolation] when there are not that many keys.
+fn reconstruct<E: Curve>(keys: &[LocalKey<E>]) -> FsDkrResult<Scalar<E>> {
+    let t = keys.first().map_or(0, |key| key.t);
+    if keys.len() <= t as usize {
+        return Err(FsDkrError::PartiesThresholdViolation {
+            threshold: t,
+            refreshed_keys: keys.len(),
+        });
+    }
+    let quorum = &keys[..=t as usize];
+    let vss = VerifiableSS::<E> {
+        parameters: ShamirSecretSharing {
+            threshold: t,
+            share_count: keys[0].n,
+        },
+        commitments: Vec::new(),
+    };
+    let indices: Vec<u16> = quorum.iter().map(|key| key.i - 1).collect();
+    let shares: Vec<Scalar<E>> = quorum
+        .iter()
+        .map(|key| key.keys_linear.x_i.clone())
+        .collect();
+    Ok(vss.reconstruct(&indices, &shares))
+}
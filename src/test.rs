@@ -1,15 +1,20 @@
 #[cfg(test)]
 mod tests {
-    use crate::refresh_message::RefreshMessage;
-    use curv::arithmetic::Converter;
+    use crate::refresh_message::{
+        validate_encryption_keys, CollectOptions, RefreshContext, RefreshMessage,
+        RefreshMessageParts, RefreshParams, SharingBasis,
+    };
+    use curv::arithmetic::{BasicOps, Converter};
     use curv::cryptographic_primitives::secret_sharing::feldman_vss::{
         ShamirSecretSharing, VerifiableSS,
     };
     use curv::elliptic::curves::secp256_k1::Secp256k1Point;
-    use curv::elliptic::curves::Secp256k1;
+    use curv::elliptic::curves::{Point, Scalar, Secp256k1, Secp256r1};
     use curv::BigInt;
-    use multi_party_ecdsa::protocols::multi_party_ecdsa::gg_2020::party_i::verify;
     use multi_party_ecdsa::protocols::multi_party_ecdsa::gg_2020::party_i::Keys;
+    use multi_party_ecdsa::protocols::multi_party_ecdsa::gg_2020::party_i::{
+        verify, SignatureRecid,
+    };
     use multi_party_ecdsa::protocols::multi_party_ecdsa::gg_2020::state_machine::keygen::{
         Keygen, LocalKey,
     };
@@ -19,60 +24,3580 @@ mod tests {
     use sha2::Sha256;
 
     use crate::add_party_message::JoinMessage;
-    use crate::error::FsDkrResult;
+    use crate::air_gapped::{finish_offline, OfflineBundle};
+    use crate::anchor::{Anchor, AnchorPolicy, AnchorReceipt, InMemoryAnchor};
+    use crate::batch_refresh::BatchRefreshMessage;
+    use crate::bip340::{bip340_challenge, negate_local_key, verify_normalizations, KeyParity};
+    use crate::complaint::Complaint;
+    use crate::dkg::{collect_dkg, dkg_confirmation, dkg_distribute, verify_dkg_confirmations};
+    use crate::epoch::key_fingerprint;
+    use crate::epoch_secrets::{derive_epoch_secrets, EpochSecrets};
+    use crate::error::{FsDkrError, FsDkrResult, Warning};
+    use crate::index_remap::IndexRemap;
+    use crate::key_lock::{CommitPolicy, KeyLock};
+    use crate::key_refresh::KeyRefresh;
+    use crate::local_key::{
+        erase_secrets, lagrange_coefficient_at, secrets_erased, validate_local_key,
+        verify_local_key,
+    };
+    use crate::observer::CommitteePublicData;
+    use crate::paillier_rotation::{apply_rotation, PaillierRotationMessage};
+    use crate::refresh_ack::{verify_acks, RefreshAck};
+    use crate::refresh_session::{RefreshSession, SessionStatus};
+    use crate::refresh_summary::RefreshSummary;
+    use crate::refreshable_key::RefreshableLocalKey;
+    use crate::revocation::RevocationStatement;
+    use crate::signed_message::SignedRefreshMessage;
+    use crate::simulation::{keygen_then_refresh, run_refresh};
+    use crate::test_utils;
+    use crate::wire::{Codec, DecodeLimits};
     use curv::cryptographic_primitives::hashing::Digest;
-    use paillier::DecryptionKey;
+    use curv::cryptographic_primitives::proofs::sigma_dlog::DLogProof;
+    use paillier::{DecryptionKey, EncryptionKey};
     use round_based::dev::Simulation;
+    use serde::de::DeserializeOwned;
     use std::collections::HashMap;
 
     type GE = Secp256k1Point;
 
     #[test]
-    fn test1() {
-        //simulate keygen
-        let t = 3;
-        let n = 6;
-        let mut keys = simulate_keygen(t, n);
+    fn test1() {
+        //simulate keygen
+        let t = 3;
+        let n = 6;
+        let mut keys = simulate_keygen(t, n);
+
+        let old_keys = keys.clone();
+        simulate_dkr::<{ crate::M_SECURITY }>(&mut keys);
+
+        // check that sum of old keys is equal to sum of new keys
+        let old_linear_secret_key: Vec<_> = (0..old_keys.len())
+            .map(|i| old_keys[i].keys_linear.x_i.clone())
+            .collect();
+
+        let new_linear_secret_key: Vec<_> = (0..keys.len())
+            .map(|i| keys[i].keys_linear.x_i.clone())
+            .collect();
+        let indices: Vec<_> = (0..(t + 1) as u16).collect();
+        let vss = VerifiableSS::<Secp256k1> {
+            parameters: ShamirSecretSharing {
+                threshold: t,
+                share_count: n,
+            },
+            commitments: Vec::new(),
+        };
+        assert_eq!(
+            vss.reconstruct(&indices[..], &old_linear_secret_key[0..(t + 1) as usize]),
+            vss.reconstruct(&indices[..], &new_linear_secret_key[0..(t + 1) as usize])
+        );
+        assert_ne!(old_linear_secret_key, new_linear_secret_key);
+    }
+
+    #[test]
+    fn test_run_refresh() {
+        let old_keys = simulate_keygen(1, 3);
+        let new_keys = run_refresh::<_, Sha256, { crate::M_SECURITY }>(&old_keys).unwrap();
+        for (old_key, new_key) in old_keys.iter().zip(new_keys.iter()) {
+            verify_local_key(new_key).unwrap();
+            assert_eq!(new_key.y_sum_s, old_key.y_sum_s);
+            assert_ne!(new_key.keys_linear.x_i, old_key.keys_linear.x_i);
+        }
+        let offline_sign = simulate_offline_stage(new_keys, &[1, 3]);
+        simulate_signing(offline_sign, b"ZenGo");
+
+        // without keys there is no secret to preserve
+        assert!(matches!(
+            run_refresh::<Secp256k1, Sha256, { crate::M_SECURITY }>(&[]),
+            Err(FsDkrError::PartiesThresholdViolation {
+                refreshed_keys: 0,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn test_old_key_secrets_erased() {
+        let old_keys = simulate_keygen(1, 3);
+        let new_keys = run_refresh::<_, Sha256, { crate::M_SECURITY }>(&old_keys).unwrap();
+        for (mut old_key, new_key) in old_keys.into_iter().zip(new_keys.iter()) {
+            assert!(!secrets_erased(&old_key));
+            erase_secrets(&mut old_key);
+            assert!(secrets_erased(&old_key));
+            assert!(validate_local_key(&old_key).is_err());
+            assert!(
+                RefreshMessage::<Secp256k1, Sha256, { crate::M_SECURITY }>::distribute(
+                    old_key.i, &old_key, 3
+                )
+                .is_err()
+            );
+            assert!(!secrets_erased(new_key));
+        }
+        let offline_sign = simulate_offline_stage(new_keys, &[1, 3]);
+        simulate_signing(offline_sign, b"ZenGo");
+    }
+
+    #[test]
+    fn test_repeated_refreshes_do_not_drift() {
+        let keys =
+            keygen_then_refresh::<Secp256k1, Sha256, { crate::M_SECURITY }>(1, 3, 5).unwrap();
+        for key in keys.iter() {
+            verify_local_key(key).unwrap();
+        }
+        let offline_sign = simulate_offline_stage(keys, &[2, 3]);
+        simulate_signing(offline_sign, b"ZenGo");
+
+        // on another curve too
+        let keys =
+            keygen_then_refresh::<Secp256r1, Sha256, { crate::M_SECURITY }>(1, 3, 1).unwrap();
+        for key in keys.iter() {
+            verify_local_key(key).unwrap();
+        }
+
+        assert!(matches!(
+            keygen_then_refresh::<Secp256k1, Sha256, { crate::M_SECURITY }>(3, 3, 1),
+            Err(FsDkrError::KeyThresholdOutOfRange { t: 3, n: 3 })
+        ));
+    }
+
+    #[test]
+    fn test_run_refresh_names_the_failing_party() {
+        let mut keys = simulate_keygen(1, 3);
+        // party 2 holds a stale public share of party 3
+        keys[1].pk_vec[2] = Point::generator().to_point();
+        match run_refresh::<_, Sha256, { crate::M_SECURITY }>(&keys) {
+            Err(FsDkrError::CollectFailed { party_index, .. }) => assert_eq!(party_index, 2),
+            other => panic!("expected a failed collect, got {:?}", other.map(|_| ())),
+        }
+    }
+
+    #[test]
+    fn test_refresh_by_online_quorum() {
+        let (t, n) = (2, 5);
+        let old_keys = simulate_keygen(t, n);
+        let online = [1u16, 3, 4];
+
+        let (broadcast_vec, new_dks): (
+            Vec<RefreshMessage<Secp256k1, Sha256, { crate::M_SECURITY }>>,
+            Vec<DecryptionKey>,
+        ) = online
+            .iter()
+            .map(|i| {
+                let key = &old_keys[(*i - 1) as usize];
+                RefreshMessage::distribute(key.i, key, n).unwrap()
+            })
+            .unzip();
+
+        // the offline parties pick up the broadcast later and keep their Paillier key
+        let keys: Vec<LocalKey<Secp256k1>> = old_keys
+            .iter()
+            .map(|key| {
+                let new_dk = match online.iter().position(|i| *i == key.i) {
+                    Some(k) => new_dks[k].clone(),
+                    None => key.paillier_dk.clone(),
+                };
+                RefreshMessage::collect(&broadcast_vec, key, new_dk, &[]).unwrap()
+            })
+            .collect();
+        for key in keys.iter() {
+            verify_local_key(key).unwrap();
+            assert_eq!(key.pk_vec, keys[0].pk_vec);
+            assert_eq!(key.paillier_key_vec, keys[0].paillier_key_vec);
+        }
+
+        let vss = VerifiableSS::<Secp256k1> {
+            parameters: ShamirSecretSharing {
+                threshold: t,
+                share_count: n,
+            },
+            commitments: Vec::new(),
+        };
+        let share = |keys: &[LocalKey<Secp256k1>], i: usize| keys[i].keys_linear.x_i.clone();
+        // two offline parties and an online one
+        let indices = [1u16, 4, 0];
+        let old_shares: Vec<_> = indices
+            .iter()
+            .map(|i| share(&old_keys, *i as usize))
+            .collect();
+        let new_shares: Vec<_> = indices.iter().map(|i| share(&keys, *i as usize)).collect();
+        assert_eq!(
+            vss.reconstruct(&indices, &old_shares),
+            vss.reconstruct(&indices, &new_shares)
+        );
+        assert_ne!(old_shares, new_shares);
+
+        let offline_sign = simulate_offline_stage(keys, &[2, 4, 5]);
+        simulate_signing(offline_sign, b"ZenGo");
+    }
+
+    #[test]
+    fn test_sign_rotate_sign() {
+        let mut keys = simulate_keygen(2, 5);
+        let offline_sign = simulate_offline_stage(keys.clone(), &[1, 2, 3]);
+        simulate_signing(offline_sign, b"ZenGo");
+        simulate_dkr::<{ crate::M_SECURITY }>(&mut keys);
+        let offline_sign = simulate_offline_stage(keys.clone(), &[2, 3, 4]);
+        simulate_signing(offline_sign, b"ZenGo");
+        simulate_dkr::<{ crate::M_SECURITY }>(&mut keys);
+        let offline_sign = simulate_offline_stage(keys, &[1, 3, 5]);
+        simulate_signing(offline_sign, b"ZenGo");
+    }
+
+    #[test]
+    fn test_keygen_then_refresh_simulations() {
+        let keys = simulate_keygen(2, 5);
+        let public_key = keys[0].y_sum_s.clone();
+        let keys = simulate_refresh::<{ crate::M_SECURITY }>(keys);
+        for (k, key) in keys.iter().enumerate() {
+            verify_local_key(key).unwrap();
+            assert_eq!(key.i as usize, k + 1);
+            assert_eq!(key.y_sum_s, public_key);
+            assert_eq!(key.pk_vec, keys[0].pk_vec);
+        }
+        let offline_sign = simulate_offline_stage(keys, &[2, 3, 4]);
+        assert_eq!(offline_sign[0].public_key(), &public_key);
+        simulate_signing(offline_sign, b"ZenGo");
+    }
+
+    #[test]
+    fn test_refresh_instantiates_with_another_curve() {
+        // nothing of the refresh is specific to secp256k1: instantiating it with P-256 checks
+        // that the bounds hold
+        type Message = RefreshMessage<Secp256r1, Sha256, { crate::M_SECURITY }>;
+        type Join = JoinMessage<Secp256r1, Sha256, { crate::M_SECURITY }>;
+        let distribute: fn(
+            u16,
+            &LocalKey<Secp256r1>,
+            u16,
+        ) -> FsDkrResult<(Message, DecryptionKey)> = Message::distribute;
+        let collect: fn(
+            &[Message],
+            &LocalKey<Secp256r1>,
+            DecryptionKey,
+            &[Join],
+        ) -> FsDkrResult<LocalKey<Secp256r1>> = Message::collect;
+        let state_machine: fn(
+            LocalKey<Secp256r1>,
+        )
+            -> FsDkrResult<KeyRefresh<Secp256r1, Sha256, { crate::M_SECURITY }>> = KeyRefresh::new;
+        let _ = (distribute, collect, state_machine);
+    }
+
+    #[test]
+    fn test_refresh_large_committee_reconstructs() {
+        // every decrypted sum is a sum of t + 1 products of scalars, far above the curve order
+        let (t, n) = (4, 9);
+        let old_keys = simulate_keygen(t, n);
+        let mut keys = old_keys.clone();
+        simulate_dkr::<{ crate::M_SECURITY }>(&mut keys);
+
+        let indices: Vec<u16> = (0..=t).collect();
+        let shares = |keys: &[LocalKey<Secp256k1>]| -> Vec<Scalar<Secp256k1>> {
+            keys.iter()
+                .take(t as usize + 1)
+                .map(|key| key.keys_linear.x_i.clone())
+                .collect()
+        };
+        assert_eq!(
+            keys[0].vss_scheme.reconstruct(&indices, &shares(&keys)),
+            old_keys[0]
+                .vss_scheme
+                .reconstruct(&indices, &shares(&old_keys))
+        );
+        for key in keys.iter() {
+            verify_local_key(key).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_batch_refresh_reconstructs_every_key() {
+        let (t, n) = (1, 3);
+        let session_id = BigInt::from(7);
+        let old_key_sets: Vec<_> = (0..3).map(|_| simulate_keygen(t, n)).collect();
+        // the keys held by every party, one per key set
+        let held: Vec<Vec<_>> = (0..n as usize)
+            .map(|p| old_key_sets.iter().map(|keys| keys[p].clone()).collect())
+            .collect();
+        let (batch_messages, new_dks): (
+            Vec<BatchRefreshMessage<Secp256k1, Sha256, { crate::M_SECURITY }>>,
+            Vec<Vec<DecryptionKey>>,
+        ) = held
+            .iter()
+            .map(|keys| BatchRefreshMessage::distribute(keys, &session_id).unwrap())
+            .unzip();
+
+        // a batch of another session is rejected as a whole
+        assert!(matches!(
+            BatchRefreshMessage::collect(
+                &batch_messages,
+                &held[0],
+                &BigInt::from(8),
+                new_dks[0].clone()
+            ),
+            Err(FsDkrError::InvalidRefreshMessage { .. })
+        ));
+        // messages moved to another key of the batch only sink the keys they were moved to
+        let mut swapped = batch_messages.clone();
+        swapped[1].messages.swap(0, 1);
+        let results =
+            BatchRefreshMessage::collect(&swapped, &held[0], &session_id, new_dks[0].clone())
+                .unwrap();
+        assert!(results[0].is_err());
+        assert!(results[1].is_err());
+        assert!(results[2].is_ok());
+
+        let refreshed: Vec<Vec<_>> = held
+            .iter()
+            .zip(new_dks)
+            .map(|(keys, dks)| {
+                BatchRefreshMessage::collect(&batch_messages, keys, &session_id, dks)
+                    .unwrap()
+                    .into_iter()
+                    .map(|result| result.unwrap())
+                    .collect()
+            })
+            .collect();
+        let indices: Vec<u16> = (0..=t).collect();
+        for (k, old_keys) in old_key_sets.iter().enumerate() {
+            let new_keys: Vec<_> = refreshed.iter().map(|keys| keys[k].clone()).collect();
+            let shares = |keys: &[LocalKey<Secp256k1>]| -> Vec<Scalar<Secp256k1>> {
+                keys.iter()
+                    .take(t as usize + 1)
+                    .map(|key| key.keys_linear.x_i.clone())
+                    .collect()
+            };
+            assert_eq!(
+                new_keys[0]
+                    .vss_scheme
+                    .reconstruct(&indices, &shares(&new_keys)),
+                old_keys[0]
+                    .vss_scheme
+                    .reconstruct(&indices, &shares(old_keys))
+            );
+            for key in new_keys.iter() {
+                verify_local_key(key).unwrap();
+                assert_eq!(key.y_sum_s, old_keys[0].y_sum_s);
+            }
+        }
+    }
+
+    #[test]
+    fn test_refresh_without_additive_shares() {
+        let (t, n) = (2, 5);
+        let old_keys = simulate_keygen(t, n);
+        let mut keys = old_keys.clone();
+        // keys imported from elsewhere may only carry their linear share
+        for key in keys.iter_mut() {
+            key.keys_additive.u_i = Scalar::zero();
+        }
+        simulate_dkr::<{ crate::M_SECURITY }>(&mut keys);
+
+        let indices: Vec<u16> = (0..=t).collect();
+        let shares = |keys: &[LocalKey<Secp256k1>]| -> Vec<Scalar<Secp256k1>> {
+            keys.iter()
+                .take(t as usize + 1)
+                .map(|key| key.keys_linear.x_i.clone())
+                .collect()
+        };
+        assert_eq!(
+            keys[0].vss_scheme.reconstruct(&indices, &shares(&keys)),
+            old_keys[0]
+                .vss_scheme
+                .reconstruct(&indices, &shares(&old_keys))
+        );
+        for key in keys.iter() {
+            verify_local_key(key).unwrap();
+            assert!(key.keys_additive.u_i.is_zero());
+        }
+    }
+
+    #[test]
+    fn test_chained_epochs_sign() {
+        let mut keys = simulate_keygen(2, 5);
+        let public_key = keys[0].y_sum_s.clone();
+        let quorums: [&[u16]; 3] = [&[1, 2, 3], &[2, 4, 5], &[1, 3, 5]];
+
+        for quorum in quorums.iter() {
+            simulate_dkr::<{ crate::M_SECURITY }>(&mut keys);
+            for key in keys.iter() {
+                verify_local_key(key).unwrap();
+                assert_eq!(key.y_sum_s, public_key);
+                assert_eq!(key.pk_vec, keys[0].pk_vec);
+                assert_eq!(key.vss_scheme, keys[0].vss_scheme);
+            }
+
+            let offline_sign = simulate_offline_stage(keys.clone(), quorum);
+            assert_eq!(offline_sign[0].public_key(), &public_key);
+            simulate_signing(offline_sign, b"ZenGo");
+        }
+    }
+
+    #[test]
+    fn test_bip340_normalization_after_refresh() {
+        let mut keys = simulate_keygen(1, 3);
+        // make sure the raw refresh output has an odd y coordinate
+        if KeyParity::of(&keys[0].y_sum_s) == KeyParity::Even {
+            keys.iter_mut().for_each(negate_local_key);
+        }
+        let mut keys: Vec<_> = keys.into_iter().map(RefreshableLocalKey::new).collect();
+        keys.iter_mut()
+            .for_each(|key| key.set_bip340_normalization(true));
+        assert!(keys.iter().all(|key| key.parity() == KeyParity::Odd));
+
+        let session_id = BigInt::from(1);
+        let n = keys.len() as u16;
+        let (broadcast_vec, new_dks): (
+            Vec<RefreshMessage<Secp256k1, Sha256, { crate::M_SECURITY }>>,
+            Vec<_>,
+        ) = keys
+            .iter()
+            .map(|key| key.distribute(&session_id, n).unwrap())
+            .unzip();
+        let normalizations: Vec<_> = keys
+            .iter_mut()
+            .zip(new_dks)
+            .map(|(key, new_dk)| {
+                key.collect(&session_id, &broadcast_vec, new_dk, &[])
+                    .unwrap()
+                    .bip340
+                    .unwrap()
+            })
+            .collect();
+
+        verify_normalizations(&normalizations).unwrap();
+        assert!(normalizations.iter().all(|n| n.negated));
+        for key in keys.iter() {
+            verify_local_key(key.inner()).unwrap();
+            assert_eq!(key.parity(), KeyParity::Even);
+        }
+
+        // a BIP340 signature by the reconstructed secret verifies against the x-only key
+        let points = [1u16, 2];
+        let x = points
+            .iter()
+            .map(|j| {
+                let x_j = &keys[*j as usize - 1].inner().keys_linear.x_i;
+                lagrange_coefficient_at(&Scalar::<Secp256k1>::zero(), *j, &points) * x_j
+            })
+            .fold(Scalar::<Secp256k1>::zero(), |acc, v| acc + v);
+        let public_key = keys[0].inner().y_sum_s.clone();
+        assert_eq!(Point::<Secp256k1>::generator() * &x, public_key);
+
+        let mut k = Scalar::<Secp256k1>::random();
+        let mut r = Point::<Secp256k1>::generator() * &k;
+        if KeyParity::of(&r) == KeyParity::Odd {
+            k = Scalar::<Secp256k1>::zero() - k;
+            r = Point::<Secp256k1>::generator() * &k;
+        }
+        let challenges: Vec<_> = keys
+            .iter()
+            .map(|key| bip340_challenge(&r, &key.inner().y_sum_s, b"ZenGo"))
+            .collect();
+        assert!(challenges.iter().all(|e| e == &challenges[0]));
+        let s = &k + &challenges[0] * &x;
+        assert_eq!(
+            Point::<Secp256k1>::generator() * &s,
+            &r + &public_key * &challenges[0]
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "export")]
+    fn test_export_unchanged_across_refresh() {
+        use crate::export::{BtcNetwork, RefreshPublicData};
+
+        // fixture key: a sharing of the secret key 1
+        let mut keys = simulate_keygen(1, 3);
+        shift_keys_to_secret(&mut keys, &Scalar::from(1u16));
+        keys.iter().for_each(|key| verify_local_key(key).unwrap());
+
+        let before = RefreshPublicData::from_local_key(&keys[0]);
+        simulate_dkr::<{ crate::M_SECURITY }>(&mut keys);
+
+        for key in keys.iter() {
+            let after = RefreshPublicData::from_local_key(key);
+            after.assert_unchanged(&before).unwrap();
+            assert_eq!(
+                after.eth_address(),
+                "0x7E5F4552091A69125d5DfCb7b8C2659029395Bdf"
+            );
+            assert_eq!(
+                after.btc_p2wpkh_address(BtcNetwork::Bitcoin),
+                "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4"
+            );
+        }
+
+        // a tweaked refresh is expected to move the key by exactly the tweak
+        let tweak = Scalar::<Secp256k1>::random();
+        let params = RefreshParams {
+            tweak: Some(tweak.clone()),
+            ..RefreshParams::default()
+        };
+        let (broadcast_vec, new_dks): (
+            Vec<RefreshMessage<Secp256k1, Sha256, { crate::M_SECURITY }>>,
+            Vec<DecryptionKey>,
+        ) = keys
+            .iter()
+            .map(|key| RefreshMessage::distribute_with_params(key.i, key, 3, &params).unwrap())
+            .unzip();
+        for (key, new_dk) in keys.iter_mut().zip(new_dks) {
+            *key = RefreshMessage::collect(&broadcast_vec, key, new_dk, &[]).unwrap();
+        }
+        let tweaked = RefreshPublicData::from_local_key(&keys[0]);
+        assert!(tweaked.assert_unchanged(&before).is_err());
+        tweaked
+            .with_tweak_point(Point::<Secp256k1>::generator() * &tweak)
+            .assert_unchanged(&before)
+            .unwrap();
+
+        let other = RefreshPublicData::from_local_key(&simulate_keygen(1, 3)[0]);
+        assert!(other.assert_unchanged(&before).is_err());
+    }
+
+    #[test]
+    fn test_distribute_rejects_weak_receiver_keys() {
+        let keys = simulate_keygen(1, 4);
+        let mut key = keys[0].clone();
+        // an even modulus of the right size and a tiny modulus
+        key.paillier_key_vec[1] =
+            EncryptionKey::from(&(&key.paillier_key_vec[1].n - BigInt::from(1)));
+        key.paillier_key_vec[2] = EncryptionKey::from(&BigInt::from(15));
+
+        let result =
+            RefreshMessage::<Secp256k1, Sha256, { crate::M_SECURITY }>::distribute(key.i, &key, 4);
+        match result {
+            Err(FsDkrError::WeakReceiverKeys { failures }) => {
+                let parties: Vec<u16> = failures.iter().map(|f| f.party_index).collect();
+                assert_eq!(parties, vec![2, 3]);
+            }
+            _ => panic!("distribute encrypted to weak receiver keys"),
+        }
+
+        // a modulus shared by two receivers names both of them
+        let mut key = keys[0].clone();
+        key.paillier_key_vec[3] = key.paillier_key_vec[0].clone();
+        let result =
+            RefreshMessage::<Secp256k1, Sha256, { crate::M_SECURITY }>::validate_receiver_keys(
+                &key.paillier_key_vec,
+                4,
+                &[],
+                crate::PAILLIER_KEY_SIZE,
+            );
+        match result {
+            Err(FsDkrError::WeakReceiverKeys { failures }) => {
+                let parties: Vec<u16> = failures.iter().map(|f| f.party_index).collect();
+                assert_eq!(parties, vec![1, 4]);
+            }
+            _ => panic!("shared modulus was accepted"),
+        }
+
+        // burned keys are rejected
+        let burned = [keys[0].paillier_key_vec[2].clone()];
+        assert!(
+            RefreshMessage::<Secp256k1, Sha256, { crate::M_SECURITY }>::validate_receiver_keys(
+                &keys[0].paillier_key_vec,
+                4,
+                &burned,
+                crate::PAILLIER_KEY_SIZE,
+            )
+            .is_err()
+        );
+
+        // a key with truncated per-party vectors is an error, not a panic
+        let mut key = keys[0].clone();
+        key.paillier_key_vec.pop();
+        assert!(matches!(
+            RefreshMessage::<Secp256k1, Sha256, { crate::M_SECURITY }>::distribute(key.i, &key, 4),
+            Err(FsDkrError::KeyVectorLength {
+                field: "paillier_key_vec",
+                len: 3,
+                n: 4
+            })
+        ));
+        let mut key = keys[0].clone();
+        key.h1_h2_n_tilde_vec.pop();
+        assert!(matches!(
+            RefreshMessage::<Secp256k1, Sha256, { crate::M_SECURITY }>::distribute(key.i, &key, 4),
+            Err(FsDkrError::KeyVectorLength {
+                field: "h1_h2_n_tilde_vec",
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn test_distribute_refuses_malformed_moduli() {
+        let keys = simulate_keygen(1, 4);
+        let mut key = keys[0].clone();
+        // 2^2048 is even and three times a valid modulus has a small factor
+        key.paillier_key_vec[1] = EncryptionKey::from(&BigInt::from(2).pow(2048));
+        key.paillier_key_vec[3] =
+            EncryptionKey::from(&(&key.paillier_key_vec[3].n * BigInt::from(3)));
+
+        let result =
+            RefreshMessage::<Secp256k1, Sha256, { crate::M_SECURITY }>::distribute(key.i, &key, 4);
+        match result {
+            Err(FsDkrError::WeakReceiverKeys { failures }) => {
+                let failures: Vec<(u16, &str)> = failures
+                    .iter()
+                    .map(|f| (f.party_index, f.reason.as_str()))
+                    .collect();
+                assert_eq!(
+                    failures,
+                    vec![(2, "even modulus"), (4, "modulus with a small factor")]
+                );
+            }
+            _ => panic!("distribute encrypted to malformed moduli"),
+        }
+
+        // the square of a prime passes the other checks but is a perfect power
+        let p = &keys[2].paillier_dk.p;
+        let eks = [
+            keys[0].paillier_key_vec[0].clone(),
+            EncryptionKey::from(&(p * p)),
+        ];
+        match validate_encryption_keys(&eks, crate::PAILLIER_KEY_SIZE - 1) {
+            Err(FsDkrError::WeakReceiverKeys { failures }) => {
+                assert_eq!(failures.len(), 1);
+                assert_eq!(failures[0].party_index, 2);
+                assert_eq!(failures[0].reason, "modulus is a perfect power");
+            }
+            _ => panic!("perfect power modulus was accepted"),
+        }
+        assert!(
+            validate_encryption_keys(&keys[0].paillier_key_vec, crate::PAILLIER_KEY_SIZE - 1)
+                .is_ok()
+        );
+        assert!(validate_encryption_keys(&keys[0].paillier_key_vec, 4096).is_err());
+
+        // distribute refuses receivers below the size it is configured to require
+        let strict = RefreshParams {
+            min_receiver_modulus_bits: 3072,
+            ..RefreshParams::default()
+        };
+        match RefreshMessage::<Secp256k1, Sha256, { crate::M_SECURITY }>::distribute_with_params(
+            keys[0].i, &keys[0], 4, &strict,
+        ) {
+            Err(FsDkrError::WeakReceiverKeys { failures }) => assert_eq!(failures.len(), 4),
+            _ => panic!("distribute encrypted to moduli below the required size"),
+        }
+    }
+
+    #[test]
+    fn test_inconsistent_keys_rejected_before_use() {
+        let (t, n) = (1, 3);
+        let keys = simulate_keygen(t, n);
+        let (broadcast_vec, new_dks): (
+            Vec<RefreshMessage<Secp256k1, Sha256, { crate::M_SECURITY }>>,
+            Vec<DecryptionKey>,
+        ) = keys
+            .iter()
+            .map(|key| RefreshMessage::distribute(key.i, key, n).unwrap())
+            .unzip();
+        let distribute = |key: &LocalKey<Secp256k1>| {
+            RefreshMessage::<Secp256k1, Sha256, { crate::M_SECURITY }>::distribute(key.i, key, n)
+                .map(|_| ())
+        };
+        let collect = |key: &LocalKey<Secp256k1>| {
+            RefreshMessage::collect(&broadcast_vec, key, new_dks[0].clone(), &[]).map(|_| ())
+        };
+
+        let mut key = keys[0].clone();
+        key.i = 0;
+        for result in [distribute(&key), collect(&key)].iter() {
+            assert!(matches!(
+                result,
+                Err(FsDkrError::KeyIndexOutOfRange {
+                    party_index: 0,
+                    n: 3
+                })
+            ));
+        }
+        let mut key = keys[0].clone();
+        key.i = 4;
+        assert!(matches!(
+            collect(&key),
+            Err(FsDkrError::KeyIndexOutOfRange { party_index: 4, .. })
+        ));
+        let mut key = keys[0].clone();
+        key.t = 3;
+        assert!(matches!(
+            distribute(&key),
+            Err(FsDkrError::KeyThresholdOutOfRange { t: 3, n: 3 })
+        ));
+        let mut key = keys[0].clone();
+        key.paillier_key_vec.truncate(2);
+        for result in [distribute(&key), collect(&key)].iter() {
+            assert!(matches!(
+                result,
+                Err(FsDkrError::KeyVectorLength {
+                    field: "paillier_key_vec",
+                    len: 2,
+                    n: 3
+                })
+            ));
+        }
+        let mut key = keys[0].clone();
+        key.paillier_dk = keys[1].paillier_dk.clone();
+        assert!(matches!(
+            collect(&key),
+            Err(FsDkrError::KeyPaillierMismatch)
+        ));
+        let mut key = keys[0].clone();
+        key.keys_linear.x_i = Scalar::zero();
+        assert!(matches!(distribute(&key), Err(FsDkrError::KeyZeroShare)));
+        assert!(matches!(
+            verify_local_key(&key),
+            Err(FsDkrError::KeyZeroShare)
+        ));
+
+        collect(&keys[0]).unwrap();
+    }
+
+    #[test]
+    fn test_lagrange_weighted_quorum_refresh() {
+        let (t, n) = (2, 5);
+        let mut keys = simulate_keygen(t, n);
+        let public_key = keys[0].y_sum_s.clone();
+        let quorum = vec![2, 4, 5];
+
+        // only the quorum distributes
+        let (broadcast_vec, new_dks): (
+            Vec<RefreshMessage<Secp256k1, Sha256, { crate::M_SECURITY }>>,
+            Vec<DecryptionKey>,
+        ) = quorum
+            .iter()
+            .map(|i| {
+                let key = &keys[*i as usize - 1];
+                RefreshMessage::distribute_with_basis(
+                    key.i,
+                    key,
+                    n,
+                    SharingBasis::LagrangeWeighted {
+                        quorum: quorum.clone(),
+                    },
+                )
+                .unwrap()
+            })
+            .unzip();
+
+        // a subset of the quorum, or a message from a party outside of it, is rejected
+        let key = &keys[0];
+        let new_dk = key.paillier_dk.clone();
+        assert!(RefreshMessage::collect(&broadcast_vec[..2], key, new_dk.clone(), &[]).is_err());
+        assert!(
+            RefreshMessage::<Secp256k1, Sha256, { crate::M_SECURITY }>::distribute_with_basis(
+                key.i,
+                key,
+                n,
+                SharingBasis::LagrangeWeighted {
+                    quorum: quorum.clone()
+                },
+            )
+            .is_err()
+        );
+
+        for key in keys.iter_mut() {
+            // parties outside of the quorum keep their Paillier key
+            let new_dk = match quorum.iter().position(|i| *i == key.i) {
+                Some(k) => new_dks[k].clone(),
+                None => key.paillier_dk.clone(),
+            };
+            *key = RefreshMessage::collect(&broadcast_vec, key, new_dk, &[]).unwrap();
+        }
+        for key in keys.iter() {
+            verify_local_key(key).unwrap();
+            assert_eq!(key.y_sum_s, public_key);
+        }
+
+        let offline_sign = simulate_offline_stage(keys, &[1, 2, 3]);
+        simulate_signing(offline_sign, b"ZenGo");
+    }
+
+    #[test]
+    fn test_deserialize_rejects_inconsistent_message() {
+        let (t, n) = (1, 3);
+        let keys = simulate_keygen(t, n);
+        let (message, _) = RefreshMessage::<Secp256k1, Sha256, { crate::M_SECURITY }>::distribute(
+            keys[0].i, &keys[0], n,
+        )
+        .unwrap();
+        let json = serde_json::to_value(&message).unwrap();
+        let parse = |json: serde_json::Value| {
+            serde_json::from_value::<RefreshMessage<Secp256k1, Sha256, { crate::M_SECURITY }>>(json)
+        };
+
+        // the committed sub-shares are not sent, but derived again on the receiving side
+        assert!(json.get("points_committed_vec").is_none());
+        let round_trip = parse(json.clone()).unwrap();
+        assert_eq!(
+            round_trip.points_committed_vec,
+            message.points_committed_vec
+        );
+
+        let mut short = json.clone();
+        short["points_encrypted_vec"].as_array_mut().unwrap().pop();
+        let error = parse(short).unwrap_err().to_string();
+        assert!(error.contains("per-receiver vectors of different lengths"));
+
+        let mut no_receivers = json.clone();
+        for field in ["pdl_proof_vec", "range_proofs", "points_encrypted_vec"].iter() {
+            no_receivers[*field] = serde_json::json!([]);
+        }
+        assert!(parse(no_receivers).is_err());
+
+        let mut no_commitments = json.clone();
+        no_commitments["coefficients_committed_vec"]["commitments"] = serde_json::json!([]);
+        let error = parse(no_commitments).unwrap_err().to_string();
+        assert!(error.contains("commitments do not match"));
+
+        // a sender index past the receivers would be out of bounds of every collector's key
+        let mut out_of_range = json.clone();
+        out_of_range["party_index"] = serde_json::json!(n + 1);
+        let error = parse(out_of_range).unwrap_err().to_string();
+        assert!(error.contains("party index out of range"));
+        let mut parts = message.clone().into_parts();
+        parts.party_index = n + 1;
+        assert!(matches!(
+            RefreshMessage::new(parts),
+            Err(FsDkrError::InvalidRefreshMessage { party_index, .. }) if party_index == n + 1
+        ));
+        let mut bytes = message.to_bytes();
+        bytes[5..7].copy_from_slice(&(n + 1).to_be_bytes());
+        assert!(
+            RefreshMessage::<Secp256k1, Sha256, { crate::M_SECURITY }>::from_bytes(&bytes, n)
+                .is_err()
+        );
+
+        let mut old_version = json;
+        old_version.as_object_mut().unwrap().remove("version");
+        let error = parse(old_version).unwrap_err().to_string();
+        assert!(error.contains("unsupported message version"));
+    }
+
+    #[test]
+    fn test_messages_of_other_versions_rejected() {
+        let (t, n) = (1, 3);
+        let keys = simulate_keygen(t, n);
+        let (message, _) = RefreshMessage::<Secp256k1, Sha256, { crate::M_SECURITY }>::distribute(
+            keys[0].i, &keys[0], n,
+        )
+        .unwrap();
+        assert!(serde_json::to_string(&message)
+            .unwrap()
+            .starts_with("{\"version\":"));
+        let json = serde_json::to_value(&message).unwrap();
+
+        // a message of the current version with a field added later is still accepted
+        let mut extended = json.clone();
+        extended["future_field"] = serde_json::json!([1, 2, 3]);
+        let parsed: RefreshMessage<Secp256k1, Sha256, { crate::M_SECURITY }> =
+            serde_json::from_value(extended).unwrap();
+        assert_eq!(parsed.message_digest(), message.message_digest());
+
+        // a message of a later version is rejected, whatever it carries
+        let mut newer = json;
+        newer["version"] = serde_json::json!(crate::refresh_message::REFRESH_MESSAGE_VERSION + 1);
+        newer["future_field"] = serde_json::json!([1, 2, 3]);
+        let parts: RefreshMessageParts<Secp256k1, Sha256, { crate::M_SECURITY }> =
+            serde_json::from_value(newer).unwrap();
+        assert!(matches!(
+            RefreshMessage::new(parts),
+            Err(FsDkrError::UnsupportedVersion {
+                found: 3,
+                supported: 2
+            })
+        ));
+
+        let mut bytes = message.to_bytes();
+        bytes[2] += 1;
+        assert!(matches!(
+            RefreshMessage::<Secp256k1, Sha256, { crate::M_SECURITY }>::from_bytes(&bytes, n),
+            Err(FsDkrError::UnsupportedVersion {
+                found: 3,
+                supported: 2
+            })
+        ));
+    }
+
+    #[test]
+    fn test_compact_encoding_round_trip() {
+        let (t, n) = (1, 3);
+        let keys = simulate_keygen(t, n);
+        let session_id = BigInt::from(5);
+        let params = RefreshParams {
+            metadata: b"ticket 42".to_vec(),
+            ..RefreshParams::default()
+        };
+        let (broadcast_vec, new_dks): (
+            Vec<RefreshMessage<Secp256k1, Sha256, { crate::M_SECURITY }>>,
+            Vec<DecryptionKey>,
+        ) = keys
+            .iter()
+            .map(|key| {
+                let prepared = RefreshMessage::prepare(key.i, key, n, &params).unwrap();
+                RefreshMessage::finalize(prepared, &session_id).unwrap()
+            })
+            .unzip();
+
+        let encoded: Vec<Vec<u8>> = broadcast_vec.iter().map(|msg| msg.to_bytes()).collect();
+        let decoded: Vec<RefreshMessage<Secp256k1, Sha256, { crate::M_SECURITY }>> = encoded
+            .iter()
+            .map(|bytes| RefreshMessage::from_bytes(bytes, n).unwrap())
+            .collect();
+        for ((msg, bytes), round_trip) in broadcast_vec.iter().zip(&encoded).zip(&decoded) {
+            assert_eq!(bytes[0], crate::compact::COMPACT_FORMAT_VERSION);
+            assert_eq!(&round_trip.to_bytes(), bytes);
+            assert_eq!(round_trip.message_digest(), msg.message_digest());
+            assert_eq!(round_trip.metadata(), b"ticket 42");
+            // integers are minimal, so the exact length varies; it stays well below serde's
+            assert!(bytes.len() * 3 < bincode::serialize(msg).unwrap().len() * 2);
+        }
+
+        // the decoded messages collect like the originals, their proofs included
+        let refreshed = RefreshMessage::collect_in_session(
+            &decoded,
+            &keys[0],
+            &session_id,
+            new_dks[0].clone(),
+            &[],
+        )
+        .unwrap();
+        verify_local_key(&refreshed).unwrap();
+
+        let bytes = &encoded[0];
+        let mut trailing = bytes.clone();
+        trailing.push(0);
+        let mut truncated = bytes.clone();
+        truncated.pop();
+        let mut unknown_version = bytes.clone();
+        unknown_version[0] += 1;
+        for (bytes, new_n) in [
+            (&trailing, n),
+            (&truncated, n),
+            (&unknown_version, n),
+            (bytes, n + 1),
+        ]
+        .iter()
+        {
+            assert!(matches!(
+                RefreshMessage::<Secp256k1, Sha256, { crate::M_SECURITY }>::from_bytes(
+                    bytes, *new_n
+                ),
+                Err(FsDkrError::InvalidEncoding { .. })
+            ));
+        }
+    }
+
+    #[test]
+    fn test_message_accessors() {
+        let (t, n) = (1, 3);
+        let keys = simulate_keygen(t, n);
+        let (message, _) = RefreshMessage::<Secp256k1, Sha256, { crate::M_SECURITY }>::distribute(
+            keys[1].i, &keys[1], n,
+        )
+        .unwrap();
+
+        assert_eq!(message.party_index(), 2);
+        assert_eq!(message.recipient_count(), n);
+        assert_eq!(message.vss_commitments().parameters.threshold, t);
+        assert_eq!(message.committed_points().len(), n as usize);
+        assert_eq!(message.encrypted_shares().len(), n as usize);
+        assert_eq!(message.pdl_proofs().len(), n as usize);
+        assert_eq!(message.range_proofs().len(), n as usize);
+        for (k, point) in message.committed_points().iter().enumerate() {
+            assert!(message
+                .vss_commitments()
+                .validate_share_public(point, k as u16 + 1)
+                .is_ok());
+        }
+
+        // a message rebuilt from its parts has to keep its vectors consistent
+        let mut parts = message.clone().into_parts();
+        parts.range_proofs.pop();
+        assert!(matches!(
+            RefreshMessage::new(parts),
+            Err(FsDkrError::InvalidRefreshMessage { party_index: 2, .. })
+        ));
+    }
+
+    #[test]
+    fn test_message_size_without_committed_points() {
+        let (t, n) = (1, 20);
+        let keys = crate::rehearsal::deal_committee(t, n);
+        let (message, _) = RefreshMessage::<Secp256k1, Sha256, { crate::M_SECURITY }>::distribute(
+            keys[0].i, &keys[0], n,
+        )
+        .unwrap();
+        let mut json = serde_json::to_value(&message).unwrap();
+        let size = serde_json::to_vec(&json).unwrap().len();
+
+        // the same message in the previous format, with one committed point per receiver
+        json["points_committed_vec"] = serde_json::to_value(&message.points_committed_vec).unwrap();
+        let previous_size = serde_json::to_vec(&json).unwrap().len();
+
+        // every committed point left out saves at least its 33 compressed bytes
+        assert!(size + 33 * n as usize <= previous_size);
+        assert_eq!(message.points_committed_vec.len(), n as usize);
+    }
+
+    #[test]
+    fn test_remap_sparse_committee() {
+        let all_keys = simulate_keygen(2, 7);
+        let public_key = all_keys[0].y_sum_s.clone();
+        // parties 3 and 5 left the committee
+        let active = [1u16, 2, 4, 6, 7];
+        let mut keys: Vec<_> = active
+            .iter()
+            .map(|i| all_keys[*i as usize - 1].clone())
+            .collect();
+        let remap = IndexRemap::densify(&active).unwrap();
+
+        let (broadcast_vec, new_dks): (
+            Vec<RefreshMessage<Secp256k1, Sha256, { crate::M_SECURITY }>>,
+            Vec<DecryptionKey>,
+        ) = keys
+            .iter_mut()
+            .map(|key| RefreshMessage::remap(key, &remap).unwrap())
+            .unzip();
+
+        // a sender renumbering differently is rejected
+        let other_remap = IndexRemap::densify(&[1, 2, 3, 4, 6, 7]).unwrap();
+        let mut other_key = all_keys[0].clone();
+        let (other_message, _) = RefreshMessage::<Secp256k1, Sha256, { crate::M_SECURITY }>::remap(
+            &mut other_key,
+            &other_remap,
+        )
+        .unwrap();
+        let mut mixed = broadcast_vec.clone();
+        mixed[0] = other_message;
+        assert!(RefreshMessage::collect(&mixed, &keys[1], new_dks[1].clone(), &[]).is_err());
+
+        for (key, new_dk) in keys.iter_mut().zip(new_dks) {
+            *key = RefreshMessage::collect(&broadcast_vec, key, new_dk, &[]).unwrap();
+        }
+        for (k, key) in keys.iter().enumerate() {
+            verify_local_key(key).unwrap();
+            assert_eq!(key.i as usize, k + 1);
+            assert_eq!(key.n, 5);
+            assert_eq!(key.y_sum_s, public_key);
+            assert_eq!(key.pk_vec, keys[0].pk_vec);
+        }
+
+        let offline_sign = simulate_offline_stage(keys, &[1, 3, 5]);
+        simulate_signing(offline_sign, b"ZenGo");
+    }
+
+    #[test]
+    fn test_sparse_indices_reconstruct_after_remap() {
+        let (t, n) = (2, 9);
+        let all_keys = simulate_keygen(t, n);
+        // the registry only knows parties 2, 5, 8 and 9
+        let active = [2u16, 5, 8, 9];
+        let mut keys: Vec<_> = active
+            .iter()
+            .map(|i| all_keys[*i as usize - 1].clone())
+            .collect();
+        let remap = IndexRemap::densify(&active).unwrap();
+        let (broadcast_vec, new_dks): (
+            Vec<RefreshMessage<Secp256k1, Sha256, { crate::M_SECURITY }>>,
+            Vec<DecryptionKey>,
+        ) = keys
+            .iter_mut()
+            .map(|key| RefreshMessage::remap(key, &remap).unwrap())
+            .unzip();
+        let refreshed: Vec<_> = keys
+            .iter()
+            .zip(new_dks)
+            .map(|(key, new_dk)| RefreshMessage::collect(&broadcast_vec, key, new_dk, &[]).unwrap())
+            .collect();
+
+        // the old shares at the sparse indices and the new ones at the dense indices share a
+        // secret
+        let old_shares: Vec<_> = active[..=t as usize]
+            .iter()
+            .map(|i| all_keys[*i as usize - 1].keys_linear.x_i.clone())
+            .collect();
+        let old_indices: Vec<u16> = active[..=t as usize].iter().map(|i| i - 1).collect();
+        let new_shares: Vec<_> = refreshed[..=t as usize]
+            .iter()
+            .map(|key| key.keys_linear.x_i.clone())
+            .collect();
+        let new_indices: Vec<u16> = (0..=t).collect();
+        assert_eq!(
+            refreshed[0]
+                .vss_scheme
+                .reconstruct(&new_indices, &new_shares),
+            all_keys[0]
+                .vss_scheme
+                .reconstruct(&old_indices, &old_shares)
+        );
+        for (k, key) in refreshed.iter().enumerate() {
+            verify_local_key(key).unwrap();
+            assert_eq!(remap.get(active[k]), Some(key.i));
+        }
+    }
+
+    #[test]
+    fn test_remove_parties() {
+        let (t, n) = (2, 5);
+        let old_keys = simulate_keygen(t, n);
+        let public_key = old_keys[0].y_sum_s.clone();
+        // party 3 is decommissioned
+        let survivors = [1u16, 2, 4, 5];
+        let mut keys: Vec<_> = survivors
+            .iter()
+            .map(|i| old_keys[*i as usize - 1].clone())
+            .collect();
+
+        let (broadcast_vec, new_dks): (
+            Vec<RefreshMessage<Secp256k1, Sha256, { crate::M_SECURITY }>>,
+            Vec<DecryptionKey>,
+        ) = keys
+            .iter_mut()
+            .map(|key| RefreshMessage::remove_parties(key, &survivors).unwrap())
+            .unzip();
+        // nothing is encrypted to the removed party
+        for message in broadcast_vec.iter() {
+            assert_eq!(message.points_encrypted_vec.len(), survivors.len());
+        }
+        for (key, new_dk) in keys.iter_mut().zip(new_dks) {
+            *key = RefreshMessage::collect(&broadcast_vec, key, new_dk, &[]).unwrap();
+        }
+        for (k, key) in keys.iter().enumerate() {
+            verify_local_key(key).unwrap();
+            assert_eq!(key.i as usize, k + 1);
+            assert_eq!(key.n, survivors.len() as u16);
+            assert_eq!(key.vss_scheme.parameters.share_count, key.n);
+            assert_eq!(key.paillier_key_vec.len(), survivors.len());
+            assert_eq!(key.y_sum_s, public_key);
+        }
+
+        // t + 1 new shares reconstruct the key, the removed party's old share does not
+        // combine with t new ones
+        let vss = VerifiableSS::<Secp256k1> {
+            parameters: ShamirSecretSharing {
+                threshold: t,
+                share_count: n,
+            },
+            commitments: Vec::new(),
+        };
+        let secret = vss.reconstruct(
+            &[0, 1, 2],
+            &[
+                old_keys[0].keys_linear.x_i.clone(),
+                old_keys[1].keys_linear.x_i.clone(),
+                old_keys[2].keys_linear.x_i.clone(),
+            ],
+        );
+        assert_eq!(
+            vss.reconstruct(
+                &[0, 1, 2],
+                &[
+                    keys[0].keys_linear.x_i.clone(),
+                    keys[1].keys_linear.x_i.clone(),
+                    keys[2].keys_linear.x_i.clone(),
+                ],
+            ),
+            secret
+        );
+        assert_ne!(
+            vss.reconstruct(
+                &[0, 1, 2],
+                &[
+                    keys[0].keys_linear.x_i.clone(),
+                    keys[1].keys_linear.x_i.clone(),
+                    old_keys[2].keys_linear.x_i.clone(),
+                ],
+            ),
+            secret
+        );
+
+        let offline_sign = simulate_offline_stage(keys, &[1, 3, 4]);
+        simulate_signing(offline_sign, b"ZenGo");
+    }
+
+    #[test]
+    fn test_self_certified_rotation() {
+        let n = 3;
+        let mut keys: Vec<RefreshableLocalKey<Secp256k1>> = simulate_keygen(1, n)
+            .into_iter()
+            .map(RefreshableLocalKey::new)
+            .collect();
+        let public_key = keys[0].inner().y_sum_s.clone();
+        let old_keys: Vec<_> = keys.iter().map(|key| key.inner().clone()).collect();
+
+        let session_id = BigInt::from(1);
+        let (broadcast_vec, new_dks): (
+            Vec<RefreshMessage<Secp256k1, Sha256, { crate::M_SECURITY }>>,
+            Vec<DecryptionKey>,
+        ) = keys
+            .iter()
+            .map(|key| key.distribute(&session_id, n).unwrap())
+            .unzip();
+        let pending: Vec<_> = keys
+            .iter_mut()
+            .zip(new_dks)
+            .map(|(key, new_dk)| {
+                key.collect_pending(&session_id, &broadcast_vec, new_dk, &[])
+                    .unwrap()
+            })
+            .collect();
+
+        // nothing is stored before commit
+        assert!(pending.iter().zip(old_keys.iter()).all(|(p, old_key)| p
+            .old_key()
+            .keys_linear
+            .x_i
+            == old_key.keys_linear.x_i));
+        let digest = pending[0].certificate_digest().clone();
+        assert!(pending.iter().all(|p| p.certificate_digest() == &digest));
+
+        // a quorum signs the digest with the old shares
+        let old_shares: Vec<_> = pending.iter().map(|p| p.old_key().clone()).collect();
+        let signature = simulate_signature(simulate_offline_stage(old_shares, &[1, 3]), &digest);
+        let certificate = pending[1].certify::<Sha256>(signature.clone()).unwrap();
+        assert!(pending[0].certify::<Sha256>(signature.clone()).is_ok());
+
+        let outcomes: Vec<_> = pending.into_iter().map(|p| p.commit()).collect();
+        assert_eq!(certificate.guard(), &outcomes[0].guard);
+        certificate.verify::<Sha256>(&public_key).unwrap();
+        assert!(keys.iter().all(|key| key.epoch() == 1));
+
+        // the certificate does not carry over to another rotation
+        let guard = certificate.guard();
+        let forged = crate::epoch::EpochGuard::new(
+            guard.epoch() + 1,
+            guard.new_fingerprint().clone(),
+            guard.old_fingerprint().clone(),
+        );
+        assert!(
+            crate::certified_rotation::SelfCertifiedRotation::new::<Sha256>(
+                forged,
+                signature,
+                &public_key
+            )
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn test_refresh_with_tweak() {
+        let (t, n) = (1, 3);
+        let mut keys = simulate_keygen(t, n);
+        let root_public_key = keys[0].y_sum_s.clone();
+        let tweak = Scalar::<Secp256k1>::random();
+        let tweak_point = Point::<Secp256k1>::generator() * &tweak;
+        let child_public_key = &root_public_key + &tweak_point;
+        let params = RefreshParams {
+            tweak: Some(tweak),
+            ..RefreshParams::default()
+        };
+
+        let (broadcast_vec, new_dks): (
+            Vec<RefreshMessage<Secp256k1, Sha256, { crate::M_SECURITY }>>,
+            Vec<DecryptionKey>,
+        ) = keys
+            .iter()
+            .map(|key| RefreshMessage::distribute_with_params(key.i, key, n, &params).unwrap())
+            .unzip();
+
+        // senders disagreeing on the tweak are rejected
+        let (untweaked, _) =
+            RefreshMessage::<Secp256k1, Sha256, { crate::M_SECURITY }>::distribute(
+                keys[0].i, &keys[0], n,
+            )
+            .unwrap();
+        let mut mixed = broadcast_vec.clone();
+        mixed[0] = untweaked;
+        assert!(RefreshMessage::collect(&mixed, &keys[1], new_dks[1].clone(), &[]).is_err());
+
+        for (key, new_dk) in keys.iter_mut().zip(new_dks) {
+            *key = RefreshMessage::collect(&broadcast_vec, key, new_dk, &[]).unwrap();
+        }
+        for key in keys.iter() {
+            verify_local_key(key).unwrap();
+            assert_eq!(key.y_sum_s, child_public_key);
+        }
+
+        let offline_sign = simulate_offline_stage(keys, &[1, 3]);
+        assert_eq!(offline_sign[0].public_key(), &child_public_key);
+        simulate_signing(offline_sign, b"ZenGo");
+    }
+
+    #[test]
+    fn test_refresh_changes_threshold() {
+        let (t, n) = (1, 5);
+        let new_t = 2;
+        let mut keys = simulate_keygen(t, n);
+        let public_key = keys[0].y_sum_s.clone();
+        let params = RefreshParams {
+            new_t: Some(new_t),
+            ..RefreshParams::default()
+        };
+
+        let (broadcast_vec, new_dks): (
+            Vec<RefreshMessage<Secp256k1, Sha256, { crate::M_SECURITY }>>,
+            Vec<DecryptionKey>,
+        ) = keys
+            .iter()
+            .map(|key| RefreshMessage::distribute_with_params(key.i, key, n, &params).unwrap())
+            .unzip();
+        assert!(broadcast_vec.iter().all(|msg| msg.new_t() == new_t));
+
+        // the new threshold has to leave an honest majority
+        for out_of_range in [0, 3].iter() {
+            let params = RefreshParams {
+                new_t: Some(*out_of_range),
+                ..RefreshParams::default()
+            };
+            assert!(matches!(
+                RefreshMessage::<Secp256k1, Sha256, { crate::M_SECURITY }>::distribute_with_params(
+                    keys[0].i, &keys[0], n, &params
+                ),
+                Err(FsDkrError::InvalidRefreshMessage { party_index: 1, .. })
+            ));
+        }
+
+        // a sender keeping the current threshold is rejected
+        let (unchanged, _) =
+            RefreshMessage::<Secp256k1, Sha256, { crate::M_SECURITY }>::distribute(
+                keys[4].i, &keys[4], n,
+            )
+            .unwrap();
+        let mut mixed = broadcast_vec.clone();
+        mixed[4] = unchanged;
+        assert!(matches!(
+            RefreshMessage::collect(&mixed, &keys[0], new_dks[0].clone(), &[]),
+            Err(FsDkrError::ThresholdMismatch { party_index: 5 })
+        ));
+
+        for (key, new_dk) in keys.iter_mut().zip(new_dks) {
+            *key = RefreshMessage::collect(&broadcast_vec, key, new_dk, &[]).unwrap();
+        }
+        for key in keys.iter() {
+            verify_local_key(key).unwrap();
+            assert_eq!(key.t, new_t);
+            assert_eq!(key.vss_scheme.parameters.threshold, new_t);
+            assert_eq!(key.vss_scheme.commitments.len(), new_t as usize + 1);
+            assert_eq!(key.y_sum_s, public_key);
+        }
+
+        // three new shares reconstruct the key, two no longer do
+        let share = |k: usize| keys[k].keys_linear.x_i.clone();
+        let secret = keys[0]
+            .vss_scheme
+            .reconstruct(&[0, 1, 2], &[share(0), share(1), share(2)]);
+        assert_eq!(Point::<Secp256k1>::generator() * &secret, public_key);
+        let old_vss = VerifiableSS::<Secp256k1> {
+            parameters: ShamirSecretSharing {
+                threshold: t,
+                share_count: n,
+            },
+            commitments: Vec::new(),
+        };
+        assert_ne!(old_vss.reconstruct(&[0, 1], &[share(0), share(1)]), secret);
+
+        let offline_sign = simulate_offline_stage(keys, &[1, 3, 5]);
+        simulate_signing(offline_sign, b"ZenGo");
+    }
+
+    #[test]
+    fn test_revocation_statement() {
+        let (t, n) = (1, 3);
+        let mut keys: Vec<RefreshableLocalKey<Secp256k1>> = simulate_keygen(t, n)
+            .into_iter()
+            .map(RefreshableLocalKey::new)
+            .collect();
+        let public_key = keys[0].inner().y_sum_s.clone();
+
+        let session_id = BigInt::from(1);
+        let (broadcast_vec, new_dks): (
+            Vec<RefreshMessage<Secp256k1, Sha256, { crate::M_SECURITY }>>,
+            Vec<DecryptionKey>,
+        ) = keys
+            .iter()
+            .map(|key| key.distribute(&session_id, n).unwrap())
+            .unzip();
+        let outcomes: Vec<_> = keys
+            .iter_mut()
+            .zip(new_dks)
+            .map(|(key, new_dk)| {
+                key.collect(&session_id, &broadcast_vec, new_dk, &[])
+                    .unwrap()
+            })
+            .collect();
+
+        let reasons = vec!["suspected-share-compromise".to_string()];
+        let notice = outcomes[0]
+            .revocation_notice(1_700_000_000, reasons.clone())
+            .unwrap();
+        assert_eq!(notice.superseded_epoch(), 0);
+        assert!(outcomes.iter().all(|outcome| outcome
+            .revocation_notice(1_700_000_000, reasons.clone())
+            .unwrap()
+            == notice));
+
+        // a quorum of the new shares signs the notice
+        let digest = notice.digest::<Sha256>();
+        let new_keys: Vec<_> = keys.iter().map(|key| key.inner().clone()).collect();
+        let signature =
+            simulate_signature(simulate_offline_stage(new_keys.clone(), &[2, 3]), &digest);
+        let statement =
+            RevocationStatement::new::<Sha256>(notice.clone(), signature, &public_key).unwrap();
+
+        let published = statement.canonical_bytes();
+        let parsed = RevocationStatement::from_canonical_bytes(&published).unwrap();
+        assert_eq!(parsed.canonical_bytes(), published);
+        parsed.verify::<Sha256>(&public_key).unwrap();
+
+        // a single share is below the quorum and can not produce a valid statement
+        let below_quorum = sign_with_secret(&new_keys[0].keys_linear.x_i, &digest);
+        assert!(matches!(
+            RevocationStatement::new::<Sha256>(notice.clone(), below_quorum, &public_key),
+            Err(FsDkrError::RevocationStatementInvalid)
+        ));
+        // while t + 1 shares together can
+        let secret = [1u16, 2]
+            .iter()
+            .map(|&i| {
+                lagrange_coefficient_at(&Scalar::zero(), i, &[1, 2])
+                    * &new_keys[i as usize - 1].keys_linear.x_i
+            })
+            .fold(Scalar::<Secp256k1>::zero(), |acc, x| acc + x);
+        let quorum = sign_with_secret(&secret, &digest);
+        assert!(RevocationStatement::new::<Sha256>(notice, quorum, &public_key).is_ok());
+    }
+
+    #[test]
+    fn test_prepare_then_finalize_for_session() {
+        use std::time::{Duration, Instant};
+
+        let (t, n) = (1, 3);
+        let mut keys = simulate_keygen(t, n);
+        let session_a = BigInt::from(1);
+        let session_b = BigInt::from(2);
+
+        let mut prepare_time = Duration::default();
+        let mut finalize_time = Duration::default();
+        let (broadcast_vec, new_dks): (
+            Vec<RefreshMessage<Secp256k1, Sha256, { crate::M_SECURITY }>>,
+            Vec<DecryptionKey>,
+        ) = keys
+            .iter()
+            .map(|key| {
+                let start = Instant::now();
+                let prepared =
+                    RefreshMessage::prepare(key.i, key, n, &RefreshParams::default()).unwrap();
+                prepare_time += start.elapsed();
+                let start = Instant::now();
+                let finalized = RefreshMessage::finalize(prepared, &session_a).unwrap();
+                finalize_time += start.elapsed();
+                finalized
+            })
+            .unzip();
+        assert!(finalize_time * 100 < prepare_time);
+
+        // messages finalized for session A are rejected in session B
+        let context_b = RefreshContext::new(&keys[0], n).with_session_id(session_b.clone());
+        assert!(matches!(
+            RefreshMessage::verify_all(&broadcast_vec, &context_b),
+            Err(FsDkrError::SessionBindingInvalid)
+        ));
+        // even once re-bound to session B under a fresh key
+        let rebound: Vec<_> = broadcast_vec
+            .iter()
+            .map(|msg| test_utils::rebind_to_session(msg, &session_b))
+            .collect();
+        assert!(matches!(
+            RefreshMessage::collect_in_session(
+                &rebound,
+                &keys[0],
+                &session_b,
+                new_dks[0].clone(),
+                &[]
+            ),
+            Err(FsDkrError::SessionBindingInvalid)
+        ));
+        // a bound message is signed as a whole: swapping the ring-Pedersen parameters the
+        // receivers would store for the sender breaks the binding
+        let mut tampered = broadcast_vec[1].clone();
+        tampered.dlog_statement = broadcast_vec[2].dlog_statement.clone();
+        assert!(broadcast_vec[1].verify_session_binding(&session_a).is_ok());
+        assert!(matches!(
+            tampered.verify_session_binding(&session_a),
+            Err(FsDkrError::SessionBindingInvalid)
+        ));
+        // and so are messages that were never bound to a session
+        let (unbound, _) = RefreshMessage::<Secp256k1, Sha256, { crate::M_SECURITY }>::distribute(
+            keys[0].i, &keys[0], n,
+        )
+        .unwrap();
+        let context_a = RefreshContext::new(&keys[0], n).with_session_id(session_a.clone());
+        assert!(matches!(
+            unbound.verify(&context_a),
+            Err(FsDkrError::SessionBindingInvalid)
+        ));
+
+        for (key, new_dk) in keys.iter_mut().zip(new_dks) {
+            let context = RefreshContext::new(key, n).with_session_id(session_a.clone());
+            let verified = RefreshMessage::verify_all(&broadcast_vec, &context).unwrap();
+            RefreshMessage::apply_refresh(&verified, key, new_dk, &[], Some(&session_a)).unwrap();
+        }
+        let offline_sign = simulate_offline_stage(keys, &[2, 3]);
+        simulate_signing(offline_sign, b"ZenGo");
+    }
+
+    #[test]
+    fn test_collect_reports_warnings() {
+        use crate::prepared_distribution::SessionBinding;
+        use curv::arithmetic::BitManipulation;
+        use paillier::{KeyGeneration, Paillier};
+        use zk_paillier::zkproofs::NiCorrectKeyProof;
+
+        let (t, n) = (1, 3);
+        let mut keys: Vec<RefreshableLocalKey<Secp256k1>> = simulate_keygen(t, n)
+            .into_iter()
+            .map(RefreshableLocalKey::new)
+            .collect();
+        // party 3 stays silent
+        let session_id = BigInt::from(1);
+        let (mut refresh_messages, mut dks): (
+            Vec<RefreshMessage<Secp256k1, Sha256, { crate::M_SECURITY }>>,
+            Vec<DecryptionKey>,
+        ) = keys[..2]
+            .iter()
+            .map(|key| key.distribute(&session_id, n).unwrap())
+            .unzip();
+
+        // party 1 moves to a modulus one bit short of the recommended size
+        let (marginal_ek, marginal_dk) = loop {
+            let (ek, dk) = Paillier::keypair_with_modulus_size(crate::PAILLIER_KEY_SIZE).keys();
+            if ek.n.bit_length() == crate::PAILLIER_KEY_SIZE - 1 {
+                break (ek, dk);
+            }
+        };
+        let mut parts = refresh_messages[0].clone().into_parts();
+        parts.dk_correctness_proof = NiCorrectKeyProof::proof(&marginal_dk, None);
+        parts.ek = marginal_ek;
+        let mut marginal = RefreshMessage::new(parts).unwrap();
+        marginal.session_binding = Some(SessionBinding::sign::<Sha256>(
+            &session_id,
+            &keys[0].inner().keys_linear.x_i,
+            &marginal.binding_fingerprint(),
+        ));
+        refresh_messages[0] = marginal;
+        dks[0] = marginal_dk;
+        // party 2 receives the message of party 1 twice, which is still a single sender
+        let mut with_duplicate = refresh_messages.clone();
+        with_duplicate.push(refresh_messages[0].clone());
+
+        for (key, dk) in keys.iter_mut().zip(dks) {
+            let received = if key.inner().i == 2 {
+                &with_duplicate
+            } else {
+                &refresh_messages
+            };
+            let outcome = key.collect(&session_id, received, dk, &[]).unwrap();
+            assert_eq!(
+                outcome.warnings,
+                vec![
+                    Warning::MarginalModulus {
+                        party_index: 1,
+                        bits: crate::PAILLIER_KEY_SIZE - 1
+                    },
+                    Warning::MinimalQuorum {
+                        received: 2,
+                        threshold: 1
+                    },
+                    Warning::SilentParties {
+                        party_indices: vec![3]
+                    },
+                ]
+            );
+        }
+    }
+
+    #[test]
+    fn test_collect_ignores_arrival_order_of_wrongly_sized_message() {
+        let (t, n) = (1, 4);
+        let keys = simulate_keygen(t, n);
+        let (mut refresh_messages, dks): (
+            Vec<RefreshMessage<Secp256k1, Sha256, { crate::M_SECURITY }>>,
+            Vec<DecryptionKey>,
+        ) = keys
+            .iter()
+            .map(|key| RefreshMessage::distribute(key.i, key, n).unwrap())
+            .unzip();
+        // sender 1 distributes to one receiver less than the committee has
+        refresh_messages[0] = RefreshMessage::distribute(keys[0].i, &keys[0], n - 1)
+            .unwrap()
+            .0;
+
+        // whether the malformed message arrives first, last or in between, every party leaves
+        // out the same sender
+        let orders: [&[usize]; 3] = [&[0, 1, 2, 3], &[1, 2, 3, 0], &[2, 0, 3, 1]];
+        let mut refreshed = Vec::new();
+        for (k, order) in (1..n as usize).zip(orders.iter()) {
+            let received: Vec<_> = order
+                .iter()
+                .map(|index| refresh_messages[*index].clone())
+                .collect();
+            let (new_key, excluded) = RefreshMessage::collect_with_options(
+                &received,
+                &keys[k],
+                dks[k].clone(),
+                &[],
+                &CollectOptions::tolerate_failures(1),
+            )
+            .unwrap();
+            assert_eq!(
+                excluded
+                    .iter()
+                    .map(|(party_index, _)| *party_index)
+                    .collect::<Vec<_>>(),
+                vec![1]
+            );
+            refreshed.push(new_key);
+        }
+        for key in refreshed.iter() {
+            verify_local_key(key).unwrap();
+            assert_eq!(key.pk_vec, refreshed[0].pk_vec);
+            assert_eq!(key.y_sum_s, keys[0].y_sum_s);
+        }
+
+        // without tolerance the order does not matter either
+        for order in orders.iter() {
+            let received: Vec<_> = order
+                .iter()
+                .map(|index| refresh_messages[*index].clone())
+                .collect();
+            assert!(RefreshMessage::collect(&received, &keys[1], dks[1].clone(), &[]).is_err());
+        }
+    }
+
+    #[test]
+    fn test_collect_tolerating_failed_senders() {
+        let (t, n) = (1, 4);
+        let keys: Vec<RefreshableLocalKey<Secp256k1>> = simulate_keygen(t, n)
+            .into_iter()
+            .map(RefreshableLocalKey::new)
+            .collect();
+        let session_id = BigInt::from(1);
+        let (mut refresh_messages, dks): (
+            Vec<RefreshMessage<Secp256k1, Sha256, { crate::M_SECURITY }>>,
+            Vec<DecryptionKey>,
+        ) = keys
+            .iter()
+            .map(|key| key.distribute(&session_id, n).unwrap())
+            .unzip();
+        let forge = |message: &RefreshMessage<Secp256k1, Sha256, { crate::M_SECURITY }>| {
+            let mut parts = message.clone().into_parts();
+            parts.points_encrypted_vec[0] = parts.points_encrypted_vec[1].clone();
+            RefreshMessage::new(parts).unwrap()
+        };
+        refresh_messages[2] = forge(&refresh_messages[2]);
+
+        // by default a single failure aborts
+        let mut key = keys[0].clone();
+        assert!(key
+            .collect(&session_id, &refresh_messages, dks[0].clone(), &[])
+            .is_err());
+
+        // more failures than tolerated abort too
+        let mut two_forged = refresh_messages.clone();
+        two_forged[3] = forge(&two_forged[3]);
+        let mut key = keys[0].clone();
+        assert!(matches!(
+            key.collect_pending_with_options(
+                &session_id,
+                &two_forged,
+                dks[0].clone(),
+                &[],
+                &CollectOptions::tolerate_failures(1)
+            ),
+            Err(FsDkrError::TooManyFailedSenders { tolerated: 1, .. })
+        ));
+
+        // the senders left out do not count towards the quorum
+        let mut key = keys[0].clone();
+        let outcome = key
+            .collect_pending_with_options(
+                &session_id,
+                &two_forged,
+                dks[0].clone(),
+                &[],
+                &CollectOptions::tolerate_failures(2),
+            )
+            .unwrap()
+            .commit();
+        assert!(outcome.warnings.contains(&Warning::MinimalQuorum {
+            received: 2,
+            threshold: 1
+        }));
+
+        // every party leaves out the same sender and ends up with the same key
+        let accepted: Vec<_> = refresh_messages
+            .iter()
+            .filter(|msg| msg.party_index != 3)
+            .cloned()
+            .collect();
+        let accepted_digest = RefreshMessage::transcript_digest(&accepted);
+        let mut refreshed = Vec::new();
+        for (mut key, dk) in keys.into_iter().zip(dks) {
+            let outcome = key
+                .collect_pending_with_options(
+                    &session_id,
+                    &refresh_messages,
+                    dk,
+                    &[],
+                    &CollectOptions::tolerate_failures(1),
+                )
+                .unwrap()
+                .commit();
+            assert_eq!(outcome.excluded.len(), 1);
+            assert_eq!(outcome.excluded[0].0, 3);
+            // the transcript and the metadata only cover the senders that were combined
+            assert_eq!(outcome.transcript_digest, accepted_digest);
+            assert!(outcome.metadata.iter().all(|(sender, _)| *sender != 3));
+            refreshed.push(key.into_inner());
+        }
+        for key in refreshed.iter() {
+            verify_local_key(key).unwrap();
+            assert_eq!(key.pk_vec, refreshed[0].pk_vec);
+            assert_eq!(key.vss_scheme, refreshed[0].vss_scheme);
+        }
+        let offline_sign = simulate_offline_stage(refreshed, &[1, 3]);
+        simulate_signing(offline_sign, b"ZenGo");
+    }
+
+    struct JsonCodec;
+
+    impl Codec for JsonCodec {
+        fn decode<T: DeserializeOwned>(&self, bytes: &[u8]) -> Result<T, String> {
+            serde_json::from_slice(bytes).map_err(|error| error.to_string())
+        }
+    }
+
+    #[test]
+    fn test_collect_from_bytes() {
+        let (t, n) = (1, 5);
+        let keys: Vec<RefreshableLocalKey<Secp256k1>> = simulate_keygen(t, n)
+            .into_iter()
+            .map(RefreshableLocalKey::new)
+            .collect();
+        let session_id = BigInt::from(1);
+        let (refresh_messages, dks): (
+            Vec<RefreshMessage<Secp256k1, Sha256, { crate::M_SECURITY }>>,
+            Vec<DecryptionKey>,
+        ) = keys
+            .iter()
+            .map(|key| key.distribute(&session_id, n).unwrap())
+            .unzip();
+        let limits = DecodeLimits::for_committee(n);
+
+        let mut blobs: Vec<(u16, Vec<u8>)> = refresh_messages
+            .iter()
+            .map(|msg| (msg.party_index, serde_json::to_vec(msg).unwrap()))
+            .collect();
+        // truncated
+        blobs[1].1.truncate(blobs[1].1.len() / 2);
+        // would decode, but is padded past the limit
+        blobs[2].1.resize(limits.max_message_len() + 1, b' ');
+        // decodes, with a forged ciphertext
+        let mut parts = refresh_messages[3].clone().into_parts();
+        parts.points_encrypted_vec[0] = parts.points_encrypted_vec[1].clone();
+        blobs[3].1 = serde_json::to_vec(&RefreshMessage::new(parts).unwrap()).unwrap();
+
+        let mut key = keys[0].clone();
+        let failures = match key.collect_from_bytes::<Sha256, _, { crate::M_SECURITY }>(
+            &session_id,
+            &blobs,
+            &JsonCodec,
+            &limits,
+            dks[0].clone(),
+            &CollectOptions::tolerate_failures(2),
+        ) {
+            Err(FsDkrError::TooManyFailedSenders { failures, .. }) => failures,
+            other => panic!("unexpected result {:?}", other.map(|_| ())),
+        };
+        assert_eq!(
+            failures
+                .iter()
+                .map(|(party_index, _)| *party_index)
+                .collect::<Vec<_>>(),
+            vec![2, 3, 4]
+        );
+        assert!(matches!(
+            failures[0].1,
+            FsDkrError::MalformedMessage { party_index: 2, .. }
+        ));
+        assert!(matches!(
+            failures[1].1,
+            FsDkrError::MalformedMessage { party_index: 3, .. }
+        ));
+        assert!(!matches!(
+            failures[2].1,
+            FsDkrError::MalformedMessage { .. }
+        ));
+        assert_eq!(key.epoch(), 0);
+
+        let mut refreshed = Vec::new();
+        for &index in &[0, 4] {
+            let mut key = keys[index].clone();
+            let outcome = key
+                .collect_from_bytes::<Sha256, _, { crate::M_SECURITY }>(
+                    &session_id,
+                    &blobs,
+                    &JsonCodec,
+                    &limits,
+                    dks[index].clone(),
+                    &CollectOptions::tolerate_failures(3),
+                )
+                .unwrap();
+            assert_eq!(
+                outcome
+                    .excluded
+                    .iter()
+                    .map(|(party_index, _)| *party_index)
+                    .collect::<Vec<_>>(),
+                vec![2, 3, 4]
+            );
+            refreshed.push(key.into_inner());
+        }
+        for key in refreshed.iter() {
+            verify_local_key(key).unwrap();
+        }
+        assert_eq!(refreshed[0].pk_vec, refreshed[1].pk_vec);
+        assert_eq!(refreshed[0].y_sum_s, keys[0].inner().y_sum_s);
+    }
+
+    #[test]
+    fn test_signing_context() {
+        let (t, n) = (1, 3);
+        let mut keys: Vec<RefreshableLocalKey<Secp256k1>> = simulate_keygen(t, n)
+            .into_iter()
+            .map(RefreshableLocalKey::new)
+            .collect();
+        let quorum = [3, 1];
+        let zero = Scalar::<Secp256k1>::zero();
+
+        let old_pk_vec = keys[0].inner().pk_vec.clone();
+        let old_weighted = {
+            let context = keys[0].signing_context(&quorum).unwrap();
+            assert_eq!(context.epoch(), 0);
+            assert_eq!(context.quorum(), &[1, 3]);
+            for j in [1u16, 3].iter() {
+                let lambda = lagrange_coefficient_at(&zero, *j, &[1, 3]);
+                assert_eq!(context.lagrange_coefficient(*j), Some(&lambda));
+                assert_eq!(
+                    context.weighted_public_share(*j),
+                    Some(&(&old_pk_vec[(*j - 1) as usize] * &lambda))
+                );
+            }
+            assert!(context.lagrange_coefficient(2).is_none());
+            assert_eq!(context.aggregate_public_key(), &keys[0].inner().y_sum_s);
+            context.weighted_public_share(1).unwrap().clone()
+        };
+        assert!(keys[0].signing_context(&[1]).is_err());
+        assert!(keys[0].signing_context(&[2, 3]).is_err());
+        assert!(keys[0].signing_context(&[1, 1]).is_err());
+
+        let (refresh_messages, dks): (
+            Vec<RefreshMessage<Secp256k1, Sha256, { crate::M_SECURITY }>>,
+            Vec<DecryptionKey>,
+        ) = keys
+            .iter()
+            .map(|key| key.distribute(&BigInt::from(1), n).unwrap())
+            .unzip();
+        for (key, dk) in keys.iter_mut().zip(dks) {
+            key.collect(&BigInt::from(1), &refresh_messages, dk, &[])
+                .unwrap();
+        }
+
+        let new_pk_vec = keys[0].inner().pk_vec.clone();
+        let context = keys[0].signing_context(&quorum).unwrap();
+        assert_eq!(context.epoch(), 1);
+        assert_ne!(context.weighted_public_share(1), Some(&old_weighted));
+        assert_eq!(
+            context.weighted_public_share(1),
+            Some(&(&new_pk_vec[0] * &lagrange_coefficient_at(&zero, 1, &[1, 3])))
+        );
+        assert_eq!(context.aggregate_public_key(), &keys[0].inner().y_sum_s);
+    }
+
+    #[test]
+    fn test_dkg_then_sign_and_refresh() {
+        let (t, n) = (1, 3);
+        let (mut registrations, paillier_keys): (
+            Vec<JoinMessage<Secp256k1, Sha256, { crate::M_SECURITY }>>,
+            Vec<Keys>,
+        ) = (0..n).map(|_| JoinMessage::distribute()).unzip();
+        for (k, registration) in registrations.iter_mut().enumerate() {
+            registration.set_party_index(k as u16 + 1);
+        }
+
+        let (dkg_messages, new_dks): (Vec<_>, Vec<_>) = (1..=n)
+            .map(|party_index| dkg_distribute(party_index, t, &registrations).unwrap())
+            .unzip();
+        // every party has to deal
+        assert!(collect_dkg(
+            1,
+            t,
+            &registrations,
+            &dkg_messages[..2],
+            &paillier_keys[0],
+            new_dks[0].clone()
+        )
+        .is_err());
+
+        let mut keys: Vec<LocalKey<Secp256k1>> = (1..=n)
+            .zip(paillier_keys.iter().zip(new_dks))
+            .map(|(party_index, (paillier_keys, new_dk))| {
+                collect_dkg(
+                    party_index,
+                    t,
+                    &registrations,
+                    &dkg_messages,
+                    paillier_keys,
+                    new_dk,
+                )
+                .unwrap()
+            })
+            .collect();
+
+        let confirmations: Vec<(u16, BigInt)> = keys
+            .iter()
+            .map(|key| (key.i, dkg_confirmation::<Secp256k1, Sha256>(key)))
+            .collect();
+        for key in keys.iter() {
+            verify_local_key(key).unwrap();
+            assert_eq!(key.y_sum_s, keys[0].y_sum_s);
+            verify_dkg_confirmations::<Secp256k1, Sha256>(key, &confirmations).unwrap();
+        }
+        let mut forged = confirmations.clone();
+        forged[2].1 = BigInt::from(1);
+        assert!(matches!(
+            verify_dkg_confirmations::<Secp256k1, Sha256>(&keys[0], &forged),
+            Err(FsDkrError::DkgConfirmationMismatch { party_index: 3 })
+        ));
+
+        let offline_sign = simulate_offline_stage(keys.clone(), &[1, 2]);
+        simulate_signing(offline_sign, b"ZenGo");
+
+        simulate_dkr::<{ crate::M_SECURITY }>(&mut keys);
+        for key in keys.iter() {
+            verify_local_key(key).unwrap();
+        }
+        let offline_sign = simulate_offline_stage(keys, &[2, 3]);
+        simulate_signing(offline_sign, b"ZenGo");
+    }
+
+    #[test]
+    fn test_old_paillier_key_retired_after_refresh() {
+        use paillier::{Decrypt, Paillier, RawCiphertext};
+
+        let (t, n) = (1, 3);
+        let mut keys = simulate_keygen(t, n);
+        let old_dk = keys[0].paillier_dk.clone();
+        let old_ek = keys[0].paillier_key_vec[0].clone();
+        simulate_dkr::<{ crate::M_SECURITY }>(&mut keys);
+        assert_ne!(keys[0].paillier_key_vec[0], old_ek);
+        for key in keys.iter() {
+            assert_eq!(key.paillier_key_vec[0], keys[0].paillier_key_vec[0]);
+        }
+
+        // the next refresh encrypts to the new key only
+        let (message, _) = RefreshMessage::<Secp256k1, Sha256, { crate::M_SECURITY }>::distribute(
+            keys[1].i, &keys[1], n,
+        )
+        .unwrap();
+        let committed = message.points_committed_vec[0].clone();
+        let ciphertext = message.into_parts().points_encrypted_vec[0].clone();
+        let decrypt = |dk: &DecryptionKey| {
+            let share = Paillier::decrypt(dk, RawCiphertext::from(ciphertext.clone()))
+                .0
+                .into_owned();
+            Point::<Secp256k1>::generator() * Scalar::<Secp256k1>::from(&share)
+        };
+        assert_eq!(decrypt(&keys[0].paillier_dk), committed);
+        assert_ne!(decrypt(&old_dk), committed);
+    }
+
+    #[test]
+    fn test_paillier_rotation_then_refresh() {
+        let (t, n) = (1, 3);
+        let keys = simulate_keygen(t, n);
+        let (messages, dks): (Vec<_>, Vec<_>) =
+            keys.iter().map(PaillierRotationMessage::distribute).unzip();
+
+        let mut duplicated = messages.clone();
+        duplicated[2] = messages[1].clone();
+        assert!(matches!(
+            apply_rotation(&keys[0], &duplicated, dks[0].clone()),
+            Err(FsDkrError::DuplicatedRefreshMessage { party_index: 2 })
+        ));
+        assert!(matches!(
+            apply_rotation(&keys[0], &messages[..2], dks[0].clone()),
+            Err(FsDkrError::InvalidQuorum { .. })
+        ));
+        assert!(matches!(
+            apply_rotation(&keys[0], &messages, dks[1].clone()),
+            Err(FsDkrError::InvalidLocalKey { .. })
+        ));
+
+        let mut rotated: Vec<_> = keys
+            .iter()
+            .zip(dks)
+            .map(|(key, dk)| apply_rotation(key, &messages, dk).unwrap())
+            .collect();
+        for (old_key, new_key) in keys.iter().zip(rotated.iter()) {
+            assert_eq!(new_key.keys_linear.x_i, old_key.keys_linear.x_i);
+            assert_eq!(new_key.y_sum_s, old_key.y_sum_s);
+            assert_eq!(new_key.pk_vec, old_key.pk_vec);
+            assert_eq!(new_key.vss_scheme, old_key.vss_scheme);
+            assert_ne!(new_key.paillier_dk.p, old_key.paillier_dk.p);
+            for (new_ek, old_ek) in new_key
+                .paillier_key_vec
+                .iter()
+                .zip(old_key.paillier_key_vec.iter())
+            {
+                assert_ne!(new_ek, old_ek);
+            }
+            verify_local_key(new_key).unwrap();
+        }
+
+        let offline_sign = simulate_offline_stage(rotated.clone(), &[1, 3]);
+        simulate_signing(offline_sign, b"ZenGo");
+        simulate_dkr::<{ crate::M_SECURITY }>(&mut rotated);
+        let offline_sign = simulate_offline_stage(rotated, &[2, 3]);
+        simulate_signing(offline_sign, b"ZenGo");
+    }
+
+    #[test]
+    fn test_collect_serialized_messages() {
+        let (t, n) = (1, 3);
+        let mut keys = simulate_keygen(t, n);
+        let (messages, dks): (
+            Vec<RefreshMessage<Secp256k1, Sha256, { crate::M_SECURITY }>>,
+            Vec<DecryptionKey>,
+        ) = keys
+            .iter()
+            .map(|key| RefreshMessage::distribute(key.i, key, n).unwrap())
+            .unzip();
+
+        let from_json: Vec<RefreshMessage<Secp256k1, Sha256, { crate::M_SECURITY }>> = messages
+            .iter()
+            .map(|msg| serde_json::from_slice(&serde_json::to_vec(msg).unwrap()).unwrap())
+            .collect();
+        let from_bincode: Vec<RefreshMessage<Secp256k1, Sha256, { crate::M_SECURITY }>> = messages
+            .iter()
+            .map(|msg| bincode::deserialize(&bincode::serialize(msg).unwrap()).unwrap())
+            .collect();
+        for (json, bincode) in from_json.iter().zip(from_bincode.iter()) {
+            assert_eq!(json.message_digest(), bincode.message_digest());
+        }
+
+        let old_public_key = keys[0].y_sum_s.clone();
+        for (k, (key, dk)) in keys.iter_mut().zip(dks).enumerate() {
+            let received = if k % 2 == 0 {
+                &from_json
+            } else {
+                &from_bincode
+            };
+            *key = RefreshMessage::collect(received, key, dk, &[]).unwrap();
+            verify_local_key(key).unwrap();
+            assert_eq!(key.y_sum_s, old_public_key);
+        }
+        let offline_sign = simulate_offline_stage(keys, &[1, 3]);
+        simulate_signing(offline_sign, b"ZenGo");
+
+        // malformed integers and points are rejected when decoding
+        let json = serde_json::to_value(&messages[0]).unwrap();
+        let parse = |json: serde_json::Value| {
+            serde_json::from_value::<RefreshMessage<Secp256k1, Sha256, { crate::M_SECURITY }>>(json)
+        };
+        let mut negative = json.clone();
+        let ciphertext = negative["points_encrypted_vec"][0]
+            .as_str()
+            .unwrap()
+            .to_string();
+        negative["points_encrypted_vec"][0] = serde_json::json!(format!("-{}", ciphertext));
+        assert!(parse(negative).is_err());
+
+        fn replace_points(value: &mut serde_json::Value, with: &str) {
+            match value {
+                serde_json::Value::String(s) if s.len() == 66 => *s = with.to_string(),
+                serde_json::Value::Array(values) => {
+                    values.iter_mut().for_each(|v| replace_points(v, with))
+                }
+                serde_json::Value::Object(map) => {
+                    map.values_mut().for_each(|v| replace_points(v, with))
+                }
+                _ => {}
+            }
+        }
+        let mut off_curve = json;
+        replace_points(
+            &mut off_curve["public_key"],
+            &format!("02{}", "ff".repeat(32)),
+        );
+        assert_ne!(
+            off_curve["public_key"],
+            serde_json::to_value(&messages[0].public_key).unwrap()
+        );
+        assert!(parse(off_curve).is_err());
+    }
+
+    #[test]
+    fn test_failed_collect_leaves_key_untouched() {
+        let (t, n) = (1, 3);
+        let keys = simulate_keygen(t, n);
+        let (broadcast_vec, new_dks): (
+            Vec<RefreshMessage<Secp256k1, Sha256, { crate::M_SECURITY }>>,
+            Vec<DecryptionKey>,
+        ) = keys
+            .iter()
+            .map(|key| RefreshMessage::distribute(key.i, key, n).unwrap())
+            .unzip();
+        let mut forged = broadcast_vec.clone();
+        let mut parts = forged[1].clone().into_parts();
+        parts.points_encrypted_vec[0] = parts.points_encrypted_vec[1].clone();
+        forged[1] = RefreshMessage::new(parts).unwrap();
+
+        let old_key = keys[0].clone();
+        assert!(RefreshMessage::collect(&forged, &keys[0], new_dks[0].clone(), &[]).is_err());
+        assert!(
+            RefreshMessage::collect(&broadcast_vec[..1], &keys[0], new_dks[0].clone(), &[])
+                .is_err()
+        );
+        assert_eq!(
+            key_fingerprint::<Secp256k1, Sha256>(&keys[0]),
+            key_fingerprint::<Secp256k1, Sha256>(&old_key)
+        );
+        assert_eq!(keys[0].keys_linear.x_i, old_key.keys_linear.x_i);
+        assert_eq!(keys[0].paillier_dk.p, old_key.paillier_dk.p);
+
+        // the party can retry with the honest messages
+        let refreshed =
+            RefreshMessage::collect(&broadcast_vec, &keys[0], new_dks[0].clone(), &[]).unwrap();
+        verify_local_key(&refreshed).unwrap();
+        assert_eq!(refreshed.y_sum_s, old_key.y_sum_s);
+        assert_ne!(refreshed.keys_linear.x_i, old_key.keys_linear.x_i);
+    }
+
+    #[test]
+    fn test_collect_any_order_rejects_duplicates() {
+        let (t, n) = (1, 3);
+        let keys = simulate_keygen(t, n);
+        let (broadcast_vec, new_dks): (
+            Vec<RefreshMessage<Secp256k1, Sha256, { crate::M_SECURITY }>>,
+            Vec<DecryptionKey>,
+        ) = keys
+            .iter()
+            .map(|key| RefreshMessage::distribute(key.i, key, n).unwrap())
+            .unzip();
+
+        // a second, freshly generated message from party 2
+        let (second, _) = RefreshMessage::distribute(keys[1].i, &keys[1], n).unwrap();
+        let mut duplicated = broadcast_vec.clone();
+        duplicated.push(second);
+        assert!(matches!(
+            RefreshMessage::collect(&duplicated, &keys[0], new_dks[0].clone(), &[]),
+            Err(FsDkrError::EquivocatingSender { party_index: 2 })
+        ));
+
+        let mut reversed = broadcast_vec.clone();
+        reversed.reverse();
+        let refreshed: Vec<_> = keys
+            .iter()
+            .zip(new_dks)
+            .enumerate()
+            .map(|(k, (key, new_dk))| {
+                let received = if k == 0 { &reversed } else { &broadcast_vec };
+                RefreshMessage::collect(received, key, new_dk, &[]).unwrap()
+            })
+            .collect();
+        for key in refreshed.iter() {
+            verify_local_key(key).unwrap();
+            assert_eq!(key.pk_vec, refreshed[1].pk_vec);
+            assert_eq!(key.paillier_key_vec, refreshed[1].paillier_key_vec);
+        }
+        let offline_sign = simulate_offline_stage(refreshed, &[1, 2]);
+        simulate_signing(offline_sign, b"ZenGo");
+    }
+
+    #[test]
+    fn test_replayed_messages_deduplicated_equivocation_rejected() {
+        let (t, n) = (1, 3);
+        let keys = simulate_keygen(t, n);
+        let (broadcast_vec, new_dks): (
+            Vec<RefreshMessage<Secp256k1, Sha256, { crate::M_SECURITY }>>,
+            Vec<DecryptionKey>,
+        ) = keys
+            .iter()
+            .map(|key| RefreshMessage::distribute(key.i, key, n).unwrap())
+            .unzip();
+        let (second, _) = RefreshMessage::distribute(keys[2].i, &keys[2], n).unwrap();
+
+        // equality and hashing follow the fingerprint of the encoding
+        let copy = RefreshMessage::from_bytes(&broadcast_vec[2].to_bytes(), n).unwrap();
+        assert_eq!(copy.fingerprint(), broadcast_vec[2].fingerprint());
+        assert_eq!(copy, broadcast_vec[2]);
+        assert_ne!(second, broadcast_vec[2]);
+        let by_sender: HashMap<_, _> = broadcast_vec
+            .iter()
+            .map(|msg| (msg.clone(), msg.party_index()))
+            .collect();
+        assert_eq!(by_sender[&copy], 3);
+        assert!(!by_sender.contains_key(&second));
+
+        // exact copies are dropped, whether replayed or decoded again
+        let mut replayed = broadcast_vec.clone();
+        replayed.push(copy);
+        replayed.push(broadcast_vec[0].clone());
+        let from_replayed =
+            RefreshMessage::collect(&replayed, &keys[0], new_dks[0].clone(), &[]).unwrap();
+        let from_unique =
+            RefreshMessage::collect(&broadcast_vec, &keys[0], new_dks[0].clone(), &[]).unwrap();
+        assert_eq!(from_replayed.keys_linear.x_i, from_unique.keys_linear.x_i);
+        assert_eq!(from_replayed.pk_vec, from_unique.pk_vec);
+
+        let mut equivocated = broadcast_vec.clone();
+        equivocated.insert(0, second);
+        assert!(matches!(
+            RefreshMessage::collect(&equivocated, &keys[0], new_dks[0].clone(), &[]),
+            Err(FsDkrError::EquivocatingSender { party_index: 3 })
+        ));
+        assert!(matches!(
+            RefreshMessage::collect_with_options(
+                &equivocated,
+                &keys[0],
+                new_dks[0].clone(),
+                &[],
+                &CollectOptions::tolerate_failures(1)
+            ),
+            Err(FsDkrError::EquivocatingSender { party_index: 3 })
+        ));
+    }
+
+    #[test]
+    fn test_collect_with_own_message() {
+        let (t, n) = (1, 3);
+        let keys = simulate_keygen(t, n);
+        let (broadcast_vec, new_dks): (
+            Vec<RefreshMessage<Secp256k1, Sha256, { crate::M_SECURITY }>>,
+            Vec<DecryptionKey>,
+        ) = keys
+            .iter()
+            .map(|key| RefreshMessage::distribute(key.i, key, n).unwrap())
+            .unzip();
+        let own = Some(&broadcast_vec[0]);
+
+        // party 1 receives the messages shuffled, without its own echo, or with it twice
+        let mut shuffled = broadcast_vec.clone();
+        shuffled.rotate_left(1);
+        let without_echo = &broadcast_vec[1..];
+        let mut duplicated = broadcast_vec.clone();
+        duplicated.push(broadcast_vec[0].clone());
+        let from_duplicated = RefreshMessage::collect_with_own_message(
+            &duplicated,
+            &keys[0],
+            own,
+            new_dks[0].clone(),
+            &[],
+        )
+        .unwrap();
+
+        // another message in the name of party 1 is not the one it distributed
+        let (forged, _) = RefreshMessage::distribute(keys[0].i, &keys[0], n).unwrap();
+        let mut with_forged = broadcast_vec[1..].to_vec();
+        with_forged.push(forged);
+        assert!(matches!(
+            RefreshMessage::collect_with_own_message(
+                &with_forged,
+                &keys[0],
+                own,
+                new_dks[0].clone(),
+                &[]
+            ),
+            Err(FsDkrError::OwnRefreshMessageMismatch { party_index: 1 })
+        ));
+        assert!(matches!(
+            RefreshMessage::collect_with_own_message(
+                &broadcast_vec,
+                &keys[1],
+                own,
+                new_dks[1].clone(),
+                &[]
+            ),
+            Err(FsDkrError::OwnRefreshMessageMismatch { party_index: 2 })
+        ));
+
+        let from_shuffled = RefreshMessage::collect_with_own_message(
+            &shuffled,
+            &keys[0],
+            own,
+            new_dks[0].clone(),
+            &[],
+        )
+        .unwrap();
+        let from_partial = RefreshMessage::collect_with_own_message(
+            without_echo,
+            &keys[0],
+            own,
+            new_dks[0].clone(),
+            &[],
+        )
+        .unwrap();
+        assert_eq!(from_shuffled.keys_linear.x_i, from_partial.keys_linear.x_i);
+        assert_eq!(
+            from_shuffled.keys_linear.x_i,
+            from_duplicated.keys_linear.x_i
+        );
+        assert_eq!(from_shuffled.pk_vec, from_partial.pk_vec);
+
+        let mut refreshed = vec![from_partial];
+        for (k, new_dk) in new_dks.into_iter().enumerate().skip(1) {
+            let key = RefreshMessage::collect_with_own_message(
+                &broadcast_vec,
+                &keys[k],
+                Some(&broadcast_vec[k]),
+                new_dk,
+                &[],
+            )
+            .unwrap();
+            refreshed.push(key);
+        }
+        for key in refreshed.iter() {
+            verify_local_key(key).unwrap();
+            assert_eq!(key.pk_vec, refreshed[0].pk_vec);
+        }
+        let offline_sign = simulate_offline_stage(refreshed, &[1, 3]);
+        simulate_signing(offline_sign, b"ZenGo");
+    }
+
+    #[test]
+    fn test_collect_signed_rejects_spoofed_sender() {
+        type Message = RefreshMessage<Secp256k1, Sha256, { crate::M_SECURITY }>;
+        let (t, n) = (1, 3);
+        let keys = simulate_keygen(t, n);
+        let (signed_vec, new_dks): (Vec<_>, Vec<_>) = keys
+            .iter()
+            .map(|key| Message::distribute_signed(key, n).unwrap())
+            .unzip();
+
+        // party 3 can not sign a message as party 2
+        let (message, _) = Message::distribute(2, &keys[1], n).unwrap();
+        assert!(matches!(
+            SignedRefreshMessage::sign(message.clone(), &keys[2]),
+            Err(FsDkrError::InvalidRefreshMessage { party_index: 3, .. })
+        ));
+        // and a share of another key does not sign for party 2
+        let other_keys = simulate_keygen(t, n);
+        let spoofed = SignedRefreshMessage::sign(message, &other_keys[1]).unwrap();
+        assert!(matches!(
+            spoofed.verify(&keys[0].pk_vec),
+            Err(FsDkrError::SenderSignatureInvalid { party_index: 2 })
+        ));
+        let mut with_spoofed = signed_vec.clone();
+        with_spoofed[1] = spoofed;
+        assert!(matches!(
+            Message::collect_signed(&with_spoofed, &keys[0], new_dks[0].clone(), &[]),
+            Err(FsDkrError::SenderSignatureInvalid { party_index: 2 })
+        ));
+
+        let new_keys: Vec<_> = keys
+            .iter()
+            .zip(new_dks)
+            .map(|(key, new_dk)| Message::collect_signed(&signed_vec, key, new_dk, &[]).unwrap())
+            .collect();
+        for key in new_keys.iter() {
+            verify_local_key(key).unwrap();
+        }
+        let offline_sign = simulate_offline_stage(new_keys, &[1, 2]);
+        simulate_signing(offline_sign, b"ZenGo");
+    }
+
+    #[test]
+    fn test_collect_in_session_rejects_other_sessions() {
+        let (t, n) = (1, 3);
+        let mut keys = simulate_keygen(t, n);
+        let finalized_for = |keys: &[LocalKey<Secp256k1>], session_id: &BigInt| {
+            keys.iter()
+                .map(|key| {
+                    let prepared =
+                        RefreshMessage::<Secp256k1, Sha256, { crate::M_SECURITY }>::prepare(
+                            key.i,
+                            key,
+                            n,
+                            &RefreshParams::default(),
+                        )
+                        .unwrap();
+                    RefreshMessage::finalize(prepared, session_id).unwrap()
+                })
+                .unzip::<_, _, Vec<_>, Vec<_>>()
+        };
+
+        let first_session = BigInt::from(1);
+        let (first_messages, first_dks) = finalized_for(&keys, &first_session);
+        assert!(matches!(
+            RefreshMessage::collect_in_session(
+                &first_messages,
+                &keys[0],
+                &BigInt::from(2),
+                first_dks[0].clone(),
+                &[]
+            ),
+            Err(FsDkrError::SessionBindingInvalid)
+        ));
+        keys = keys
+            .iter()
+            .zip(first_dks)
+            .map(|(key, new_dk)| {
+                RefreshMessage::collect_in_session(
+                    &first_messages,
+                    key,
+                    &first_session,
+                    new_dk,
+                    &[],
+                )
+                .unwrap()
+            })
+            .collect();
+
+        // a message of the first session replayed into the second one
+        let second_session = BigInt::from(2);
+        let (mut second_messages, second_dks) = finalized_for(&keys, &second_session);
+        let honest = std::mem::replace(&mut second_messages[1], first_messages[1].clone());
+        assert!(RefreshMessage::collect_in_session(
+            &second_messages,
+            &keys[0],
+            &second_session,
+            second_dks[0].clone(),
+            &[]
+        )
+        .is_err());
+
+        // and re-bound to the second session, from the first session or from another session of
+        // the same key
+        let (other_messages, _) = finalized_for(&keys, &BigInt::from(3));
+        for replayed in [&first_messages[1], &other_messages[1]] {
+            second_messages[1] = test_utils::rebind_to_session(replayed, &second_session);
+            assert!(matches!(
+                RefreshMessage::collect_in_session(
+                    &second_messages,
+                    &keys[0],
+                    &second_session,
+                    second_dks[0].clone(),
+                    &[]
+                ),
+                Err(FsDkrError::SessionBindingInvalid)
+            ));
+        }
+        second_messages[1] = honest;
+        RefreshMessage::collect_in_session(
+            &second_messages,
+            &keys[0],
+            &second_session,
+            second_dks[0].clone(),
+            &[],
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_errors_name_the_misbehaving_party() {
+        let (t, n) = (1, 4);
+        let keys = simulate_keygen(t, n);
+        let (mut broadcast_vec, new_dks): (
+            Vec<RefreshMessage<Secp256k1, Sha256, { crate::M_SECURITY }>>,
+            Vec<DecryptionKey>,
+        ) = keys
+            .iter()
+            .map(|key| RefreshMessage::distribute(key.i, key, n).unwrap())
+            .unzip();
+
+        // party 3 sends party 2 a ciphertext that does not match its commitment
+        let mut parts = broadcast_vec[2].clone().into_parts();
+        parts.points_encrypted_vec[1] = parts.points_encrypted_vec[0].clone();
+        broadcast_vec[2] = RefreshMessage::new(parts).unwrap();
+
+        for (key, new_dk) in keys.iter().zip(new_dks) {
+            assert!(matches!(
+                RefreshMessage::collect(&broadcast_vec, key, new_dk, &[]),
+                Err(FsDkrError::PDLwSlackProofInvalid {
+                    party_index: 3,
+                    receiver: 2
+                })
+            ));
+        }
+    }
+
+    #[test]
+    fn test_share_mismatch_names_the_sender() {
+        let (t, n) = (1, 3);
+        let keys = simulate_keygen(t, n);
+        let broadcast_vec: Vec<RefreshMessage<Secp256k1, Sha256, { crate::M_SECURITY }>> = keys
+            .iter()
+            .map(|key| RefreshMessage::distribute(key.i, key, n).unwrap().0)
+            .collect();
+        let honest: Vec<_> = broadcast_vec.iter().collect();
+        assert!(RefreshMessage::blame_sub_share(&honest, &keys[0]).is_none());
+
+        // party 2 gives party 1 the sub-share of party 3
+        let swapped = test_utils::swap_ciphertexts(&broadcast_vec[1], 1, 3);
+        let messages = [&broadcast_vec[0], &swapped, &broadcast_vec[2]];
+        assert!(matches!(
+            RefreshMessage::blame_sub_share(&messages, &keys[0]),
+            Some(FsDkrError::SubShareMismatch {
+                party_index: 2,
+                receiver: 1
+            })
+        ));
+        // party 2, which still gets the right sub-share, has nobody to blame
+        assert!(RefreshMessage::blame_sub_share(&messages, &keys[1]).is_none());
+    }
+
+    #[test]
+    fn test_collect_rejects_adversarial_messages() {
+        let (t, n) = (1, 3);
+        let keys = simulate_keygen(t, n);
+        let (broadcast_vec, new_dks): (
+            Vec<RefreshMessage<Secp256k1, Sha256, { crate::M_SECURITY }>>,
+            Vec<DecryptionKey>,
+        ) = keys
+            .iter()
+            .map(|key| RefreshMessage::distribute(key.i, key, n).unwrap())
+            .unzip();
+        let collect_with = |forged: RefreshMessage<Secp256k1, Sha256, { crate::M_SECURITY }>| {
+            let mut received = broadcast_vec.clone();
+            received[1] = forged;
+            RefreshMessage::collect(&received, &keys[0], new_dks[0].clone(), &[])
+        };
+        let honest = &broadcast_vec[1];
+
+        assert!(matches!(
+            collect_with(test_utils::corrupt_ciphertext(honest, 3)),
+            Err(FsDkrError::PDLwSlackProofInvalid {
+                party_index: 2,
+                receiver: 3
+            })
+        ));
+        assert!(matches!(
+            collect_with(test_utils::swap_ciphertexts(honest, 1, 2)),
+            Err(FsDkrError::PDLwSlackProofInvalid {
+                party_index: 2,
+                receiver: 1
+            })
+        ));
+        assert!(matches!(
+            collect_with(test_utils::range_proof_for_wrong_ciphertext(honest, 2, 1)),
+            Err(FsDkrError::RangeProof {
+                party_index: 2,
+                receiver: 2
+            })
+        ));
+        assert!(matches!(
+            collect_with(test_utils::ring_pedersen_proof_of_another_message(
+                honest,
+                &broadcast_vec[2]
+            )),
+            Err(FsDkrError::RingPedersenProofValidation { party_index: 2 })
+        ));
+        assert!(matches!(
+            collect_with(test_utils::replace_paillier_key(
+                honest,
+                &broadcast_vec[2].ek
+            )),
+            Err(FsDkrError::PaillierVerificationError { party_index: 2 })
+        ));
+        assert!(matches!(
+            collect_with(test_utils::drop_last_receiver(honest)),
+            Err(FsDkrError::InvalidRefreshMessage { party_index: 2, .. })
+        ));
+        assert!(matches!(
+            RefreshMessage::new(test_utils::parts_with_short_vectors(honest)),
+            Err(FsDkrError::InvalidRefreshMessage { party_index: 2, .. })
+        ));
+
+        // the honest messages still collect
+        let refreshed =
+            RefreshMessage::collect(&broadcast_vec, &keys[0], new_dks[0].clone(), &[]).unwrap();
+        verify_local_key(&refreshed).unwrap();
+    }
+
+    #[test]
+    fn test_prefilter_messages_as_they_arrive() {
+        let (t, n) = (2, 4);
+        let mut keys = simulate_keygen(t, n);
+        let public_key = keys[0].y_sum_s.clone();
+        let (mut broadcast_vec, new_dks): (
+            Vec<RefreshMessage<Secp256k1, Sha256, { crate::M_SECURITY }>>,
+            Vec<DecryptionKey>,
+        ) = keys
+            .iter()
+            .map(|key| RefreshMessage::distribute(key.i, key, n).unwrap())
+            .unzip();
+        let mut parts = broadcast_vec[1].clone().into_parts();
+        parts.points_encrypted_vec[0] = parts.points_encrypted_vec[2].clone();
+        broadcast_vec[1] = RefreshMessage::new(parts).unwrap();
+
+        // a coordinator holding no share checks each message with public data only, and drops
+        // party 2's before the quorum is reached
+        let context = RefreshContext::new(&keys[0], n);
+        let mut accepted = Vec::new();
+        for msg in broadcast_vec.iter() {
+            if msg.verify(&context).is_ok() {
+                accepted.push(msg.clone());
+            }
+            if accepted.len() > t as usize {
+                break;
+            }
+        }
+        let senders: Vec<u16> = accepted.iter().map(|msg| msg.party_index).collect();
+        assert_eq!(senders, vec![1, 3, 4]);
+
+        for (key, new_dk) in keys.iter_mut().zip(new_dks) {
+            *key = RefreshMessage::collect(&accepted, key, new_dk, &[]).unwrap();
+        }
+        for key in keys.iter() {
+            verify_local_key(key).unwrap();
+            assert_eq!(key.y_sum_s, public_key);
+        }
+        let offline_sign = simulate_offline_stage(keys, &[1, 2, 4]);
+        simulate_signing(offline_sign, b"ZenGo");
+    }
+
+    #[test]
+    fn test_observer_verifies_refresh() {
+        let (t, n) = (1, 3);
+        let mut keys = simulate_keygen(t, n);
+        // the watchtower only ever sees the public data, e.g. serialized
+        let committee: CommitteePublicData<Secp256k1> = serde_json::from_str(
+            &serde_json::to_string(&CommitteePublicData::from_local_key(&keys[0])).unwrap(),
+        )
+        .unwrap();
+        let (broadcast_vec, new_dks): (
+            Vec<RefreshMessage<Secp256k1, Sha256, { crate::M_SECURITY }>>,
+            Vec<DecryptionKey>,
+        ) = keys
+            .iter()
+            .map(|key| RefreshMessage::distribute(key.i, key, n).unwrap())
+            .unzip();
+
+        RefreshMessage::verify_refresh(&broadcast_vec, &committee).unwrap();
+        assert!(matches!(
+            RefreshMessage::verify_refresh(&broadcast_vec[..1], &committee),
+            Err(FsDkrError::PartiesThresholdViolation { .. })
+        ));
+        let mut tampered = broadcast_vec.clone();
+        let mut parts = tampered[0].clone().into_parts();
+        parts.points_encrypted_vec[2] = parts.points_encrypted_vec[1].clone();
+        tampered[0] = RefreshMessage::new(parts).unwrap();
+        assert!(matches!(
+            RefreshMessage::verify_refresh(&tampered, &committee),
+            Err(FsDkrError::PDLwSlackProofInvalid {
+                party_index: 1,
+                receiver: 3
+            })
+        ));
+
+        // the parties accept the set the observer verified
+        for (key, new_dk) in keys.iter_mut().zip(new_dks) {
+            *key = RefreshMessage::collect(&broadcast_vec, key, new_dk, &[]).unwrap();
+            assert_eq!(&key.y_sum_s, committee.public_key());
+        }
+    }
+
+    #[test]
+    fn test_verify_once_finalize_for_every_party() {
+        let (t, n) = (2, 5);
+        let keys = simulate_keygen(t, n);
+        let (broadcast_vec, new_dks): (
+            Vec<RefreshMessage<Secp256k1, Sha256, { crate::M_SECURITY }>>,
+            Vec<DecryptionKey>,
+        ) = keys
+            .iter()
+            .map(|key| RefreshMessage::distribute(key.i, key, n).unwrap())
+            .unzip();
+        let committee = CommitteePublicData::from_local_key(&keys[0]);
+        let verified = RefreshMessage::verify_public(&broadcast_vec, &committee).unwrap();
+
+        // a key of another committee can not use the token
+        let other_keys = simulate_keygen(t, n);
+        assert!(matches!(
+            verified
+                .clone()
+                .finalize(&other_keys[0], new_dks[0].clone()),
+            Err(FsDkrError::RefreshContextMismatch)
+        ));
+
+        let refreshed: Vec<_> = keys
+            .iter()
+            .zip(new_dks.clone())
+            .map(|(key, new_dk)| verified.clone().finalize(key, new_dk).unwrap())
+            .collect();
+        let collected =
+            RefreshMessage::collect(&broadcast_vec, &keys[2], new_dks[2].clone(), &[]).unwrap();
+        assert_eq!(refreshed[2].keys_linear.x_i, collected.keys_linear.x_i);
+        for key in refreshed.iter() {
+            verify_local_key(key).unwrap();
+            assert_eq!(key.pk_vec, verified.pk_vec());
+            assert_eq!(&key.y_sum_s, verified.public_key());
+        }
+        let offline_sign = simulate_offline_stage(refreshed, &[1, 3, 5]);
+        simulate_signing(offline_sign, b"ZenGo");
+    }
+
+    #[test]
+    fn test_refresh_summary_matches_new_key() {
+        let (t, n) = (1, 3);
+        let keys = simulate_keygen(t, n);
+        let session_id = BigInt::from(2024);
+        let (broadcast_vec, new_dks): (
+            Vec<RefreshMessage<Secp256k1, Sha256, { crate::M_SECURITY }>>,
+            Vec<DecryptionKey>,
+        ) = keys
+            .iter()
+            .map(|key| {
+                let prepared =
+                    RefreshMessage::prepare(key.i, key, n, &RefreshParams::default()).unwrap();
+                RefreshMessage::finalize(prepared, &session_id).unwrap()
+            })
+            .unzip();
+
+        let (new_key, summary) = RefreshMessage::collect_with_summary(
+            &broadcast_vec,
+            &keys[1],
+            Some(&session_id),
+            new_dks[1].clone(),
+            &[],
+        )
+        .unwrap();
+        assert_eq!(summary.pk_vec(), &new_key.pk_vec[..]);
+        assert_eq!(summary.public_key(), &new_key.y_sum_s);
+        assert_eq!(summary.party_index(), 2);
+        assert_eq!(summary.session_id(), Some(&session_id));
+        assert_eq!(summary.senders(), &[1, 2, 3]);
+        for (msg, (sender, fingerprint)) in broadcast_vec.iter().zip(summary.fingerprints()) {
+            assert_eq!(msg.party_index(), *sender);
+            assert_eq!(&msg.fingerprint(), fingerprint);
+        }
+
+        // the summary can be persisted as is
+        let json = serde_json::to_string(&summary).unwrap();
+        let decoded: RefreshSummary<Secp256k1> = serde_json::from_str(&json).unwrap();
+        assert_eq!(decoded, summary);
+    }
+
+    #[test]
+    fn test_complaint_verified_by_outsider() {
+        let (t, n) = (1, 3);
+        let keys = simulate_keygen(t, n);
+        let (mut broadcast_vec, new_dks): (
+            Vec<RefreshMessage<Secp256k1, Sha256, { crate::M_SECURITY }>>,
+            Vec<DecryptionKey>,
+        ) = keys
+            .iter()
+            .map(|key| RefreshMessage::distribute(key.i, key, n).unwrap())
+            .unzip();
+        let honest = broadcast_vec[2].clone();
+        broadcast_vec[2] = test_utils::corrupt_ciphertext(&broadcast_vec[2], 2);
+
+        let (error, complaints) = RefreshMessage::collect_with_complaints(
+            &broadcast_vec,
+            &keys[0],
+            None,
+            new_dks[0].clone(),
+            &[],
+        )
+        .unwrap_err();
+        assert!(matches!(
+            error,
+            FsDkrError::PDLwSlackProofInvalid {
+                party_index: 3,
+                receiver: 2
+            }
+        ));
+        assert_eq!(complaints.len(), 1);
+        assert_eq!(complaints[0].accused(), 3);
+
+        // an outsider only gets the complaint and the public data of the committee
+        let json = serde_json::to_string(&complaints[0]).unwrap();
+        let complaint: Complaint<Secp256k1, Sha256, { crate::M_SECURITY }> =
+            serde_json::from_str(&json).unwrap();
+        let committee = CommitteePublicData::from_local_key(&keys[1]);
+        assert!(complaint.verify(&committee));
+        assert_eq!(
+            complaint.message().fingerprint(),
+            broadcast_vec[2].fingerprint()
+        );
+
+        // the honest message does not back the accusation
+        let mut forged: serde_json::Value = serde_json::to_value(&complaint).unwrap();
+        forged["message"] = serde_json::to_value(&honest).unwrap();
+        let forged: Complaint<Secp256k1, Sha256, { crate::M_SECURITY }> =
+            serde_json::from_value(forged).unwrap();
+        assert!(!forged.verify(&committee));
+        let other_keys = simulate_keygen(t, n);
+        assert!(!complaint.verify(&CommitteePublicData::from_local_key(&other_keys[0])));
+
+        broadcast_vec[2] = honest;
+        RefreshMessage::collect_with_complaints(
+            &broadcast_vec,
+            &keys[0],
+            None,
+            new_dks[0].clone(),
+            &[],
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_refresh_acks() {
+        let (t, n) = (1, 3);
+        let old_keys = simulate_keygen(t, n);
+        let committee = CommitteePublicData::from_local_key(&old_keys[0]);
+        let (broadcast_vec, new_dks): (
+            Vec<RefreshMessage<Secp256k1, Sha256, { crate::M_SECURITY }>>,
+            Vec<DecryptionKey>,
+        ) = old_keys
+            .iter()
+            .map(|key| RefreshMessage::distribute(key.i, key, n).unwrap())
+            .unzip();
+        let new_keys: Vec<_> = old_keys
+            .iter()
+            .zip(new_dks)
+            .map(|(key, new_dk)| RefreshMessage::collect(&broadcast_vec, key, new_dk, &[]).unwrap())
+            .collect();
+
+        let acks: Vec<RefreshAck<Secp256k1, Sha256>> =
+            new_keys.iter().map(RefreshAck::new).collect();
+        verify_acks(&acks, &broadcast_vec, &committee).unwrap();
+
+        assert!(matches!(
+            verify_acks(&acks[..2], &broadcast_vec, &committee),
+            Err(FsDkrError::RefreshAckMissing { party_index: 3 })
+        ));
+        // party 2 acknowledging the share it held before the refresh, or any other share
+        for wrong_share in [
+            old_keys[1].keys_linear.x_i.clone(),
+            Scalar::<Secp256k1>::random(),
+        ]
+        .iter()
+        {
+            let mut wrong_acks = acks.clone();
+            wrong_acks[1] = RefreshAck {
+                party_index: 2,
+                proof: DLogProof::prove(wrong_share),
+            };
+            assert!(matches!(
+                verify_acks(&wrong_acks, &broadcast_vec, &committee),
+                Err(FsDkrError::RefreshAckMismatch { party_index: 2 })
+            ));
+        }
+    }
+
+    #[test]
+    fn test_collect_rejects_resharing_another_secret() {
+        let (t, n) = (1, 3);
+        let keys = simulate_keygen(t, n);
+        let (mut broadcast_vec, new_dks): (
+            Vec<RefreshMessage<Secp256k1, Sha256, { crate::M_SECURITY }>>,
+            Vec<DecryptionKey>,
+        ) = keys
+            .iter()
+            .map(|key| RefreshMessage::distribute(key.i, key, n).unwrap())
+            .unzip();
+
+        // party 2 reshares a secret of its choosing, with proofs that are otherwise valid
+        let mut rogue_key = keys[1].clone();
+        rogue_key.keys_linear.x_i = Scalar::random();
+        let (rogue_message, _) = RefreshMessage::distribute(2, &rogue_key, n).unwrap();
+        broadcast_vec[1] = rogue_message;
+
+        for (key, new_dk) in keys.iter().zip(new_dks) {
+            assert!(matches!(
+                RefreshMessage::collect(&broadcast_vec, key, new_dk, &[]),
+                Err(FsDkrError::ConstantTermMismatch { party_index: 2 })
+            ));
+        }
+    }
+
+    #[test]
+    fn test_weighted_resharing_of_another_secret_rejected() {
+        let (t, n) = (1, 3);
+        let keys = simulate_keygen(t, n);
+        let quorum = vec![1, 2];
+        let distribute = |key: &LocalKey<Secp256k1>| {
+            RefreshMessage::<Secp256k1, Sha256, { crate::M_SECURITY }>::distribute_with_basis(
+                key.i,
+                key,
+                n,
+                SharingBasis::LagrangeWeighted {
+                    quorum: quorum.clone(),
+                },
+            )
+            .unwrap()
+        };
+        let (honest, _) = distribute(&keys[0]);
+
+        // party 2 weights a secret of its choosing instead of its share
+        let mut rogue_key = keys[1].clone();
+        rogue_key.keys_linear.x_i = Scalar::random();
+        let (rogue, _) = distribute(&rogue_key);
+        let broadcast_vec = vec![honest, rogue];
+        for key in keys.iter() {
+            assert!(matches!(
+                RefreshMessage::collect(&broadcast_vec, key, key.paillier_dk.clone(), &[]),
+                Err(FsDkrError::ConstantTermMismatch { party_index: 2 })
+            ));
+        }
+    }
+
+    #[test]
+    fn test_collector_requires_larger_moduli() {
+        use curv::arithmetic::BitManipulation;
+
+        let (t, n) = (1, 3);
+        let keys = simulate_keygen(t, n);
+        let too_small = RefreshParams {
+            paillier_modulus_bits: 1024,
+            ..RefreshParams::default()
+        };
+        assert!(matches!(
+            RefreshMessage::<Secp256k1, Sha256, { crate::M_SECURITY }>::distribute_with_params(
+                keys[0].i, &keys[0], n, &too_small
+            ),
+            Err(FsDkrError::InvalidRefreshMessage { .. })
+        ));
+
+        // party 1 rotates to a 3072-bit modulus, the others keep the default size
+        let (broadcast_vec, new_dks): (
+            Vec<RefreshMessage<Secp256k1, Sha256, { crate::M_SECURITY }>>,
+            Vec<DecryptionKey>,
+        ) = keys
+            .iter()
+            .map(|key| {
+                let params = RefreshParams {
+                    paillier_modulus_bits: if key.i == 1 {
+                        3072
+                    } else {
+                        crate::PAILLIER_KEY_SIZE
+                    },
+                    ..RefreshParams::default()
+                };
+                RefreshMessage::distribute_with_params(key.i, key, n, &params).unwrap()
+            })
+            .unzip();
+        assert!(broadcast_vec[0].ek.n.bit_length() >= 3071);
+
+        // a collector requiring 3072 bits rejects the other two
+        let strict = CollectOptions::tolerate_failures(1).with_min_modulus_bits(3072);
+        match RefreshMessage::collect_with_options(
+            &broadcast_vec,
+            &keys[1],
+            new_dks[1].clone(),
+            &[],
+            &strict,
+        ) {
+            Err(FsDkrError::TooManyFailedSenders { failures, .. }) => {
+                let senders: Vec<u16> = failures.iter().map(|(sender, _)| *sender).collect();
+                assert_eq!(senders, vec![2, 3]);
+                for (_, error) in failures.iter() {
+                    assert!(matches!(
+                        error,
+                        FsDkrError::ModuliTooSmall {
+                            required_bits: 3072,
+                            ..
+                        }
+                    ));
+                }
+            }
+            other => panic!("expected too small moduli, got {:?}", other.map(|_| ())),
+        }
+
+        // the default policy accepts the mix
+        for (key, new_dk) in keys.iter().zip(new_dks) {
+            let new_key = RefreshMessage::collect(&broadcast_vec, key, new_dk, &[]).unwrap();
+            verify_local_key(&new_key).unwrap();
+        }
+    }
+
+    #[cfg(feature = "tracing")]
+    #[test]
+    fn test_tracing_spans_leak_no_share() {
+        use std::sync::{Arc, Mutex};
+        use tracing::field::{Field, Visit};
+        use tracing::span::{Attributes, Id, Record};
+        use tracing::{Event, Metadata, Subscriber};
+
+        #[derive(Default)]
+        struct Recorded {
+            spans: Vec<String>,
+            values: Vec<String>,
+        }
+
+        struct Values<'a>(&'a mut Vec<String>);
+
+        impl Visit for Values<'_> {
+            fn record_debug(&mut self, _field: &Field, value: &dyn std::fmt::Debug) {
+                self.0.push(format!("{:?}", value));
+            }
+        }
+
+        struct Recorder(Arc<Mutex<Recorded>>);
+
+        impl Subscriber for Recorder {
+            fn enabled(&self, _metadata: &Metadata<'_>) -> bool {
+                true
+            }
+
+            fn new_span(&self, span: &Attributes<'_>) -> Id {
+                let mut recorded = self.0.lock().unwrap();
+                recorded.spans.push(span.metadata().name().to_string());
+                span.record(&mut Values(&mut recorded.values));
+                Id::from_u64(recorded.spans.len() as u64)
+            }
+
+            fn record(&self, _span: &Id, values: &Record<'_>) {
+                values.record(&mut Values(&mut self.0.lock().unwrap().values));
+            }
+
+            fn record_follows_from(&self, _span: &Id, _follows: &Id) {}
+
+            fn event(&self, event: &Event<'_>) {
+                event.record(&mut Values(&mut self.0.lock().unwrap().values));
+            }
+
+            fn enter(&self, _span: &Id) {}
+
+            fn exit(&self, _span: &Id) {}
+        }
+
+        let (t, n) = (1, 3);
+        let keys = simulate_keygen(t, n);
+        let recorded = Arc::new(Mutex::new(Recorded::default()));
+        let new_keys = tracing::subscriber::with_default(Recorder(recorded.clone()), || {
+            run_refresh::<_, Sha256, { crate::M_SECURITY }>(&keys).unwrap()
+        });
+
+        let recorded = recorded.lock().unwrap();
+        // with the parallel feature, the spans of each receiver may be on the rayon threads,
+        // out of reach of a thread-local subscriber
+        if cfg!(not(feature = "parallel")) {
+            for name in ["encrypt", "prove_pdl", "prove_range"].iter() {
+                assert!(recorded.spans.iter().any(|span| span == name), "{}", name);
+            }
+        }
+        for name in [
+            "distribute",
+            "share_polynomial",
+            "paillier_keygen",
+            "prove_ring_pedersen",
+            "collect",
+            "verify_message",
+            "verify_ciphertext",
+            "verify_vss",
+            "decrypt",
+        ]
+        .iter()
+        {
+            assert!(recorded.spans.iter().any(|span| span == name), "{}", name);
+        }
+        let secrets: Vec<String> = keys
+            .iter()
+            .chain(new_keys.iter())
+            .map(|key| key.keys_linear.x_i.to_bigint().to_string())
+            .collect();
+        for value in recorded.values.iter() {
+            assert!(value.len() < 16, "{}", value);
+            assert!(!secrets.contains(value));
+        }
+    }
+
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn test_parallel_and_sequential_encryption_agree() {
+        use crate::zk_pdl_with_slack::PDLwSlackStatement;
+        use paillier::{Decrypt, Paillier, RawCiphertext};
 
-        let old_keys = keys.clone();
-        simulate_dkr::<{ crate::M_SECURITY }>(&mut keys);
+        type Msg = RefreshMessage<Secp256k1, Sha256, { crate::M_SECURITY }>;
+        let (t, n) = (2, 5);
+        let keys = simulate_keygen(t, n);
+        let shares: Vec<Scalar<Secp256k1>> = (0..n).map(|_| Scalar::random()).collect();
+        let points: Vec<_> = shares
+            .iter()
+            .map(|share| Point::generator() * share)
+            .collect();
 
-        // check that sum of old keys is equal to sum of new keys
-        let old_linear_secret_key: Vec<_> = (0..old_keys.len())
-            .map(|i| old_keys[i].keys_linear.x_i.clone())
+        let parallel = Msg::encrypt_sub_shares(&keys[0], &shares, &points);
+        let sequential: Vec<_> = (0..n as usize)
+            .map(|i| Msg::encrypt_sub_share(&keys[0], i, &shares[i], &points[i]))
             .collect();
+        assert_eq!(parallel.0.len(), sequential.len());
+        for (i, (ciphertext, _, _)) in sequential.iter().enumerate() {
+            let encrypted = [&parallel.0[i], ciphertext];
+            let proofs = [
+                (&parallel.1[i], &parallel.2[i]),
+                (&sequential[i].1, &sequential[i].2),
+            ];
+            for (ciphertext, (pdl_proof, range_proof)) in encrypted.iter().zip(proofs.iter()) {
+                // both paths encrypt the sub-share of receiver i to receiver i
+                let plaintext = Paillier::decrypt(
+                    &keys[i].paillier_dk,
+                    RawCiphertext::from((*ciphertext).clone()),
+                )
+                .0
+                .into_owned();
+                assert_eq!(plaintext, shares[i].to_bigint());
 
-        let new_linear_secret_key: Vec<_> = (0..keys.len())
-            .map(|i| keys[i].keys_linear.x_i.clone())
+                let dlog_statement = &keys[0].h1_h2_n_tilde_vec[i];
+                let statement = PDLwSlackStatement {
+                    ciphertext: (*ciphertext).clone(),
+                    ek: keys[0].paillier_key_vec[i].clone(),
+                    Q: points[i].clone(),
+                    G: Point::generator().to_point(),
+                    h1: dlog_statement.g.clone(),
+                    h2: dlog_statement.ni.clone(),
+                    N_tilde: dlog_statement.N.clone(),
+                };
+                pdl_proof.verify(&statement).unwrap();
+                assert!(range_proof.verify(ciphertext, &statement.ek, dlog_statement));
+            }
+        }
+
+        // and a whole refresh distributed in parallel verifies
+        let new_keys = run_refresh::<_, Sha256, { crate::M_SECURITY }>(&keys).unwrap();
+        let offline_sign = simulate_offline_stage(new_keys, &[1, 2, 3]);
+        simulate_signing(offline_sign, b"ZenGo");
+    }
+
+    #[test]
+    fn test_refresh_metadata() {
+        let (t, n) = (1, 3);
+        let mut keys: Vec<RefreshableLocalKey<Secp256k1>> = simulate_keygen(t, n)
+            .into_iter()
+            .map(RefreshableLocalKey::new)
             .collect();
-        let indices: Vec<_> = (0..(t + 1) as u16).collect();
-        let vss = VerifiableSS::<Secp256k1> {
-            parameters: ShamirSecretSharing {
-                threshold: t,
-                share_count: n,
-            },
-            commitments: Vec::new(),
+        let session_id = BigInt::from(1);
+
+        let too_long = RefreshParams {
+            metadata: vec![0u8; crate::refresh_message::MAX_METADATA_LEN + 1],
+            ..RefreshParams::default()
         };
-        assert_eq!(
-            vss.reconstruct(&indices[..], &old_linear_secret_key[0..(t + 1) as usize]),
-            vss.reconstruct(&indices[..], &new_linear_secret_key[0..(t + 1) as usize])
+        assert!(matches!(
+            keys[0].distribute_with_params::<Sha256, { crate::M_SECURITY }>(
+                &session_id,
+                n,
+                &too_long
+            ),
+            Err(FsDkrError::InvalidRefreshMessage { .. })
+        ));
+
+        let (broadcast_vec, new_dks): (
+            Vec<RefreshMessage<Secp256k1, Sha256, { crate::M_SECURITY }>>,
+            Vec<DecryptionKey>,
+        ) = keys
+            .iter()
+            .map(|key| {
+                let params = RefreshParams {
+                    metadata: format!("CHG-{}", key.inner().i).into_bytes(),
+                    ..RefreshParams::default()
+                };
+                let prepared =
+                    RefreshMessage::prepare(key.inner().i, key.inner(), n, &params).unwrap();
+                RefreshMessage::finalize(prepared, &session_id).unwrap()
+            })
+            .unzip();
+
+        // tampering with the metadata breaks the session binding and changes the transcript
+        let mut json = serde_json::to_value(&broadcast_vec[0]).unwrap();
+        json["metadata"] = serde_json::to_value(b"CHG-9".to_vec()).unwrap();
+        let tampered: RefreshMessage<Secp256k1, Sha256, { crate::M_SECURITY }> =
+            serde_json::from_value(json).unwrap();
+        assert_eq!(tampered.metadata(), b"CHG-9");
+        let context = RefreshContext::new(keys[0].inner(), n).with_session_id(session_id.clone());
+        assert!(matches!(
+            tampered.verify(&context),
+            Err(FsDkrError::SessionBindingInvalid)
+        ));
+        let mut tampered_vec = broadcast_vec.clone();
+        tampered_vec[0] = tampered;
+        assert_ne!(
+            RefreshMessage::transcript_digest(&tampered_vec),
+            RefreshMessage::transcript_digest(&broadcast_vec)
         );
-        assert_ne!(old_linear_secret_key, new_linear_secret_key);
+        assert!(RefreshMessage::verify_all(&broadcast_vec, &context).is_ok());
+
+        for (key, new_dk) in keys.iter_mut().zip(new_dks) {
+            let outcome = key
+                .collect(&session_id, &broadcast_vec, new_dk, &[])
+                .unwrap();
+            assert_eq!(
+                outcome.metadata,
+                vec![
+                    (1, b"CHG-1".to_vec()),
+                    (2, b"CHG-2".to_vec()),
+                    (3, b"CHG-3".to_vec())
+                ]
+            );
+        }
     }
 
     #[test]
-    fn test_sign_rotate_sign() {
-        let mut keys = simulate_keygen(2, 5);
-        let offline_sign = simulate_offline_stage(keys.clone(), &[1, 2, 3]);
+    fn test_verified_messages_bound_to_context() {
+        let (t, n) = (1, 3);
+        let mut keys = simulate_keygen(t, n);
+        let session_id = BigInt::from(7);
+        let (broadcast_vec, new_dks): (
+            Vec<RefreshMessage<Secp256k1, Sha256, { crate::M_SECURITY }>>,
+            Vec<DecryptionKey>,
+        ) = keys
+            .iter()
+            .map(|key| {
+                let prepared =
+                    RefreshMessage::prepare(key.i, key, n, &RefreshParams::default()).unwrap();
+                RefreshMessage::finalize(prepared, &session_id).unwrap()
+            })
+            .unzip();
+
+        let context = RefreshContext::new(&keys[0], n).with_session_id(session_id.clone());
+        let verified = RefreshMessage::verify_all(&broadcast_vec, &context).unwrap();
+
+        // applying the messages in another session, or outside of any session, is caught
+        for other_session in [Some(BigInt::from(8)), None].iter() {
+            let mut key = keys[0].clone();
+            assert!(matches!(
+                RefreshMessage::apply_refresh(
+                    &verified,
+                    &mut key,
+                    new_dks[0].clone(),
+                    &[],
+                    other_session.as_ref()
+                ),
+                Err(FsDkrError::RefreshContextMismatch)
+            ));
+        }
+
+        // so is applying them to a key of another committee
+        let mut other_key = simulate_keygen(t, n).remove(0);
+        assert!(matches!(
+            RefreshMessage::apply_refresh(
+                &verified,
+                &mut other_key,
+                new_dks[0].clone(),
+                &[],
+                Some(&session_id)
+            ),
+            Err(FsDkrError::RefreshContextMismatch)
+        ));
+
+        for (key, new_dk) in keys.iter_mut().zip(new_dks) {
+            let context = RefreshContext::new(key, n).with_session_id(session_id.clone());
+            let verified = RefreshMessage::verify_all(&broadcast_vec, &context).unwrap();
+            RefreshMessage::apply_refresh(&verified, key, new_dk, &[], Some(&session_id)).unwrap();
+        }
+        for key in keys.iter() {
+            verify_local_key(key).unwrap();
+        }
+        let offline_sign = simulate_offline_stage(keys, &[1, 3]);
         simulate_signing(offline_sign, b"ZenGo");
-        simulate_dkr::<{ crate::M_SECURITY }>(&mut keys);
-        let offline_sign = simulate_offline_stage(keys.clone(), &[2, 3, 4]);
+    }
+
+    #[test]
+    fn test_collect_verify_only_then_apply() {
+        let (t, n) = (1, 3);
+        let mut keys = simulate_keygen(t, n);
+        let old_keys = keys.clone();
+
+        let (broadcast_vec, new_dks): (
+            Vec<RefreshMessage<Secp256k1, Sha256, { crate::M_SECURITY }>>,
+            Vec<DecryptionKey>,
+        ) = keys
+            .iter()
+            .map(|key| RefreshMessage::distribute(key.i, key, n).unwrap())
+            .unzip();
+
+        let verified: Vec<_> = keys
+            .iter()
+            .map(|key| RefreshMessage::collect_verify_only(&broadcast_vec, key, &[]).unwrap())
+            .collect();
+        // verifying leaves the key untouched
+        for (key, old_key) in keys.iter().zip(old_keys.iter()) {
+            assert_eq!(key.keys_linear.x_i, old_key.keys_linear.x_i);
+            assert_eq!(key.paillier_key_vec, old_key.paillier_key_vec);
+            assert_eq!(key.pk_vec, old_key.pk_vec);
+        }
+        assert_eq!(verified[0].public_key(), &keys[0].y_sum_s);
+
+        // a message swapped in after verification is caught when applying
+        let (other, _) = RefreshMessage::<Secp256k1, Sha256, { crate::M_SECURITY }>::distribute(
+            keys[0].i, &keys[0], n,
+        )
+        .unwrap();
+        let mut swapped = broadcast_vec.clone();
+        swapped[0] = other;
+        assert!(matches!(
+            RefreshMessage::collect_verified(
+                &swapped,
+                &keys[1],
+                new_dks[1].clone(),
+                &[],
+                verified[1].clone()
+            ),
+            Err(FsDkrError::VerifiedSetMismatch)
+        ));
+
+        // and so is a token applied to another party's key
+        assert!(matches!(
+            RefreshMessage::collect_verified(
+                &broadcast_vec,
+                &keys[1],
+                new_dks[1].clone(),
+                &[],
+                verified[0].clone()
+            ),
+            Err(FsDkrError::VerifiedSetMismatch)
+        ));
+
+        for ((key, new_dk), verified) in keys.iter_mut().zip(new_dks).zip(verified) {
+            *key = RefreshMessage::collect_verified(&broadcast_vec, key, new_dk, &[], verified)
+                .unwrap();
+        }
+        for key in keys.iter() {
+            verify_local_key(key).unwrap();
+        }
+
+        let offline_sign = simulate_offline_stage(keys, &[1, 2]);
         simulate_signing(offline_sign, b"ZenGo");
-        simulate_dkr::<{ crate::M_SECURITY }>(&mut keys);
-        let offline_sign = simulate_offline_stage(keys, &[1, 3, 5]);
+    }
+
+    #[test]
+    fn test_air_gapped_collect() {
+        // the online host only holds the public part of its key
+        struct OnlineHost {
+            public_key: LocalKey<Secp256k1>,
+        }
+        // the air-gapped machine holds the decryption keys
+        struct OfflineMachine {
+            dk: DecryptionKey,
+            new_dk: DecryptionKey,
+        }
+
+        let (t, n) = (1, 3);
+        let mut keys = simulate_keygen(t, n);
+        let (broadcast_vec, new_dks): (
+            Vec<RefreshMessage<Secp256k1, Sha256, { crate::M_SECURITY }>>,
+            Vec<DecryptionKey>,
+        ) = keys
+            .iter()
+            .map(|key| RefreshMessage::distribute(key.i, key, n).unwrap())
+            .unzip();
+
+        let mut online = OnlineHost {
+            public_key: keys[0].clone(),
+        };
+        let offline = OfflineMachine {
+            dk: keys[0].paillier_dk.clone(),
+            new_dk: new_dks[0].clone(),
+        };
+        online.public_key.keys_linear.x_i = Scalar::<Secp256k1>::zero();
+        online.public_key.paillier_dk = DecryptionKey {
+            p: BigInt::from(0),
+            q: BigInt::from(0),
+        };
+
+        let bundle =
+            RefreshMessage::collect_online(&broadcast_vec, &online.public_key, &[]).unwrap();
+        let recorded_digest = bundle.digest::<Sha256>();
+        let json = serde_json::to_string(&bundle).unwrap();
+
+        // a bundle tampered in transit is refused
+        let mut tampered: OfflineBundle<Secp256k1> = serde_json::from_str(&json).unwrap();
+        tampered.ciphertext_sum = tampered.ciphertext_sum + BigInt::from(1);
+        assert!(matches!(
+            finish_offline::<_, Sha256>(
+                tampered.clone(),
+                &recorded_digest,
+                &offline.dk,
+                offline.new_dk.clone()
+            ),
+            Err(FsDkrError::OfflineBundleTampered)
+        ));
+        // even if the recorded digest was swapped too, the share does not match
+        let swapped_digest = tampered.digest::<Sha256>();
+        assert!(matches!(
+            finish_offline::<_, Sha256>(
+                tampered,
+                &swapped_digest,
+                &offline.dk,
+                offline.new_dk.clone()
+            ),
+            Err(FsDkrError::OfflineShareMismatch)
+        ));
+
+        let carried: OfflineBundle<Secp256k1> = serde_json::from_str(&json).unwrap();
+        let new_key =
+            finish_offline::<_, Sha256>(carried, &recorded_digest, &offline.dk, offline.new_dk)
+                .unwrap();
+        verify_local_key(&new_key).unwrap();
+
+        for (key, new_dk) in keys.iter_mut().zip(new_dks).skip(1) {
+            *key = RefreshMessage::collect(&broadcast_vec, key, new_dk, &[]).unwrap();
+        }
+        keys[0] = new_key;
+        assert_eq!(keys[0].pk_vec, keys[1].pk_vec);
+        let offline_sign = simulate_offline_stage(keys, &[1, 3]);
         simulate_signing(offline_sign, b"ZenGo");
     }
 
+    #[test]
+    fn test_collect_light() {
+        let (t, n) = (1, 3);
+        let mut keys = simulate_keygen(t, n);
+        let (broadcast_vec, new_dks): (
+            Vec<RefreshMessage<Secp256k1, Sha256, { crate::M_SECURITY }>>,
+            Vec<DecryptionKey>,
+        ) = keys
+            .iter()
+            .map(|key| RefreshMessage::distribute(key.i, key, n).unwrap())
+            .unzip();
+        let (light_broadcasts, slices): (Vec<_>, Vec<Vec<_>>) = broadcast_vec
+            .iter()
+            .cloned()
+            .map(RefreshMessage::into_light)
+            .unzip();
+        // the slices each receiver gets over its point-to-point channels
+        let slices_for = |receiver: u16| -> Vec<_> {
+            slices
+                .iter()
+                .map(|sender_slices| sender_slices[receiver as usize - 1].clone())
+                .collect()
+        };
+
+        // a slice swapped between receivers does not match the root
+        let mut swapped = slices_for(2);
+        swapped[0] = slices[0][2].clone();
+        swapped[0].receiver = 2;
+        assert!(matches!(
+            RefreshMessage::collect_light(
+                &light_broadcasts,
+                &swapped,
+                &keys[1],
+                new_dks[1].clone()
+            ),
+            Err(FsDkrError::MerklePathInvalid { party_index: 1 })
+        ));
+
+        // neither does a slice of another distribution than the broadcast one
+        let (other, _) = RefreshMessage::<Secp256k1, Sha256, { crate::M_SECURITY }>::distribute(
+            keys[0].i, &keys[0], n,
+        )
+        .unwrap();
+        let (other_broadcast, _) = other.into_light();
+        assert_ne!(
+            other_broadcast.merkle_root(),
+            light_broadcasts[0].merkle_root()
+        );
+        let mut mismatched = light_broadcasts.clone();
+        mismatched[0] = other_broadcast;
+        assert!(matches!(
+            RefreshMessage::collect_light(
+                &mismatched,
+                &slices_for(2),
+                &keys[1],
+                new_dks[1].clone()
+            ),
+            Err(FsDkrError::MerklePathInvalid { party_index: 1 })
+        ));
+
+        for (key, new_dk) in keys.iter_mut().zip(new_dks) {
+            if key.i == 2 {
+                *key =
+                    RefreshMessage::collect_light(&light_broadcasts, &slices_for(2), key, new_dk)
+                        .unwrap();
+            } else {
+                *key = RefreshMessage::collect(&broadcast_vec, key, new_dk, &[]).unwrap();
+            }
+        }
+        for key in keys.iter() {
+            verify_local_key(key).unwrap();
+            assert_eq!(key.pk_vec, keys[0].pk_vec);
+        }
+        let offline_sign = simulate_offline_stage(keys, &[1, 2]);
+        simulate_signing(offline_sign, b"ZenGo");
+    }
+
+    #[test]
+    #[cfg(feature = "mnemonic")]
+    fn test_share_mnemonic_after_refresh() {
+        let n = 3;
+        let mut keys: Vec<RefreshableLocalKey<Secp256k1>> = simulate_keygen(1, n)
+            .into_iter()
+            .map(RefreshableLocalKey::new)
+            .collect();
+        let (broadcast_vec, new_dks): (
+            Vec<RefreshMessage<Secp256k1, Sha256, { crate::M_SECURITY }>>,
+            Vec<DecryptionKey>,
+        ) = keys
+            .iter()
+            .map(|key| key.distribute(&BigInt::from(1), n).unwrap())
+            .unzip();
+        let outcomes: Vec<_> = keys
+            .iter_mut()
+            .zip(new_dks)
+            .map(|(key, new_dk)| {
+                key.collect(&BigInt::from(1), &broadcast_vec, new_dk, &[])
+                    .unwrap()
+            })
+            .collect();
+
+        let words = outcomes[1]
+            .share_mnemonic::<_, Sha256>(keys[1].inner(), Some("paper"))
+            .unwrap();
+        assert_eq!(words, keys[1].share_mnemonic(Some("paper")));
+        assert!(matches!(
+            outcomes[1].share_mnemonic::<_, Sha256>(&simulate_keygen(1, n)[1], None),
+            Err(FsDkrError::MnemonicShareMismatch)
+        ));
+        let share = keys[1].inner().keys_linear.x_i.clone();
+        let mut restored = keys[1].clone();
+        assert!(restored
+            .restore_share_from_mnemonic(&words, Some("wrong"))
+            .is_err());
+        assert!(keys[0]
+            .restore_share_from_mnemonic(&words, Some("paper"))
+            .is_err());
+        restored
+            .restore_share_from_mnemonic(&words, Some("paper"))
+            .unwrap();
+        assert_eq!(restored.inner().keys_linear.x_i, share);
+    }
+
+    #[test]
+    fn test_rehearsal() {
+        let real_keys = simulate_keygen(1, 3);
+        let mut rehearsal_keys = crate::rehearsal::rehearsal_fixture(&real_keys[0]);
+        assert_eq!(rehearsal_keys.len(), 3);
+        for (k, key) in rehearsal_keys.iter().enumerate() {
+            assert!(format!("{:?}", key).contains("REHEARSAL"));
+            let local_key = key.rehearsal_local_key();
+            verify_local_key(local_key).unwrap();
+            assert_eq!(local_key.i as usize, k + 1);
+            assert_eq!(local_key.t, real_keys[0].t);
+            assert_ne!(local_key.y_sum_s, real_keys[0].y_sum_s);
+        }
+
+        let report =
+            crate::rehearsal::rehearse::<_, Sha256, { crate::M_SECURITY }>(&mut rehearsal_keys);
+        assert!(report.all_green());
+        assert_eq!(report.parties.len(), 3);
+        rehearsal_keys
+            .iter()
+            .for_each(|key| verify_local_key(key.rehearsal_local_key()).unwrap());
+    }
+
     #[test]
     fn test_remove_sign_rotate_sign() {
         let mut keys = simulate_keygen(2, 5);
@@ -137,9 +3662,9 @@ mod tests {
                 Vec::with_capacity(keys.len() + join_messages.len());
             // all existing parties rotate aware of the join_messages
             for i in 0..keys.len() as usize {
-                RefreshMessage::collect(
+                keys[i] = RefreshMessage::collect(
                     refresh_messages.as_slice(),
-                    &mut keys[i],
+                    &keys[i],
                     dk_keys[i].clone(),
                     join_messages.as_slice(),
                 )
@@ -215,6 +3740,453 @@ mod tests {
         simulate_signing(offline_sign, b"ZenGo");
     }
 
+    #[test]
+    fn test_add_party_to_committee() {
+        let (t, n) = (1, 3);
+        let mut keys = simulate_keygen(t, n);
+        let public_key = keys[0].y_sum_s.clone();
+
+        // the newcomer registers its Paillier key and is assigned the next index
+        let (mut join_message, paillier_keys) =
+            JoinMessage::<Secp256k1, Sha256, { crate::M_SECURITY }>::distribute();
+        join_message.set_party_index(n + 1);
+        let join_messages = vec![join_message];
+
+        // the existing parties keep their indices
+        let old_to_new_map: HashMap<u16, u16> = (1..=n).map(|i| (i, i)).collect();
+        let (refresh_messages, dk_keys): (Vec<_>, Vec<_>) = keys
+            .iter_mut()
+            .map(|key| {
+                RefreshMessage::replace(&join_messages, key, &old_to_new_map, n + 1).unwrap()
+            })
+            .unzip();
+        for (key, dk) in keys.iter_mut().zip(dk_keys) {
+            *key = RefreshMessage::collect(&refresh_messages, key, dk, &join_messages).unwrap();
+        }
+        let new_key = join_messages[0]
+            .collect(&refresh_messages, paillier_keys, &join_messages, t, n + 1)
+            .unwrap();
+        keys.push(new_key);
+
+        for (k, key) in keys.iter().enumerate() {
+            assert_eq!(key.i, k as u16 + 1);
+            assert_eq!((key.t, key.n), (t, n + 1));
+            assert_eq!(key.y_sum_s, public_key);
+            assert_eq!(key.vss_scheme, keys[0].vss_scheme);
+            assert_eq!(key.pk_vec, keys[0].pk_vec);
+            assert_eq!(key.paillier_key_vec, keys[0].paillier_key_vec);
+            verify_local_key(key).unwrap();
+        }
+
+        let offline_sign = simulate_offline_stage(keys.clone(), &[1, 4]);
+        simulate_signing(offline_sign, b"ZenGo");
+        let offline_sign = simulate_offline_stage(keys, &[3, 4]);
+        simulate_signing(offline_sign, b"ZenGo");
+    }
+
+    #[test]
+    fn test_refreshable_local_key_epochs() {
+        let (t, n) = (2, 4);
+        let mut keys: Vec<RefreshableLocalKey<Secp256k1>> = simulate_keygen(t, n)
+            .into_iter()
+            .map(RefreshableLocalKey::new)
+            .collect();
+
+        for epoch in 1..=2u64 {
+            let session_id = BigInt::from(epoch as i32);
+            let (refresh_messages, dks): (
+                Vec<RefreshMessage<Secp256k1, Sha256, { crate::M_SECURITY }>>,
+                Vec<DecryptionKey>,
+            ) = keys
+                .iter()
+                .map(|key| key.distribute(&session_id, n).unwrap())
+                .unzip();
+            let digest = RefreshMessage::transcript_digest(&refresh_messages);
+            // some parties receive a message twice, which changes nothing
+            let mut with_duplicate = refresh_messages.clone();
+            with_duplicate.push(refresh_messages[0].clone());
+            assert_eq!(RefreshMessage::transcript_digest(&with_duplicate), digest);
+
+            for (k, (key, dk)) in keys.iter_mut().zip(dks).enumerate() {
+                let received = if k % 2 == 0 {
+                    &refresh_messages
+                } else {
+                    &with_duplicate
+                };
+                let outcome = key.collect(&session_id, received, dk, &[]).unwrap();
+                assert_eq!(outcome.guard.epoch(), epoch);
+                assert_eq!(outcome.transcript_digest, digest);
+                assert_eq!(key.epoch(), epoch);
+                assert_eq!(key.last_session(), Some(&session_id));
+                assert_eq!(key.prev_transcript_digest(), Some(&digest));
+                assert_eq!(key.burned_eks().len(), epoch as usize * n as usize);
+            }
+        }
+
+        // a failed collect leaves both the key and the metadata untouched
+        let (refresh_messages, dks): (
+            Vec<RefreshMessage<Secp256k1, Sha256, { crate::M_SECURITY }>>,
+            Vec<DecryptionKey>,
+        ) = keys
+            .iter()
+            .map(|key| key.distribute(&BigInt::from(3), n).unwrap())
+            .unzip();
+        let before = keys[0].clone();
+        assert!(keys[0]
+            .collect(
+                &BigInt::from(3),
+                &refresh_messages[..t as usize],
+                dks[0].clone(),
+                &[]
+            )
+            .is_err());
+        assert_eq!(keys[0].epoch(), before.epoch());
+        assert_eq!(keys[0].last_session(), before.last_session());
+        assert_eq!(
+            keys[0].prev_transcript_digest(),
+            before.prev_transcript_digest()
+        );
+        assert_eq!(
+            keys[0].inner().keys_linear.x_i,
+            before.inner().keys_linear.x_i
+        );
+
+        // collecting the same session twice is refused
+        assert!(matches!(
+            keys[0].collect(&BigInt::from(2), &refresh_messages, dks[0].clone(), &[]),
+            Err(FsDkrError::SessionReused)
+        ));
+
+        // within the epoch, the messages of session 3 are rejected in another session, even once
+        // re-bound to it
+        let session_id = BigInt::from(4);
+        let rebound: Vec<_> = refresh_messages
+            .iter()
+            .map(|msg| test_utils::rebind_to_session(msg, &session_id))
+            .collect();
+        for messages in [&refresh_messages, &rebound] {
+            assert!(matches!(
+                keys[0].collect(&session_id, messages, dks[0].clone(), &[]),
+                Err(FsDkrError::SessionBindingInvalid)
+            ));
+        }
+        assert_eq!(keys[0].epoch(), before.epoch());
+        keys[0]
+            .collect(&BigInt::from(3), &refresh_messages, dks[0].clone(), &[])
+            .unwrap();
+    }
+
+    #[test]
+    fn test_key_lock_defers_commit_until_signing_done() {
+        use std::sync::{mpsc, Arc};
+        use std::thread;
+        use std::time::Duration;
+
+        let (t, n) = (1, 3);
+        let keys: Vec<RefreshableLocalKey<Secp256k1>> = simulate_keygen(t, n)
+            .into_iter()
+            .map(RefreshableLocalKey::new)
+            .collect();
+        let session_id = BigInt::from(1);
+        let (refresh_messages, dks): (
+            Vec<RefreshMessage<Secp256k1, Sha256, { crate::M_SECURITY }>>,
+            Vec<DecryptionKey>,
+        ) = keys
+            .iter()
+            .map(|key| key.distribute(&session_id, n).unwrap())
+            .unzip();
+        let lock = Arc::new(KeyLock::new(keys[0].clone()));
+
+        // a signing session holds a lease across the commit attempt
+        let lease = lock.lease().unwrap();
+        assert_eq!(lease.epoch(), 0);
+        assert!(matches!(
+            lock.collect(
+                &session_id,
+                &refresh_messages,
+                dks[0].clone(),
+                &[],
+                CommitPolicy::Fail
+            ),
+            Err(FsDkrError::KeyInUse { leases: 1 })
+        ));
+        assert!(matches!(
+            lock.collect(
+                &session_id,
+                &refresh_messages,
+                dks[0].clone(),
+                &[],
+                CommitPolicy::Wait(Duration::from_millis(10))
+            ),
+            Err(FsDkrError::KeyInUse { leases: 1 })
+        ));
+        assert_eq!(lock.epoch().unwrap(), 0);
+        drop(lease);
+
+        // with the wait policy the commit goes through once the session finishes
+        let (leased, wait_for_lease) = mpsc::channel();
+        let signer = {
+            let lock = Arc::clone(&lock);
+            thread::spawn(move || {
+                let lease = lock.lease().unwrap();
+                leased.send(()).unwrap();
+                thread::sleep(Duration::from_millis(200));
+                assert_eq!(lease.epoch(), 0);
+            })
+        };
+        wait_for_lease.recv().unwrap();
+        let outcome = lock
+            .collect(
+                &session_id,
+                &refresh_messages,
+                dks[0].clone(),
+                &[],
+                CommitPolicy::Wait(Duration::from_secs(60)),
+            )
+            .unwrap();
+        signer.join().unwrap();
+        assert_eq!(outcome.guard.epoch(), 1);
+        assert_eq!(lock.active_leases().unwrap(), 0);
+        assert_eq!(lock.lease().unwrap().epoch(), 1);
+    }
+
+    #[test]
+    fn test_epoch_transport_keys_agree() {
+        let (t, n) = (1, 3);
+        let mut keys: Vec<RefreshableLocalKey<Secp256k1>> = simulate_keygen(t, n)
+            .into_iter()
+            .map(RefreshableLocalKey::new)
+            .collect();
+        let (refresh_messages, dks): (
+            Vec<RefreshMessage<Secp256k1, Sha256, { crate::M_SECURITY }>>,
+            Vec<DecryptionKey>,
+        ) = keys
+            .iter()
+            .map(|key| key.distribute(&BigInt::from(1), n).unwrap())
+            .unzip();
+
+        let secrets: Vec<EpochSecrets> = keys
+            .iter_mut()
+            .zip(dks)
+            .map(|(key, dk)| {
+                let outcome = key
+                    .collect(&BigInt::from(1), &refresh_messages, dk, &[])
+                    .unwrap();
+                let new_key = key.inner();
+                derive_epoch_secrets(
+                    &outcome,
+                    new_key.i,
+                    &new_key.keys_linear.x_i,
+                    &new_key.pk_vec,
+                )
+            })
+            .collect();
+
+        for (i, own) in secrets.iter().enumerate() {
+            let own_index = i as u16 + 1;
+            assert_eq!(own.epoch(), 1);
+            assert!(own.peer(own_index).is_none());
+            for peer in secrets
+                .iter()
+                .filter(|peer| peer.party_index() != own_index)
+            {
+                let ours = own.peer(peer.party_index()).unwrap();
+                let theirs = peer.peer(own_index).unwrap();
+                assert_eq!(ours.send(), theirs.receive());
+                assert_eq!(ours.receive(), theirs.send());
+                assert_ne!(ours.send(), ours.receive());
+            }
+        }
+        let first = secrets[0].peer(2).unwrap().send();
+        let second = secrets[0].peer(3).unwrap().send();
+        assert_ne!(first, second);
+    }
+
+    struct FailingAnchor;
+
+    impl Anchor for FailingAnchor {
+        fn publish(&self, _digest: [u8; 32]) -> FsDkrResult<AnchorReceipt> {
+            Err(FsDkrError::AnchorFailed {
+                reason: "log unavailable".to_string(),
+            })
+        }
+
+        fn verify(&self, _digest: [u8; 32], _receipt: &AnchorReceipt) -> bool {
+            false
+        }
+    }
+
+    #[test]
+    fn test_anchored_commit() {
+        let (t, n) = (1, 3);
+        let mut keys: Vec<RefreshableLocalKey<Secp256k1>> = simulate_keygen(t, n)
+            .into_iter()
+            .map(RefreshableLocalKey::new)
+            .collect();
+        let session_id = BigInt::from(1);
+        let (refresh_messages, dks): (
+            Vec<RefreshMessage<Secp256k1, Sha256, { crate::M_SECURITY }>>,
+            Vec<DecryptionKey>,
+        ) = keys
+            .iter()
+            .map(|key| key.distribute(&session_id, n).unwrap())
+            .unzip();
+
+        // a required anchor that fails aborts the commit
+        let before = keys[0].clone();
+        let pending = keys[0]
+            .collect_pending(&session_id, &refresh_messages, dks[0].clone(), &[])
+            .unwrap();
+        assert!(matches!(
+            pending.commit_anchored(&FailingAnchor, AnchorPolicy::Required),
+            Err(FsDkrError::AnchorFailed { .. })
+        ));
+        assert_eq!(keys[0].epoch(), before.epoch());
+        assert_eq!(
+            keys[0].inner().keys_linear.x_i,
+            before.inner().keys_linear.x_i
+        );
+
+        // a best effort anchor that fails still commits, without a receipt
+        let outcome = keys[0]
+            .collect_pending(&session_id, &refresh_messages, dks[0].clone(), &[])
+            .unwrap()
+            .commit_anchored(&FailingAnchor, AnchorPolicy::BestEffort)
+            .unwrap();
+        assert_eq!(keys[0].epoch(), 1);
+        assert!(outcome.anchor_receipt.is_none());
+
+        let anchor = InMemoryAnchor::new();
+        for (key, dk) in keys.iter_mut().zip(dks).skip(1) {
+            let outcome = key
+                .collect_pending(&session_id, &refresh_messages, dk, &[])
+                .unwrap()
+                .commit_anchored(&anchor, AnchorPolicy::Required)
+                .unwrap();
+            outcome.verify_anchor(&anchor).unwrap();
+            assert_eq!(key.anchor_receipt(), outcome.anchor_receipt.as_ref());
+
+            let mut forged = outcome.clone();
+            forged.transcript_digest = forged.transcript_digest + BigInt::from(1);
+            assert!(forged.verify_anchor(&anchor).is_err());
+        }
+        assert_eq!(anchor.len(), n as usize - 1);
+    }
+
+    #[test]
+    fn test_interleaved_refresh_sessions() {
+        type Session = RefreshSession<Secp256k1, Sha256, { crate::M_SECURITY }>;
+        fn assert_send<T: Send>() {}
+        assert_send::<Session>();
+
+        let (t, n) = (1, 3);
+        let committees = [simulate_keygen(t, n), simulate_keygen(t, n)];
+        let session_ids = [BigInt::from(1), BigInt::from(2)];
+        let mut sessions: Vec<Vec<Session>> = Vec::new();
+        let mut broadcasts = Vec::new();
+        for (keys, session_id) in committees.iter().zip(session_ids.iter()) {
+            let (started, messages): (Vec<_>, Vec<_>) = keys
+                .iter()
+                .map(|key| Session::start(key, session_id.clone()).unwrap())
+                .unzip();
+            sessions.push(started);
+            broadcasts.push(messages);
+        }
+
+        // the messages of both sessions arrive interleaved at every party of both committees
+        for sender in 0..n as usize {
+            for (c, committee_sessions) in sessions.iter_mut().enumerate() {
+                for (receiver, session) in committee_sessions.iter_mut().enumerate() {
+                    if sender == 0 {
+                        assert!(session.try_complete(&committees[c][receiver]).is_none());
+                    }
+                    for (origin, messages) in broadcasts.iter().enumerate() {
+                        let msg = messages[sender].clone();
+                        let result = session.handle(msg);
+                        if origin != c {
+                            assert!(matches!(
+                                result,
+                                Err(FsDkrError::SessionMismatch { party_index })
+                                    if party_index == sender as u16 + 1
+                            ));
+                        } else if sender == receiver {
+                            assert!(matches!(
+                                result,
+                                Err(FsDkrError::DuplicatedRefreshMessage { .. })
+                            ));
+                        } else {
+                            result.unwrap();
+                        }
+                    }
+                }
+            }
+        }
+
+        // a second, different message of a sender is equivocation
+        let (_, other) = Session::start(&committees[0][1], session_ids[0].clone()).unwrap();
+        assert!(matches!(
+            sessions[0][0].handle(other),
+            Err(FsDkrError::EquivocatingSender { party_index: 2 })
+        ));
+
+        for (c, committee_sessions) in sessions.iter_mut().enumerate() {
+            let old_keys = &committees[c];
+            let new_keys: Vec<_> = committee_sessions
+                .iter_mut()
+                .zip(old_keys.iter())
+                .map(|(session, old_key)| {
+                    assert_eq!(session.status(), SessionStatus::QuorumReached);
+                    session.try_complete(old_key).unwrap().unwrap()
+                })
+                .collect();
+            for (new_key, old_key) in new_keys.iter().zip(old_keys.iter()) {
+                assert_eq!(new_key.y_sum_s, old_key.y_sum_s);
+                assert_ne!(new_key.keys_linear.x_i, old_key.keys_linear.x_i);
+            }
+            // a completed session takes no more messages
+            assert!(committee_sessions[0].try_complete(&old_keys[0]).is_none());
+            assert!(matches!(
+                committee_sessions[0].handle(broadcasts[c][1].clone()),
+                Err(FsDkrError::MalformedMessage { .. })
+            ));
+            let offline_sign = simulate_offline_stage(new_keys, &[1, 2]);
+            simulate_signing(offline_sign, b"ZenGo");
+        }
+    }
+
+    // Shifts the shared secret of `keys` to `target` by adding the same constant to every share.
+    fn shift_keys_to_secret(keys: &mut [LocalKey<Secp256k1>], target: &Scalar<Secp256k1>) {
+        let points: Vec<u16> = (1..=keys[0].t + 1).collect();
+        let secret = points
+            .iter()
+            .map(|j| {
+                let x_j = &keys[*j as usize - 1].keys_linear.x_i;
+                lagrange_coefficient_at(&Scalar::<Secp256k1>::zero(), *j, &points) * x_j
+            })
+            .fold(Scalar::<Secp256k1>::zero(), |acc, v| acc + v);
+        let shift = target - &secret;
+        let shift_point = Point::<Secp256k1>::generator() * &shift;
+
+        for key in keys.iter_mut() {
+            key.keys_linear.x_i = &key.keys_linear.x_i + &shift;
+            key.keys_linear.y = &key.keys_linear.y + &shift_point;
+            key.y_sum_s = &key.y_sum_s + &shift_point;
+            for pk in key.pk_vec.iter_mut() {
+                *pk = &*pk + &shift_point;
+            }
+            key.vss_scheme.commitments[0] = &key.vss_scheme.commitments[0] + &shift_point;
+        }
+    }
+
+    #[cfg(feature = "fast-insecure-fixtures")]
+    fn simulate_keygen(t: u16, n: u16) -> Vec<LocalKey<Secp256k1>> {
+        use std::sync::atomic::{AtomicU64, Ordering};
+        // a new seed for every committee, so tests never share moduli
+        static SEED: AtomicU64 = AtomicU64::new(0);
+        crate::fixtures::fixture_committee(t, n, SEED.fetch_add(1, Ordering::Relaxed))
+    }
+
+    #[cfg(not(feature = "fast-insecure-fixtures"))]
     fn simulate_keygen(t: u16, n: u16) -> Vec<LocalKey<Secp256k1>> {
         //simulate keygen
         let mut simulation = Simulation::new();
@@ -227,6 +4199,19 @@ mod tests {
         simulation.run().unwrap()
     }
 
+    fn simulate_refresh<const M: usize>(
+        keys: Vec<LocalKey<Secp256k1>>,
+    ) -> Vec<LocalKey<Secp256k1>> {
+        let mut simulation = Simulation::new();
+        simulation.enable_benchmarks(false);
+
+        for key in keys {
+            simulation.add_party(KeyRefresh::<Secp256k1, Sha256, M>::new(key).unwrap());
+        }
+
+        simulation.run().unwrap()
+    }
+
     fn simulate_dkr_removal<const M: usize>(
         keys: &mut Vec<LocalKey<Secp256k1>>,
         remove_party_indices: Vec<u16>,
@@ -280,8 +4265,8 @@ mod tests {
                 continue;
             }
 
-            RefreshMessage::collect(
-                broadcast_messages[party].clone().as_slice(),
+            *key = RefreshMessage::collect(
+                broadcast_messages[party].as_slice(),
                 key,
                 new_dks[party].clone(),
                 &[],
@@ -292,7 +4277,7 @@ mod tests {
         for remove_party_index in remove_party_indices {
             let result = RefreshMessage::collect(
                 &broadcast_messages[&(remove_party_index as usize)],
-                &mut keys[remove_party_index as usize],
+                &keys[remove_party_index as usize],
                 new_dks[&(remove_party_index as usize)].clone(),
                 &[],
             );
@@ -318,7 +4303,7 @@ mod tests {
 
         // keys will be updated to refreshed values
         for i in 0..keys.len() as usize {
-            RefreshMessage::collect(&broadcast_vec, &mut keys[i], new_dks[i].clone(), &[])
+            keys[i] = RefreshMessage::collect(&broadcast_vec, &keys[i], new_dks[i].clone(), &[])
                 .expect("");
         }
 
@@ -373,6 +4358,25 @@ mod tests {
             .all(|signature| verify(&signature, &pk, &message).is_ok()));
     }
 
+    fn simulate_signature(offline: Vec<CompletedOfflineStage>, message: &BigInt) -> SignatureRecid {
+        let parties = offline
+            .iter()
+            .map(|o| SignManual::new(message.clone(), o.clone()))
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        let (mut parties, local_sigs): (Vec<_>, Vec<_>) = parties.into_iter().unzip();
+        parties.remove(0).complete(&local_sigs[1..]).unwrap()
+    }
+
+    // plain ECDSA with a single secret, standing in for a signature by a subset of the shares
+    fn sign_with_secret(secret: &Scalar<Secp256k1>, message: &BigInt) -> SignatureRecid {
+        let k = Scalar::<Secp256k1>::random();
+        let r_point = Point::<Secp256k1>::generator() * &k;
+        let r = Scalar::<Secp256k1>::from(&r_point.x_coord().unwrap());
+        let s = k.invert().unwrap() * (Scalar::<Secp256k1>::from(message) + &r * secret);
+        SignatureRecid { r, s, recid: 0 }
+    }
+
     fn create_hash(big_ints: &[&BigInt]) -> BigInt {
         let hasher = Sha256::new();
 
@@ -0,0 +1,120 @@
+//! Adversarial refresh messages for testing abort handling.
+//!
+//! Every helper takes an honest [RefreshMessage] and returns a copy that collect rejects with a
+//! specific [FsDkrError](crate::error::FsDkrError), named in the helper's documentation. The
+//! receivers are counted from 1, like party indices.
+//!
+//! This module is only available with the `test-utils` feature; the messages it builds are
+//! useless outside of tests.
+
+use crate::prepared_distribution::SessionBinding;
+use crate::refresh_message::{RefreshMessage, RefreshMessageParts};
+use curv::cryptographic_primitives::hashing::Digest;
+use curv::elliptic::curves::{Curve, Scalar};
+use curv::BigInt;
+use paillier::EncryptionKey;
+
+fn rebuild<E: Curve, H: Digest + Clone, const M: usize>(
+    parts: RefreshMessageParts<E, H, M>,
+) -> RefreshMessage<E, H, M> {
+    RefreshMessage::new(parts).expect("the helpers keep the message well-formed")
+}
+
+/// Changes the ciphertext sent to `receiver` so it no longer encrypts the committed sub-share.
+/// Fails with `PDLwSlackProofInvalid` for `receiver`.
+pub fn corrupt_ciphertext<E: Curve, H: Digest + Clone, const M: usize>(
+    msg: &RefreshMessage<E, H, M>,
+    receiver: u16,
+) -> RefreshMessage<E, H, M> {
+    let mut parts = msg.clone().into_parts();
+    let k = receiver as usize - 1;
+    parts.points_encrypted_vec[k] = &parts.points_encrypted_vec[k] + &BigInt::from(1);
+    rebuild(parts)
+}
+
+/// Swaps the ciphertexts sent to receivers `i` and `j`, leaving the proofs in place. The
+/// committed sub-shares are derived from the commitments and can not be swapped themselves.
+/// Fails with `PDLwSlackProofInvalid` for the lower of `i` and `j`.
+pub fn swap_ciphertexts<E: Curve, H: Digest + Clone, const M: usize>(
+    msg: &RefreshMessage<E, H, M>,
+    i: u16,
+    j: u16,
+) -> RefreshMessage<E, H, M> {
+    let mut parts = msg.clone().into_parts();
+    parts
+        .points_encrypted_vec
+        .swap(i as usize - 1, j as usize - 1);
+    rebuild(parts)
+}
+
+/// Replaces the range proof of the ciphertext sent to `receiver` by the valid proof of the
+/// ciphertext sent to `other`. Fails with `RangeProof` for `receiver`.
+pub fn range_proof_for_wrong_ciphertext<E: Curve, H: Digest + Clone, const M: usize>(
+    msg: &RefreshMessage<E, H, M>,
+    receiver: u16,
+    other: u16,
+) -> RefreshMessage<E, H, M> {
+    let mut parts = msg.clone().into_parts();
+    parts.range_proofs[receiver as usize - 1] = parts.range_proofs[other as usize - 1].clone();
+    rebuild(parts)
+}
+
+/// Replaces the ring-Pedersen proof by the valid proof of `other`, made for other parameters.
+/// Fails with `RingPedersenProofValidation`.
+pub fn ring_pedersen_proof_of_another_message<E: Curve, H: Digest + Clone, const M: usize>(
+    msg: &RefreshMessage<E, H, M>,
+    other: &RefreshMessage<E, H, M>,
+) -> RefreshMessage<E, H, M> {
+    let mut parts = msg.clone().into_parts();
+    parts.ring_pedersen_proof = other.clone().into_parts().ring_pedersen_proof;
+    rebuild(parts)
+}
+
+/// Announces `ek` as the new Paillier key of the sender, without a proof for it. Fails with
+/// `PaillierVerificationError`.
+pub fn replace_paillier_key<E: Curve, H: Digest + Clone, const M: usize>(
+    msg: &RefreshMessage<E, H, M>,
+    ek: &EncryptionKey,
+) -> RefreshMessage<E, H, M> {
+    let mut parts = msg.clone().into_parts();
+    parts.ek = ek.clone();
+    rebuild(parts)
+}
+
+/// Strips the session binding and binds the message to `session_id` under a fresh key, as
+/// anyone who captured it could. Fails with `SessionBindingInvalid` in the session.
+pub fn rebind_to_session<E: Curve, H: Digest + Clone, const M: usize>(
+    msg: &RefreshMessage<E, H, M>,
+    session_id: &BigInt,
+) -> RefreshMessage<E, H, M> {
+    let mut rebound = msg.clone();
+    rebound.session_binding = Some(SessionBinding::sign::<H>(
+        session_id,
+        &Scalar::<E>::random(),
+        &msg.binding_fingerprint(),
+    ));
+    rebound
+}
+
+/// Drops the last receiver, leaving a message that is consistent in itself but shares to one
+/// party less than the committee. Fails with `InvalidRefreshMessage`.
+pub fn drop_last_receiver<E: Curve, H: Digest + Clone, const M: usize>(
+    msg: &RefreshMessage<E, H, M>,
+) -> RefreshMessage<E, H, M> {
+    let mut parts = msg.clone().into_parts();
+    parts.points_encrypted_vec.pop();
+    parts.pdl_proof_vec.pop();
+    parts.range_proofs.pop();
+    parts.coefficients_committed_vec.parameters.share_count -= 1;
+    rebuild(parts)
+}
+
+/// The parts of `msg` with the range proof of the last receiver dropped. [RefreshMessage::new]
+/// and deserialization reject them with `InvalidRefreshMessage`.
+pub fn parts_with_short_vectors<E: Curve, H: Digest + Clone, const M: usize>(
+    msg: &RefreshMessage<E, H, M>,
+) -> RefreshMessageParts<E, H, M> {
+    let mut parts = msg.clone().into_parts();
+    parts.range_proofs.pop();
+    parts
+}
@@ -0,0 +1,13 @@
+//! Spans of the `tracing` feature.
+//!
+//! [trace_span!] enters a debug span until the end of the enclosing block, so a subscriber sees
+//! where a refresh spends its time. The fields are party indices and sizes only, never a share,
+//! a plaintext or a ciphertext. Without the feature the macro expands to nothing and the crate
+//! does not depend on `tracing`.
+
+macro_rules! trace_span {
+    ($name:literal $(, $field:ident = $value:expr)* $(,)?) => {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::debug_span!($name $(, $field = $value)*).entered();
+    };
+}
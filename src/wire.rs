@@ -0,0 +1,82 @@
+//! Decoding refresh messages received as raw bytes.
+//!
+//! The crate does not pick a wire format: the application provides a [Codec] for whatever
+//! serde format it sends messages in. [decode_message] bounds the size of a blob before handing
+//! it to the codec, decodes it through [RefreshMessage::new] and checks that it was sent by the
+//! party it claims to come from. Every failure is a [FsDkrError::MalformedMessage] naming the
+//! sender, so it can be told apart from a message that decodes but fails verification.
+//! [RefreshableLocalKey::collect_from_bytes] runs this for every blob before collecting.
+//!
+//! [RefreshableLocalKey::collect_from_bytes]: crate::refreshable_key::RefreshableLocalKey::collect_from_bytes
+
+use crate::error::{FsDkrError, FsDkrResult};
+use crate::refresh_message::RefreshMessage;
+use curv::cryptographic_primitives::hashing::Digest;
+use curv::elliptic::curves::Curve;
+use serde::de::DeserializeOwned;
+
+/// A serde format refresh messages are sent in.
+pub trait Codec {
+    fn decode<T: DeserializeOwned>(&self, bytes: &[u8]) -> Result<T, String>;
+}
+
+/// Size of a message without its per-receiver data, allowing for hex-encoded integers.
+const MAX_MESSAGE_BASE_LEN: usize = 256 * 1024;
+/// Size of the ciphertext and proofs of one receiver, allowing for hex-encoded integers.
+const MAX_MESSAGE_LEN_PER_RECEIVER: usize = 32 * 1024;
+
+/// Bounds checked on a blob before it is decoded.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DecodeLimits {
+    max_message_len: usize,
+}
+
+impl DecodeLimits {
+    /// Limits fitting a refresh message to `new_n` receivers in any reasonable encoding.
+    pub fn for_committee(new_n: u16) -> Self {
+        DecodeLimits {
+            max_message_len: MAX_MESSAGE_BASE_LEN + new_n as usize * MAX_MESSAGE_LEN_PER_RECEIVER,
+        }
+    }
+
+    pub fn with_max_message_len(max_message_len: usize) -> Self {
+        DecodeLimits { max_message_len }
+    }
+
+    pub fn max_message_len(&self) -> usize {
+        self.max_message_len
+    }
+}
+
+/// Decodes the refresh message `bytes` received from `sender`, see the [module docs](self).
+pub fn decode_message<E, H, C, const M: usize>(
+    sender: u16,
+    bytes: &[u8],
+    codec: &C,
+    limits: &DecodeLimits,
+) -> FsDkrResult<RefreshMessage<E, H, M>>
+where
+    E: Curve,
+    H: Digest + Clone,
+    C: Codec,
+{
+    let malformed = |reason: String| FsDkrError::MalformedMessage {
+        party_index: sender,
+        reason,
+    };
+    if bytes.len() > limits.max_message_len {
+        return Err(malformed(format!(
+            "{} bytes, at most {} accepted",
+            bytes.len(),
+            limits.max_message_len
+        )));
+    }
+    let message: RefreshMessage<E, H, M> = codec.decode(bytes).map_err(malformed)?;
+    if message.party_index != sender {
+        return Err(malformed(format!(
+            "claims to come from party {}",
+            message.party_index
+        )));
+    }
+    Ok(message)
+}
@@ -9,6 +9,7 @@
 
 use std::marker::PhantomData;
 
+use crate::compact::{Reader, Writer};
 use crate::error::{FsDkrError, FsDkrResult};
 use curv::arithmetic::traits::*;
 use curv::cryptographic_primitives::hashing::{Digest, DigestExt};
@@ -19,6 +20,7 @@ use curv::elliptic::curves::Secp256k1;
 use curv::BigInt;
 use paillier::EncryptionKey;
 use serde::{Deserialize, Serialize};
+use zeroize::Zeroize;
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct PDLwSlackStatement<E: Curve = Secp256k1> {
@@ -30,12 +32,27 @@ pub struct PDLwSlackStatement<E: Curve = Secp256k1> {
     pub h2: BigInt,
     pub N_tilde: BigInt,
 }
+/// Witness of a [PDLwSlackProof], wiped when dropped.
 #[derive(Clone)]
 pub struct PDLwSlackWitness<E: Curve = Secp256k1> {
     pub x: Scalar<E>,
     pub r: BigInt,
 }
 
+impl<E: Curve> Zeroize for PDLwSlackWitness<E> {
+    fn zeroize(&mut self) {
+        // the scalar wipes itself when it is dropped
+        self.x = Scalar::<E>::zero();
+        self.r.zeroize();
+    }
+}
+
+impl<E: Curve> Drop for PDLwSlackWitness<E> {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PDLwSlackProof<E: Curve, H: Digest + Clone> {
     z: BigInt,
@@ -45,28 +62,25 @@ pub struct PDLwSlackProof<E: Curve, H: Digest + Clone> {
     s1: BigInt,
     s2: BigInt,
     s3: BigInt,
-    _phantom: PhantomData<H>,
+    _phantom: PhantomData<fn() -> H>,
 }
 
 impl<E: Curve, H: Digest + Clone> PDLwSlackProof<E, H> {
+    /// The nonces and the integer copies of the witness are wiped before returning.
     pub fn prove(witness: &PDLwSlackWitness<E>, statement: &PDLwSlackStatement<E>) -> Self {
         let q3 = Scalar::<E>::group_order().pow(3);
         let q_N_tilde = Scalar::<E>::group_order() * &statement.N_tilde;
         let q3_N_tilde = &q3 * &statement.N_tilde;
 
-        let alpha = BigInt::sample_below(&q3);
+        let mut alpha = BigInt::sample_below(&q3);
         let one = BigInt::one();
-        let beta = BigInt::sample_range(&one, &(&statement.ek.n - &one));
-        let rho = BigInt::sample_below(&q_N_tilde);
-        let gamma = BigInt::sample_below(&q3_N_tilde);
+        let mut beta = BigInt::sample_range(&one, &(&statement.ek.n - &one));
+        let mut rho = BigInt::sample_below(&q_N_tilde);
+        let mut gamma = BigInt::sample_below(&q3_N_tilde);
+        let mut x = witness.x.to_bigint();
 
-        let z = commitment_unknown_order(
-            &statement.h1,
-            &statement.h2,
-            &statement.N_tilde,
-            &witness.x.to_bigint(),
-            &rho,
-        );
+        let z =
+            commitment_unknown_order(&statement.h1, &statement.h2, &statement.N_tilde, &x, &rho);
         let u1 = statement.G.clone() * Scalar::<E>::from(&alpha);
         let u2 = commitment_unknown_order(
             &(&statement.ek.n + BigInt::one()),
@@ -93,9 +107,14 @@ impl<E: Curve, H: Digest + Clone> PDLwSlackProof<E, H> {
             .chain_bigint(&u3)
             .result_bigint();
 
-        let s1 = &e * witness.x.to_bigint() + alpha;
+        let s1 = &e * &x + &alpha;
         let s2 = commitment_unknown_order(&witness.r, &beta, &statement.ek.n, &e, &BigInt::one());
-        let s3 = &e * rho + gamma;
+        let s3 = &e * &rho + &gamma;
+        x.zeroize();
+        alpha.zeroize();
+        beta.zeroize();
+        rho.zeroize();
+        gamma.zeroize();
 
         PDLwSlackProof {
             z,
@@ -164,6 +183,27 @@ impl<E: Curve, H: Digest + Clone> PDLwSlackProof<E, H> {
             })
         }
     }
+
+    pub(crate) fn write_compact(&self, writer: &mut Writer) {
+        writer.int(&self.z);
+        writer.point(&self.u1);
+        for value in [&self.u2, &self.u3, &self.s1, &self.s2, &self.s3].iter() {
+            writer.int(value);
+        }
+    }
+
+    pub(crate) fn read_compact(reader: &mut Reader) -> FsDkrResult<Self> {
+        Ok(PDLwSlackProof {
+            z: reader.int()?,
+            u1: reader.point()?,
+            u2: reader.int()?,
+            u3: reader.int()?,
+            s1: reader.int()?,
+            s2: reader.int()?,
+            s3: reader.int()?,
+            _phantom: PhantomData,
+        })
+    }
 }
 
 pub fn commitment_unknown_order(